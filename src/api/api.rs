@@ -1,12 +1,22 @@
 use std::collections::HashMap;
 
+use alloy::eips::eip2930::AccessListResult;
 use alloy::primitives::keccak256;
-use jsonrpsee::core::RpcResult;
+use alloy_rpc_types_eth::state::StateOverride;
+use alloy_rpc_types_eth::{BlockOverrides, EIP1186AccountProofResponse};
+use alloy_rpc_types_trace::geth::GethDebugTracingOptions;
+use alloy_serde::JsonStorageKey;
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
 
-use crate::api::types::{Base64Bytes, EthCall, GetLogsFilter, PrecompileData};
+use crate::api::types::{
+    Base64Bytes, BlockNumberOrHash, BlockOperation, EthCall, FilterChanges, GetLogsFilter,
+    InscriptionEstimate, PrecompileData, ReceiptProof, SimulatePayload, SimulatedBlock,
+    TraceResult,
+};
 use crate::db::types::{
     AddressED, BlockResponseED, BytecodeED, LogED, TraceED, TxED, TxReceiptED, B256ED, U256ED,
+    U64ED,
 };
 use crate::global::{CARGO_PKG_VERSION, CARGO_RUST_VERSION, CONFIG, INDEXER_ADDRESS};
 use crate::types::RawBytes;
@@ -21,12 +31,16 @@ lazy_static::lazy_static! {
         "brc20_withdraw".to_string(),
         "brc20_initialise".to_string(),
         "brc20_finaliseBlock".to_string(),
+        "brc20_addBlock".to_string(),
+        "brc20_abortBlock".to_string(),
         "brc20_transact".to_string(),
         "brc20_reorg".to_string(),
         "brc20_commitToDatabase".to_string(),
         "brc20_clearCaches".to_string(),
         "debug_getBlockTraceString".to_string(), // Expensive, indexer-only debug method
         "debug_getBlockTraceHash".to_string(), // Expensive, indexer-only debug method
+        "debug_traceBlockByNumber".to_string(), // Executes whole blocks again, indexer-only debug method
+        "debug_traceBlockByHash".to_string(), // Executes whole blocks again, indexer-only debug method
     ];
 }
 
@@ -120,6 +134,21 @@ pub trait Brc20ProgApi {
     #[method(name = "brc20_balance")]
     async fn brc20_balance(&self, pkscript: String, ticker: String) -> RpcResult<String>;
 
+    /// Estimates the gas for a deploy or call inscription, and the minimum inscription byte length
+    /// that provides it, deploys are estimated if no contract is given
+    ///
+    /// The data is returned with the compression method that yields the smallest inscription
+    #[method(name = "brc20_estimateInscription")]
+    async fn brc20_estimate_inscription(
+        &self,
+        from_pkscript: String,
+        contract_address: Option<AddressED>,
+        contract_inscription_id: Option<String>,
+        data: Option<RawBytes>,
+        base64_data: Option<Base64Bytes>,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<InscriptionEstimate>;
+
     /// Initialises the BRC20 prog module with the given genesis hash and timestamp
     #[method(name = "brc20_initialise")]
     async fn brc20_initialise(
@@ -136,6 +165,12 @@ pub trait Brc20ProgApi {
         inscription_id: String,
     ) -> RpcResult<Option<TxReceiptED>>;
 
+    /// Retrieves the Merkle proof of the receipt of given transaction hash against the receipts root
+    /// of its block
+    #[method(name = "brc20_getReceiptProof")]
+    async fn brc20_get_receipt_proof(&self, transaction: B256ED)
+        -> RpcResult<Option<ReceiptProof>>;
+
     /// Retrieves inscription id for given transaction hash
     #[method(name = "brc20_getInscriptionIdByTxHash")]
     async fn brc20_get_inscription_id_by_tx_hash(
@@ -159,6 +194,27 @@ pub trait Brc20ProgApi {
         block_tx_count: u64,
     ) -> RpcResult<()>;
 
+    /// Adds a block with all its operations in order, then finalises it
+    ///
+    /// Operations are executed as if they were sent one by one with increasing transaction
+    /// indexes, if any of them fails, the whole block is discarded and the state is left as it
+    /// was before the call
+    ///
+    /// Returns the receipts of all transactions in the block, in order
+    #[method(name = "brc20_addBlock")]
+    async fn brc20_add_block(
+        &self,
+        timestamp: u64,
+        hash: B256ED,
+        operations: Vec<BlockOperation>,
+    ) -> RpcResult<Vec<TxReceiptED>>;
+
+    /// Discards the transactions added to the block being indexed, so it can be indexed again
+    ///
+    /// Finalised blocks are kept, even if they are not committed to the database yet
+    #[method(name = "brc20_abortBlock")]
+    async fn brc20_abort_block(&self) -> RpcResult<()>;
+
     /// Reverts the state to the given latest valid block number
     #[method(name = "brc20_reorg")]
     async fn brc20_reorg(&self, latest_valid_block_number: u64) -> RpcResult<()>;
@@ -185,7 +241,7 @@ pub trait Brc20ProgApi {
     #[method(name = "eth_getBlockByNumber")]
     async fn eth_get_block_by_number(
         &self,
-        block: String,
+        block: BlockNumberOrHash,
         is_full: Option<bool>,
     ) -> RpcResult<BlockResponseED>;
 
@@ -202,12 +258,15 @@ pub trait Brc20ProgApi {
     async fn eth_get_transaction_count(
         &self,
         account: AddressED,
-        block: String,
+        block: BlockNumberOrHash,
     ) -> RpcResult<String>;
 
     /// Returns the transaction count by block number
     #[method(name = "eth_getBlockTransactionCountByNumber")]
-    async fn eth_get_block_transaction_count_by_number(&self, block: String) -> RpcResult<String>;
+    async fn eth_get_block_transaction_count_by_number(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<String>;
 
     /// Returns the transaction count by block hash
     #[method(name = "eth_getBlockTransactionCountByHash")]
@@ -217,40 +276,107 @@ pub trait Brc20ProgApi {
     #[method(name = "eth_getLogs")]
     async fn eth_get_logs(&self, filter: GetLogsFilter) -> RpcResult<Vec<LogED>>;
 
+    /// Creates a filter to poll for logs matching the given filter, returns the filter ID
+    #[method(name = "eth_newFilter")]
+    async fn eth_new_filter(&self, filter: GetLogsFilter) -> RpcResult<String>;
+
+    /// Creates a filter to poll for new blocks, returns the filter ID
+    #[method(name = "eth_newBlockFilter")]
+    async fn eth_new_block_filter(&self) -> RpcResult<String>;
+
+    /// Returns the changes for the filter since the last poll
+    #[method(name = "eth_getFilterChanges")]
+    async fn eth_get_filter_changes(&self, filter_id: String) -> RpcResult<FilterChanges>;
+
+    /// Returns all logs matching the log filter
+    #[method(name = "eth_getFilterLogs")]
+    async fn eth_get_filter_logs(&self, filter_id: String) -> RpcResult<Vec<LogED>>;
+
+    /// Removes the filter, returns true if the filter existed
+    #[method(name = "eth_uninstallFilter")]
+    async fn eth_uninstall_filter(&self, filter_id: String) -> RpcResult<bool>;
+
     /// Calls a contract with the given parameters
+    ///
+    /// Use `Brc20ProgOverridesApi` to pass state and block overrides
     #[method(name = "eth_call")]
-    async fn eth_call(&self, eth_call: EthCall, block: Option<String>) -> RpcResult<String>;
+    async fn eth_call(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String>;
 
     /// Calls a contract with the given parameters for multiple calls
     #[method(name = "eth_callMany")]
     async fn eth_call_many(
         &self,
         eth_calls: Vec<EthCall>,
-        block: Option<String>,
+        block: Option<BlockNumberOrHash>,
         precompile_data: Option<PrecompileData>,
     ) -> RpcResult<Vec<String>>;
 
     /// Estimates the gas for the given transaction
     #[method(name = "eth_estimateGas")]
-    async fn eth_estimate_gas(&self, eth_call: EthCall, block: Option<String>)
-        -> RpcResult<String>;
+    async fn eth_estimate_gas(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String>;
 
     /// Estimates the gas for the given transactions
     #[method(name = "eth_estimateGasMany")]
     async fn eth_estimate_gas_many(
         &self,
         eth_calls: Vec<EthCall>,
-        block: Option<String>,
+        block: Option<BlockNumberOrHash>,
         precompile_data: Option<PrecompileData>,
     ) -> RpcResult<Vec<String>>;
 
+    /// Simulates calls in a sequence of blocks, with block and state overrides applied before each block
+    ///
+    /// Calls are traced if tracer options are given
+    #[method(name = "eth_simulateV1")]
+    async fn eth_simulate_v1(
+        &self,
+        payload: SimulatePayload,
+        block: Option<BlockNumberOrHash>,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Vec<SimulatedBlock>>;
+
+    /// Returns the accounts and storage slots accessed by the call, and the gas used with them
+    #[method(name = "eth_createAccessList")]
+    async fn eth_create_access_list(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+    ) -> RpcResult<AccessListResult>;
+
     /// Get storage for the given contract and memory location
     #[method(name = "eth_getStorageAt")]
-    async fn eth_get_storage_at(&self, contract: AddressED, location: U256ED) -> RpcResult<String>;
+    async fn eth_get_storage_at(
+        &self,
+        contract: AddressED,
+        location: U256ED,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String>;
+
+    /// Get the proof of an account and its storage slots against the state root of a block
+    #[method(name = "eth_getProof")]
+    async fn eth_get_proof(
+        &self,
+        account: AddressED,
+        storage_keys: Vec<JsonStorageKey>,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<EIP1186AccountProofResponse>;
 
     /// Returns the bytecode of the contract at the given address
     #[method(name = "eth_getCode")]
-    async fn eth_get_code(&self, contract: AddressED) -> RpcResult<BytecodeED>;
+    async fn eth_get_code(
+        &self,
+        contract: AddressED,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<BytecodeED>;
 
     /// Returns the transaction receipt for the given transaction hash
     #[method(name = "eth_getTransactionReceipt")]
@@ -259,17 +385,65 @@ pub trait Brc20ProgApi {
         transaction: B256ED,
     ) -> RpcResult<Option<TxReceiptED>>;
 
-    /// Returns the trace for the given transaction hash
+    /// Returns the trace for the given transaction hash in `callTracer` format
+    ///
+    /// Use `Brc20ProgTracingApi` to pass tracer options
     #[method(name = "debug_traceTransaction")]
     async fn debug_trace_transaction(&self, transaction: B256ED) -> RpcResult<Option<TraceED>>;
 
+    /// Executes a call without committing it, returns the trace for the call
+    #[method(name = "debug_traceCall")]
+    async fn debug_trace_call(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<TraceResult>;
+
+    /// Returns the traces for every transaction in the given block number
+    #[method(name = "debug_traceBlockByNumber")]
+    async fn debug_trace_block_by_number(
+        &self,
+        block: BlockNumberOrHash,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<Vec<TraceResult>>>;
+
+    /// Returns the traces for every transaction in the given block hash
+    #[method(name = "debug_traceBlockByHash")]
+    async fn debug_trace_block_by_hash(
+        &self,
+        block: B256ED,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<Vec<TraceResult>>>;
+
     /// Returns the block trace string for the given block number
     #[method(name = "debug_getBlockTraceString")]
-    async fn debug_get_block_trace_string(&self, block: String) -> RpcResult<Option<String>>;
+    async fn debug_get_block_trace_string(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>>;
 
     /// Returns the trace hash for the given block number
     #[method(name = "debug_getBlockTraceHash")]
-    async fn debug_get_block_trace_hash(&self, block: String) -> RpcResult<Option<String>>;
+    async fn debug_get_block_trace_hash(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>>;
+
+    /// Returns the consensus hash for the given block number, a rolling hash of the trace hashes and
+    /// state roots of all blocks up to it
+    #[method(name = "brc20_getConsensusHash")]
+    async fn brc20_get_consensus_hash(&self, block: BlockNumberOrHash)
+        -> RpcResult<Option<B256ED>>;
+
+    /// Returns the first block whose consensus hash differs from the given consensus hashes of
+    /// consecutive blocks starting at first_block, None if all of them match
+    #[method(name = "brc20_findDivergentBlock")]
+    async fn brc20_find_divergent_block(
+        &self,
+        first_block: BlockNumberOrHash,
+        reference_hashes: Vec<B256ED>,
+    ) -> RpcResult<Option<U64ED>>;
 
     /// Returns the transaction by hash
     #[method(name = "eth_getTransactionByHash")]
@@ -317,7 +491,11 @@ pub trait Brc20ProgApi {
 
     /// Returns the balance of the account at the given address (0 in BRC20)
     #[method(name = "eth_getBalance")]
-    async fn eth_get_balance(&self, _address: AddressED, _block: String) -> RpcResult<String> {
+    async fn eth_get_balance(
+        &self,
+        _address: AddressED,
+        _block: BlockNumberOrHash,
+    ) -> RpcResult<String> {
         Ok("0x0".to_string())
     }
 
@@ -413,17 +591,89 @@ pub trait Brc20ProgApi {
 
     /// Returns the raw header for the given block hash or number
     #[method(name = "debug_getRawHeader")]
-    async fn debug_get_raw_header(&self, block_hash_or_number: String)
-        -> RpcResult<Option<String>>;
+    async fn debug_get_raw_header(
+        &self,
+        block_hash_or_number: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>>;
 
     /// Returns the raw block for the given block hash or number
     #[method(name = "debug_getRawBlock")]
-    async fn debug_get_raw_block(&self, block_hash_or_number: String) -> RpcResult<Option<String>>;
+    async fn debug_get_raw_block(
+        &self,
+        block_hash_or_number: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>>;
 
     /// Returns the raw receipts for the given block hash or number
     #[method(name = "debug_getRawReceipts")]
     async fn debug_get_raw_receipts(
         &self,
-        block_hash_or_number: String,
+        block_hash_or_number: BlockNumberOrHash,
     ) -> RpcResult<Option<Vec<String>>>;
 }
+
+/// Calls with state and block overrides are in a separate trait, so `Brc20ProgApi` clients keep the
+/// `eth_call`, `eth_callMany` and `eth_estimateGas` signatures without overrides
+#[rpc(server, client)]
+pub trait Brc20ProgOverridesApi {
+    /// Calls a contract with the given parameters, state and block overrides are applied before the call
+    #[method(name = "eth_call")]
+    async fn eth_call_with_overrides(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<String>;
+
+    /// Calls a contract with the given parameters for multiple calls, state and block overrides are applied before the calls
+    #[method(name = "eth_callMany")]
+    async fn eth_call_many_with_overrides(
+        &self,
+        eth_calls: Vec<EthCall>,
+        block: Option<BlockNumberOrHash>,
+        precompile_data: Option<PrecompileData>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<String>>;
+
+    /// Estimates the gas for the given transaction, state and block overrides are applied before the call
+    #[method(name = "eth_estimateGas")]
+    async fn eth_estimate_gas_with_overrides(
+        &self,
+        eth_call: EthCall,
+        block: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<String>;
+}
+
+/// Tracing methods with tracer options are in a separate trait, so `Brc20ProgApi` clients keep
+/// the `debug_traceTransaction` signature without options
+#[rpc(server, client)]
+pub trait Brc20ProgTracingApi {
+    /// Returns the trace for the given transaction hash, in `callTracer` format if no options are given
+    #[method(name = "debug_traceTransaction")]
+    async fn debug_trace_transaction_with_options(
+        &self,
+        transaction: B256ED,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<TraceResult>>;
+}
+
+/// Subscriptions are in a separate trait, as they are only available over WebSocket
+#[rpc(server, client)]
+pub trait Brc20ProgSubscriptionApi {
+    /// Subscribes to new blocks with "newHeads", or to logs matching the filter with "logs"
+    ///
+    /// Logs removed by a reorg are sent again with removed set to true
+    #[subscription(
+        name = "eth_subscribe" => "eth_subscription",
+        unsubscribe = "eth_unsubscribe",
+        item = serde_json::Value
+    )]
+    async fn eth_subscribe(
+        &self,
+        kind: String,
+        filter: Option<GetLogsFilter>,
+    ) -> SubscriptionResult;
+}
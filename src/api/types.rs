@@ -1,15 +1,24 @@
 use std::collections::HashMap;
 use std::error::Error;
 
+use alloy::eips::eip2718::Encodable2718;
 use alloy::primitives::hex::FromHex;
 use alloy::primitives::{Bytes, B256};
+use alloy::trie::proof::{verify_proof, ProofRetainer};
+use alloy::trie::root::adjust_index_for_rlp;
+use alloy::trie::{HashBuilder, Nibbles};
+use alloy_rpc_types_eth::state::StateOverride;
+use alloy_rpc_types_eth::BlockOverrides;
+use alloy_rpc_types_trace::geth::{DefaultFrame, FourByteFrame, PreStateFrame};
 use base64::prelude::BASE64_STANDARD_NO_PAD;
 use base64::Engine;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_either::SingleOrVec;
 
 use crate::global::CALLDATA_LIMIT;
-use crate::types::{AddressED, B256ED};
+use crate::types::{
+    AddressED, BytesED, LogED, TraceED, TxReceiptED, B256ED, U128ED, U256ED, U64ED, U8ED,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Represents a call to a contract with optional parameters for from, to, data, and input.
@@ -21,6 +30,15 @@ pub struct EthCall {
     /// The data to send with the call
     #[serde(alias = "input", alias = "data")]
     pub data: Option<RawBytes>,
+    /// The gas limit for the call, capped at the configured call gas limit
+    pub gas: Option<U64ED>,
+    /// The gas price for the call
+    #[serde(rename = "gasPrice")]
+    pub gas_price: Option<U128ED>,
+    /// The value to send with the call
+    pub value: Option<U256ED>,
+    /// The nonce for the call, it's not checked against the nonce of the sender
+    pub nonce: Option<U64ED>,
 }
 
 impl EthCall {
@@ -30,10 +48,52 @@ impl EthCall {
             from,
             to,
             data: Some(data),
+            gas: None,
+            gas_price: None,
+            value: None,
+            nonce: None,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Represents a block parameter, either as a string or as an object described in
+/// [EIP-1898](https://eips.ethereum.org/EIPS/eip-1898).
+///
+/// Strings can be a block number, a block tag such as `latest`, or a block hash.
+pub enum BlockNumberOrHash {
+    /// Block number, tag or hash as a string
+    String(String),
+    /// Block hash object
+    Hash {
+        /// The hash of the block
+        #[serde(rename = "blockHash")]
+        block_hash: B256ED,
+        /// Whether the block should be in the canonical chain
+        #[serde(rename = "requireCanonical", default)]
+        require_canonical: bool,
+    },
+    /// Block number object
+    Number {
+        /// The number or tag of the block
+        #[serde(rename = "blockNumber")]
+        block_number: String,
+    },
+}
+
+impl From<String> for BlockNumberOrHash {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for BlockNumberOrHash {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Represents the data returned from a precompile execution.
 pub struct PrecompileData {
@@ -79,6 +139,301 @@ impl GetLogsFilter {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Represents the changes returned by eth_getFilterChanges since the last poll.
+///
+/// Empty changes are serialized as an empty list, and are deserialized as `Hashes`.
+pub enum FilterChanges {
+    /// Hashes of new blocks, returned for block filters
+    Hashes(Vec<B256ED>),
+    /// New logs, and logs removed by a reorg, returned for log filters
+    Logs(Vec<LogED>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Represents the result of debug tracing methods, depending on the tracer in the options.
+///
+/// Refer to [Geth built-in tracers](https://geth.ethereum.org/docs/developers/evm-tracing/built-in-tracers) for more details.
+///
+/// Empty prestate and 4byte results are serialized as an empty object, and are deserialized as `PreState`.
+pub enum TraceResult {
+    /// `callTracer` result, returned when no tracer options are given
+    Call(Box<TraceED>),
+    /// Struct logger result, returned when tracer options are given without a tracer
+    StructLog(DefaultFrame),
+    /// `prestateTracer` result, either the prestate or the diff if `diffMode` is set
+    PreState(PreStateFrame),
+    /// `4byteTracer` result, number of calls for each selector and calldata size
+    FourByte(FourByteFrame),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the blocks simulated by eth_simulateV1.
+pub struct SimulatePayload {
+    /// The blocks to simulate, in order, each block is simulated on top of the previous one
+    #[serde(rename = "blockStateCalls")]
+    pub block_state_calls: Vec<SimulateBlock>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Represents a block simulated by eth_simulateV1, overrides are applied before the calls.
+///
+/// Block number and timestamp are increased by one for each block if they are not overridden.
+pub struct SimulateBlock {
+    /// The block overrides, such as number and timestamp
+    #[serde(rename = "blockOverrides")]
+    pub block_overrides: Option<BlockOverrides>,
+    /// The state overrides, kept for the following blocks
+    #[serde(rename = "stateOverrides")]
+    pub state_overrides: Option<StateOverride>,
+    /// The calls to execute in the block, in order
+    #[serde(default)]
+    pub calls: Vec<EthCall>,
+    /// The precompile data for the calls in the block
+    #[serde(rename = "precompileData")]
+    pub precompile_data: Option<PrecompileData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the result of a block simulated by eth_simulateV1.
+pub struct SimulatedBlock {
+    /// The block number
+    pub number: U64ED,
+    /// The block hash, generated from the block number
+    pub hash: B256ED,
+    /// The block timestamp
+    pub timestamp: U64ED,
+    /// The total gas used by the calls in the block
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U64ED,
+    /// The results of the calls in the block, in order
+    pub calls: Vec<SimulatedCall>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the result of a call simulated by eth_simulateV1.
+pub struct SimulatedCall {
+    /// The data returned by the call, or the revert data if the call reverted
+    #[serde(rename = "returnData")]
+    pub return_data: BytesED,
+    /// The logs emitted by the call, simulated calls have an empty transaction hash
+    pub logs: Vec<LogED>,
+    /// The gas used by the call
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U64ED,
+    /// The status of the call, 1 for success and 0 for failure
+    pub status: U8ED,
+    /// The error, if the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<SimulatedCallError>,
+    /// The trace of the call, if tracer options are given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Represents the error of a failed call simulated by eth_simulateV1.
+pub struct SimulatedCallError {
+    /// The error code, 3 for reverted calls and -32015 for other failures, same as Geth
+    pub code: i32,
+    /// The error message
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the gas and inscription size estimated by brc20_estimateInscription.
+pub struct InscriptionEstimate {
+    /// The gas required by the transaction
+    pub gas: U64ED,
+    /// The minimum inscription byte length that provides the required gas
+    #[serde(rename = "inscriptionByteLen")]
+    pub inscription_byte_len: U64ED,
+    /// The compression method that yields the smallest data, 0x00 for uncompressed, 0x01 for nada
+    /// and 0x02 for zstd
+    pub compression: U8ED,
+    /// The base64 encoded data with the selected compression, to be used in the inscription
+    #[serde(rename = "base64Data")]
+    pub base64_data: Base64Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Represents a Merkle proof of a transaction receipt against the receipts root of its block,
+/// returned by brc20_getReceiptProof.
+///
+/// Receipts are keyed by the index of their transaction in the receipts trie, same as Ethereum, so
+/// the proof also shows the position of the transaction in the block, and the logs of the receipt
+/// are proven along with it.
+pub struct ReceiptProof {
+    /// The proven receipt
+    pub receipt: TxReceiptED,
+    /// The receipts root of the block containing the receipt
+    #[serde(rename = "receiptsRoot")]
+    pub receipts_root: B256ED,
+    /// The RLP encoded trie nodes on the path to the receipt, starting with the root
+    pub proof: Vec<BytesED>,
+}
+
+impl ReceiptProof {
+    /// Create a proof of a receipt from all the receipts of its block
+    ///
+    /// receipts: &[TxReceiptED] - receipts of the block, in transaction order
+    /// index: usize - the index of the receipt to prove
+    pub(crate) fn new(receipts: &[TxReceiptED], index: usize) -> Result<Self, Box<dyn Error>> {
+        let receipt = receipts
+            .get(index)
+            .ok_or_else(|| format!("Receipt index {} is out of bounds", index))?
+            .clone();
+        let target = receipt_trie_key(index);
+        let mut hash_builder =
+            HashBuilder::default().with_proof_retainer(ProofRetainer::new(vec![target]));
+        // Leaves are added in the order of their keys, as in Ethereum's ordered tries
+        for i in 0..receipts.len() {
+            let index = adjust_index_for_rlp(i, receipts.len());
+            hash_builder.add_leaf(
+                receipt_trie_key(index),
+                &receipts[index].receipt_envelope().encoded_2718(),
+            );
+        }
+        let receipts_root = hash_builder.root();
+        let proof = hash_builder
+            .take_proof_nodes()
+            .matching_nodes_sorted(&target)
+            .into_iter()
+            .map(|(_, node)| node.into())
+            .collect();
+        Ok(Self {
+            receipt,
+            receipts_root: receipts_root.into(),
+            proof,
+        })
+    }
+
+    /// Verify that the receipt is in the receipts trie with the given root, at the index of its
+    /// transaction
+    ///
+    /// The receipts root should be taken from a trusted block header rather than from the proof
+    ///
+    /// receipts_root: B256 - the receipts root of the block containing the receipt
+    pub fn verify(&self, receipts_root: B256) -> Result<(), Box<dyn Error>> {
+        let index: u64 = self.receipt.transaction_index.into();
+        verify_proof(
+            receipts_root,
+            receipt_trie_key(index as usize),
+            Some(self.receipt.receipt_envelope().encoded_2718()),
+            self.proof.iter().map(|node| &node.bytes),
+        )
+        .map_err(|error| format!("Invalid receipt proof: {}", error).into())
+    }
+}
+
+/// Key of a receipt in the receipts trie of its block, the RLP encoded index of its transaction
+fn receipt_trie_key(index: usize) -> Nibbles {
+    Nibbles::unpack(alloy_rlp::encode_fixed_size(&index))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Represents an operation in a block indexed by brc20_addBlock.
+///
+/// Each variant takes the same parameters as the method with the same name, except the block
+/// timestamp, hash and transaction index, which are set by brc20_addBlock.
+pub enum BlockOperation {
+    /// A contract deployment, see brc20_deploy
+    Deploy {
+        /// The pkscript of the sender
+        #[serde(rename = "fromPkscript")]
+        from_pkscript: String,
+        /// The data of the inscription
+        data: Option<RawBytes>,
+        /// The base64 encoded data of the inscription, used if data is not given
+        #[serde(rename = "base64Data")]
+        base64_data: Option<Base64Bytes>,
+        /// The inscription ID
+        #[serde(rename = "inscriptionId")]
+        inscription_id: String,
+        /// The inscription byte length, it determines the gas limit
+        #[serde(rename = "inscriptionByteLen")]
+        inscription_byte_len: u64,
+        /// The transaction ID of the OP_RETURN transaction
+        #[serde(rename = "opReturnTxId")]
+        op_return_tx_id: B256ED,
+    },
+    /// A contract call, see brc20_call
+    ///
+    /// Contracts deployed earlier in the same block can be called by their inscription ID
+    Call {
+        /// The pkscript of the sender
+        #[serde(rename = "fromPkscript")]
+        from_pkscript: String,
+        /// The address of the contract to call
+        #[serde(rename = "contractAddress")]
+        contract_address: Option<AddressED>,
+        /// The inscription ID of the contract to call, used instead of the address if given
+        #[serde(rename = "contractInscriptionId")]
+        contract_inscription_id: Option<String>,
+        /// The data of the inscription
+        data: Option<RawBytes>,
+        /// The base64 encoded data of the inscription, used if data is not given
+        #[serde(rename = "base64Data")]
+        base64_data: Option<Base64Bytes>,
+        /// The inscription ID
+        #[serde(rename = "inscriptionId")]
+        inscription_id: String,
+        /// The inscription byte length, it determines the gas limit
+        #[serde(rename = "inscriptionByteLen")]
+        inscription_byte_len: u64,
+        /// The transaction ID of the OP_RETURN transaction
+        #[serde(rename = "opReturnTxId")]
+        op_return_tx_id: B256ED,
+    },
+    /// A signed transaction, see brc20_transact
+    Transact {
+        /// The signed transaction data
+        #[serde(rename = "rawTxData")]
+        raw_tx_data: Option<RawBytes>,
+        /// The base64 encoded signed transaction data, used if raw tx data is not given
+        #[serde(rename = "base64RawTxData")]
+        base64_raw_tx_data: Option<Base64Bytes>,
+        /// The inscription ID
+        #[serde(rename = "inscriptionId")]
+        inscription_id: String,
+        /// The inscription byte length, it determines the gas limit
+        #[serde(rename = "inscriptionByteLen")]
+        inscription_byte_len: u64,
+        /// The transaction ID of the OP_RETURN transaction
+        #[serde(rename = "opReturnTxId")]
+        op_return_tx_id: B256ED,
+    },
+    /// A BRC20 deposit, see brc20_deposit
+    Deposit {
+        /// The pkscript of the receiver
+        #[serde(rename = "toPkscript")]
+        to_pkscript: String,
+        /// The BRC20 ticker
+        ticker: String,
+        /// The amount of tokens
+        amount: U256ED,
+        /// The inscription ID
+        #[serde(rename = "inscriptionId")]
+        inscription_id: String,
+    },
+    /// A BRC20 withdrawal, see brc20_withdraw
+    Withdraw {
+        /// The pkscript of the sender
+        #[serde(rename = "fromPkscript")]
+        from_pkscript: String,
+        /// The BRC20 ticker
+        ticker: String,
+        /// The amount of tokens
+        amount: U256ED,
+        /// The inscription ID
+        #[serde(rename = "inscriptionId")]
+        inscription_id: String,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// A wrapper for base64 encoded bytes that can be serialized and deserialized.
 /// This struct is used to handle the encoding and decoding of bytes in the BRC20 protocol.
@@ -131,6 +486,20 @@ impl Base64Bytes {
         Self(None)
     }
 
+    /// Returns the compression method of the encoded bytes, which is the first decoded byte.
+    ///
+    /// 0x00 = uncompressed, 0x01 = nada, 0x02 = zstd
+    pub fn compression(&self) -> Option<u8> {
+        let inner = self.0.as_ref()?;
+        let inner = inner
+            .split_once('=')
+            .map_or(inner.as_str(), |(base64, _)| base64);
+        BASE64_STANDARD_NO_PAD
+            .decode(inner)
+            .ok()
+            .and_then(|decoded| decoded.first().copied())
+    }
+
     // This is used by the server, so doesn't need to be public
     #[cfg(feature = "server")]
     pub(crate) fn value(&self) -> Option<Bytes> {
@@ -323,6 +692,110 @@ mod tests {
         function transfer(address receiver, bytes ticker, uint256 amount);
     }
 
+    #[test]
+    fn test_block_number_or_hash_deserialize() {
+        assert_eq!(
+            serde_json::from_str::<BlockNumberOrHash>("\"latest\"").unwrap(),
+            BlockNumberOrHash::from("latest")
+        );
+        assert_eq!(
+            serde_json::from_str::<BlockNumberOrHash>(
+                r#"{"blockHash": "0x0101010101010101010101010101010101010101010101010101010101010101"}"#
+            )
+            .unwrap(),
+            BlockNumberOrHash::Hash {
+                block_hash: B256::repeat_byte(1).into(),
+                require_canonical: false,
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<BlockNumberOrHash>(
+                r#"{"blockHash": "0x0101010101010101010101010101010101010101010101010101010101010101", "requireCanonical": true}"#
+            )
+            .unwrap(),
+            BlockNumberOrHash::Hash {
+                block_hash: B256::repeat_byte(1).into(),
+                require_canonical: true,
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<BlockNumberOrHash>(r#"{"blockNumber": "0x1"}"#).unwrap(),
+            BlockNumberOrHash::Number {
+                block_number: "0x1".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_eth_call_deserialize() {
+        let call: EthCall = serde_json::from_str(
+            r#"{"to": "0x0101010101010101010101010101010101010101", "input": "0x", "gas": "0x5208", "gasPrice": "0x1", "value": "0x2", "nonce": "0x3"}"#,
+        )
+        .unwrap();
+        assert_eq!(call.from, None);
+        assert_eq!(call.gas, Some(21000u64.into()));
+        assert_eq!(call.gas_price, Some(1u64.into()));
+        assert_eq!(call.value, Some(2u64.into()));
+        assert_eq!(call.nonce, Some(3u64.into()));
+    }
+
+    #[test]
+    fn test_simulate_payload_deserialize() {
+        let payload: SimulatePayload = serde_json::from_str(
+            r#"{"blockStateCalls": [{"blockOverrides": {"number": "0x3e8"}, "calls": [{"to": "0x0101010101010101010101010101010101010101", "data": "0x"}]}, {}]}"#,
+        )
+        .unwrap();
+        assert_eq!(payload.block_state_calls.len(), 2);
+        assert_eq!(
+            payload.block_state_calls[0]
+                .block_overrides
+                .as_ref()
+                .unwrap()
+                .number,
+            Some(alloy::primitives::U256::from(1000))
+        );
+        assert_eq!(payload.block_state_calls[0].calls.len(), 1);
+        assert!(payload.block_state_calls[1].calls.is_empty());
+        assert!(payload.block_state_calls[1].state_overrides.is_none());
+    }
+
+    #[test]
+    fn test_block_operations_deserialize() {
+        let operations: Vec<BlockOperation> = serde_json::from_str(
+            r#"[
+                {"type": "deposit", "toPkscript": "0x01", "ticker": "test", "amount": "0x64", "inscriptionId": "deposit"},
+                {"type": "call", "fromPkscript": "0x01", "contractInscriptionId": "deploy", "base64Data": "AN6tvu8", "inscriptionId": "call", "inscriptionByteLen": 1000, "opReturnTxId": "0x0000000000000000000000000000000000000000000000000000000000000000"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(operations.len(), 2);
+        let BlockOperation::Deposit { amount, .. } = &operations[0] else {
+            panic!("Expected a deposit");
+        };
+        assert_eq!(amount.uint, U256::from(100));
+        let BlockOperation::Call {
+            contract_address,
+            contract_inscription_id,
+            data,
+            base64_data,
+            inscription_byte_len,
+            ..
+        } = &operations[1]
+        else {
+            panic!("Expected a call");
+        };
+        assert!(contract_address.is_none());
+        assert_eq!(contract_inscription_id.as_deref(), Some("deploy"));
+        assert!(data.is_none());
+        assert_eq!(
+            base64_data.as_ref().unwrap().value().unwrap(),
+            Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(*inscription_byte_len, 1000);
+
+        assert!(serde_json::from_str::<BlockOperation>(r#"{"type": "mint"}"#).is_err());
+    }
+
     #[test]
     fn test_calldata_base64_roundtrip() {
         let address: &str = "0xdead09C7d1621C9D49EdD5c070933b500ac5beef";
@@ -344,6 +817,30 @@ mod tests {
         assert_eq!(decoded, data);
     }
 
+    #[test]
+    fn test_base64_bytes_compression() {
+        // Short data with escaped bytes is not compressed
+        let uncompressed = Base64Bytes::from_bytes(Bytes::from(vec![0xde, 0xff])).unwrap();
+        assert_eq!(uncompressed.compression(), Some(0x00));
+
+        // Zero bytes are removed by nada
+        let mut data = vec![0u8; 64];
+        data.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let nada = Base64Bytes::from_bytes(Bytes::from(data)).unwrap();
+        assert_eq!(nada.compression(), Some(0x01));
+
+        // Repeated data is better for zstd
+        let zstd = Base64Bytes::from_bytes(Bytes::from(vec![0xde, 0xad, 0xbe, 0xef].repeat(1024)))
+            .unwrap();
+        assert_eq!(zstd.compression(), Some(0x02));
+
+        assert_eq!(Base64Bytes::empty().compression(), None);
+        assert_eq!(
+            Base64Bytes::new("AA==".to_string()).compression(),
+            Some(0x00)
+        );
+    }
+
     #[test]
     fn test_decode_bytes_from_base64_data_uncompressed() {
         // 0x00 to indicate uncompressed
@@ -453,4 +950,70 @@ mod tests {
         let result = decode_bytes_from_inscription_data(&invalid_base64.to_string());
         assert_eq!(result, None);
     }
+
+    fn receipt(index: u64) -> TxReceiptED {
+        TxReceiptED::new(
+            [1u8; 32].into(),
+            2u64.into(),
+            None,
+            [3u8; 20].into(),
+            Some([4u8; 20].into()),
+            B256::from(U256::from(index)).into(),
+            index.into(),
+            index % 3 != 0,
+            &vec![alloy::primitives::Log {
+                address: [5u8; 20].into(),
+                data: alloy::primitives::LogData::new_unchecked(
+                    vec![B256::from(U256::from(index))],
+                    vec![6u8; 32].into(),
+                ),
+            }],
+            21000,
+            (21000 * (index + 1)).into(),
+            index.into(),
+            ((index % 3) as u8).into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_receipt_proof() {
+        // Indexes around 0x7f and 0x80 are encoded differently in the trie keys
+        for count in [1u64, 2, 130] {
+            let receipts = (0..count).map(receipt).collect::<Vec<_>>();
+            let receipts_root = alloy::consensus::proofs::calculate_receipt_root(
+                &receipts
+                    .iter()
+                    .map(TxReceiptED::receipt_envelope)
+                    .collect::<Vec<_>>(),
+            );
+            for index in [
+                0,
+                count / 2,
+                count - 1,
+                127.min(count - 1),
+                128.min(count - 1),
+            ] {
+                let proof = ReceiptProof::new(&receipts, index as usize).unwrap();
+                assert_eq!(proof.receipt, receipts[index as usize]);
+                assert_eq!(proof.receipts_root.bytes, receipts_root);
+                proof.verify(receipts_root).unwrap();
+                assert!(proof.verify(B256::ZERO).is_err());
+
+                // A modified receipt doesn't verify with the same proof
+                let mut modified = proof.clone();
+                modified.receipt.logs[0].data = vec![7u8; 32].into();
+                assert!(modified.verify(receipts_root).is_err());
+            }
+        }
+        assert!(ReceiptProof::new(&[receipt(0)], 1).is_err());
+    }
+
+    #[test]
+    fn test_receipt_proof_serde() {
+        let proof = ReceiptProof::new(&[receipt(0), receipt(1)], 1).unwrap();
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["receiptsRoot"], proof.receipts_root.bytes.to_string());
+        assert_eq!(serde_json::from_value::<ReceiptProof>(json).unwrap(), proof);
+    }
 }
@@ -1,27 +1,36 @@
 use std::error::Error;
+use std::path::Path;
 use std::process::exit;
 
 use brc20_prog::Brc20ProgConfig;
 use tracing::error;
 
 pub struct Args {
-    pub log_level: tracing::Level, // passed with -l or --log-level
-    pub log_file: Option<String>,  // passed with -f or --log-file
+    pub log_level: tracing::Level,       // passed with -l or --log-level
+    pub log_file: Option<String>,        // passed with -f or --log-file
+    pub migrate: bool,                   // passed as the migrate command
+    pub export_snapshot: Option<String>, // passed as the export-snapshot command
+    pub import_snapshot: Option<String>, // passed as the import-snapshot command
 }
 
 /// Parses the command line arguments and returns an Args struct
-/// containing the log level and log file.
+/// containing the log level, log file and the command to run.
 /// -f and -l are used to set the log file and log level respectively.
+/// migrate migrates the database to the current version and exits, instead of starting the server.
+/// export-snapshot and import-snapshot export or import a snapshot directory and exit.
 fn parse_args() -> Args {
     let args = std::env::args().collect::<Vec<_>>();
-    let mut log_level = tracing::Level::WARN;
+    let mut log_level = None;
     let mut log_file = None;
+    let mut migrate = false;
+    let mut export_snapshot = None;
+    let mut import_snapshot = None;
 
     for i in 1..args.len() {
         match args[i].as_str() {
             "-l" | "--log-level" => {
                 if i + 1 < args.len() {
-                    log_level = args[i + 1].parse().unwrap_or(tracing::Level::WARN);
+                    log_level = Some(args[i + 1].parse().unwrap_or(tracing::Level::WARN));
                 }
             }
             "-f" | "--log-file" => {
@@ -30,19 +39,45 @@ fn parse_args() -> Args {
                 }
             }
             "-h" | "--help" => {
-                println!("Usage: brc20_prog [OPTIONS]");
+                println!("Usage: brc20_prog [OPTIONS] [COMMAND]");
+                println!("Commands:");
+                println!("  migrate                   Migrate the database to the current version and exit");
+                println!("  export-snapshot <dir>     Export a snapshot of the latest block");
+                println!("  import-snapshot <dir>     Import a snapshot into an empty database");
                 println!("Options:");
                 println!("  -l, --log-level <level>   Set the log level (default: WARN)");
                 println!("  -f, --log-file <file>     Set the log file");
                 println!("  -h, --help                Show this help message");
                 std::process::exit(0);
             }
+            "migrate" => {
+                migrate = true;
+            }
+            "export-snapshot" => {
+                if i + 1 < args.len() {
+                    export_snapshot = Some(args[i + 1].clone());
+                }
+            }
+            "import-snapshot" => {
+                if i + 1 < args.len() {
+                    import_snapshot = Some(args[i + 1].clone());
+                }
+            }
             _ => {}
         }
     }
+    let logs_progress = migrate || export_snapshot.is_some() || import_snapshot.is_some();
     Args {
-        log_level,
+        // Migration and snapshot progress is logged at info level, so it's shown by default
+        log_level: log_level.unwrap_or(if logs_progress {
+            tracing::Level::INFO
+        } else {
+            tracing::Level::WARN
+        }),
         log_file,
+        migrate,
+        export_snapshot,
+        import_snapshot,
     }
 }
 
@@ -64,6 +99,39 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     println!("BRC20 Prog v{}", env!("CARGO_PKG_VERSION"));
 
+    if args.migrate {
+        if let Err(err) = brc20_prog::migrate(Brc20ProgConfig::from_env()) {
+            error!("Error migrating database: {}", err);
+            exit(1);
+        }
+        println!("Database is up to date");
+        return Ok(());
+    }
+
+    if let Some(path) = args.export_snapshot {
+        match brc20_prog::export_snapshot(Brc20ProgConfig::from_env(), Path::new(&path)) {
+            Ok(block_number) => println!("Exported snapshot of block {} to {}", block_number, path),
+            Err(err) => {
+                error!("Error exporting snapshot: {}", err);
+                exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = args.import_snapshot {
+        match brc20_prog::import_snapshot(Brc20ProgConfig::from_env(), Path::new(&path)) {
+            Ok(block_number) => {
+                println!("Imported snapshot of block {} from {}", block_number, path)
+            }
+            Err(err) => {
+                error!("Error importing snapshot: {}", err);
+                exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     let server = brc20_prog::start(Brc20ProgConfig::from_env().into()).await;
     let Ok(server_handle) = server else {
         error!("Error starting server: {}", server.unwrap_err());
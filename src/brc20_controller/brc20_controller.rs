@@ -101,7 +101,10 @@ mod tests {
 
         // A 32-byte ABI word decodes to its value.
         let encoded = Bytes::from(U256::from(123_456u64).to_be_bytes::<32>().to_vec());
-        assert_eq!(decode_brc20_balance_result(Some(&encoded)), U256::from(123_456u64));
+        assert_eq!(
+            decode_brc20_balance_result(Some(&encoded)),
+            U256::from(123_456u64)
+        );
 
         // Malformed (too short) data decodes to zero rather than panicking.
         let malformed = Bytes::from(vec![1, 2, 3]);
@@ -1,32 +1,90 @@
 #![cfg(feature = "server")]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Display;
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 
-use alloy::primitives::{Address, Bloom, Bytes, FixedBytes, Log, B256, U256, U64};
+use alloy::eips::eip2930::AccessList;
+use alloy::primitives::{keccak256, Address, Bloom, Bytes, FixedBytes, Log, B256, U256, U64};
+use alloy::trie::{TrieAccount, EMPTY_ROOT_HASH};
+use alloy_rlp::Decodable;
 use revm::context::result::ExecutionResult;
 use revm::context::DBErrorMarker;
 use revm::primitives::map::DefaultHashBuilder;
 use revm::state::{Account, AccountInfo, Bytecode};
 use revm::{Database as DatabaseTrait, DatabaseCommit};
-use rs_merkle::algorithms::Sha256;
-use rs_merkle::MerkleTree;
-use serde_either::SingleOrVec;
+use rocksdb::{IteratorMode, Options, WriteBatch, DB, DEFAULT_COLUMN_FAMILY_NAME};
 
+use crate::db::brc20_prog_database_view::Brc20ProgDatabaseView;
 use crate::db::cached_database::{BlockCachedDatabase, BlockHistoryCacheData};
-use crate::db::database::BlockDatabase;
+use crate::db::database::{column_family, open_database, write_batch, BlockDatabase, TrieDatabase};
 use crate::db::types::{
-    AccountInfoED, AddressED, BlockResponseED, BytecodeED, LogED, RawBlock, Signature, TraceED,
-    TxED, TxReceiptED, B256ED, U128ED, U256ED, U512ED, U64ED,
+    receipts_root, transactions_root, AccountInfoED, AddressED, BlockResponseED, BytecodeED,
+    Decode, RawBlock, Signature, TraceED, TxED, TxReceiptED, B256ED, U128ED, U256ED, U512ED, U64ED,
 };
 use crate::global::database::ConfigDatabase;
-use crate::global::{MAX_FUTURE_TRANSACTION_BLOCKS, MAX_REORG_HISTORY_SIZE};
+use crate::global::{ARCHIVE_MODE_KEY, MAX_FUTURE_TRANSACTION_BLOCKS, MAX_REORG_HISTORY_SIZE};
 
 static DB_MUTEX_ERROR: &str = "Database mutex error";
 
-static MAX_BLOCK_NUMBER_KEY: &str = "max_block_number";
+pub(super) static MAX_BLOCK_NUMBER_KEY: &str = "max_block_number";
+
+/// Set when the database is imported from a snapshot, blocks before it can't be reorged as their history isn't kept
+pub(super) static SNAPSHOT_BLOCK_NUMBER_KEY: &str = "snapshot_block_number";
+
+/// Set when stores are migrated from separate databases, until partially committed blocks are reverted
+static PARTIAL_COMMIT_CHECK_KEY: &str = "partial_commit_check";
+
+/// Name of the database all stores are kept in, each store uses column families named after it
+pub(super) static DATABASE_NAME: &str = "brc20_prog";
+
+/// Name of the database legacy stores are migrated into, it's renamed to DATABASE_NAME once complete
+static MIGRATION_DATABASE_NAME: &str = "brc20_prog_migration";
+
+/// Number of entries copied from a legacy store in a single batch
+static MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// Names of the BlockCachedDatabase stores
+static CACHED_DATABASE_NAMES: [&str; 16] = [
+    "account_memory",
+    "code",
+    "account",
+    "number_and_index_to_tx_hash",
+    "tx_receipt",
+    "log_address_index",
+    "log_topic_index",
+    "inscription_id_to_tx_hash",
+    "contract_address_to_inscription_id",
+    "tx",
+    "account_and_nonce_to_tx_hash",
+    "pending_tx_hash_to_tx_id",
+    "tx_hash_to_tx_id",
+    "tx_hash_to_timestamp",
+    "tx_trace",
+    "block_hash_to_number",
+];
+
+/// Names of the BlockDatabase stores
+pub(super) static BLOCK_DATABASE_NAMES: [&str; 4] = [
+    "block_number_to_block",
+    "block_number_to_raw_block",
+    "block_number_to_hash",
+    "block_number_to_consensus_hash",
+];
+
+/// Name of the store keeping the nodes of the state trie and the storage tries of accounts
+static TRIE_NODES_NAME: &str = "trie_nodes";
+
+/// Name of the global values store
+pub(super) static GLOBAL_VALUES_NAME: &str = "global";
+
+/// Name of the store keeping the configuration values, see validate_config_database
+///
+/// Older versions kept it in a separate database with the same name, relative to the base path
+pub(super) static CONFIG_NAME: &str = "config";
 
 pub struct Brc20ProgDatabase {
     /// Account address to memory location
@@ -48,6 +106,16 @@ pub struct Brc20ProgDatabase {
     db_tx_receipt:
         Option<BlockCachedDatabase<B256ED, TxReceiptED, BlockHistoryCacheData<TxReceiptED>>>,
 
+    /// Log address, block number and log index to tx hash
+    /// This is used to find logs emitted by a contract without loading every receipt
+    db_log_address_index:
+        Option<BlockCachedDatabase<(AddressED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>>,
+
+    /// Log topic0, block number and log index to tx hash
+    /// This is used to find logs with a given event signature without loading every receipt
+    db_log_topic_index:
+        Option<BlockCachedDatabase<(B256ED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>>,
+
     /// Tx hash to Tx
     db_tx: Option<BlockCachedDatabase<B256ED, TxED, BlockHistoryCacheData<TxED>>>,
 
@@ -60,6 +128,15 @@ pub struct Brc20ProgDatabase {
     db_pending_txes_op_return_tx_ids:
         Option<BlockCachedDatabase<B256ED, B256ED, BlockHistoryCacheData<B256ED>>>,
 
+    /// TX IDs for transactions, a map of TxHash to Bitcoin TX ID
+    /// This is used to re-execute transactions with the same precompile inputs
+    db_tx_op_return_tx_ids:
+        Option<BlockCachedDatabase<B256ED, B256ED, BlockHistoryCacheData<B256ED>>>,
+
+    /// Timestamps for transactions, a map of TxHash to the timestamp it was executed with
+    /// This is used to re-execute transactions with the same block environment
+    db_tx_timestamps: Option<BlockCachedDatabase<B256ED, U64ED, BlockHistoryCacheData<U64ED>>>,
+
     /// TxHash to trace
     db_tx_trace: Option<BlockCachedDatabase<B256ED, TraceED, BlockHistoryCacheData<TraceED>>>,
 
@@ -84,11 +161,42 @@ pub struct Brc20ProgDatabase {
     /// Block number to block hash
     db_block_number_to_hash: Option<BlockDatabase<B256ED>>,
 
+    /// Block number to consensus hash, see set_consensus_hash
+    db_block_number_to_consensus_hash: Option<BlockDatabase<B256ED>>,
+
+    /// Trie node hash to trie node, for the state trie and the storage tries of accounts
+    db_trie_nodes: Option<TrieDatabase>,
+
     /// Global configuration values
     db_global_values: Option<ConfigDatabase>,
 
+    /// Database the stores are kept in, changes to all stores are written to it in a single batch
+    db: Option<Arc<DB>>,
+
     /// Cache for latest block number and block hash
     latest_block_number: Option<(u64, B256)>,
+
+    /// Whether every change is kept, allowing historical reads and reorgs at any depth
+    archive_mode: bool,
+
+    /// State at the start of the block being indexed, None if no block is started
+    block_start: Option<BlockStart>,
+
+    /// Accounts and storage slots changed since the state root of the last block was computed
+    state_changes: HashMap<Address, HashSet<U256>>,
+
+    /// Whether the state trie of the next block is built from all accounts instead of the changed ones
+    ///
+    /// Changes are only tracked in memory, so this is set if changes to the next block were committed
+    /// before a restart
+    rebuild_state_trie: bool,
+}
+
+/// State of a Brc20ProgDatabase at the start of a block, see Brc20ProgDatabase::begin_block
+struct BlockStart {
+    latest_block_number: Option<(u64, B256)>,
+    max_block_number: Option<String>,
+    state_changes: HashMap<Address, HashSet<U256>>,
 }
 
 impl Default for Brc20ProgDatabase {
@@ -99,68 +207,354 @@ impl Default for Brc20ProgDatabase {
             db_account: None,
             db_number_and_index_to_tx_hash: None,
             db_tx_receipt: None,
+            db_log_address_index: None,
+            db_log_topic_index: None,
             db_tx: None,
             db_pending_txes: None,
             db_pending_txes_op_return_tx_ids: None,
+            db_tx_op_return_tx_ids: None,
+            db_tx_timestamps: None,
             db_tx_trace: None,
             db_inscription_id_to_tx_hash: None,
             db_contract_address_to_inscription_id: None,
             db_block_number_to_block: None,
             db_block_number_to_raw_block: None,
             db_block_number_to_hash: None,
+            db_block_number_to_consensus_hash: None,
             db_block_hash_to_number: None,
+            db_trie_nodes: None,
             db_global_values: None,
+            db: None,
             latest_block_number: None,
+            archive_mode: false,
+            block_start: None,
+            state_changes: HashMap::new(),
+            rebuild_state_trie: false,
         }
     }
 }
 
 impl Brc20ProgDatabase {
+    /// Open the database at base_path, creating it if it doesn't exist
+    ///
+    /// Stores kept in separate databases by older versions are migrated into a single database first
     pub fn new(base_path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::with_archive_mode(base_path, false)
+    }
+
+    /// Open the database at base_path like Brc20ProgDatabase::new, keeping every state change if archive_mode
+    /// is set, see Brc20ProgConfig::archive_mode
+    pub fn with_archive_mode(base_path: &Path, archive_mode: bool) -> Result<Self, Box<dyn Error>> {
         rlimit::Resource::NOFILE.set(4096, 8192)?;
 
+        let mut database = Self::from_database(
+            Self::open_raw_database(base_path, archive_mode)?,
+            archive_mode,
+        )?;
+        if database
+            .db_global_values
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .get(PARTIAL_COMMIT_CHECK_KEY.to_string())?
+            .is_some_and(|value| value == true.to_string())
+        {
+            database.repair_partial_commit()?;
+        }
+        database.rebuild_state_trie =
+            database.get_block_tx_count(database.get_next_block_height()?)? > 0;
+        Ok(database)
+    }
+
+    /// Open the database the stores are kept in, without opening the stores
+    ///
+    /// Stores kept in separate databases by older versions are migrated into a single database first, this is
+    /// used by version migrations, which read and write column families of the stores directly
+    pub(super) fn open_raw_database(
+        base_path: &Path,
+        archive_mode: bool,
+    ) -> Result<Arc<DB>, Box<dyn Error>> {
+        let path = base_path.join(DATABASE_NAME);
+        if !path.exists() && base_path.join("block_number_to_hash").exists() {
+            Self::migrate_legacy_databases(base_path, archive_mode)?;
+        }
+
+        open_database(&path, &Self::column_families(archive_mode))
+    }
+
+    /// Open the configuration values of the database at base_path, creating the database if it doesn't exist
+    ///
+    /// Configuration values kept in a separate database by older versions are moved into the database first.
+    /// The database should be closed before it's opened again, e.g. by Brc20ProgDatabase::new
+    pub(crate) fn open_config_database(base_path: &Path) -> Result<ConfigDatabase, Box<dyn Error>> {
+        let legacy_path = base_path.join(CONFIG_NAME);
+        if legacy_path.exists() {
+            Self::migrate_legacy_config_database(base_path, &legacy_path)?;
+        }
+
+        let db = open_database(&base_path.join(DATABASE_NAME), &[CONFIG_NAME.to_string()])?;
+        ConfigDatabase::from_column_family(db, CONFIG_NAME)
+    }
+
+    /// Move the configuration values kept in a separate database by older versions into the database
+    ///
+    /// Legacy stores are migrated first, as that migration only runs if the database doesn't exist yet. The
+    /// separate database is removed after the values are written, so they are copied again if that's interrupted
+    fn migrate_legacy_config_database(
+        base_path: &Path,
+        legacy_path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let archive_mode = ConfigDatabase::new(base_path, CONFIG_NAME)?
+            .get(ARCHIVE_MODE_KEY.clone())?
+            .is_some_and(|value| value == true.to_string());
+
+        let db = Self::open_raw_database(base_path, archive_mode)?;
+        let entry_count = copy_legacy_database(&db, legacy_path, CONFIG_NAME)?;
+        drop(db);
+
+        std::fs::remove_dir_all(legacy_path)?;
+        tracing::info!("Migrated {} entries from {}", entry_count, CONFIG_NAME);
+        Ok(())
+    }
+
+    fn from_database(db: Arc<DB>, archive_mode: bool) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
-            db_account_memory: Some(BlockCachedDatabase::new(&base_path, "account_memory")?),
-            db_code: Some(BlockCachedDatabase::new(&base_path, "code")?),
-            db_account: Some(BlockCachedDatabase::new(&base_path, "account")?),
+            db_account_memory: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "account_memory",
+                archive_mode,
+            )?),
+            db_code: Some(BlockCachedDatabase::new(db.clone(), "code", archive_mode)?),
+            db_account: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "account",
+                archive_mode,
+            )?),
             db_number_and_index_to_tx_hash: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "number_and_index_to_tx_hash",
+                archive_mode,
+            )?),
+            db_tx_receipt: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "tx_receipt",
+                archive_mode,
+            )?),
+            db_log_address_index: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "log_address_index",
+                archive_mode,
+            )?),
+            db_log_topic_index: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "log_topic_index",
+                archive_mode,
             )?),
-            db_tx_receipt: Some(BlockCachedDatabase::new(&base_path, "tx_receipt")?),
             db_inscription_id_to_tx_hash: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "inscription_id_to_tx_hash",
+                archive_mode,
             )?),
             db_contract_address_to_inscription_id: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "contract_address_to_inscription_id",
+                archive_mode,
             )?),
-            db_tx: Some(BlockCachedDatabase::new(&base_path, "tx")?),
+            db_tx: Some(BlockCachedDatabase::new(db.clone(), "tx", archive_mode)?),
             db_pending_txes: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "account_and_nonce_to_tx_hash",
+                archive_mode,
             )?),
             db_pending_txes_op_return_tx_ids: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "pending_tx_hash_to_tx_id",
+                archive_mode,
+            )?),
+            db_tx_op_return_tx_ids: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "tx_hash_to_tx_id",
+                archive_mode,
+            )?),
+            db_tx_timestamps: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "tx_hash_to_timestamp",
+                archive_mode,
+            )?),
+            db_tx_trace: Some(BlockCachedDatabase::new(
+                db.clone(),
+                "tx_trace",
+                archive_mode,
             )?),
-            db_tx_trace: Some(BlockCachedDatabase::new(&base_path, "tx_trace")?),
             db_block_hash_to_number: Some(BlockCachedDatabase::new(
-                &base_path,
+                db.clone(),
                 "block_hash_to_number",
+                archive_mode,
             )?),
             db_block_number_to_block: Some(BlockDatabase::new(
-                &base_path,
+                db.clone(),
                 "block_number_to_block",
             )?),
             db_block_number_to_raw_block: Some(BlockDatabase::new(
-                &base_path,
+                db.clone(),
                 "block_number_to_raw_block",
             )?),
-            db_block_number_to_hash: Some(BlockDatabase::new(&base_path, "block_number_to_hash")?),
-            db_global_values: Some(ConfigDatabase::new(&base_path, "global")?),
+            db_block_number_to_hash: Some(BlockDatabase::new(db.clone(), "block_number_to_hash")?),
+            db_block_number_to_consensus_hash: Some(BlockDatabase::new(
+                db.clone(),
+                "block_number_to_consensus_hash",
+            )?),
+            db_trie_nodes: Some(TrieDatabase::new(db.clone(), TRIE_NODES_NAME)?),
+            db_global_values: Some(ConfigDatabase::from_column_family(
+                db.clone(),
+                GLOBAL_VALUES_NAME,
+            )?),
+            db: Some(db),
             latest_block_number: None,
+            archive_mode,
+            block_start: None,
+            state_changes: HashMap::new(),
+            rebuild_state_trie: false,
+        })
+    }
+
+    /// Column families used by the stores
+    fn column_families(archive_mode: bool) -> Vec<String> {
+        let mut column_families = Vec::new();
+        for name in CACHED_DATABASE_NAMES {
+            // Column families only depend on the name, so any key and value types can be used here
+            column_families.extend(BlockCachedDatabase::<
+                B256ED,
+                B256ED,
+                BlockHistoryCacheData<B256ED>,
+            >::column_families(name, archive_mode));
+        }
+        column_families.extend(BLOCK_DATABASE_NAMES.iter().map(|name| name.to_string()));
+        column_families.push(TRIE_NODES_NAME.to_string());
+        column_families.push(GLOBAL_VALUES_NAME.to_string());
+        column_families.push(CONFIG_NAME.to_string());
+        column_families
+    }
+
+    /// Names of the databases stores were kept in by older versions, relative to the base path
+    fn legacy_database_names() -> Vec<String> {
+        let mut names = Vec::new();
+        for name in CACHED_DATABASE_NAMES {
+            names.push(name.to_string());
+            names.push(format!("{}_cache", name));
+            names.push(format!("{}_archive", name));
+        }
+        names.extend(BLOCK_DATABASE_NAMES.iter().map(|name| name.to_string()));
+        names.push(GLOBAL_VALUES_NAME.to_string());
+        names
+    }
+
+    /// Migrate stores kept in separate databases by older versions into column families of a single database
+    ///
+    /// Stores are copied into a new database, which replaces them only after the copy is complete, so an
+    /// interrupted migration is started over on the next run. Legacy databases are removed once they are
+    /// replaced, if that's interrupted they are left behind and can be removed manually
+    ///
+    /// Partially committed blocks are reverted when the stores are opened, after version migrations are run,
+    /// as older versions may use encodings the stores can't read yet
+    fn migrate_legacy_databases(
+        base_path: &Path,
+        archive_mode: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let migration_path = base_path.join(MIGRATION_DATABASE_NAME);
+        if migration_path.exists() {
+            tracing::warn!("Removing the database of an interrupted migration");
+            std::fs::remove_dir_all(&migration_path)?;
+        }
+
+        tracing::info!("Migrating stores to a single database, this may take a while...");
+        let db = open_database(&migration_path, &Self::column_families(archive_mode))?;
+        for name in Self::legacy_database_names() {
+            let legacy_path = base_path.join(&name);
+            if !legacy_path.exists() {
+                continue;
+            }
+            let entry_count = copy_legacy_database(&db, &legacy_path, &name)?;
+            tracing::info!("Migrated {} entries from {}", entry_count, name);
+        }
+        let mut global_values = ConfigDatabase::from_column_family(db.clone(), GLOBAL_VALUES_NAME)?;
+        global_values.set(PARTIAL_COMMIT_CHECK_KEY.to_string(), true.to_string())?;
+        global_values.flush()?;
+        drop(global_values);
+        drop(db);
+
+        std::fs::rename(&migration_path, base_path.join(DATABASE_NAME))?;
+        for name in Self::legacy_database_names() {
+            let legacy_path = base_path.join(&name);
+            if legacy_path.exists() {
+                std::fs::remove_dir_all(&legacy_path)?;
+            }
+        }
+        tracing::info!("Migration complete");
+        Ok(())
+    }
+
+    /// Revert blocks that were only partially committed by older versions
+    ///
+    /// Older versions committed each store separately, so a crash during a commit could leave some stores
+    /// ahead of the others. Block hashes were committed first and block numbers by hash last, so every store
+    /// is complete up to the block before the first one whose hash doesn't map back to its number.
+    fn repair_partial_commit(&mut self) -> Result<(), Box<dyn Error>> {
+        // Cleared together with the changes reverting the blocks, so it's checked again if that fails
+        self.db_global_values
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .set(PARTIAL_COMMIT_CHECK_KEY.to_string(), false.to_string())?;
+
+        let db = self.db.clone().expect(DB_MUTEX_ERROR);
+        let mut last_block_number = None;
+        let mut first_incomplete_block_number = None;
+        for kv_pair in db.full_iterator_cf(
+            column_family(&db, "block_number_to_hash")?,
+            IteratorMode::Start,
+        ) {
+            let (key, value) = kv_pair?;
+            let block_number: u64 = U64ED::decode_vec(&key.to_vec())?.into();
+            let block_hash = B256ED::decode_vec(&value.to_vec())?;
+            last_block_number = Some(block_number);
+            if first_incomplete_block_number.is_none()
+                && self
+                    .get_block_number(block_hash.into())?
+                    .map(Into::<u64>::into)
+                    != Some(block_number)
+            {
+                first_incomplete_block_number = Some(block_number);
+            }
+        }
+
+        let (Some(last_block_number), Some(first_incomplete_block_number)) =
+            (last_block_number, first_incomplete_block_number)
+        else {
+            return self
+                .db_global_values
+                .as_mut()
+                .expect(DB_MUTEX_ERROR)
+                .flush();
+        };
+
+        tracing::warn!(
+            "Blocks {} to {} are partially committed, reverting them",
+            first_incomplete_block_number,
+            last_block_number
+        );
+        let latest_valid_block_number = first_incomplete_block_number
+            .checked_sub(1)
+            .ok_or("First block is partially committed, please reindex")?;
+        // Max block number was written before the commit, it's set to the last block to check the reorg depth
+        self.db_global_values.as_mut().expect(DB_MUTEX_ERROR).set(
+            MAX_BLOCK_NUMBER_KEY.to_string(),
+            last_block_number.to_string(),
+        )?;
+        self.reorg(latest_valid_block_number).map_err(|error| {
+            format!(
+                "Partially committed blocks can't be reverted, please reindex: {}",
+                error
+            )
+            .into()
         })
     }
 
@@ -182,13 +576,14 @@ impl Brc20ProgDatabase {
         match self.latest_block_number {
             Some((block_number, _)) => return Ok(block_number + 1),
             None => {
+                // Genesis block is the next block if there are no blocks yet
                 return Ok(self
                     .db_block_number_to_hash
                     .as_ref()
                     .expect(DB_MUTEX_ERROR)
                     .last_key()?
-                    .unwrap_or(0)
-                    + 1);
+                    .map(|block_number| block_number + 1)
+                    .unwrap_or(0));
             }
         }
     }
@@ -204,6 +599,18 @@ impl Brc20ProgDatabase {
             .latest(&U512ED::from_addr_u256(account, mem_loc)?)
     }
 
+    pub fn get_account_memory_at(
+        &self,
+        account: Address,
+        mem_loc: U256,
+        block_number: u64,
+    ) -> Result<Option<U256ED>, Box<dyn Error>> {
+        self.db_account_memory
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .at(&U512ED::from_addr_u256(account, mem_loc)?, block_number)
+    }
+
     pub fn set_account_memory(
         &mut self,
         account: Address,
@@ -216,6 +623,10 @@ impl Brc20ProgDatabase {
             &U512ED::from_addr_u256(account, mem_loc)?,
             value.into(),
         )?;
+        self.state_changes
+            .entry(account)
+            .or_default()
+            .insert(mem_loc);
 
         Ok(())
     }
@@ -227,6 +638,17 @@ impl Brc20ProgDatabase {
             .latest(&code_hash.into())
     }
 
+    pub fn get_code_at(
+        &self,
+        code_hash: B256,
+        block_number: u64,
+    ) -> Result<Option<BytecodeED>, Box<dyn Error>> {
+        self.db_code
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .at(&code_hash.into(), block_number)
+    }
+
     pub fn set_code(&mut self, code_hash: B256, bytecode: Bytecode) -> Result<(), Box<dyn Error>> {
         let block_number = self.get_next_block_height()?;
         self.db_code.as_mut().expect(DB_MUTEX_ERROR).set(
@@ -236,95 +658,51 @@ impl Brc20ProgDatabase {
         )
     }
 
-    fn get_number_and_index_key(block_number: u64, tx_idx: u64) -> u128 {
+    pub(super) fn get_number_and_index_key(block_number: u64, tx_idx: u64) -> u128 {
         ((block_number as u128) << 64) | tx_idx as u128
     }
 
-    pub fn get_logs(
-        &self,
-        block_number_from: Option<u64>,
-        block_number_to: Option<u64>,
-        contract_address: Option<Address>,
-        topics: Option<Vec<SingleOrVec<Option<B256>>>>,
-    ) -> Result<Vec<LogED>, Box<dyn Error>> {
-        let latest_block_number = self.get_latest_block_height()?;
-        let block_number_from = block_number_from.unwrap_or(latest_block_number);
-        let block_number_to = block_number_to.unwrap_or(block_number_from);
-
-        // Limit the number of blocks to be fetched
-        // DB is under a lock here and if this takes too long, it will block other threads
-        //
-        // TODO: This is a temporary solution, we can potentially avoid using a mutex for reads
-        // TODO: Also, test this, maybe it's not that slow?
-        if block_number_to - block_number_from > 5 {
-            return Err("Block range is too large, please limit it to 5 blocks".into());
-        }
-
-        let mut logs = Vec::new();
-
-        let tx_ids = self
-            .db_number_and_index_to_tx_hash
-            .as_ref()
-            .expect(DB_MUTEX_ERROR)
-            .get_range(
-                &Self::get_number_and_index_key(block_number_from, 0).into(),
-                &Self::get_number_and_index_key(block_number_to + 1, 0).into(),
-            )?;
-
-        for tx_pair in tx_ids {
-            let tx_id = tx_pair.1;
-            let Some(tx_receipt) = self.get_tx_receipt(tx_id.into())? else {
-                continue;
-            };
-
-            for log in tx_receipt.logs {
-                if let Some(ref address) = contract_address {
-                    if log.address.address != *address {
-                        continue;
-                    }
-                }
-
-                let mut matched = true;
-                if let Some(ref topics) = topics {
-                    for idx in 0..topics.len() {
-                        match topics[idx] {
-                            SingleOrVec::Single(ref topic) => {
-                                if let Some(ref topic) = topic {
-                                    if log.topics.len() <= idx || log.topics[idx].bytes != *topic {
-                                        matched = false;
-                                        break;
-                                    }
-                                }
-                            }
-                            SingleOrVec::Vec(ref topics) => {
-                                if log.topics.len() <= idx {
-                                    matched = false;
-                                    break;
-                                }
-                                if !topics.iter().any(|x| {
-                                    if let Some(ref topic) = x {
-                                        log.topics[idx].bytes == *topic
-                                    } else {
-                                        // We ignore the None case in OR condition, it's an invalid case
-                                        // and we don't want to match all
-                                        false
-                                    }
-                                }) {
-                                    matched = false;
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                if matched {
-                    logs.push(log);
-                }
-            }
-        }
-
-        Ok(logs)
+    /// Get a view of the database to read from without holding a reference to it
+    ///
+    /// The view is immutable, take a snapshot of it before releasing the lock this database is under,
+    /// see Brc20ProgDatabaseView::snapshot
+    pub fn view(&self) -> Result<Brc20ProgDatabaseView, Box<dyn Error>> {
+        Ok(Brc20ProgDatabaseView {
+            account_memory: self
+                .db_account_memory
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            code: self.db_code.as_ref().expect(DB_MUTEX_ERROR).view(),
+            account: self.db_account.as_ref().expect(DB_MUTEX_ERROR).view(),
+            number_and_index_to_tx_hash: self
+                .db_number_and_index_to_tx_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            tx_receipt: self.db_tx_receipt.as_ref().expect(DB_MUTEX_ERROR).view(),
+            log_address_index: self
+                .db_log_address_index
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            log_topic_index: self
+                .db_log_topic_index
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            block_number_to_block: self
+                .db_block_number_to_block
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            block_number_to_hash: self
+                .db_block_number_to_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .view(),
+            latest_block_number: self.get_latest_block_height()?,
+        })
     }
 
     pub fn get_block_tx_count(&self, block_number: u64) -> Result<u64, Box<dyn Error>> {
@@ -470,6 +848,8 @@ impl Brc20ProgDatabase {
         v: u8,
         r: U256,
         s: U256,
+        tx_type: u8,
+        access_list: Option<AccessList>,
     ) -> Result<(), Box<dyn Error>> {
         self.require_block_does_not_exist(block_hash, block_number)?;
 
@@ -486,6 +866,7 @@ impl Brc20ProgDatabase {
             output.as_ref().map(|o| o.gas_used()).unwrap_or(0).into(),
             cumulative_gas_used.into(),
             start_log_index.into(),
+            tx_type.into(),
         )?;
 
         let tx = TxED::new(
@@ -500,6 +881,8 @@ impl Brc20ProgDatabase {
             data.clone().into(),
             inscription_id.clone(),
             Signature::new(v.into(), r.into(), s.into()),
+            tx_type.into(),
+            access_list,
         );
 
         self.db_tx
@@ -518,6 +901,21 @@ impl Brc20ProgDatabase {
 
         self.set_tx_hash_by_inscription_id(inscription_id, tx_hash)?;
 
+        for log in tx_receipt.logs.iter() {
+            let log_key: U128ED =
+                Self::get_number_and_index_key(block_number, log.log_index.uint.to()).into();
+            self.db_log_address_index
+                .as_mut()
+                .expect(DB_MUTEX_ERROR)
+                .set(block_number, &(log.address, log_key), tx_hash.into())?;
+            if let Some(topic0) = log.topics.first() {
+                self.db_log_topic_index
+                    .as_mut()
+                    .expect(DB_MUTEX_ERROR)
+                    .set(block_number, &(*topic0, log_key), tx_hash.into())?;
+            }
+        }
+
         Ok(self.db_tx_receipt.as_mut().expect(DB_MUTEX_ERROR).set(
             block_number,
             &tx_hash.into(),
@@ -554,6 +952,45 @@ impl Brc20ProgDatabase {
             .latest(&tx_hash.into())
     }
 
+    pub fn set_tx_op_return_tx_id(
+        &mut self,
+        tx_hash: B256,
+        op_return_tx_id: B256,
+    ) -> Result<(), Box<dyn Error>> {
+        let block_number = self.get_next_block_height()?;
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .set(block_number, &tx_hash.into(), op_return_tx_id.into())
+    }
+
+    pub fn get_tx_op_return_tx_id(&self, tx_hash: B256) -> Result<Option<B256ED>, Box<dyn Error>> {
+        self.db_tx_op_return_tx_ids
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .latest(&tx_hash.into())
+    }
+
+    pub fn set_tx_timestamp(
+        &mut self,
+        tx_hash: B256,
+        timestamp: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let block_number = self.get_next_block_height()?;
+        self.db_tx_timestamps.as_mut().expect(DB_MUTEX_ERROR).set(
+            block_number,
+            &tx_hash.into(),
+            timestamp.into(),
+        )
+    }
+
+    pub fn get_tx_timestamp(&self, tx_hash: B256) -> Result<Option<U64ED>, Box<dyn Error>> {
+        self.db_tx_timestamps
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .latest(&tx_hash.into())
+    }
+
     pub fn get_pending_tx(
         &self,
         account: Address,
@@ -626,17 +1063,73 @@ impl Brc20ProgDatabase {
             .latest(&account.into())
     }
 
+    pub fn get_account_info_at(
+        &self,
+        account: Address,
+        block_number: u64,
+    ) -> Result<Option<AccountInfoED>, Box<dyn Error>> {
+        self.db_account
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .at(&account.into(), block_number)
+    }
+
+    /// Checks if the state at the given block number can be served
+    ///
+    /// State history is only kept for the last MAX_REORG_HISTORY_SIZE blocks, unless archive mode is enabled, and
+    /// isn't kept for blocks before the snapshot the database was imported from
+    pub fn require_state_available(&self, block_number: u64) -> Result<(), Box<dyn Error>> {
+        if let Some(snapshot_block_number) = self.get_snapshot_block_number()? {
+            if block_number < snapshot_block_number {
+                return Err(format!(
+                    "State at block {} is not available, the database was imported from a snapshot of block {}",
+                    block_number, snapshot_block_number
+                )
+                .into());
+            }
+        }
+        if self.archive_mode {
+            return Ok(());
+        }
+        let latest_block_number = self.get_latest_block_height()?;
+        if block_number + MAX_REORG_HISTORY_SIZE <= latest_block_number {
+            return Err(format!(
+                "State at block {} is not available, only the last {} blocks are kept",
+                block_number, MAX_REORG_HISTORY_SIZE
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn is_archive_mode(&self) -> bool {
+        self.archive_mode
+    }
+
+    /// Returns the block number of the snapshot the database was imported from, if it was imported
+    fn get_snapshot_block_number(&self) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self
+            .db_global_values
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .get(SNAPSHOT_BLOCK_NUMBER_KEY.to_string())?
+            .map(|x| x.parse::<u64>())
+            .transpose()?)
+    }
+
     pub fn set_account_info(
         &mut self,
         account: Address,
         value: AccountInfo,
     ) -> Result<(), Box<dyn Error>> {
         let block_number = self.get_next_block_height()?;
-        Ok(self.db_account.as_mut().expect(DB_MUTEX_ERROR).set(
+        self.db_account.as_mut().expect(DB_MUTEX_ERROR).set(
             block_number,
             &account.into(),
             value.into(),
-        )?)
+        )?;
+        self.state_changes.entry(account).or_default();
+        Ok(())
     }
 
     pub fn generate_raw_block(&self, block: BlockResponseED) -> Result<RawBlock, Box<dyn Error>> {
@@ -684,6 +1177,7 @@ impl Brc20ProgDatabase {
         block_timestamp: u64,
         gas_used: u64,
         total_time_took: u128,
+        state_root: B256,
     ) -> Result<BlockResponseED, Box<dyn Error>> {
         let parent_hash = if block_number == 0 {
             B256::ZERO
@@ -703,28 +1197,27 @@ impl Brc20ProgDatabase {
 
         tx_ids.sort_by(|a, b| a.0.cmp(&b.0));
 
-        let leaves = tx_ids
-            .iter()
-            .map(|x| x.1.bytes.0)
-            .collect::<Vec<[u8; 32]>>();
-
-        let tx_merkle = MerkleTree::<Sha256>::from_leaves(leaves.as_slice());
-
         let mut transactions = Vec::new();
+        let mut txes = Vec::new();
+        let mut receipts = Vec::new();
         let mut bloom = Bloom::new([0u8; 256]);
         for tx_pair in tx_ids {
             let tx_id = tx_pair.1.into();
+            if let Some(tx) = self.get_tx_by_hash(tx_id)? {
+                txes.push(tx);
+            }
             if let Some(tx_receipt) = self.get_tx_receipt(tx_id)? {
-                for log in tx_receipt.logs {
+                for log in tx_receipt.logs.iter() {
                     bloom.accrue_log(
                         &Log::new(
                             log.address.address,
                             log.topics.iter().map(|x| x.bytes).collect(),
-                            log.data.bytes,
+                            log.data.bytes.clone(),
                         )
                         .unwrap_or(Log::empty()),
                     );
                 }
+                receipts.push(tx_receipt);
             }
             transactions.push(tx_id.into());
         }
@@ -738,13 +1231,205 @@ impl Brc20ProgDatabase {
             block_timestamp.into(),
             total_time_took.into(),
             transactions,
-            tx_merkle.root().unwrap_or([0; 32]).into(),
+            transactions_root(&txes).into(),
             parent_hash.into(),
+            receipts_root(&receipts).into(),
+            state_root.into(),
         );
 
         Ok(block_response)
     }
 
+    /// Computes the state root after the changes made since the previous block
+    ///
+    /// Accounts and storage slots changed since the previous block are updated in its state trie, the trie
+    /// is built from all accounts instead if the previous block has no state root. New trie nodes are
+    /// committed with the other changes.
+    ///
+    /// block_number: u64 - the block the state root is computed for
+    pub fn compute_state_root(&mut self, block_number: u64) -> Result<B256, Box<dyn Error>> {
+        let parent_state_root = match block_number.checked_sub(1) {
+            Some(parent_block_number) => self
+                .get_block(parent_block_number)?
+                .map(|block| block.state_root.bytes)
+                // Blocks indexed by older versions don't have a state root
+                .filter(|state_root| !state_root.is_zero()),
+            None => None,
+        };
+        let state_root = match parent_state_root {
+            Some(parent_state_root) if !self.rebuild_state_trie => {
+                self.update_state_trie(parent_state_root)?
+            }
+            _ => self.build_state_trie()?,
+        };
+        self.state_changes.clear();
+        self.rebuild_state_trie = false;
+        Ok(state_root)
+    }
+
+    /// Updates the changed accounts and their changed storage slots in the state trie with the given root
+    fn update_state_trie(&mut self, parent_state_root: B256) -> Result<B256, Box<dyn Error>> {
+        let mut account_changes = Vec::new();
+        for (account, slots) in self.state_changes.iter() {
+            let parent_storage_root = match self
+                .db_trie_nodes
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .get(parent_state_root, keccak256(account))?
+            {
+                Some(value) => TrieAccount::decode(&mut value.as_slice())?.storage_root,
+                None => EMPTY_ROOT_HASH,
+            };
+            let mut storage_changes = Vec::new();
+            for slot in slots {
+                storage_changes.push((
+                    keccak256(B256::from(*slot)),
+                    self.get_account_memory(*account, *slot)?
+                        .and_then(|value| storage_trie_value(value.uint)),
+                ));
+            }
+            let storage_root = self
+                .db_trie_nodes
+                .as_mut()
+                .expect(DB_MUTEX_ERROR)
+                .update(parent_storage_root, storage_changes)?;
+            account_changes.push((
+                keccak256(account),
+                self.get_account_info(*account)?
+                    .map(|info| state_trie_value(info, storage_root)),
+            ));
+        }
+        self.db_trie_nodes
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .update(parent_state_root, account_changes)
+    }
+
+    /// Builds the state trie from all accounts and their storage
+    fn build_state_trie(&mut self) -> Result<B256, Box<dyn Error>> {
+        tracing::info!("Building the state trie from all accounts");
+        let mut storage = HashMap::<Address, Vec<_>>::new();
+        for (key, value) in self
+            .db_account_memory
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .all()?
+        {
+            let (account, slot) = key.to_addr_u256();
+            storage
+                .entry(account)
+                .or_default()
+                .push((keccak256(B256::from(slot)), storage_trie_value(value.uint)));
+        }
+
+        let accounts = self.db_account.as_ref().expect(DB_MUTEX_ERROR).all()?;
+        let db_trie_nodes = self.db_trie_nodes.as_mut().expect(DB_MUTEX_ERROR);
+        let mut account_changes = Vec::new();
+        for (account, info) in accounts {
+            let storage_root = db_trie_nodes.update(
+                EMPTY_ROOT_HASH,
+                storage.remove(&account.address).unwrap_or_default(),
+            )?;
+            account_changes.push((
+                keccak256(account.address),
+                Some(state_trie_value(info, storage_root)),
+            ));
+        }
+        db_trie_nodes.update(EMPTY_ROOT_HASH, account_changes)
+    }
+
+    /// Builds and commits the state trie of the latest block, for stores imported from a snapshot without
+    /// the trie nodes
+    ///
+    /// Returns an error if the state root of the imported accounts doesn't match the one in the block
+    pub(super) fn build_imported_state_trie(&mut self) -> Result<(), Box<dyn Error>> {
+        let block_number = self.get_latest_block_height()?;
+        let block_state_root = self
+            .get_block(block_number)?
+            .ok_or_else(|| format!("Block {} not found", block_number))?
+            .state_root
+            .bytes;
+        if block_state_root.is_zero() {
+            return Err(format!(
+                "Block {} was indexed before state roots were introduced, it has no state root",
+                block_number
+            )
+            .into());
+        }
+        let state_root = self.build_state_trie()?;
+        if state_root != block_state_root {
+            return Err(format!(
+                "State root {} doesn't match the state root of block {}: {}",
+                state_root, block_number, block_state_root
+            )
+            .into());
+        }
+        self.commit_changes()
+    }
+
+    /// Gets an account from the state trie of a block, with the proof of its value against the block's
+    /// state root
+    ///
+    /// account: Address - the account to get
+    /// block_number: u64 - the block to get the account at
+    ///
+    /// Returns: (Option<TrieAccount>, Vec<Bytes>) - the account, None if it doesn't exist, and its proof
+    pub fn get_account_proof(
+        &self,
+        account: Address,
+        block_number: u64,
+    ) -> Result<(Option<TrieAccount>, Vec<Bytes>), Box<dyn Error>> {
+        let state_root = self
+            .get_block(block_number)?
+            .ok_or_else(|| format!("Block {} not found", block_number))?
+            .state_root
+            .bytes;
+        if state_root.is_zero() {
+            return Err(format!("State root of block {} is not available", block_number).into());
+        }
+        if !self.archive_mode
+            && block_number + MAX_REORG_HISTORY_SIZE < self.get_latest_block_height()?
+        {
+            return Err(format!(
+                "State trie of block {} is pruned, enable archive mode to get proofs of older blocks",
+                block_number
+            )
+            .into());
+        }
+        let (value, proof) = self
+            .db_trie_nodes
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .get_proof(state_root, keccak256(account))?;
+        let account = value
+            .map(|value| TrieAccount::decode(&mut value.as_slice()))
+            .transpose()?;
+        Ok((account, proof))
+    }
+
+    /// Gets a storage slot from a storage trie, with the proof of its value against the trie's root
+    ///
+    /// storage_root: B256 - the storage root of the account, from get_account_proof
+    /// slot: U256 - the storage slot to get
+    ///
+    /// Returns: (U256, Vec<Bytes>) - the value of the slot and its proof
+    pub fn get_storage_proof(
+        &self,
+        storage_root: B256,
+        slot: U256,
+    ) -> Result<(U256, Vec<Bytes>), Box<dyn Error>> {
+        let (value, proof) = self
+            .db_trie_nodes
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .get_proof(storage_root, keccak256(B256::from(slot)))?;
+        let value = value
+            .map(|value| U256::decode(&mut value.as_slice()))
+            .transpose()?
+            .unwrap_or(U256::ZERO);
+        Ok((value, proof))
+    }
+
     pub fn get_block(&self, block_number: u64) -> Result<Option<BlockResponseED>, Box<dyn Error>> {
         self.db_block_number_to_block
             .as_ref()
@@ -801,6 +1486,62 @@ impl Brc20ProgDatabase {
             .map(|op| op.map(|x| x.into()))
     }
 
+    pub fn get_consensus_hash(&self, block_number: u64) -> Result<Option<B256>, Box<dyn Error>> {
+        self.db_block_number_to_consensus_hash
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .get(block_number)
+            .map(|op| op.map(|x| x.into()))
+    }
+
+    /// Computes and stores the consensus hash of a block
+    ///
+    /// The consensus hash is the sha256 hash of the consensus hash of the previous block, the trace hash of
+    /// the block and its state root, so indexers with the same consensus hash at a block agree on every
+    /// block up to it. The first block uses zero as the previous consensus hash, and blocks indexed by older
+    /// versions get their consensus hashes when the database is migrated.
+    ///
+    /// block_number: u64 - the block to compute the consensus hash for
+    /// trace_hash: B256 - the trace hash of the block, as returned by debug_getBlockTraceHash
+    /// state_root: B256 - the state root of the block
+    ///
+    /// Returns: B256 - the consensus hash of the block
+    pub fn set_consensus_hash(
+        &mut self,
+        block_number: u64,
+        trace_hash: B256,
+        state_root: B256,
+    ) -> Result<B256, Box<dyn Error>> {
+        let previous_consensus_hash = match block_number.checked_sub(1) {
+            Some(parent_block_number) => self.get_consensus_hash(parent_block_number)?,
+            None => None,
+        }
+        .unwrap_or(B256::ZERO);
+        let consensus_hash =
+            Self::compute_consensus_hash(previous_consensus_hash, trace_hash, state_root)?;
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .set(block_number, consensus_hash.into());
+        Ok(consensus_hash)
+    }
+
+    /// Computes a consensus hash from the consensus hash of the previous block, see set_consensus_hash
+    pub(crate) fn compute_consensus_hash(
+        previous_consensus_hash: B256,
+        trace_hash: B256,
+        state_root: B256,
+    ) -> Result<B256, Box<dyn Error>> {
+        Ok(B256::from_str(&sha256::digest(
+            [
+                previous_consensus_hash.as_slice(),
+                trace_hash.as_slice(),
+                state_root.as_slice(),
+            ]
+            .concat(),
+        ))?)
+    }
+
     pub fn set_block_hash(
         &mut self,
         block_number: u64,
@@ -834,73 +1575,148 @@ impl Brc20ProgDatabase {
     pub fn commit_changes(&mut self) -> Result<(), Box<dyn Error>> {
         let next_block = self.get_next_block_height()?;
 
-        self.db_global_values
+        let mut batch = WriteBatch::default();
+        self.write_changes(&mut batch, next_block)?;
+        write_batch(self.db.as_ref().expect(DB_MUTEX_ERROR), batch)?;
+
+        self.clear_caches()?;
+        if !self.archive_mode
+            && self
+                .db_trie_nodes
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .should_prune()
+        {
+            self.prune_state_tries()?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the trie nodes that are not in the state tries of the last MAX_REORG_HISTORY_SIZE blocks
+    ///
+    /// Older blocks can't be reorged to without archive mode, so their state tries are not needed
+    fn prune_state_tries(&mut self) -> Result<(), Box<dyn Error>> {
+        let latest_block_number = self.get_latest_block_height()?;
+        let mut roots = Vec::new();
+        for block_number in
+            latest_block_number.saturating_sub(MAX_REORG_HISTORY_SIZE)..=latest_block_number
+        {
+            if let Some(block) = self.get_block(block_number)? {
+                roots.push(block.state_root.bytes);
+            }
+        }
+        let deleted = self
+            .db_trie_nodes
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .flush()?;
+            .prune(roots, |value| {
+                // Storage values are not lists, so only accounts are decoded
+                TrieAccount::decode(&mut &value[..])
+                    .ok()
+                    .map(|account| account.storage_root)
+            })?;
+        tracing::info!("Pruned {} state trie nodes", deleted);
+        Ok(())
+    }
+
+    /// Add changes of all stores to a batch, so they are committed atomically
+    ///
+    /// History caches older than MAX_REORG_HISTORY_SIZE blocks before block_number are dropped
+    fn write_changes(
+        &self,
+        batch: &mut WriteBatch,
+        block_number: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        self.db_global_values
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch)?;
         self.db_block_number_to_hash
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit()?;
+            .write_to_batch(batch)?;
+        self.db_block_number_to_consensus_hash
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch)?;
         self.db_block_number_to_block
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit()?;
+            .write_to_batch(batch)?;
         self.db_block_number_to_raw_block
-            .as_mut()
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch)?;
+        self.db_trie_nodes
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit()?;
+            .write_to_batch(batch)?;
 
         self.db_number_and_index_to_tx_hash
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_inscription_id_to_tx_hash
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_contract_address_to_inscription_id
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_tx
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_pending_txes
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_pending_txes_op_return_tx_ids
-            .as_mut()
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch, block_number)?;
+        self.db_tx_op_return_tx_ids
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
+        self.db_tx_timestamps
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch, block_number)?;
         self.db_tx_trace
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_tx_receipt
-            .as_mut()
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch, block_number)?;
+        self.db_log_address_index
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .write_to_batch(batch, block_number)?;
+        self.db_log_topic_index
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_account_memory
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_code
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_account
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
         self.db_block_hash_to_number
-            .as_mut()
+            .as_ref()
             .expect(DB_MUTEX_ERROR)
-            .commit(next_block)?;
+            .write_to_batch(batch, block_number)?;
 
-        self.clear_caches()?;
         Ok(())
     }
 
@@ -918,6 +1734,10 @@ impl Brc20ProgDatabase {
             .as_mut()
             .expect(DB_MUTEX_ERROR)
             .clear_cache();
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
         self.db_block_hash_to_number
             .as_mut()
             .expect(DB_MUTEX_ERROR)
@@ -939,6 +1759,14 @@ impl Brc20ProgDatabase {
             .as_mut()
             .expect(DB_MUTEX_ERROR)
             .clear_cache();
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
+        self.db_tx_timestamps
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
         self.db_tx_trace
             .as_mut()
             .expect(DB_MUTEX_ERROR)
@@ -947,6 +1775,14 @@ impl Brc20ProgDatabase {
             .as_mut()
             .expect(DB_MUTEX_ERROR)
             .clear_cache();
+        self.db_log_address_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
+        self.db_log_topic_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
         self.db_number_and_index_to_tx_hash
             .as_mut()
             .expect(DB_MUTEX_ERROR)
@@ -959,8 +1795,380 @@ impl Brc20ProgDatabase {
             .as_mut()
             .expect(DB_MUTEX_ERROR)
             .clear_cache();
+        self.db_trie_nodes
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
+        self.db_global_values
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .clear_cache();
 
         self.latest_block_number = None;
+        self.block_start = None;
+        Ok(())
+    }
+
+    /// Returns the number of cached entries that are not committed to the database yet
+    ///
+    /// This is used as an estimate of the memory used by the caches
+    pub fn cache_size(&self) -> usize {
+        self.db_account_memory
+            .as_ref()
+            .expect(DB_MUTEX_ERROR)
+            .cache_size()
+            + self.db_code.as_ref().expect(DB_MUTEX_ERROR).cache_size()
+            + self.db_account.as_ref().expect(DB_MUTEX_ERROR).cache_size()
+            + self
+                .db_number_and_index_to_tx_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_tx_receipt
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_log_address_index
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_log_topic_index
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self.db_tx.as_ref().expect(DB_MUTEX_ERROR).cache_size()
+            + self
+                .db_pending_txes
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_pending_txes_op_return_tx_ids
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_tx_op_return_tx_ids
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_tx_timestamps
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_tx_trace
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_inscription_id_to_tx_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_contract_address_to_inscription_id
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_block_hash_to_number
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_block_number_to_block
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_block_number_to_raw_block
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_block_number_to_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_block_number_to_consensus_hash
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+            + self
+                .db_trie_nodes
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .cache_size()
+    }
+
+    /// Starts a block, changes made after this can be discarded with abort_block
+    ///
+    /// It does nothing if a block is already started, so it can be called before every change
+    pub fn begin_block(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.block_start.is_some() {
+            return Ok(());
+        }
+        self.block_start = Some(BlockStart {
+            latest_block_number: self.latest_block_number,
+            max_block_number: self
+                .db_global_values
+                .as_ref()
+                .expect(DB_MUTEX_ERROR)
+                .get(MAX_BLOCK_NUMBER_KEY.to_string())?,
+            state_changes: self.state_changes.clone(),
+        });
+        self.db_account_memory
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_code.as_mut().expect(DB_MUTEX_ERROR).begin_block();
+        self.db_account
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_number_and_index_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_tx_receipt
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_log_address_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_log_topic_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_tx.as_mut().expect(DB_MUTEX_ERROR).begin_block();
+        self.db_pending_txes
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_pending_txes_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_tx_timestamps
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_tx_trace
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_inscription_id_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_contract_address_to_inscription_id
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_block_hash_to_number
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_block_number_to_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_block_number_to_raw_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_block_number_to_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .begin_block();
+        Ok(())
+    }
+
+    /// Whether a block is started, i.e. it has changes that are not finalised or aborted yet
+    pub fn is_block_started(&self) -> bool {
+        self.block_start.is_some()
+    }
+
+    /// Ends the block, its changes can't be aborted after this
+    pub fn end_block(&mut self) {
+        self.block_start = None;
+        self.db_account_memory
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_code.as_mut().expect(DB_MUTEX_ERROR).end_block();
+        self.db_account.as_mut().expect(DB_MUTEX_ERROR).end_block();
+        self.db_number_and_index_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_tx_receipt
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_log_address_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_log_topic_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_tx.as_mut().expect(DB_MUTEX_ERROR).end_block();
+        self.db_pending_txes
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_pending_txes_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_tx_timestamps
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_tx_trace.as_mut().expect(DB_MUTEX_ERROR).end_block();
+        self.db_inscription_id_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_contract_address_to_inscription_id
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_block_hash_to_number
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_block_number_to_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_block_number_to_raw_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_block_number_to_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .end_block();
+    }
+
+    /// Discards the changes made since the block started, previously finalised blocks are kept
+    ///
+    /// Changes committed after the block started are not reverted, committing ends the block
+    pub fn abort_block(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(block_start) = self.block_start.take() else {
+            return Ok(());
+        };
+        self.db_account_memory
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_code.as_mut().expect(DB_MUTEX_ERROR).abort_block();
+        self.db_account
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_number_and_index_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_tx_receipt
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_log_address_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_log_topic_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_tx.as_mut().expect(DB_MUTEX_ERROR).abort_block();
+        self.db_pending_txes
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_pending_txes_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_tx_timestamps
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_tx_trace
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_inscription_id_to_tx_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_contract_address_to_inscription_id
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_block_hash_to_number
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_block_number_to_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_block_number_to_raw_block
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_block_number_to_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .abort_block();
+
+        self.latest_block_number = block_start.latest_block_number;
+        self.state_changes = block_start.state_changes;
+        let db_global_values = self.db_global_values.as_mut().expect(DB_MUTEX_ERROR);
+        match block_start.max_block_number {
+            Some(max_block_number) => {
+                db_global_values.set(MAX_BLOCK_NUMBER_KEY.to_string(), max_block_number)?
+            }
+            None => db_global_values.remove(MAX_BLOCK_NUMBER_KEY.to_string())?,
+        }
         Ok(())
     }
 
@@ -973,7 +2181,9 @@ impl Brc20ProgDatabase {
             .map(|x| x.parse::<u64>().unwrap_or(0))
             .unwrap_or(0);
 
-        if max_global_block_number > MAX_REORG_HISTORY_SIZE + latest_valid_block_number {
+        if !self.archive_mode
+            && max_global_block_number > MAX_REORG_HISTORY_SIZE + latest_valid_block_number
+        {
             return Err(format!(
                 "Latest valid block number {} is too far behind max recorded block height: {}",
                 latest_valid_block_number, max_global_block_number
@@ -981,70 +2191,159 @@ impl Brc20ProgDatabase {
             .into());
         }
 
+        if let Some(snapshot_block_number) = self.get_snapshot_block_number()? {
+            if latest_valid_block_number < snapshot_block_number {
+                return Err(format!(
+                    "Latest valid block number {} is before the snapshot the database was imported from: {}",
+                    latest_valid_block_number, snapshot_block_number
+                )
+                .into());
+            }
+        }
+
+        let mut batch = WriteBatch::default();
         self.db_account_memory
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_code
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_account
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_block_hash_to_number
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_number_and_index_to_tx_hash
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_tx_receipt
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
+        self.db_log_address_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .reorg(&mut batch, latest_valid_block_number)?;
+        self.db_log_topic_index
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_inscription_id_to_tx_hash
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_contract_address_to_inscription_id
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_tx
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_pending_txes
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_pending_txes_op_return_tx_ids
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
+        self.db_tx_op_return_tx_ids
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .reorg(&mut batch, latest_valid_block_number)?;
+        self.db_tx_timestamps
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_tx_trace
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
 
         self.db_block_number_to_hash
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
+        self.db_block_number_to_consensus_hash
+            .as_mut()
+            .expect(DB_MUTEX_ERROR)
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_block_number_to_block
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
         self.db_block_number_to_raw_block
             .as_mut()
             .expect(DB_MUTEX_ERROR)
-            .reorg(latest_valid_block_number)?;
+            .reorg(&mut batch, latest_valid_block_number)?;
+
+        self.write_changes(&mut batch, latest_valid_block_number)?;
+        write_batch(self.db.as_ref().expect(DB_MUTEX_ERROR), batch)?;
+
+        // Changes to reorged blocks are reverted, the state trie of the next block starts from the last valid one
+        self.state_changes.clear();
+        self.clear_caches()
+    }
+}
+
+/// Value of a storage slot in the storage trie of its account, None if the slot is empty
+fn storage_trie_value(value: U256) -> Option<Vec<u8>> {
+    (!value.is_zero()).then(|| alloy_rlp::encode(value))
+}
 
-        Ok(self.commit_changes()?)
+/// Value of an account in the state trie
+fn state_trie_value(info: AccountInfoED, storage_root: B256) -> Vec<u8> {
+    alloy_rlp::encode(TrieAccount {
+        nonce: info.nonce.into(),
+        balance: info.balance.uint,
+        storage_root,
+        code_hash: info.code_hash.bytes,
+    })
+}
+
+/// Copy every column family of a legacy store into the column family it's migrated to
+///
+/// The default column family is migrated to the column family named after the store, others to the one
+/// named after the store and the column family, column families that are not used anymore are skipped
+///
+/// Returns: u64 - the number of entries copied
+fn copy_legacy_database(db: &DB, legacy_path: &Path, name: &str) -> Result<u64, Box<dyn Error>> {
+    let legacy_column_families = DB::list_cf(&Options::default(), legacy_path)?;
+    let legacy_db = DB::open_cf(&Options::default(), legacy_path, &legacy_column_families)?;
+
+    let mut entry_count = 0;
+    for legacy_column_family in legacy_column_families.iter() {
+        let target_name = if legacy_column_family == DEFAULT_COLUMN_FAMILY_NAME {
+            name.to_string()
+        } else {
+            format!("{}_{}", name, legacy_column_family)
+        };
+        let Some(target) = db.cf_handle(&target_name) else {
+            continue;
+        };
+
+        let mut batch = WriteBatch::default();
+        for kv_pair in legacy_db.full_iterator_cf(
+            column_family(&legacy_db, legacy_column_family)?,
+            IteratorMode::Start,
+        ) {
+            let (key, value) = kv_pair?;
+            batch.put_cf(target, key, value);
+            entry_count += 1;
+            if batch.len() >= MIGRATION_BATCH_SIZE {
+                write_batch(db, std::mem::take(&mut batch))?;
+            }
+        }
+        write_batch(db, batch)?;
     }
+    Ok(entry_count)
 }
 
 #[derive(Debug)]
@@ -1052,6 +2351,12 @@ pub struct DBError(Box<dyn Error>);
 
 impl DBErrorMarker for DBError {}
 
+impl From<Box<dyn Error>> for DBError {
+    fn from(error: Box<dyn Error>) -> Self {
+        DBError(error)
+    }
+}
+
 impl Display for DBError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "DBError: {}", self.0)
@@ -1139,10 +2444,16 @@ impl DatabaseCommit for Brc20ProgDatabase {
 #[cfg(test)]
 mod tests {
     use alloy::primitives::LogData;
+    use alloy::trie::proof::verify_proof;
+    use alloy::trie::root::{state_root_unhashed, storage_root_unhashed};
+    use alloy::trie::Nibbles;
     use revm::context::result::{Output, SuccessReason};
+    use serde_either::SingleOrVec;
     use tempfile::TempDir;
 
     use super::*;
+    use crate::db::types::Encode;
+    use crate::global::DB_VERSION_KEY;
 
     #[test]
     fn test_db() {
@@ -1213,6 +2524,336 @@ mod tests {
         );
     }
 
+    fn finalise_block(db: &mut Brc20ProgDatabase, block_number: u64) -> BlockResponseED {
+        let block_hash = B256::from(U256::from(block_number + 1));
+        db.begin_block().unwrap();
+        let state_root = db.compute_state_root(block_number).unwrap();
+        let block = db
+            .generate_block(block_hash, block_number, 0, 0, 0, state_root)
+            .unwrap();
+        db.set_block(block_number, block.clone()).unwrap();
+        db.set_block_hash(block_number, block_hash).unwrap();
+        db.end_block();
+        block
+    }
+
+    fn expected_state_root(accounts: &[(Address, AccountInfo, Vec<(u64, u64)>)]) -> B256 {
+        state_root_unhashed(accounts.iter().map(|(address, info, storage)| {
+            let storage_root = storage_root_unhashed(
+                storage
+                    .iter()
+                    .filter(|(_, value)| *value != 0)
+                    .map(|(slot, value)| (B256::from(U256::from(*slot)), U256::from(*value))),
+            );
+            (
+                *address,
+                TrieAccount {
+                    nonce: info.nonce,
+                    balance: info.balance,
+                    storage_root,
+                    code_hash: info.code_hash,
+                },
+            )
+        }))
+    }
+
+    #[test]
+    fn test_state_root() {
+        let path = TempDir::new().unwrap();
+        let contract: Address = [1u8; 20].into();
+        let user: Address = [2u8; 20].into();
+        let contract_info = AccountInfo {
+            nonce: 1,
+            code_hash: [3u8; 32].into(),
+            ..Default::default()
+        };
+        let mut user_info = AccountInfo {
+            balance: U256::from(100),
+            nonce: 4,
+            ..Default::default()
+        };
+
+        let state_root = {
+            let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+            db.set_account_info(contract, contract_info.clone())
+                .unwrap();
+            db.set_account_info(user, user_info.clone()).unwrap();
+            db.set_account_memory(contract, U256::from(1), U256::from(10))
+                .unwrap();
+            db.set_account_memory(contract, U256::from(2), U256::from(20))
+                .unwrap();
+            let block = finalise_block(&mut db, 0);
+            assert_eq!(
+                block.state_root.bytes,
+                expected_state_root(&[
+                    (contract, contract_info.clone(), vec![(1, 10), (2, 20)]),
+                    (user, user_info.clone(), vec![]),
+                ])
+            );
+            assert_eq!(block.transactions_root.bytes, EMPTY_ROOT_HASH);
+            assert_eq!(block.receipts_root.bytes, EMPTY_ROOT_HASH);
+
+            // Only changed accounts and slots are updated in the state trie of the previous block
+            user_info.balance = U256::from(50);
+            db.set_account_info(user, user_info.clone()).unwrap();
+            db.set_account_memory(contract, U256::from(1), U256::ZERO)
+                .unwrap();
+            db.set_account_memory(contract, U256::from(3), U256::from(30))
+                .unwrap();
+            let expected = expected_state_root(&[
+                (contract, contract_info.clone(), vec![(2, 20), (3, 30)]),
+                (user, user_info.clone(), vec![]),
+            ]);
+            assert_eq!(finalise_block(&mut db, 1).state_root.bytes, expected);
+
+            // Aborted changes are not in the state root
+            db.begin_block().unwrap();
+            db.set_account_memory(user, U256::from(4), U256::from(40))
+                .unwrap();
+            db.abort_block().unwrap();
+            assert_eq!(finalise_block(&mut db, 2).state_root.bytes, expected);
+
+            db.commit_changes().unwrap();
+            expected
+        };
+
+        // The state trie is kept in the database, and rebuilding it gives the same root
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+        assert!(!db.rebuild_state_trie);
+        db.rebuild_state_trie = true;
+        assert_eq!(finalise_block(&mut db, 3).state_root.bytes, state_root);
+
+        db.set_account_memory(user, U256::from(5), U256::from(50))
+            .unwrap();
+        assert_eq!(
+            finalise_block(&mut db, 4).state_root.bytes,
+            expected_state_root(&[
+                (contract, contract_info, vec![(2, 20), (3, 30)]),
+                (user, user_info, vec![(5, 50)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_state_proofs() {
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+        let contract: Address = [1u8; 20].into();
+        let missing: Address = [2u8; 20].into();
+        let contract_info = AccountInfo {
+            nonce: 1,
+            code_hash: [3u8; 32].into(),
+            ..Default::default()
+        };
+        db.set_account_info(contract, contract_info.clone())
+            .unwrap();
+        db.set_account_memory(contract, U256::from(1), U256::from(10))
+            .unwrap();
+        let state_root = finalise_block(&mut db, 0).state_root.bytes;
+
+        db.set_account_memory(contract, U256::from(1), U256::from(20))
+            .unwrap();
+        finalise_block(&mut db, 1);
+
+        // Proofs are against the state of the requested block
+        let (account, proof) = db.get_account_proof(contract, 0).unwrap();
+        let account = account.unwrap();
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code_hash, contract_info.code_hash);
+        verify_proof(
+            state_root,
+            Nibbles::unpack(keccak256(contract)),
+            Some(alloy_rlp::encode(account)),
+            &proof,
+        )
+        .unwrap();
+
+        let (value, proof) = db
+            .get_storage_proof(account.storage_root, U256::from(1))
+            .unwrap();
+        assert_eq!(value, U256::from(10));
+        verify_proof(
+            account.storage_root,
+            Nibbles::unpack(keccak256(B256::from(U256::from(1)))),
+            Some(alloy_rlp::encode(value)),
+            &proof,
+        )
+        .unwrap();
+
+        // Missing accounts and slots are proven to be absent
+        let (account, proof) = db.get_account_proof(missing, 0).unwrap();
+        assert!(account.is_none());
+        verify_proof(
+            state_root,
+            Nibbles::unpack(keccak256(missing)),
+            None,
+            &proof,
+        )
+        .unwrap();
+        assert_eq!(
+            db.get_storage_proof(EMPTY_ROOT_HASH, U256::from(1))
+                .unwrap(),
+            (U256::ZERO, vec![])
+        );
+
+        // Blocks without a state root can't be proven
+        assert!(db.get_account_proof(contract, 2).is_err());
+        let mut block = db.get_block(1).unwrap().unwrap();
+        block.state_root = B256::ZERO.into();
+        db.set_block(1, block).unwrap();
+        assert_eq!(
+            db.get_account_proof(contract, 1).unwrap_err().to_string(),
+            "State root of block 1 is not available"
+        );
+    }
+
+    #[test]
+    fn test_prune_state_tries() {
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+        let contract: Address = [1u8; 20].into();
+        let trie_node_count = |db: &Brc20ProgDatabase| {
+            let db = db.db.as_ref().unwrap();
+            db.iterator_cf(
+                column_family(db, TRIE_NODES_NAME).unwrap(),
+                IteratorMode::Start,
+            )
+            .count()
+        };
+        db.set_account_info(contract, AccountInfo::default())
+            .unwrap();
+        for slot in 0..100u64 {
+            db.set_account_memory(contract, U256::from(slot), U256::from(1))
+                .unwrap();
+        }
+        finalise_block(&mut db, 0);
+        db.commit_changes().unwrap();
+        let initial_count = trie_node_count(&db);
+
+        // Each block replaces the nodes on the path to the changed slot
+        let last_block = 3 * MAX_REORG_HISTORY_SIZE;
+        for block_number in 1..=last_block {
+            db.set_account_memory(contract, U256::from(block_number), U256::from(2))
+                .unwrap();
+            finalise_block(&mut db, block_number);
+            db.commit_changes().unwrap();
+        }
+        assert!(trie_node_count(&db) < 2 * initial_count);
+
+        // State tries within the reorg history are kept
+        let oldest_kept = last_block - MAX_REORG_HISTORY_SIZE;
+        for block_number in oldest_kept..=last_block {
+            db.get_account_proof(contract, block_number).unwrap();
+        }
+        assert_eq!(
+            db.get_account_proof(contract, oldest_kept - 1)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "State trie of block {} is pruned, enable archive mode to get proofs of older blocks",
+                oldest_kept - 1
+            )
+        );
+
+        // Archive databases keep every state trie
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::with_archive_mode(path.path(), true).unwrap();
+        db.set_account_info(contract, AccountInfo::default())
+            .unwrap();
+        for block_number in 0..=last_block {
+            db.set_account_memory(contract, U256::from(block_number), U256::from(2))
+                .unwrap();
+            finalise_block(&mut db, block_number);
+            db.commit_changes().unwrap();
+        }
+        db.get_account_proof(contract, 0).unwrap();
+    }
+
+    #[test]
+    fn test_consensus_hash() {
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+        let trace_hash: B256 = [1u8; 32].into();
+        let state_root: B256 = [2u8; 32].into();
+
+        // The first block starts from zero, following blocks include the previous consensus hash
+        let first = db.set_consensus_hash(0, trace_hash, state_root).unwrap();
+        assert_eq!(
+            first,
+            B256::from_str(&sha256::digest(
+                [
+                    B256::ZERO.as_slice(),
+                    trace_hash.as_slice(),
+                    state_root.as_slice()
+                ]
+                .concat()
+            ))
+            .unwrap()
+        );
+        let second = db.set_consensus_hash(1, trace_hash, state_root).unwrap();
+        assert_eq!(
+            second,
+            B256::from_str(&sha256::digest(
+                [
+                    first.as_slice(),
+                    trace_hash.as_slice(),
+                    state_root.as_slice()
+                ]
+                .concat()
+            ))
+            .unwrap()
+        );
+        assert_eq!(db.get_consensus_hash(0).unwrap(), Some(first));
+        assert_eq!(db.get_consensus_hash(1).unwrap(), Some(second));
+        assert_eq!(db.get_consensus_hash(2).unwrap(), None);
+
+        // Aborted blocks don't keep their consensus hash
+        db.begin_block().unwrap();
+        db.set_consensus_hash(2, trace_hash, state_root).unwrap();
+        db.abort_block().unwrap();
+        assert_eq!(db.get_consensus_hash(2).unwrap(), None);
+
+        // Reorged blocks are removed, and a different block replaces them
+        db.commit_changes().unwrap();
+        db.reorg(0).unwrap();
+        assert_eq!(db.get_consensus_hash(0).unwrap(), Some(first));
+        assert_eq!(db.get_consensus_hash(1).unwrap(), None);
+        let replaced = db.set_consensus_hash(1, state_root, trace_hash).unwrap();
+        assert_ne!(replaced, second);
+    }
+
+    #[test]
+    fn test_abort_first_block() {
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+
+        db.begin_block().unwrap();
+        db.set_block_hash(0, B256::from([1u8; 32])).unwrap();
+        db.abort_block().unwrap();
+        assert!(db.get_block_hash(0).unwrap().is_none());
+        assert_eq!(
+            db.db_global_values
+                .as_ref()
+                .unwrap()
+                .get(MAX_BLOCK_NUMBER_KEY.to_string())
+                .unwrap(),
+            None
+        );
+
+        // Max block number isn't kept after committing either
+        db.commit_changes().unwrap();
+        drop(db);
+        let db = Brc20ProgDatabase::new(path.path()).unwrap();
+        assert_eq!(
+            db.db_global_values
+                .as_ref()
+                .unwrap()
+                .get(MAX_BLOCK_NUMBER_KEY.to_string())
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_tx_methods() {
         let path = TempDir::new().unwrap().keep();
@@ -1257,6 +2898,8 @@ mod tests {
                 0u8,
                 U256::from(0),
                 U256::from(0),
+                0,
+                None,
             )
             .unwrap();
             db.set_block_hash(block_number, block_hash).unwrap();
@@ -1302,6 +2945,7 @@ mod tests {
                 output.gas_used().into(),
                 cumulative_gas_used.into(),
                 start_log_index.into(),
+                0u8.into(),
             )
             .unwrap()
         );
@@ -1364,6 +3008,8 @@ mod tests {
                 0u8,
                 U256::from(0),
                 U256::from(0),
+                0,
+                None,
             )
             .unwrap();
             db.set_block_hash(block_number, block_hash).unwrap();
@@ -1372,8 +3018,10 @@ mod tests {
         }
 
         let db = Brc20ProgDatabase::new(&path).unwrap();
+        let view = db.view().unwrap();
+        let snapshot = view.snapshot(block_number);
 
-        let logs = db
+        let logs = snapshot
             .get_logs(
                 Some(block_number),
                 Some(block_number),
@@ -1382,6 +3030,8 @@ mod tests {
                     SingleOrVec::Vec(vec![Some([7u8; 32].into()), Some([10u8; 32].into())]),
                     SingleOrVec::Single(Some([8u8; 32].into())),
                 ]),
+                0,
+                0,
             )
             .unwrap();
 
@@ -1399,7 +3049,7 @@ mod tests {
         assert_eq!(logs[1].topics[2].bytes.0, [11u8; 32]);
         assert_eq!(logs[1].data.bytes.to_vec(), [11u8; 32].to_vec());
 
-        let logs = db
+        let logs = snapshot
             .get_logs(
                 Some(block_number),
                 Some(block_number),
@@ -1409,6 +3059,8 @@ mod tests {
                     SingleOrVec::Single(None),
                     SingleOrVec::Single(Some([11u8; 32].into())),
                 ]),
+                0,
+                0,
             )
             .unwrap();
 
@@ -1420,4 +3072,348 @@ mod tests {
         assert_eq!(logs[0].topics[2].bytes.0, [11u8; 32]);
         assert_eq!(logs[0].data.bytes.to_vec(), [11u8; 32].to_vec());
     }
+
+    #[test]
+    fn test_get_logs_wide_range() {
+        let path = TempDir::new().unwrap();
+        let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+
+        let address_a: Address = [3u8; 20].into();
+        let address_b: Address = [4u8; 20].into();
+        let transfer_topic: B256 = [7u8; 32].into();
+        let approval_topic: B256 = [8u8; 32].into();
+
+        // Every block has a single tx, emitting a transfer from address_a and an approval
+        // from address_b, with the block number as the second topic
+        for block_number in 0..100u64 {
+            let block_hash = B256::from(U256::from(block_number + 1));
+            let tx_hash = B256::from(U256::from(block_number + 1000));
+            let block_topic: B256 = U256::from(block_number).into();
+            let output = ExecutionResult::Success {
+                reason: SuccessReason::Return,
+                gas_used: 10,
+                gas_refunded: 0,
+                logs: vec![
+                    Log::<LogData>::new(
+                        address_a,
+                        vec![transfer_topic, block_topic],
+                        Vec::new().into(),
+                    )
+                    .unwrap(),
+                    Log::<LogData>::new(
+                        address_b,
+                        vec![approval_topic, block_topic],
+                        Vec::new().into(),
+                    )
+                    .unwrap(),
+                ],
+                output: Output::Call(Vec::new().into()),
+            };
+
+            db.set_tx_receipt(
+                block_hash,
+                block_number,
+                None,
+                [5u8; 20].into(),
+                Some(address_a),
+                &Vec::new().into(),
+                tx_hash,
+                0,
+                Some(output),
+                10,
+                block_number,
+                0,
+                format!("inscription_id_{}", block_number),
+                10000,
+                0u8,
+                U256::from(0),
+                U256::from(0),
+                0,
+                None,
+            )
+            .unwrap();
+            let block = db
+                .generate_block(block_hash, block_number, 0, 10, 0, B256::ZERO)
+                .unwrap();
+            db.set_block(block_number, block).unwrap();
+            db.set_block_hash(block_number, block_hash).unwrap();
+            db.commit_changes().unwrap();
+        }
+
+        let view = db.view().unwrap();
+        let snapshot = view.snapshot(99);
+
+        // Address index
+        let logs = snapshot
+            .get_logs(Some(0), Some(99), Some(address_a), None, 0, 0)
+            .unwrap();
+        assert_eq!(logs.len(), 100);
+        assert!(logs.iter().all(|log| log.address.address == address_a));
+        assert!(logs
+            .windows(2)
+            .all(|x| x[0].block_number.uint < x[1].block_number.uint));
+
+        // Topic index
+        let logs = snapshot
+            .get_logs(
+                Some(10),
+                Some(19),
+                None,
+                Some(vec![SingleOrVec::Single(Some(approval_topic))]),
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(logs.len(), 10);
+        assert!(logs.iter().all(|log| log.address.address == address_b));
+
+        // Topic index with a second topic
+        let logs = snapshot
+            .get_logs(
+                Some(0),
+                Some(99),
+                None,
+                Some(vec![
+                    SingleOrVec::Vec(vec![Some(transfer_topic), Some(approval_topic)]),
+                    SingleOrVec::Single(Some(U256::from(42).into())),
+                ]),
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].address.address, address_a);
+        assert_eq!(logs[1].address.address, address_b);
+        assert_eq!(logs[0].block_number, 42u64.into());
+
+        // Bloom filters, without an address or topic0
+        let logs = snapshot
+            .get_logs(
+                Some(0),
+                Some(99),
+                None,
+                Some(vec![
+                    SingleOrVec::Single(None),
+                    SingleOrVec::Single(Some(U256::from(42).into())),
+                ]),
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].block_number, 42u64.into());
+
+        // Every log
+        let logs = snapshot
+            .get_logs(Some(0), Some(99), None, None, 0, 0)
+            .unwrap();
+        assert_eq!(logs.len(), 200);
+
+        // First topic without any topic in the OR condition matches every log
+        for topic0 in [SingleOrVec::Vec(vec![]), SingleOrVec::Vec(vec![None])] {
+            let logs = snapshot
+                .get_logs(Some(0), Some(99), None, Some(vec![topic0.clone()]), 0, 0)
+                .unwrap();
+            assert_eq!(logs.len(), 200);
+            assert!(snapshot
+                .get_logs(Some(0), Some(99), None, Some(vec![topic0]), 0, 99)
+                .is_err());
+        }
+
+        // Result limit
+        assert!(snapshot
+            .get_logs(Some(0), Some(99), None, None, 199, 0)
+            .is_err());
+        assert!(snapshot
+            .get_logs(Some(0), Some(99), Some(address_a), None, 99, 0)
+            .is_err());
+        assert_eq!(
+            snapshot
+                .get_logs(Some(0), Some(99), Some(address_a), None, 100, 0)
+                .unwrap()
+                .len(),
+            100
+        );
+        let both_topics = Some(vec![SingleOrVec::Vec(vec![
+            Some(transfer_topic),
+            Some(approval_topic),
+        ])]);
+        assert!(snapshot
+            .get_logs(Some(0), Some(99), None, both_topics.clone(), 199, 0)
+            .is_err());
+        let logs = snapshot
+            .get_logs(Some(0), Some(99), None, both_topics, 200, 0)
+            .unwrap();
+        assert_eq!(logs.len(), 200);
+        // Logs of both topics are returned in order
+        assert!(logs.windows(2).all(|x| {
+            (x[0].block_number.uint, x[0].log_index.uint)
+                < (x[1].block_number.uint, x[1].log_index.uint)
+        }));
+
+        // Scan block limit only applies to queries without an address or first topic
+        assert!(snapshot
+            .get_logs(Some(0), Some(99), None, None, 0, 99)
+            .is_err());
+        assert_eq!(
+            snapshot
+                .get_logs(Some(0), Some(98), None, None, 0, 99)
+                .unwrap()
+                .len(),
+            198
+        );
+        assert_eq!(
+            snapshot
+                .get_logs(Some(0), Some(99), Some(address_a), None, 0, 99)
+                .unwrap()
+                .len(),
+            100
+        );
+        // Blocks after the latest one are not scanned
+        assert_eq!(
+            snapshot
+                .get_logs(Some(0), Some(u64::MAX), None, None, 0, 0)
+                .unwrap()
+                .len(),
+            200
+        );
+
+        // Invalid range
+        assert!(snapshot
+            .get_logs(Some(1), Some(0), None, None, 0, 0)
+            .is_err());
+
+        // Reorged logs are removed from the indexes
+        drop(snapshot);
+        drop(view);
+        db.reorg(94).unwrap();
+        let view = db.view().unwrap();
+        let snapshot = view.snapshot(94);
+        let logs = snapshot
+            .get_logs(Some(0), Some(99), Some(address_a), None, 0, 0)
+            .unwrap();
+        assert_eq!(logs.len(), 95);
+        let logs = snapshot
+            .get_logs(
+                Some(0),
+                Some(99),
+                None,
+                Some(vec![SingleOrVec::Single(Some(approval_topic))]),
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(logs.len(), 95);
+    }
+
+    // Splits the database into separate databases for each store, as kept by older versions
+    fn split_into_legacy_databases(base_path: &Path) {
+        let path = base_path.join(DATABASE_NAME);
+        let column_families = Brc20ProgDatabase::column_families(false);
+        {
+            let db = open_database(&path, &column_families).unwrap();
+            let mut opts = Options::default();
+            opts.create_if_missing(true);
+            for name in column_families.iter() {
+                let legacy_db = DB::open(&opts, base_path.join(name)).unwrap();
+                for kv_pair in
+                    db.full_iterator_cf(column_family(&db, name).unwrap(), IteratorMode::Start)
+                {
+                    let (key, value) = kv_pair.unwrap();
+                    legacy_db.put(key, value).unwrap();
+                }
+            }
+        }
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_databases_with_partial_commit() {
+        let path = TempDir::new().unwrap();
+        let address = [1u8; 20].into();
+        let block_hash = |block_number: u64| B256::from([block_number as u8 + 1; 32]);
+
+        {
+            let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+            for block_number in 0..3 {
+                db.set_account_info(
+                    address,
+                    AccountInfo {
+                        nonce: block_number,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+                db.set_block_hash(block_number, block_hash(block_number))
+                    .unwrap();
+                db.commit_changes().unwrap();
+            }
+        }
+        split_into_legacy_databases(path.path());
+
+        // Block 2 is committed to every store except the last one
+        {
+            let block_hash_key = B256ED::from(block_hash(2)).encode_vec();
+            DB::open_default(path.path().join("block_hash_to_number"))
+                .unwrap()
+                .delete(&block_hash_key)
+                .unwrap();
+            DB::open_default(path.path().join("block_hash_to_number_cache"))
+                .unwrap()
+                .delete(&block_hash_key)
+                .unwrap();
+        }
+
+        let db = Brc20ProgDatabase::new(path.path()).unwrap();
+        assert!(path.path().join(DATABASE_NAME).exists());
+        for name in Brc20ProgDatabase::legacy_database_names() {
+            assert!(!path.path().join(name).exists());
+        }
+
+        assert_eq!(db.get_latest_block_height().unwrap(), 1);
+        assert!(db.get_block_hash(2).unwrap().is_none());
+        assert_eq!(db.get_block_hash(1).unwrap().unwrap(), block_hash(1));
+        assert_eq!(
+            db.get_account_info(address).unwrap().unwrap().nonce,
+            1u64.into()
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_config_database() {
+        let path = TempDir::new().unwrap();
+        let block_hash = B256::from([1u8; 32]);
+        {
+            let mut db = Brc20ProgDatabase::new(path.path()).unwrap();
+            db.set_block_hash(0, block_hash).unwrap();
+            db.commit_changes().unwrap();
+        }
+        {
+            let mut config_database = Brc20ProgDatabase::open_config_database(path.path()).unwrap();
+            config_database
+                .set(DB_VERSION_KEY.clone(), "11".to_string())
+                .unwrap();
+            config_database.flush().unwrap();
+        }
+        // Configuration values are kept in a separate database along with the stores
+        split_into_legacy_databases(path.path());
+        assert!(path.path().join(CONFIG_NAME).exists());
+
+        let config_database = Brc20ProgDatabase::open_config_database(path.path()).unwrap();
+        assert_eq!(
+            config_database.get(DB_VERSION_KEY.clone()).unwrap(),
+            Some("11".to_string())
+        );
+        assert!(!path.path().join(CONFIG_NAME).exists());
+        for name in Brc20ProgDatabase::legacy_database_names() {
+            assert!(!path.path().join(name).exists());
+        }
+        drop(config_database);
+
+        // Directories named like legacy stores are left as is once the database exists
+        std::fs::create_dir(path.path().join("code")).unwrap();
+        let db = Brc20ProgDatabase::new(path.path()).unwrap();
+        assert_eq!(db.get_block_hash(0).unwrap().unwrap(), block_hash);
+        assert!(path.path().join("code").exists());
+    }
 }
@@ -0,0 +1,381 @@
+#![cfg(feature = "server")]
+
+use std::error::Error;
+
+use alloy::primitives::{Address, Bloom, BloomInput, B256, U256};
+use revm::state::{AccountInfo, Bytecode};
+use revm::{Database as DatabaseTrait, DatabaseRef};
+use serde_either::SingleOrVec;
+
+use crate::db::brc20_prog_database::DBError;
+use crate::db::cached_database::{
+    BlockCachedDatabaseReader, BlockCachedDatabaseView, BlockHistoryCacheData,
+};
+use crate::db::database::{BlockDatabaseReader, BlockDatabaseView};
+use crate::db::types::{
+    AccountInfoED, AddressED, BlockResponseED, BytecodeED, LogED, TxReceiptED, B256ED, U128ED,
+    U256ED, U512ED,
+};
+use crate::db::Brc20ProgDatabase;
+
+/// Immutable view of the parts of Brc20ProgDatabase needed to serve reads, i.e. EVM calls and logs
+///
+/// A view is created under a brief read lock, after that it can be used without holding the lock,
+/// so indexing can continue while readers use the view
+pub struct Brc20ProgDatabaseView {
+    pub(super) account_memory:
+        BlockCachedDatabaseView<U512ED, U256ED, BlockHistoryCacheData<U256ED>>,
+    pub(super) code: BlockCachedDatabaseView<B256ED, BytecodeED, BlockHistoryCacheData<BytecodeED>>,
+    pub(super) account:
+        BlockCachedDatabaseView<AddressED, AccountInfoED, BlockHistoryCacheData<AccountInfoED>>,
+    pub(super) number_and_index_to_tx_hash:
+        BlockCachedDatabaseView<U128ED, B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) tx_receipt:
+        BlockCachedDatabaseView<B256ED, TxReceiptED, BlockHistoryCacheData<TxReceiptED>>,
+    pub(super) log_address_index:
+        BlockCachedDatabaseView<(AddressED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) log_topic_index:
+        BlockCachedDatabaseView<(B256ED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) block_number_to_block: BlockDatabaseView<BlockResponseED>,
+    pub(super) block_number_to_hash: BlockDatabaseView<B256ED>,
+    pub(super) latest_block_number: u64,
+}
+
+impl Brc20ProgDatabaseView {
+    /// Latest finalised block number when the view was created
+    pub fn latest_block_number(&self) -> u64 {
+        self.latest_block_number
+    }
+
+    /// Take a snapshot of the view
+    ///
+    /// This should be called before the lock used to create the view is released, so the
+    /// underlying databases are in the same state as the view
+    ///
+    /// block_number: u64 - the block number to read the state at
+    /// Returns: Brc20ProgDatabaseReader - reader that doesn't see changes made after the snapshot
+    pub fn snapshot(&self, block_number: u64) -> Brc20ProgDatabaseReader<'_> {
+        Brc20ProgDatabaseReader {
+            account_memory: self.account_memory.snapshot(),
+            code: self.code.snapshot(),
+            account: self.account.snapshot(),
+            number_and_index_to_tx_hash: self.number_and_index_to_tx_hash.snapshot(),
+            tx_receipt: self.tx_receipt.snapshot(),
+            log_address_index: self.log_address_index.snapshot(),
+            log_topic_index: self.log_topic_index.snapshot(),
+            block_number_to_block: self.block_number_to_block.snapshot(),
+            block_number_to_hash: self.block_number_to_hash.snapshot(),
+            latest_block_number: self.latest_block_number,
+            state_block_number: block_number,
+        }
+    }
+}
+
+/// Reads the state as it was at the end of a block, along with blocks and logs
+///
+/// It's also used as an EVM database to run calls at that block
+pub struct Brc20ProgDatabaseReader<'a> {
+    pub(super) account_memory:
+        BlockCachedDatabaseReader<'a, U512ED, U256ED, BlockHistoryCacheData<U256ED>>,
+    pub(super) code:
+        BlockCachedDatabaseReader<'a, B256ED, BytecodeED, BlockHistoryCacheData<BytecodeED>>,
+    pub(super) account: BlockCachedDatabaseReader<
+        'a,
+        AddressED,
+        AccountInfoED,
+        BlockHistoryCacheData<AccountInfoED>,
+    >,
+    pub(super) number_and_index_to_tx_hash:
+        BlockCachedDatabaseReader<'a, U128ED, B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) tx_receipt:
+        BlockCachedDatabaseReader<'a, B256ED, TxReceiptED, BlockHistoryCacheData<TxReceiptED>>,
+    pub(super) log_address_index:
+        BlockCachedDatabaseReader<'a, (AddressED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) log_topic_index:
+        BlockCachedDatabaseReader<'a, (B256ED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    pub(super) block_number_to_block: BlockDatabaseReader<'a, BlockResponseED>,
+    pub(super) block_number_to_hash: BlockDatabaseReader<'a, B256ED>,
+    pub(super) latest_block_number: u64,
+    pub(super) state_block_number: u64,
+}
+
+impl Brc20ProgDatabaseReader<'_> {
+    pub fn get_account_info(
+        &self,
+        account: Address,
+    ) -> Result<Option<AccountInfoED>, Box<dyn Error>> {
+        self.account.at(&account.into(), self.state_block_number)
+    }
+
+    pub fn get_code(&self, code_hash: B256) -> Result<Option<BytecodeED>, Box<dyn Error>> {
+        self.code.at(&code_hash.into(), self.state_block_number)
+    }
+
+    pub fn get_account_memory(
+        &self,
+        account: Address,
+        mem_loc: U256,
+    ) -> Result<Option<U256ED>, Box<dyn Error>> {
+        self.account_memory.at(
+            &U512ED::from_addr_u256(account, mem_loc)?,
+            self.state_block_number,
+        )
+    }
+
+    pub fn get_block_hash(&self, block_number: u64) -> Result<Option<B256>, Box<dyn Error>> {
+        self.block_number_to_hash
+            .get(block_number)
+            .map(|op| op.map(|x| x.into()))
+    }
+
+    pub fn get_tx_receipt(&self, tx_hash: B256) -> Result<Option<TxReceiptED>, Box<dyn Error>> {
+        self.tx_receipt.latest(&tx_hash.into())
+    }
+
+    /// Get logs in a block range, matching the given address and topics
+    ///
+    /// Logs are looked up from the address index if an address is given, or from the topic0 index
+    /// if the first topic is given, otherwise blocks whose bloom filter can't match are skipped
+    ///
+    /// result_limit: u64 - max number of logs to return (0 for unlimited), an error is returned
+    /// if there are more matching logs, so the caller can narrow the block range
+    /// scan_block_limit: u64 - max number of blocks to scan when there's no address or topic0 to
+    /// look up logs from the indexes (0 for unlimited)
+    pub fn get_logs(
+        &self,
+        block_number_from: Option<u64>,
+        block_number_to: Option<u64>,
+        contract_address: Option<Address>,
+        topics: Option<Vec<SingleOrVec<Option<B256>>>>,
+        result_limit: u64,
+        scan_block_limit: u64,
+    ) -> Result<Vec<LogED>, Box<dyn Error>> {
+        let block_number_from = block_number_from.unwrap_or(self.latest_block_number);
+        let block_number_to = block_number_to.unwrap_or(block_number_from);
+
+        if block_number_from > block_number_to {
+            return Err("Invalid block range, fromBlock is greater than toBlock".into());
+        }
+
+        let start_key = Brc20ProgDatabase::get_number_and_index_key(block_number_from, 0);
+        let end_key =
+            Brc20ProgDatabase::get_number_and_index_key(block_number_to.saturating_add(1), 0);
+
+        let mut logs = Vec::new();
+        // Logs of a transaction are next to each other in the indexes, so only its receipt is kept
+        let mut tx_receipt: Option<(B256ED, Option<TxReceiptED>)> = None;
+        let mut add_log = |log_key: U128ED, tx_hash: B256ED| -> Result<bool, Box<dyn Error>> {
+            if tx_receipt.as_ref().is_none_or(|(hash, _)| *hash != tx_hash) {
+                tx_receipt = Some((tx_hash, self.get_tx_receipt(tx_hash.into())?));
+            }
+            let Some((_, Some(tx_receipt))) = tx_receipt.as_ref() else {
+                return Ok(true);
+            };
+
+            let log_index = log_key.uint.to::<u128>() as u64;
+            if let Some(log) = tx_receipt
+                .logs
+                .iter()
+                .find(|log| log.log_index.uint.to::<u64>() == log_index)
+            {
+                if log.matches(contract_address, &topics) {
+                    logs.push(log.clone());
+                    check_result_limit(logs.len(), result_limit)?;
+                }
+            }
+            Ok(true)
+        };
+
+        // Index entries are read in order and the limit is checked as logs are found, so wide
+        // ranges don't load every entry in memory
+        if let Some(address) = contract_address {
+            self.log_address_index.for_each_in_range(
+                &(address.into(), start_key.into()),
+                &(address.into(), end_key.into()),
+                |(_, log_key), tx_hash| add_log(log_key, tx_hash),
+            )?;
+        } else if let Some(topic0s) = topics.as_ref().and_then(|x| indexed_topics(x.first()?)) {
+            for topic0 in topic0s {
+                self.log_topic_index.for_each_in_range(
+                    &(topic0.into(), start_key.into()),
+                    &(topic0.into(), end_key.into()),
+                    |(_, log_key), tx_hash| add_log(log_key, tx_hash),
+                )?;
+            }
+            // Logs of different topics are found one topic after the other
+            logs.sort_by_key(|log| (log.block_number, log.log_index));
+        } else {
+            if scan_block_limit != 0 && block_number_to - block_number_from >= scan_block_limit {
+                return Err(format!(
+                    "Block range is too large for a query without an address or first topic, please limit it to {} blocks",
+                    scan_block_limit
+                )
+                .into());
+            }
+            return self.scan_logs(block_number_from, block_number_to, &topics, result_limit);
+        }
+
+        Ok(logs)
+    }
+
+    /// Get logs by going through every transaction in the range, used when there's no address
+    /// or topic0 to look up logs from the indexes
+    ///
+    /// Blocks are read one by one, so the scan stops as soon as the result limit is exceeded
+    fn scan_logs(
+        &self,
+        block_number_from: u64,
+        block_number_to: u64,
+        topics: &Option<Vec<SingleOrVec<Option<B256>>>>,
+        result_limit: u64,
+    ) -> Result<Vec<LogED>, Box<dyn Error>> {
+        let mut logs = Vec::new();
+        // Blocks after the one being indexed have no transactions
+        let block_number_to = block_number_to.min(self.latest_block_number.saturating_add(1));
+        for block_number in block_number_from..=block_number_to {
+            if !self.block_may_match(block_number, topics)? {
+                continue;
+            }
+
+            let mut tx_ids = self.number_and_index_to_tx_hash.get_range(
+                &Brc20ProgDatabase::get_number_and_index_key(block_number, 0).into(),
+                &Brc20ProgDatabase::get_number_and_index_key(block_number.saturating_add(1), 0)
+                    .into(),
+            )?;
+            tx_ids.sort_by_key(|x| x.0);
+
+            for (_, tx_hash) in tx_ids {
+                let Some(tx_receipt) = self.get_tx_receipt(tx_hash.into())? else {
+                    continue;
+                };
+
+                for log in tx_receipt.logs {
+                    if log.matches(None, topics) {
+                        logs.push(log);
+                        check_result_limit(logs.len(), result_limit)?;
+                    }
+                }
+            }
+        }
+
+        Ok(logs)
+    }
+
+    /// Check the bloom filter of a block to see if it may contain logs matching the topics
+    ///
+    /// Blocks that are not finalised yet don't have a bloom filter, so they may always match
+    fn block_may_match(
+        &self,
+        block_number: u64,
+        topics: &Option<Vec<SingleOrVec<Option<B256>>>>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let Some(topics) = topics else {
+            return Ok(true);
+        };
+        let Some(block) = self.block_number_to_block.get(block_number)? else {
+            return Ok(true);
+        };
+
+        let bloom = Bloom::from(block.logs_bloom.bytes);
+        Ok(topics.iter().all(|topic| match topic {
+            SingleOrVec::Single(None) => true,
+            SingleOrVec::Single(Some(topic)) => {
+                bloom.contains_input(BloomInput::Raw(topic.as_slice()))
+            }
+            SingleOrVec::Vec(topics) => {
+                topics.iter().all(|x| x.is_none())
+                    || topics.iter().any(|x| {
+                        x.is_some_and(|topic| {
+                            bloom.contains_input(BloomInput::Raw(topic.as_slice()))
+                        })
+                    })
+            }
+        }))
+    }
+}
+
+/// Returns the topics that can be looked up from the topic index, or None to match any topic
+fn indexed_topics(topic: &SingleOrVec<Option<B256>>) -> Option<Vec<B256>> {
+    match topic {
+        SingleOrVec::Single(topic) => topic.map(|x| vec![x]),
+        // None is ignored in OR condition, see LogED::matches
+        SingleOrVec::Vec(topics) => {
+            let topics: Vec<B256> = topics.iter().flatten().cloned().collect();
+            // An OR condition without topics matches any topic, see LogED::matches
+            (!topics.is_empty()).then_some(topics)
+        }
+    }
+}
+
+fn check_result_limit(result_count: usize, result_limit: u64) -> Result<(), Box<dyn Error>> {
+    if result_limit != 0 && result_count as u64 > result_limit {
+        return Err(format!(
+            "Query returned more than {} results, please narrow the block range",
+            result_limit
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl DatabaseRef for Brc20ProgDatabaseReader<'_> {
+    type Error = DBError;
+
+    /// Get basic account information.
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.get_account_info(address)
+            .map(|x| {
+                x.map(|x| {
+                    let mut account_info: AccountInfo = x.into();
+                    account_info.code = Some(
+                        self.code_by_hash_ref(account_info.code_hash)
+                            .unwrap_or_default(),
+                    );
+                    account_info
+                })
+            })
+            .map_err(DBError::from)
+    }
+
+    /// Get account code by its hash.
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.get_code(code_hash)
+            .map(|x| x.map(|x| x.bytecode).unwrap_or(Bytecode::new()))
+            .map_err(DBError::from)
+    }
+
+    /// Get storage value of address at index.
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.get_account_memory(address, index)
+            .map(|x| x.map(|x| x.uint).unwrap_or(U256::ZERO))
+            .map_err(DBError::from)
+    }
+
+    /// Get block hash by block number.
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.get_block_hash(number)
+            .map(|x| x.unwrap_or(B256::ZERO))
+            .map_err(DBError::from)
+    }
+}
+
+// Reads don't modify the reader, so the EVM database methods are the same as the reference ones
+impl DatabaseTrait for Brc20ProgDatabaseReader<'_> {
+    type Error = DBError;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.basic_ref(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.code_by_hash_ref(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.storage_ref(address, index)
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.block_hash_ref(number)
+    }
+}
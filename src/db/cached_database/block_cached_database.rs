@@ -1,18 +1,39 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::hash::Hash;
-use std::path::Path;
+use std::sync::Arc;
 
-use rocksdb::{IteratorMode, Options, DB};
+use rocksdb::{IteratorMode, WriteBatch, DB};
 
 use crate::db::cached_database::BlockHistoryCache;
+use crate::db::database::{
+    column_family, ArchiveDatabase, ArchiveDatabaseReader, DatabaseSource, LayeredMap,
+};
 use crate::db::types::{Decode, Encode};
 
+// Changes that are not written to the archive yet, mapped to the block numbers they were made at
+type ArchiveChanges<K, V> = LayeredMap<K, Arc<BTreeMap<u64, Option<V>>>>;
+
+// Cache entries and archive changes of keys changed since the block started, as they were before
+// the first change, used to abort the block
+type BlockJournal<K, V, C> = HashMap<K, (Option<Arc<C>>, Option<Arc<BTreeMap<u64, Option<V>>>>)>;
+
 // Database to store data that is mapped to a block number with a history cache
 //
-// It uses a cache to store the data in memory and only writes to the database when commit is called
+// It uses a cache to store the data in memory and only writes to the database when its changes are
+// written with a batch, see write_to_batch
 // It also supports reorg by reverting back the state to the latest valid block
 //
+// Latest values and their history caches are kept in two column families of a shared database,
+// named after the database and with a _cache suffix
+//
+// If archive is enabled, every change is also kept in an ArchiveDatabase, so values can be read
+// at any block number and reorgs are not limited to MAX_REORG_HISTORY_SIZE blocks
+//
+// The cache is shared with views of the database using a LayeredMap, so readers can keep using
+// a view while the database is being modified, and only the keys changed after it are copied
+//
 // K: the type of the key
 // V: the type of the value to store
 // C: the type of the cache
@@ -23,11 +44,17 @@ where
     V: Encode + Decode + Clone + Eq,
     C: BlockHistoryCache<V> + Encode + Decode + Clone,
 {
-    db: DB,
-    cache_db: DB,
-    cache: HashMap<K, C>,
+    db: Arc<DB>,
+    column_family: String,
+    cache_column_family: String,
+    cache: LayeredMap<K, Arc<C>>,
+
+    archive: Option<Arc<ArchiveDatabase<K, V>>>,
+    // Changes that are not written to the archive yet, all changes since the last commit
+    archive_changes: ArchiveChanges<K, V>,
 
-    _phantom: std::marker::PhantomData<V>,
+    // Journal of the block being indexed, None if no block is started
+    block_journal: Option<BlockJournal<K, V, C>>,
 }
 
 impl<K, V, C> BlockCachedDatabase<K, V, C>
@@ -38,27 +65,51 @@ where
 {
     /// Create a new BlockCachedDatabase
     ///
-    /// It creates a new database if it does not exist
+    /// The database should be opened with the column families returned by BlockCachedDatabase::column_families
     ///
-    /// path: &Path - the path to store the database
+    /// db: Arc<DB> - the database to store the data in
     /// name: &str - the name of the database
+    /// archive: bool - whether to keep every change in an archive database
     ///
     /// Returns: BlockCachedDatabase<K, V, C> - the created BlockCachedDatabase
-    pub fn new(path: &Path, name: &str) -> Result<Self, Box<dyn Error>> {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.set_max_open_files(256);
-        let db = DB::open(&opts, &path.join(Path::new(name)))?;
-        let cache_db = DB::open(&opts, &path.join(Path::new(&format!("{}_cache", name))))?;
-        let cache = HashMap::new();
+    pub fn new(db: Arc<DB>, name: &str, archive: bool) -> Result<Self, Box<dyn Error>> {
+        let cache_column_family = format!("{}_cache", name);
+        column_family(&db, name)?;
+        column_family(&db, &cache_column_family)?;
+        let archive = if archive {
+            Some(Arc::new(ArchiveDatabase::new(
+                db.clone(),
+                &format!("{}_archive", name),
+            )?))
+        } else {
+            None
+        };
         Ok(Self {
             db,
-            cache_db,
-            cache,
-            _phantom: std::marker::PhantomData,
+            column_family: name.to_string(),
+            cache_column_family,
+            cache: LayeredMap::default(),
+            archive,
+            archive_changes: LayeredMap::default(),
+            block_journal: None,
         })
     }
 
+    /// Column families used by a BlockCachedDatabase with the given name
+    ///
+    /// name: &str - the name of the database
+    /// archive: bool - whether the database keeps every change in an archive database
+    pub fn column_families(name: &str, archive: bool) -> Vec<String> {
+        let mut column_families = vec![name.to_string(), format!("{}_cache", name)];
+        if archive {
+            column_families.extend(ArchiveDatabase::<K, V>::column_families(&format!(
+                "{}_archive",
+                name
+            )));
+        }
+        column_families
+    }
+
     /// Get the value for a key
     ///
     /// It first checks the cache and then the database
@@ -68,61 +119,47 @@ where
     /// key: &K - the key to get the value for
     /// Returns: Option<V> - the value for the key
     pub fn latest(&self, key: &K) -> Result<Option<V>, Box<dyn Error>> {
-        if let Some(cache) = self.cache.get(key) {
-            return Ok(cache.latest());
-        }
-        if let Some(value) = self.db.get(&key.encode_vec())? {
-            let value = V::decode_vec(&value.to_vec())?;
-            return Ok(Some(value));
-        }
-        return Ok(None);
+        self.reader().latest(key)
     }
 
-    /// Get the range of values between start_key and end_key
+    /// Get the value for a key as it was at the end of a block
     ///
-    /// It returns a list of key-value pairs between start_key and end_key
+    /// See BlockCachedDatabaseReader::at for details
     ///
-    /// This only works if keys can be compared in their encoded form
+    /// key: &K - the key to get the value for
+    /// block_number: u64 - the block number to get the value at
+    /// Returns: Option<V> - the value for the key at the given block number
+    pub fn at(&self, key: &K, block_number: u64) -> Result<Option<V>, Box<dyn Error>> {
+        self.reader().at(key, block_number)
+    }
+
+    /// Get the range of values between start_key and end_key
+    ///
+    /// See BlockCachedDatabaseReader::get_range for details
     ///
     /// start_key: &K - the start key
     /// end_key: &K - the end key, exclusive
     /// Returns: Vec<(K, V)> - the list of key-value pairs
     pub fn get_range(&self, start_key: &K, end_key: &K) -> Result<Vec<(K, V)>, Box<dyn Error>> {
-        let mut kv_pairs = HashMap::new();
-        let start_key_bytes = start_key.encode_vec();
-        let end_key_bytes = end_key.encode_vec();
-
-        for kv_pair in self.db.iterator(IteratorMode::From(
-            &start_key_bytes,
-            rocksdb::Direction::Forward,
-        )) {
-            let (key, value) = kv_pair?;
-            if *key >= *end_key_bytes {
-                break;
-            }
-            let key = K::decode_vec(&key.to_vec())?;
-            let value = V::decode_vec(&value.to_vec())?;
-            kv_pairs.insert(key, value);
-        }
+        self.reader().get_range(start_key, end_key)
+    }
 
-        for key in self.cache.keys() {
-            let key_bytes = key.encode_vec();
-            if *key_bytes < *start_key_bytes {
-                continue;
-            }
-            if *key_bytes >= *end_key_bytes {
-                break;
-            }
-            if let Some(cache) = self.cache.get(key) {
-                if let Some(value) = cache.latest() {
-                    kv_pairs.insert(key.clone(), value.clone());
-                } else {
-                    kv_pairs.remove(key);
-                }
-            }
+    /// Get a view of the database
+    ///
+    /// The view shares the current cache and the underlying databases, changes made after this call
+    /// are not visible to the view's cache, so the databases should be read from a snapshot, see
+    /// BlockCachedDatabaseView::snapshot
+    ///
+    /// Returns: BlockCachedDatabaseView<K, V, C> - the view of the database
+    pub fn view(&self) -> BlockCachedDatabaseView<K, V, C> {
+        BlockCachedDatabaseView {
+            db: self.db.clone(),
+            column_family: self.column_family.clone(),
+            cache_column_family: self.cache_column_family.clone(),
+            cache: self.cache.clone(),
+            archive: self.archive.clone(),
+            archive_changes: self.archive_changes.clone(),
         }
-
-        Ok(kv_pairs.into_iter().collect())
     }
 
     /// Returns all keys and values in the database
@@ -131,7 +168,10 @@ where
     pub fn all(&self) -> Result<Vec<(K, V)>, Box<dyn Error>> {
         let mut kv_pairs: HashMap<K, V> = HashMap::new();
 
-        for kv_pair in self.db.full_iterator(IteratorMode::Start) {
+        for kv_pair in self.db.full_iterator_cf(
+            column_family(&self.db, &self.column_family)?,
+            IteratorMode::Start,
+        ) {
             let (key, value) = kv_pair?;
             let key = K::decode_vec(&key.to_vec())?;
             let value = V::decode_vec(&value.to_vec())?;
@@ -140,7 +180,7 @@ where
             }
         }
 
-        for (key, cache) in &self.cache {
+        for (key, cache) in self.cache.iter() {
             if let Some(value) = cache.latest() {
                 kv_pairs.insert(key.clone(), value.clone());
             } else {
@@ -153,73 +193,119 @@ where
 
     /// Set the value for a key
     ///
-    /// It sets the value in the cache, it's not written to the database until commit is called
+    /// It sets the value in the cache, it's not written to the database until it's written with a batch
     ///
     /// block_number: U256 - the block number to set the value for
     /// key: K - the key to set the value for
     /// value: V - the value to set
     pub fn set(&mut self, block_number: u64, key: &K, value: V) -> Result<(), Box<dyn Error>> {
+        self.record_block_change(key);
         let cache = self.retrieve_cache(&key)?;
         cache.set(block_number, value);
+        self.record_archive_change(block_number, key);
         Ok(())
     }
 
     /// Unset the value for a key
     ///
-    /// It removes the value from the cache, it's not written to the database until commit is called
+    /// It removes the value from the cache, it's not written to the database until it's written with a batch
     /// block_number: U256 - the block number to unset the value for
     /// key: K - the key to unset the value for
     pub fn unset(&mut self, block_number: u64, key: &K) -> Result<(), Box<dyn Error>> {
+        self.record_block_change(key);
         let cache = self.retrieve_cache(&key)?;
         cache.unset(block_number);
+        self.record_archive_change(block_number, key);
         Ok(())
     }
 
-    /// Commit the cache to the database
+    /// Start a block, changes made after this can be discarded with abort_block
     ///
-    /// It writes all the values in the cache to the database and clears the cache
+    /// It does nothing if a block is already started
+    pub fn begin_block(&mut self) {
+        if self.block_journal.is_none() {
+            self.block_journal = Some(HashMap::new());
+        }
+    }
+
+    /// End the block, its changes can't be aborted after this
+    pub fn end_block(&mut self) {
+        self.block_journal = None;
+    }
+
+    /// Discard the changes made since the block started, and end the block
+    ///
+    /// Only the keys changed in the block are restored, so the cost doesn't depend on the cache size
+    pub fn abort_block(&mut self) {
+        let Some(journal) = self.block_journal.take() else {
+            return;
+        };
+        for (key, (cache, archive_changes)) in journal {
+            match cache {
+                Some(cache) => self.cache.insert(key.clone(), cache),
+                None => self.cache.remove(&key),
+            };
+            match archive_changes {
+                Some(changes) => self.archive_changes.insert(key, changes),
+                None => self.archive_changes.remove(&key),
+            };
+        }
+    }
+
+    /// Add the values in the cache, their history and archive changes to a batch
     ///
-    /// block_number: U256 - the block number to commit at
-    pub fn commit(&mut self, block_number: u64) -> Result<(), Box<dyn Error>> {
+    /// Values are written to the database when the batch is written, the cache should be cleared after that
+    ///
+    /// batch: &mut WriteBatch - the batch to add the values to
+    /// block_number: u64 - the block number to commit at, history older than MAX_REORG_HISTORY_SIZE is dropped
+    pub fn write_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        block_number: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        self.write_archive_changes(batch)?;
+
+        let db_cf = column_family(&self.db, &self.column_family)?;
+        let cache_cf = column_family(&self.db, &self.cache_column_family)?;
         for (key, cache) in self.cache.iter() {
             let key_bytes = key.encode_vec();
-            let cache_bytes = cache.encode_vec();
             if cache.is_old(block_number) {
-                self.cache_db.delete(&key_bytes)?;
+                batch.delete_cf(cache_cf, &key_bytes);
             } else {
-                self.cache_db.put(&key_bytes, &cache_bytes)?;
+                batch.put_cf(cache_cf, &key_bytes, cache.encode_vec());
             }
 
             if let Some(value) = cache.latest() {
-                self.db.put(&key_bytes, &value.encode_vec())?;
+                batch.put_cf(db_cf, &key_bytes, value.encode_vec());
             } else {
-                self.db.delete(&key_bytes)?;
+                batch.delete_cf(db_cf, &key_bytes);
             }
         }
-
-        let keys_to_remove: Vec<K> = self
-            .cache
-            .iter()
-            .filter(|(_, cache)| cache.is_old(block_number))
-            .map(|(key, _)| key.clone())
-            .collect();
-        for key in keys_to_remove {
-            self.cache.remove(&key);
-        }
-
-        self.clear_cache();
         Ok(())
     }
 
     /// Revert the state to the latest valid block
     ///
-    /// It reverts the state of all the caches to the latest valid block and clears the cache
+    /// It reverts the state of all the caches to the latest valid block, reverted caches should be written with
+    /// write_to_batch at the latest valid block, in the same batch as the archive changes added here
     ///
-    /// latest_valid_block_number: U256 - the latest valid block number
-    pub fn reorg(&mut self, latest_valid_block_number: u64) -> Result<(), Box<dyn Error>> {
+    /// batch: &mut WriteBatch - the batch to add the archive changes to
+    /// latest_valid_block_number: u64 - the latest valid block number
+    pub fn reorg(
+        &mut self,
+        batch: &mut WriteBatch,
+        latest_valid_block_number: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.archive.is_some() {
+            self.reorg_archive(batch, latest_valid_block_number)?;
+        }
+
         let mut keys = HashSet::new();
         {
-            for kv_pair in self.cache_db.full_iterator(IteratorMode::Start) {
+            for kv_pair in self.db.full_iterator_cf(
+                column_family(&self.db, &self.cache_column_family)?,
+                IteratorMode::Start,
+            ) {
                 keys.insert(K::decode_vec(&kv_pair?.0.to_vec())?);
             }
             for key in self.cache.keys() {
@@ -230,35 +316,398 @@ where
             let cache = self.retrieve_cache(&key)?;
             cache.reorg(latest_valid_block_number);
         }
-        self.commit(latest_valid_block_number)?;
-        self.clear_cache();
         Ok(())
     }
 
     /// Clear the cache
     ///
-    /// It clears the cache, make sure to call commit before clearing the cache to write the data to the database
-    /// Otherwise the data will be lost
+    /// It clears the cache, make sure to write the cache with a batch before clearing it to write the data to the
+    /// database, otherwise the data will be lost
     pub fn clear_cache(&mut self) {
-        self.cache.clear();
+        // Views may still be using the old cache, so it's replaced instead of cleared
+        self.cache = LayeredMap::default();
+        self.archive_changes = LayeredMap::default();
+        self.block_journal = None;
+    }
+
+    /// Get the number of keys in the cache, i.e. keys changed since the last commit
+    pub fn cache_size(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Get a reader for the database, it sees all changes as soon as they are made
+    pub fn reader(&self) -> BlockCachedDatabaseReader<'_, K, V, C> {
+        BlockCachedDatabaseReader {
+            db: DatabaseSource::Live(&self.db),
+            column_family: &self.column_family,
+            cache_column_family: &self.cache_column_family,
+            cache: &self.cache,
+            archive: self.archive.as_ref().map(|archive| archive.reader()),
+            archive_changes: &self.archive_changes,
+        }
+    }
+
+    fn record_block_change(&mut self, key: &K) {
+        let Some(journal) = self.block_journal.as_mut() else {
+            return;
+        };
+        if !journal.contains_key(key) {
+            journal.insert(
+                key.clone(),
+                (
+                    self.cache.get(key).cloned(),
+                    self.archive_changes.get(key).cloned(),
+                ),
+            );
+        }
+    }
+
+    fn record_archive_change(&mut self, block_number: u64, key: &K) {
+        if self.archive.is_none() {
+            return;
+        }
+        let value = self.cache.get(key).and_then(|cache| cache.latest());
+        match self.archive_changes.get_mut(key) {
+            Some(changes) => {
+                Arc::make_mut(changes).insert(block_number, value);
+            }
+            None => self.archive_changes.insert(
+                key.clone(),
+                Arc::new(BTreeMap::from([(block_number, value)])),
+            ),
+        }
+    }
+
+    fn write_archive_changes(&self, batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        let Some(archive) = self.archive.as_ref() else {
+            return Ok(());
+        };
+        let changes = self
+            .archive_changes
+            .iter()
+            .flat_map(|(key, changes)| {
+                changes
+                    .iter()
+                    .map(move |(block_number, value)| (key.clone(), *block_number, value.clone()))
+            })
+            .collect();
+        archive.write(batch, changes)
+    }
+
+    // Reverts keys changed after the latest valid block using the archive, this works for any reorg depth
+    // Reverted keys get a new cache with only their value at the latest valid block, so their history is dropped
+    // Remaining keys are reverted using their history caches as usual
+    fn reorg_archive(
+        &mut self,
+        batch: &mut WriteBatch,
+        latest_valid_block_number: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        let archive = self.archive.clone().ok_or("Archive not found")?;
+
+        // Uncommitted changes after the latest valid block are dropped, the rest are written with the cache
+        let reverted_keys: HashSet<K> = self
+            .archive_changes
+            .iter()
+            .filter(|(_, changes)| {
+                changes
+                    .range(latest_valid_block_number + 1..)
+                    .next()
+                    .is_some()
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in reverted_keys.iter() {
+            if let Some(changes) = self.archive_changes.get_mut(key) {
+                Arc::make_mut(changes).split_off(&(latest_valid_block_number + 1));
+            }
+        }
+
+        let mut reverted_values: HashMap<K, Option<V>> = archive
+            .reorg(batch, latest_valid_block_number)?
+            .into_iter()
+            .collect();
+        for key in reverted_keys {
+            if let Entry::Vacant(entry) = reverted_values.entry(key) {
+                let value = archive
+                    .reader()
+                    .at(entry.key(), latest_valid_block_number)?;
+                entry.insert(value);
+            }
+        }
+
+        for (key, mut value) in reverted_values {
+            // Uncommitted changes are not in the archive yet
+            if let Some((_, change)) = self
+                .archive_changes
+                .get(&key)
+                .and_then(|changes| changes.range(..=latest_valid_block_number).next_back())
+            {
+                value = change.clone();
+            }
+            self.cache.insert(key, Arc::new(C::new(value)));
+        }
+        Ok(())
     }
 
     fn retrieve_cache(&mut self, key: &K) -> Result<&mut C, Box<dyn Error>> {
         if self.cache.contains_key(key) {
             // Do nothing, the cache is already in memory
-        } else if let Some(cache_bytes) = self.cache_db.get(&key.encode_vec())? {
+        } else if let Some(cache_bytes) = self.db.get_cf(
+            column_family(&self.db, &self.cache_column_family)?,
+            key.encode_vec(),
+        )? {
             let cache = C::decode_vec(&cache_bytes.to_vec())?;
-            self.cache.insert(key.clone(), cache);
+            self.cache.insert(key.clone(), Arc::new(cache));
         } else {
             // This is a cache miss, retrieve the value from the database to make sure
             // we have an old value at hand if reorg occurs
             let stored_value = self
                 .db
-                .get(&key.encode_vec())?
+                .get_cf(
+                    column_family(&self.db, &self.column_family)?,
+                    key.encode_vec(),
+                )?
                 .and_then(|value| V::decode_vec(&value.to_vec()).ok());
-            self.cache.insert(key.clone(), C::new(stored_value));
+            self.cache
+                .insert(key.clone(), Arc::new(C::new(stored_value)));
+        }
+        // Caches shared with a view are copied before they are modified
+        let cache = self.cache.get_mut(key).ok_or("Cache not found")?;
+        Ok(Arc::make_mut(cache))
+    }
+}
+
+/// Immutable view of a BlockCachedDatabase
+///
+/// It shares the cache and the databases with the BlockCachedDatabase it was created from, and can be
+/// used without holding a reference to it, so readers don't block the writer and vice versa
+pub struct BlockCachedDatabaseView<K, V, C>
+where
+    K: Encode + Decode + Eq + Hash + Clone,
+    V: Encode + Decode + Clone + Eq,
+    C: BlockHistoryCache<V> + Encode + Decode + Clone,
+{
+    db: Arc<DB>,
+    column_family: String,
+    cache_column_family: String,
+    cache: LayeredMap<K, Arc<C>>,
+    archive: Option<Arc<ArchiveDatabase<K, V>>>,
+    archive_changes: ArchiveChanges<K, V>,
+}
+
+impl<K, V, C> BlockCachedDatabaseView<K, V, C>
+where
+    K: Encode + Decode + Eq + Hash + Clone,
+    V: Encode + Decode + Clone + Eq,
+    C: BlockHistoryCache<V> + Encode + Decode + Clone,
+{
+    /// Take a snapshot of the view
+    ///
+    /// The snapshot should be taken while the database can't be modified, i.e. right after the view is
+    /// created, so the database is in the same state as the view's cache
+    ///
+    /// Returns: BlockCachedDatabaseReader<K, V, C> - reader that doesn't see changes made after the snapshot
+    pub fn snapshot(&self) -> BlockCachedDatabaseReader<'_, K, V, C> {
+        BlockCachedDatabaseReader {
+            db: DatabaseSource::snapshot(&self.db),
+            column_family: &self.column_family,
+            cache_column_family: &self.cache_column_family,
+            cache: &self.cache,
+            archive: self.archive.as_ref().map(|archive| archive.snapshot()),
+            archive_changes: &self.archive_changes,
+        }
+    }
+}
+
+/// Reads values from a BlockCachedDatabase, either live or from a snapshot
+pub struct BlockCachedDatabaseReader<'a, K, V, C>
+where
+    K: Encode + Decode + Eq + Hash + Clone,
+    V: Encode + Decode + Clone + Eq,
+    C: BlockHistoryCache<V> + Encode + Decode + Clone,
+{
+    db: DatabaseSource<'a>,
+    column_family: &'a str,
+    cache_column_family: &'a str,
+    cache: &'a LayeredMap<K, Arc<C>>,
+    archive: Option<ArchiveDatabaseReader<'a, K, V>>,
+    archive_changes: &'a ArchiveChanges<K, V>,
+}
+
+impl<K, V, C> BlockCachedDatabaseReader<'_, K, V, C>
+where
+    K: Encode + Decode + Eq + Hash + Clone,
+    V: Encode + Decode + Clone + Eq,
+    C: BlockHistoryCache<V> + Encode + Decode + Clone,
+{
+    /// Get the value for a key
+    ///
+    /// It first checks the cache and then the database
+    /// If the value is not found, it returns None
+    /// If the value is found, it returns Some(value)
+    ///
+    /// key: &K - the key to get the value for
+    /// Returns: Option<V> - the value for the key
+    pub fn latest(&self, key: &K) -> Result<Option<V>, Box<dyn Error>> {
+        if let Some(cache) = self.cache.get(key) {
+            return Ok(cache.latest());
+        }
+        if let Some(value) = self.db.get_cf(self.column_family, key.encode_vec())? {
+            let value = V::decode_vec(&value.to_vec())?;
+            return Ok(Some(value));
+        }
+        return Ok(None);
+    }
+
+    /// Get the value for a key as it was at the end of a block
+    ///
+    /// It first checks the cache, then the history stored in the cache database and falls back to the
+    /// latest value in the database if there is no history for the key, meaning it hasn't changed recently
+    ///
+    /// History is only kept for MAX_REORG_HISTORY_SIZE blocks, so the caller should make sure the block number
+    /// is within this range, unless archive is enabled
+    ///
+    /// key: &K - the key to get the value for
+    /// block_number: u64 - the block number to get the value at
+    /// Returns: Option<V> - the value for the key at the given block number
+    pub fn at(&self, key: &K, block_number: u64) -> Result<Option<V>, Box<dyn Error>> {
+        if let Some(archive) = &self.archive {
+            // Uncommitted changes are not in the archive yet
+            if let Some((_, value)) = self
+                .archive_changes
+                .get(key)
+                .and_then(|changes| changes.range(..=block_number).next_back())
+            {
+                return Ok(value.clone());
+            }
+            return archive.at(key, block_number);
+        }
+        if let Some(cache) = self.cache.get(key) {
+            return Ok(cache.at(block_number));
+        }
+        if let Some(cache_bytes) = self.db.get_cf(self.cache_column_family, key.encode_vec())? {
+            return Ok(C::decode_vec(&cache_bytes.to_vec())?.at(block_number));
+        }
+        if let Some(value) = self.db.get_cf(self.column_family, key.encode_vec())? {
+            let value = V::decode_vec(&value.to_vec())?;
+            return Ok(Some(value));
         }
-        Ok(self.cache.get_mut(key).ok_or("Cache not found")?)
+        return Ok(None);
+    }
+
+    /// Get the range of values between start_key and end_key
+    ///
+    /// It returns a list of key-value pairs between start_key and end_key
+    ///
+    /// This only works if keys can be compared in their encoded form
+    ///
+    /// start_key: &K - the start key
+    /// end_key: &K - the end key, exclusive
+    /// Returns: Vec<(K, V)> - the list of key-value pairs
+    pub fn get_range(&self, start_key: &K, end_key: &K) -> Result<Vec<(K, V)>, Box<dyn Error>> {
+        let mut kv_pairs = HashMap::new();
+        let start_key_bytes = start_key.encode_vec();
+        let end_key_bytes = end_key.encode_vec();
+
+        for kv_pair in self.db.iterator_cf(
+            self.column_family,
+            IteratorMode::From(&start_key_bytes, rocksdb::Direction::Forward),
+        )? {
+            let (key, value) = kv_pair?;
+            if *key >= *end_key_bytes {
+                break;
+            }
+            let key = K::decode_vec(&key.to_vec())?;
+            let value = V::decode_vec(&value.to_vec())?;
+            kv_pairs.insert(key, value);
+        }
+
+        // Cache is not ordered, so all keys are checked
+        for (key, cache) in self.cache.iter() {
+            let key_bytes = key.encode_vec();
+            if *key_bytes < *start_key_bytes || *key_bytes >= *end_key_bytes {
+                continue;
+            }
+            if let Some(value) = cache.latest() {
+                kv_pairs.insert(key.clone(), value.clone());
+            } else {
+                kv_pairs.remove(key);
+            }
+        }
+
+        Ok(kv_pairs.into_iter().collect())
+    }
+
+    /// Go through the values between start_key and end_key in the order of their encoded keys
+    ///
+    /// Unlike get_range, values are not collected, so the callback can stop early by returning false
+    ///
+    /// This only works if keys can be compared in their encoded form
+    ///
+    /// start_key: &K - the start key
+    /// end_key: &K - the end key, exclusive
+    /// callback: called with each key-value pair, returns whether to continue
+    pub fn for_each_in_range<F>(
+        &self,
+        start_key: &K,
+        end_key: &K,
+        mut callback: F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(K, V) -> Result<bool, Box<dyn Error>>,
+    {
+        let start_key_bytes = start_key.encode_vec();
+        let end_key_bytes = end_key.encode_vec();
+
+        // Cache is not ordered, so keys in the range are sorted to be merged with the database
+        let mut cached: BTreeMap<Vec<u8>, (K, Option<V>)> = BTreeMap::new();
+        for (key, cache) in self.cache.iter() {
+            let key_bytes = key.encode_vec();
+            if *key_bytes < *start_key_bytes || *key_bytes >= *end_key_bytes {
+                continue;
+            }
+            cached.insert(key_bytes, (key.clone(), cache.latest()));
+        }
+        let mut cached = cached.into_iter().peekable();
+
+        for kv_pair in self.db.iterator_cf(
+            self.column_family,
+            IteratorMode::From(&start_key_bytes, rocksdb::Direction::Forward),
+        )? {
+            let (key_bytes, value) = kv_pair?;
+            if *key_bytes >= *end_key_bytes {
+                break;
+            }
+            while let Some((_, (key, value))) =
+                cached.next_if(|(cached_key, _)| **cached_key < *key_bytes)
+            {
+                if let Some(value) = value {
+                    if !callback(key, value)? {
+                        return Ok(());
+                    }
+                }
+            }
+            // Cached values replace the ones in the database
+            let (key, value) = match cached.next_if(|(cached_key, _)| **cached_key == *key_bytes) {
+                Some((_, (key, Some(value)))) => (key, value),
+                Some((_, (_, None))) => continue,
+                None => (
+                    K::decode_vec(&key_bytes.to_vec())?,
+                    V::decode_vec(&value.to_vec())?,
+                ),
+            };
+            if !callback(key, value)? {
+                return Ok(());
+            }
+        }
+        for (_, (key, value)) in cached {
+            if let Some(value) = value {
+                if !callback(key, value)? {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -270,17 +719,50 @@ mod tests {
 
     use super::*;
     use crate::db::cached_database::BlockHistoryCacheData;
-    use crate::db::types::{AccountInfoED, AddressED};
+    use crate::db::database::{open_database, write_batch};
+    use crate::db::types::{AccountInfoED, AddressED, U256ED};
+
+    fn open<V, C>(path: &TempDir, archive: bool) -> BlockCachedDatabase<AddressED, V, C>
+    where
+        V: Encode + Decode + Clone + Eq,
+        C: BlockHistoryCache<V> + Encode + Decode + Clone,
+    {
+        let db = open_database(
+            path.path(),
+            &BlockCachedDatabase::<AddressED, V, C>::column_families("test_db", archive),
+        )
+        .unwrap();
+        BlockCachedDatabase::new(db, "test_db", archive).unwrap()
+    }
+
+    fn commit<V, C>(db: &mut BlockCachedDatabase<AddressED, V, C>, block_number: u64)
+    where
+        V: Encode + Decode + Clone + Eq,
+        C: BlockHistoryCache<V> + Encode + Decode + Clone,
+    {
+        let mut batch = WriteBatch::default();
+        db.write_to_batch(&mut batch, block_number).unwrap();
+        write_batch(&db.db, batch).unwrap();
+        db.clear_cache();
+    }
+
+    fn reorg<V, C>(db: &mut BlockCachedDatabase<AddressED, V, C>, latest_valid_block_number: u64)
+    where
+        V: Encode + Decode + Clone + Eq,
+        C: BlockHistoryCache<V> + Encode + Decode + Clone,
+    {
+        let mut batch = WriteBatch::default();
+        db.reorg(&mut batch, latest_valid_block_number).unwrap();
+        db.write_to_batch(&mut batch, latest_valid_block_number)
+            .unwrap();
+        write_batch(&db.db, batch).unwrap();
+        db.clear_cache();
+    }
 
     #[test]
     fn test_cache_only() {
         let path = TempDir::new().unwrap();
-        let mut db = BlockCachedDatabase::<
-            AddressED,
-            AccountInfoED,
-            BlockHistoryCacheData<AccountInfoED>,
-        >::new(path.path(), "test_db")
-        .unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
 
         let address: Address = "0x1234567890123456789012345678901234567890"
             .parse()
@@ -310,12 +792,7 @@ mod tests {
     #[test]
     fn test_database_commit() {
         let path = TempDir::new().unwrap();
-        let mut db = BlockCachedDatabase::<
-            AddressED,
-            AccountInfoED,
-            BlockHistoryCacheData<AccountInfoED>,
-        >::new(path.path(), "test_db")
-        .unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
 
         let address: Address = "0x1234567890123456789012345678901234567890"
             .parse()
@@ -335,20 +812,28 @@ mod tests {
         assert_eq!(account_info.nonce, 1u64.into());
         assert_eq!(account_info.code_hash, B256::from([1; 32]).into());
 
-        db.commit(1).unwrap();
-
-        let real_db = db.db;
+        commit(&mut db, 1);
 
-        let account_info = real_db.get(address_ed.encode_vec()).unwrap();
+        let account_info = db
+            .db
+            .get_cf(
+                column_family(&db.db, "test_db").unwrap(),
+                address_ed.encode_vec(),
+            )
+            .unwrap();
 
         let account_info = AccountInfoED::decode_vec(&account_info.unwrap().to_vec()).unwrap();
         assert_eq!(account_info.balance, U256::from(100).into());
         assert_eq!(account_info.nonce, 1u64.into());
         assert_eq!(account_info.code_hash, B256::from([1; 32]).into());
 
-        let cache_db = db.cache_db;
-
-        let cache = cache_db.get(address_ed.encode_vec()).unwrap();
+        let cache = db
+            .db
+            .get_cf(
+                column_family(&db.db, "test_db_cache").unwrap(),
+                address_ed.encode_vec(),
+            )
+            .unwrap();
 
         let cache =
             BlockHistoryCacheData::<AccountInfoED>::decode_vec(&cache.unwrap().to_vec()).unwrap();
@@ -363,12 +848,7 @@ mod tests {
     #[test]
     fn test_database_reorg() {
         let path = TempDir::new().unwrap();
-        let mut db = BlockCachedDatabase::<
-            AddressED,
-            AccountInfoED,
-            BlockHistoryCacheData<AccountInfoED>,
-        >::new(path.path(), "test_db")
-        .unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
 
         let address: Address = "0x1234567890123456789012345678901234567890"
             .parse()
@@ -388,8 +868,8 @@ mod tests {
         assert_eq!(account_info.nonce, 1u64.into());
         assert_eq!(account_info.code_hash, B256::from([1; 32]).into());
 
-        db.commit(1).unwrap();
-        db.reorg(0).unwrap();
+        commit(&mut db, 1);
+        reorg(&mut db, 0);
         db.clear_cache();
 
         let account_info = db.latest(&address_ed);
@@ -399,12 +879,7 @@ mod tests {
     #[test]
     fn test_database_reorg_10_blocks() {
         let path = TempDir::new().unwrap();
-        let mut db = BlockCachedDatabase::<
-            AddressED,
-            AccountInfoED,
-            BlockHistoryCacheData<AccountInfoED>,
-        >::new(path.path(), "test_db")
-        .unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
 
         let address: Address = "0x1234567890123456789012345678901234567890"
             .parse()
@@ -424,11 +899,11 @@ mod tests {
                 .into(),
             );
         }
-        db.commit(10).unwrap();
+        commit(&mut db, 10);
 
-        db.reorg(5).unwrap();
+        reorg(&mut db, 5);
 
-        db.commit(5).unwrap();
+        commit(&mut db, 5);
 
         let account_info = db.latest(&address_ed).unwrap().unwrap();
         assert_eq!(account_info.balance, U256::from(100 + 5).into());
@@ -437,14 +912,107 @@ mod tests {
     }
 
     #[test]
-    fn test_reorg_after_removing_an_old_cache() {
+    fn test_at() {
         let path = TempDir::new().unwrap();
-        let mut db = BlockCachedDatabase::<
-            AddressED,
-            AccountInfoED,
-            BlockHistoryCacheData<AccountInfoED>,
-        >::new(path.path(), "test_db")
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
+
+        let address_ed: AddressED = Address::from([1; 20]).into();
+
+        for i in 1..=5 {
+            db.set(
+                i,
+                &address_ed,
+                AccountInfo {
+                    balance: U256::from(100 + i),
+                    nonce: i,
+                    code_hash: [1; 32].into(),
+                    code: None,
+                }
+                .into(),
+            )
+            .unwrap();
+            commit(&mut db, i + 1);
+        }
+
+        assert!(db.at(&address_ed, 0).unwrap().is_none());
+        assert_eq!(db.at(&address_ed, 3).unwrap().unwrap().nonce, 3u64.into());
+        assert_eq!(db.at(&address_ed, 5).unwrap().unwrap().nonce, 5u64.into());
+
+        // Uncommitted values are not visible at committed heights
+        db.set(
+            6,
+            &address_ed,
+            AccountInfo {
+                balance: U256::from(106),
+                nonce: 6,
+                code_hash: [1; 32].into(),
+                code: None,
+            }
+            .into(),
+        )
         .unwrap();
+        assert_eq!(db.at(&address_ed, 5).unwrap().unwrap().nonce, 5u64.into());
+        assert_eq!(db.at(&address_ed, 6).unwrap().unwrap().nonce, 6u64.into());
+
+        // Old history is dropped, latest value is returned from the database
+        db.clear_cache();
+        commit(&mut db, 100);
+        assert_eq!(db.at(&address_ed, 100).unwrap().unwrap().nonce, 5u64.into());
+    }
+
+    #[test]
+    fn test_archive_at_and_deep_reorg() {
+        let path = TempDir::new().unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, true);
+
+        let address_ed: AddressED = Address::from([1; 20]).into();
+        let other_address_ed: AddressED = Address::from([2; 20]).into();
+        let account_info = |nonce: u64| -> AccountInfoED {
+            AccountInfo {
+                balance: U256::from(100 + nonce),
+                nonce,
+                code_hash: [1; 32].into(),
+                code: None,
+            }
+            .into()
+        };
+
+        for i in 1..=50 {
+            db.set(i, &address_ed, account_info(i)).unwrap();
+            if i % 20 == 0 {
+                commit(&mut db, i + 1);
+            }
+        }
+        db.set(50, &other_address_ed, account_info(50)).unwrap();
+        commit(&mut db, 51);
+        db.set(52, &address_ed, account_info(52)).unwrap();
+
+        // Values are available at any block, including uncommitted ones
+        assert!(db.at(&address_ed, 0).unwrap().is_none());
+        assert_eq!(db.at(&address_ed, 3).unwrap().unwrap().nonce, 3u64.into());
+        assert_eq!(db.at(&address_ed, 25).unwrap().unwrap().nonce, 25u64.into());
+        assert_eq!(db.at(&address_ed, 51).unwrap().unwrap().nonce, 50u64.into());
+        assert_eq!(db.at(&address_ed, 52).unwrap().unwrap().nonce, 52u64.into());
+
+        // Reorgs deeper than MAX_REORG_HISTORY_SIZE are possible
+        reorg(&mut db, 5);
+        assert_eq!(db.latest(&address_ed).unwrap().unwrap().nonce, 5u64.into());
+        assert!(db.latest(&other_address_ed).unwrap().is_none());
+        assert_eq!(db.at(&address_ed, 100).unwrap().unwrap().nonce, 5u64.into());
+
+        db.set(6, &other_address_ed, account_info(6)).unwrap();
+        commit(&mut db, 7);
+        assert_eq!(
+            db.latest(&other_address_ed).unwrap().unwrap().nonce,
+            6u64.into()
+        );
+        assert_eq!(db.at(&address_ed, 6).unwrap().unwrap().nonce, 5u64.into());
+    }
+
+    #[test]
+    fn test_reorg_after_removing_an_old_cache() {
+        let path = TempDir::new().unwrap();
+        let mut db = open::<AccountInfoED, BlockHistoryCacheData<AccountInfoED>>(&path, false);
 
         let address: Address = "0x1234567890123456789012345678901234567890"
             .parse()
@@ -465,11 +1033,11 @@ mod tests {
         .unwrap();
 
         // Value committed at block height 10 first
-        db.commit(10).unwrap();
+        commit(&mut db, 10);
 
         // This should remove the old cache, as the cache latest value is at block height 10
-        db.commit(22).unwrap();
-        db.reorg(21).unwrap();
+        commit(&mut db, 22);
+        reorg(&mut db, 21);
 
         // Value set at block height 22, causing a new cache to be created
         db.set(
@@ -487,12 +1055,136 @@ mod tests {
 
         // Another reorg at block height 22, with an old cache
         // that doesn't see beyond block 11, this fails.
-        db.commit(22).unwrap();
-        db.reorg(21).unwrap();
+        commit(&mut db, 22);
+        reorg(&mut db, 21);
 
         let account_info = db.latest(&address_ed).unwrap().unwrap();
         assert_eq!(account_info.balance, U256::from(100 + 10).into());
         assert_eq!(account_info.nonce, 10u64.into());
         assert_eq!(account_info.code_hash, B256::from([1; 32]).into());
     }
+
+    #[test]
+    fn test_view_snapshot_is_isolated() {
+        let path = TempDir::new().unwrap();
+        let mut db = open::<U256ED, BlockHistoryCacheData<U256ED>>(&path, false);
+
+        let address: AddressED = [1u8; 20].into();
+        let other_address: AddressED = [2u8; 20].into();
+        db.set(1, &address, U256::from(100).into()).unwrap();
+        db.set(1, &other_address, U256::from(1000).into()).unwrap();
+        commit(&mut db, 1);
+        db.set(2, &address, U256::from(200).into()).unwrap();
+
+        let view = db.view();
+        let snapshot = view.snapshot();
+
+        // Modify both the cache and the database after the snapshot
+        db.set(3, &address, U256::from(300).into()).unwrap();
+        db.unset(3, &other_address).unwrap();
+        commit(&mut db, 3);
+        db.set(4, &address, U256::from(400).into()).unwrap();
+
+        assert_eq!(
+            snapshot.latest(&address).unwrap().unwrap(),
+            U256::from(200).into()
+        );
+        assert_eq!(
+            snapshot.at(&address, 1).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+        assert_eq!(
+            snapshot.latest(&other_address).unwrap().unwrap(),
+            U256::from(1000).into()
+        );
+
+        assert_eq!(
+            db.latest(&address).unwrap().unwrap(),
+            U256::from(400).into()
+        );
+        assert_eq!(db.at(&address, 3).unwrap().unwrap(), U256::from(300).into());
+        assert!(db.latest(&other_address).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_abort_block() {
+        let path = TempDir::new().unwrap();
+        let mut db = open::<U256ED, BlockHistoryCacheData<U256ED>>(&path, true);
+
+        let address: AddressED = [1u8; 20].into();
+        let committed_address: AddressED = [2u8; 20].into();
+        let new_address: AddressED = [3u8; 20].into();
+        db.set(1, &committed_address, U256::from(10).into())
+            .unwrap();
+        commit(&mut db, 1);
+        db.set(2, &address, U256::from(200).into()).unwrap();
+
+        // Changes of the aborted block are discarded, including keys loaded from the database
+        db.begin_block();
+        db.set(3, &address, U256::from(300).into()).unwrap();
+        db.set(3, &address, U256::from(301).into()).unwrap();
+        db.unset(3, &committed_address).unwrap();
+        db.set(3, &new_address, U256::from(30).into()).unwrap();
+        db.abort_block();
+
+        assert_eq!(
+            db.latest(&address).unwrap().unwrap(),
+            U256::from(200).into()
+        );
+        assert_eq!(
+            db.latest(&committed_address).unwrap().unwrap(),
+            U256::from(10).into()
+        );
+        assert!(db.latest(&new_address).unwrap().is_none());
+        assert!(db.archive_changes.get(&new_address).is_none());
+        assert_eq!(db.archive_changes.get(&address).unwrap().len(), 1);
+
+        // Ended blocks can't be aborted
+        db.begin_block();
+        db.set(3, &address, U256::from(300).into()).unwrap();
+        db.end_block();
+        db.abort_block();
+        assert_eq!(
+            db.latest(&address).unwrap().unwrap(),
+            U256::from(300).into()
+        );
+    }
+
+    #[test]
+    fn test_for_each_in_range() {
+        let path = TempDir::new().unwrap();
+        let mut db = open::<U256ED, BlockHistoryCacheData<U256ED>>(&path, false);
+
+        for byte in 1..=5u8 {
+            db.set(1, &[byte; 20].into(), U256::from(byte).into())
+                .unwrap();
+        }
+        commit(&mut db, 1);
+        // Cached changes are merged with the database in key order
+        db.set(2, &[2u8; 20].into(), U256::from(20).into()).unwrap();
+        db.unset(2, &[3u8; 20].into()).unwrap();
+        db.set(2, &[6u8; 20].into(), U256::from(6).into()).unwrap();
+        db.set(2, &[0u8; 20].into(), U256::from(0).into()).unwrap();
+
+        let collect = |limit: usize| {
+            let mut values = Vec::new();
+            db.reader()
+                .for_each_in_range(&[1u8; 20].into(), &[7u8; 20].into(), |key, value| {
+                    values.push((key, value));
+                    Ok(values.len() < limit)
+                })
+                .unwrap();
+            values
+        };
+        let expected: Vec<(AddressED, U256ED)> = vec![
+            ([1u8; 20].into(), U256::from(1).into()),
+            ([2u8; 20].into(), U256::from(20).into()),
+            ([4u8; 20].into(), U256::from(4).into()),
+            ([5u8; 20].into(), U256::from(5).into()),
+            ([6u8; 20].into(), U256::from(6).into()),
+        ];
+        assert_eq!(collect(usize::MAX), expected);
+        // The callback stops the iteration
+        assert_eq!(collect(2), expected[..2]);
+    }
 }
@@ -19,6 +19,7 @@ where
 {
     fn new(initial_value: Option<V>) -> Self;
     fn latest(&self) -> Option<V>;
+    fn at(&self, block_number: u64) -> Option<V>;
     fn set(&mut self, block_number: u64, value: V);
     fn unset(&mut self, block_number: u64);
     fn reorg(&mut self, latest_valid_block_number: u64);
@@ -72,6 +73,22 @@ where
             .expect("Cache is never empty")
     }
 
+    /// Get the value as it was at the end of a block number
+    ///
+    /// Returns the value set at the greatest block number less than or equal to the given block number.
+    /// Values older than the history kept in the cache are not available, in that case the oldest
+    /// stored value is returned, so callers should make sure the block number is within MAX_REORG_HISTORY_SIZE.
+    ///
+    /// block_number: u64 - the block number
+    fn at(&self, block_number: u64) -> Option<V> {
+        self.cache
+            .range(..=block_number)
+            .next_back()
+            .or_else(|| self.cache.iter().next())
+            .map(|(_, value)| value.clone())
+            .expect("Cache is never empty")
+    }
+
     /// Set the value for a block number
     ///
     /// block_number: U256 - the block number
@@ -301,6 +318,22 @@ mod tests {
         assert_eq!(cache.cache.len(), 1);
     }
 
+    #[test]
+    fn test_at() {
+        let mut cache = BlockHistoryCacheData::<U256ED>::new(None);
+
+        cache.set(2, U256::from(200).into());
+        cache.set(4, U256::from(400).into());
+        cache.unset(6);
+
+        assert!(cache.at(1).is_none());
+        assert_eq!(cache.at(2).unwrap(), U256::from(200).into());
+        assert_eq!(cache.at(3).unwrap(), U256::from(200).into());
+        assert_eq!(cache.at(5).unwrap(), U256::from(400).into());
+        assert!(cache.at(6).is_none());
+        assert!(cache.at(100).is_none());
+    }
+
     #[test]
     #[should_panic]
     fn test_reorg_too_old() {
@@ -4,4 +4,6 @@ mod block_history_cache;
 pub use block_history_cache::{BlockHistoryCache, BlockHistoryCacheData};
 
 mod block_cached_database;
-pub use block_cached_database::BlockCachedDatabase;
+pub use block_cached_database::{
+    BlockCachedDatabase, BlockCachedDatabaseReader, BlockCachedDatabaseView,
+};
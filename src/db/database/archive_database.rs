@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+
+use crate::db::database::{column_family, DatabaseSource};
+use crate::db::types::{Decode, Encode};
+
+/// Database to store every change of a value together with the block number it was made at
+///
+/// Unlike the history kept in BlockHistoryCacheData, changes are never dropped unless there is a reorg,
+/// so values can be read at any block number and reorgs can go deeper than MAX_REORG_HISTORY_SIZE
+///
+/// It uses the following column families, prefixed with the name of the archive:
+/// values: encoded key + block number to the value set at that block, None if it was unset
+/// block_keys: block number + encoded key, to find keys changed after a block without scanning all values
+///
+/// K: the type of the key, encoded keys should not be prefixes of each other (fixed length or length prefixed)
+/// V: the type of the value to store
+pub struct ArchiveDatabase<K, V>
+where
+    K: Encode + Decode,
+    V: Encode + Decode,
+{
+    db: Arc<DB>,
+    values_cf: String,
+    block_keys_cf: String,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V> ArchiveDatabase<K, V>
+where
+    K: Encode + Decode,
+    V: Encode + Decode,
+{
+    /// Create a new ArchiveDatabase
+    ///
+    /// The database should be opened with the column families returned by ArchiveDatabase::column_families
+    ///
+    /// db: Arc<DB> - the database to store the archive in
+    /// name: &str - the name of the archive
+    ///
+    /// Returns: ArchiveDatabase<K, V> - the created ArchiveDatabase
+    pub fn new(db: Arc<DB>, name: &str) -> Result<Self, Box<dyn Error>> {
+        let [values_cf, block_keys_cf] = Self::column_families(name);
+        column_family(&db, &values_cf)?;
+        column_family(&db, &block_keys_cf)?;
+
+        Ok(Self {
+            db,
+            values_cf,
+            block_keys_cf,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Column families used by an archive with the given name
+    pub fn column_families(name: &str) -> [String; 2] {
+        [format!("{}_values", name), format!("{}_block_keys", name)]
+    }
+
+    /// Get a reader for the archive, it sees all changes as soon as they are written
+    pub fn reader(&self) -> ArchiveDatabaseReader<'_, K, V> {
+        self.reader_from(DatabaseSource::Live(&self.db))
+    }
+
+    /// Take a snapshot of the archive
+    ///
+    /// Returns: ArchiveDatabaseReader<K, V> - reader that doesn't see changes written after the snapshot
+    pub fn snapshot(&self) -> ArchiveDatabaseReader<'_, K, V> {
+        self.reader_from(DatabaseSource::snapshot(&self.db))
+    }
+
+    /// Add changes to a batch, they are written to the archive when the batch is written
+    ///
+    /// batch: &mut WriteBatch - the batch to add the changes to
+    /// changes: Vec<(K, u64, Option<V>)> - key, block number and the value set at that block
+    pub fn write(
+        &self,
+        batch: &mut WriteBatch,
+        changes: Vec<(K, u64, Option<V>)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let values_cf = column_family(&self.db, &self.values_cf)?;
+        let block_keys_cf = column_family(&self.db, &self.block_keys_cf)?;
+
+        for (key, block_number, value) in changes {
+            let key_bytes = key.encode_vec();
+            batch.put_cf(
+                values_cf,
+                Self::value_key(&key_bytes, block_number),
+                value.encode_vec(),
+            );
+            batch.put_cf(block_keys_cf, Self::block_key(block_number, &key_bytes), []);
+        }
+        Ok(())
+    }
+
+    /// Revert the archive to the latest valid block
+    ///
+    /// It adds the removal of all changes after the latest valid block to a batch
+    ///
+    /// batch: &mut WriteBatch - the batch to add the removals to
+    /// latest_valid_block_number: u64 - the latest valid block number
+    /// Returns: Vec<(K, Option<V>)> - keys changed after the latest valid block, with their values at that block
+    pub fn reorg(
+        &self,
+        batch: &mut WriteBatch,
+        latest_valid_block_number: u64,
+    ) -> Result<Vec<(K, Option<V>)>, Box<dyn Error>> {
+        let values_cf = column_family(&self.db, &self.values_cf)?;
+        let block_keys_cf = column_family(&self.db, &self.block_keys_cf)?;
+
+        let mut changed_keys = HashSet::new();
+        let start_key = (latest_valid_block_number + 1).to_be_bytes();
+        for kv_pair in self.db.iterator_cf(
+            block_keys_cf,
+            IteratorMode::From(&start_key, Direction::Forward),
+        ) {
+            let (block_key, _) = kv_pair?;
+            let (block_number_bytes, key_bytes) = block_key.split_at(8);
+            let block_number = u64::decode_vec(&block_number_bytes.to_vec())?;
+            batch.delete_cf(block_keys_cf, &block_key);
+            batch.delete_cf(values_cf, Self::value_key(key_bytes, block_number));
+            changed_keys.insert(key_bytes.to_vec());
+        }
+
+        // Values at the latest valid block are not affected by the removals above
+        let reader = self.reader();
+        let mut reverted_values = Vec::new();
+        for key_bytes in changed_keys {
+            let value = reader.at_encoded(&key_bytes, latest_valid_block_number)?;
+            reverted_values.push((K::decode_vec(&key_bytes)?, value));
+        }
+
+        Ok(reverted_values)
+    }
+
+    fn reader_from<'a>(&'a self, source: DatabaseSource<'a>) -> ArchiveDatabaseReader<'a, K, V> {
+        ArchiveDatabaseReader {
+            source,
+            values_cf: &self.values_cf,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn value_key(key_bytes: &[u8], block_number: u64) -> Vec<u8> {
+        let mut value_key = key_bytes.to_vec();
+        value_key.extend_from_slice(&block_number.to_be_bytes());
+        value_key
+    }
+
+    fn block_key(block_number: u64, key_bytes: &[u8]) -> Vec<u8> {
+        let mut block_key = block_number.to_be_bytes().to_vec();
+        block_key.extend_from_slice(key_bytes);
+        block_key
+    }
+}
+
+/// Reads values from an ArchiveDatabase, either live or from a snapshot
+pub struct ArchiveDatabaseReader<'a, K, V>
+where
+    K: Encode + Decode,
+    V: Encode + Decode,
+{
+    source: DatabaseSource<'a>,
+    values_cf: &'a str,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V> ArchiveDatabaseReader<'_, K, V>
+where
+    K: Encode + Decode,
+    V: Encode + Decode,
+{
+    /// Get the value for a key as it was at the end of a block
+    ///
+    /// key: &K - the key to get the value for
+    /// block_number: u64 - the block number to get the value at
+    /// Returns: Option<V> - the value for the key, None if it was not set at the given block
+    pub fn at(&self, key: &K, block_number: u64) -> Result<Option<V>, Box<dyn Error>> {
+        self.at_encoded(&key.encode_vec(), block_number)
+    }
+
+    fn at_encoded(&self, key_bytes: &[u8], block_number: u64) -> Result<Option<V>, Box<dyn Error>> {
+        let mut iterator = self.source.raw_iterator_cf(self.values_cf)?;
+        iterator.seek_for_prev(ArchiveDatabase::<K, V>::value_key(key_bytes, block_number));
+        iterator.status()?;
+        match (iterator.key(), iterator.value()) {
+            (Some(stored_key), Some(value))
+                if stored_key.len() == key_bytes.len() + 8 && stored_key.starts_with(key_bytes) =>
+            {
+                Ok(Option::<V>::decode_vec(&value.to_vec())?)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use alloy::primitives::U256;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::db::database::{open_database, write_batch};
+    use crate::db::types::{AddressED, U256ED};
+
+    fn open_archive(path: &Path) -> ArchiveDatabase<AddressED, U256ED> {
+        let db = open_database(
+            path,
+            &ArchiveDatabase::<AddressED, U256ED>::column_families("test_db"),
+        )
+        .unwrap();
+        ArchiveDatabase::new(db, "test_db").unwrap()
+    }
+
+    fn write(
+        db: &ArchiveDatabase<AddressED, U256ED>,
+        changes: Vec<(AddressED, u64, Option<U256ED>)>,
+    ) {
+        let mut batch = WriteBatch::default();
+        db.write(&mut batch, changes).unwrap();
+        write_batch(&db.db, batch).unwrap();
+    }
+
+    #[test]
+    fn test_archive_at() {
+        let path = TempDir::new().unwrap();
+        let db = open_archive(path.path());
+
+        let address: AddressED = [1u8; 20].into();
+        let other_address: AddressED = [2u8; 20].into();
+        write(
+            &db,
+            vec![
+                (address, 1, Some(U256::from(100).into())),
+                (address, 20, Some(U256::from(200).into())),
+                (other_address, 10, Some(U256::from(300).into())),
+                (address, 30, None),
+            ],
+        );
+
+        assert!(db.reader().at(&address, 0).unwrap().is_none());
+        assert_eq!(
+            db.reader().at(&address, 1).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+        assert_eq!(
+            db.reader().at(&address, 19).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+        assert_eq!(
+            db.reader().at(&address, 25).unwrap().unwrap(),
+            U256::from(200).into()
+        );
+        assert!(db.reader().at(&address, 30).unwrap().is_none());
+        assert!(db.reader().at(&other_address, 9).unwrap().is_none());
+        assert_eq!(
+            db.reader().at(&other_address, 100).unwrap().unwrap(),
+            U256::from(300).into()
+        );
+    }
+
+    #[test]
+    fn test_archive_reorg() {
+        let path = TempDir::new().unwrap();
+        let db = open_archive(path.path());
+
+        let address: AddressED = [1u8; 20].into();
+        let other_address: AddressED = [2u8; 20].into();
+        write(
+            &db,
+            vec![
+                (address, 1, Some(U256::from(100).into())),
+                (address, 20, Some(U256::from(200).into())),
+                (other_address, 25, Some(U256::from(300).into())),
+            ],
+        );
+
+        let mut batch = WriteBatch::default();
+        let mut reverted = db.reorg(&mut batch, 5).unwrap();
+        write_batch(&db.db, batch).unwrap();
+        reverted.sort_by_key(|(key, _)| key.address);
+        assert_eq!(
+            reverted,
+            vec![
+                (address, Some(U256::from(100).into())),
+                (other_address, None),
+            ]
+        );
+
+        assert_eq!(
+            db.reader().at(&address, 30).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+        assert!(db.reader().at(&other_address, 30).unwrap().is_none());
+
+        // Changes are persisted
+        drop(db);
+        let db = open_archive(path.path());
+        assert_eq!(
+            db.reader().at(&address, 30).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+    }
+
+    #[test]
+    fn test_archive_snapshot() {
+        let path = TempDir::new().unwrap();
+        let db = open_archive(path.path());
+
+        let address: AddressED = [1u8; 20].into();
+        write(&db, vec![(address, 1, Some(U256::from(100).into()))]);
+
+        let snapshot = db.snapshot();
+        write(&db, vec![(address, 2, Some(U256::from(200).into()))]);
+
+        assert_eq!(
+            snapshot.at(&address, 2).unwrap().unwrap(),
+            U256::from(100).into()
+        );
+        assert_eq!(
+            db.reader().at(&address, 2).unwrap().unwrap(),
+            U256::from(200).into()
+        );
+    }
+}
@@ -1,15 +1,19 @@
 use std::collections::BTreeMap;
 use std::error::Error;
-use std::path::Path;
+use std::sync::Arc;
 
-use rocksdb::{IteratorMode, Options, DB};
+use rocksdb::{IteratorMode, WriteBatch, DB};
 
+use crate::db::database::{column_family, DatabaseSource, LayeredMap};
 use crate::db::types::{Decode, Encode, U64ED};
 
 /// Database to store data that is mapped to a block number
 ///
-/// It uses a cache to store the data in memory and only writes to the database when commit is called
+/// It uses a cache to store the data in memory and only writes to the database when its changes are
+/// written with a batch, see write_to_batch
 /// It also supports reorg by deleting data that is not valid anymore
+/// The cache is shared with views of the database using a LayeredMap, so only blocks set after a view is
+/// created are copied
 /// It uses U256 as the key (block_number) and V as the value
 ///
 /// V: the type of the value to store
@@ -18,8 +22,11 @@ pub struct BlockDatabase<V>
 where
     V: Encode + Decode + Clone,
 {
-    db: DB,
-    cache: BTreeMap<u64, V>,
+    db: Arc<DB>,
+    column_family: String,
+    cache: LayeredMap<u64, V>,
+    // Values of block numbers set since the block started, as they were before, None if no block is started
+    block_journal: Option<BTreeMap<u64, Option<V>>>,
 }
 
 impl<V> BlockDatabase<V>
@@ -28,20 +35,19 @@ where
 {
     /// Create a new BlockDatabase
     ///
-    /// It creates a new database if it does not exist
+    /// The database should be opened with a column family named after the BlockDatabase
     ///
-    /// path: &Path - the path to the database
-    /// name: &str - the name of the database
+    /// db: Arc<DB> - the database to store the data in
+    /// name: &str - the name of the database, also the name of its column family
     ///
     /// Returns: BlockDatabase<V> - the created BlockDatabase
-    pub fn new(path: &Path, name: &str) -> Result<Self, Box<dyn Error>> {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.set_max_open_files(256);
-        let db = DB::open(&opts, &path.join(Path::new(name)))?;
+    pub fn new(db: Arc<DB>, name: &str) -> Result<Self, Box<dyn Error>> {
+        column_family(&db, name)?;
         Ok(Self {
             db,
-            cache: BTreeMap::new(),
+            column_family: name.to_string(),
+            cache: LayeredMap::default(),
+            block_journal: None,
         })
     }
 
@@ -54,37 +60,90 @@ where
     /// block_number: u64 - the block number to get the value for
     /// Returns: Option<V> - the value for the block number
     pub fn get(&self, key: u64) -> Result<Option<V>, Box<dyn Error>> {
-        if let Some(value) = self.cache.get(&key) {
-            return Ok(Some(value.clone()));
-        }
+        self.reader().get(key)
+    }
 
-        let Some(value_bytes) = self.db.get(&key.encode_vec())? else {
-            return Ok(None);
-        };
+    /// Get the number of blocks in the cache, i.e. blocks set since the last commit
+    pub fn cache_size(&self) -> usize {
+        self.cache.len()
+    }
 
-        let value = V::decode_vec(&value_bytes)?;
-        Ok(Some(value))
+    /// Get a reader for the database, it sees all changes as soon as they are made
+    pub fn reader(&self) -> BlockDatabaseReader<'_, V> {
+        BlockDatabaseReader {
+            db: DatabaseSource::Live(&self.db),
+            column_family: &self.column_family,
+            cache: &self.cache,
+        }
+    }
+
+    /// Get a view of the database
+    //
+    /// The view shares the current cache and the underlying database, changes made after this
+    /// call are not visible to the view's cache, the database itself should be read from a snapshot
+    //
+    /// Returns: BlockDatabaseView<V> - the view of the database
+    pub fn view(&self) -> BlockDatabaseView<V> {
+        BlockDatabaseView {
+            db: self.db.clone(),
+            column_family: self.column_family.clone(),
+            cache: self.cache.clone(),
+        }
     }
 
     /// Set the value for a block number
     //
-    /// It sets the value in the cache, it's not written to the database until commit is called
+    /// It sets the value in the cache, it's not written to the database until it's written with a batch
     //
     /// block_number: u64 - the block number to set the value for
     /// value: V - the value to set
     pub fn set(&mut self, block_number: u64, value: V) {
-        self.cache.insert(block_number, value.clone());
+        if let Some(journal) = self.block_journal.as_mut() {
+            journal
+                .entry(block_number)
+                .or_insert_with(|| self.cache.get(&block_number).cloned());
+        }
+        self.cache.insert(block_number, value);
     }
 
-    /// Commit the cache to the database
+    /// Start a block, values set after this can be discarded with abort_block
     //
-    /// It writes all the values in the cache to the database
+    /// It does nothing if a block is already started
+    pub fn begin_block(&mut self) {
+        if self.block_journal.is_none() {
+            self.block_journal = Some(BTreeMap::new());
+        }
+    }
+
+    /// End the block, its values can't be discarded after this
+    pub fn end_block(&mut self) {
+        self.block_journal = None;
+    }
+
+    /// Discard the values set since the block started, and end the block
+    pub fn abort_block(&mut self) {
+        let Some(journal) = self.block_journal.take() else {
+            return;
+        };
+        for (block_number, value) in journal {
+            match value {
+                Some(value) => self.cache.insert(block_number, value),
+                None => self.cache.remove(&block_number),
+            };
+        }
+    }
+
+    /// Add the values in the cache to a batch
+    //
+    /// Values are written to the database when the batch is written
     /// It does not clear the cache
-    pub fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+    //
+    /// batch: &mut WriteBatch - the batch to add the values to
+    pub fn write_to_batch(&self, batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        let cf_handle = column_family(&self.db, &self.column_family)?;
         for (key, value) in self.cache.iter() {
-            self.db.put(&key.encode_vec(), &value.encode_vec())?;
+            batch.put_cf(cf_handle, key.encode_vec(), value.encode_vec());
         }
-        self.db.flush()?;
         Ok(())
     }
 
@@ -92,10 +151,12 @@ where
     //
     /// It clears the cache
     //
-    /// This does not delete the data from the database, make sure to call commit before clearing the cache
+    /// This does not delete the data from the database, make sure to write the cache with a batch before clearing it
     /// to write the data to the database, otherwise the data will be lost
     pub fn clear_cache(&mut self) {
-        self.cache.clear();
+        // Views may still be using the old cache, so it's replaced instead of cleared
+        self.cache = LayeredMap::default();
+        self.block_journal = None;
     }
 
     /// Get the last key in the database
@@ -105,28 +166,42 @@ where
     //
     /// Returns: Option<u64> - the last key in the database
     pub fn last_key(&self) -> Result<Option<u64>, Box<dyn Error>> {
-        let db_last_key = match self.db.full_iterator(IteratorMode::End).take(1).last() {
+        let db_last_key = match self
+            .db
+            .full_iterator_cf(
+                column_family(&self.db, &self.column_family)?,
+                IteratorMode::End,
+            )
+            .take(1)
+            .last()
+        {
             Some(Ok((key, _))) => Some(U64ED::decode_vec(&key.to_vec())?.into()),
             _ => None,
         };
 
-        let cache_last_key = self.cache.keys().last().map(|key| *key);
+        let cache_last_key = self.cache.keys().max().copied();
 
         Ok(std::cmp::max(db_last_key, cache_last_key))
     }
 
     /// Reorg the database
     //
-    /// It deletes all the data that is not valid anymore, i.e. the data with block number greater than latest_valid_block_number
-    /// Make sure to call commit after calling this function to write the changes to the database
+    /// It removes all the data that is not valid anymore from the cache, i.e. the data with block number
+    /// greater than latest_valid_block_number, and adds their deletion from the database to a batch
     //
+    /// batch: &mut WriteBatch - the batch to add the deletions to
     /// latest_valid_block_number: u64 - the latest valid block number
-    pub fn reorg(&mut self, latest_valid_block_number: u64) -> Result<(), Box<dyn Error>> {
+    pub fn reorg(
+        &mut self,
+        batch: &mut WriteBatch,
+        latest_valid_block_number: u64,
+    ) -> Result<(), Box<dyn Error>> {
         let mut current = latest_valid_block_number + 1;
         let last_block = self.last_key()?;
         if let Some(end) = last_block {
+            let cf_handle = column_family(&self.db, &self.column_family)?;
             while end >= current {
-                self.db.delete(&U64ED::from(current).encode_vec())?;
+                batch.delete_cf(cf_handle, U64ED::from(current).encode_vec());
                 self.cache.remove(&current);
                 current += 1;
             }
@@ -135,19 +210,102 @@ where
     }
 }
 
+/// Immutable view of a BlockDatabase
+///
+/// It's used to read the database without holding a reference to it, so it can be modified while the view is in use
+pub struct BlockDatabaseView<V>
+where
+    V: Encode + Decode + Clone,
+{
+    db: Arc<DB>,
+    column_family: String,
+    cache: LayeredMap<u64, V>,
+}
+
+impl<V> BlockDatabaseView<V>
+where
+    V: Encode + Decode + Clone,
+{
+    /// Take a snapshot of the view
+    //
+    /// Returns: BlockDatabaseReader<V> - reader that doesn't see changes made after the view was created
+    pub fn snapshot(&self) -> BlockDatabaseReader<'_, V> {
+        BlockDatabaseReader {
+            db: DatabaseSource::snapshot(&self.db),
+            column_family: &self.column_family,
+            cache: &self.cache,
+        }
+    }
+}
+
+/// Reads values from a BlockDatabase, either live or from a snapshot
+pub struct BlockDatabaseReader<'a, V>
+where
+    V: Encode + Decode + Clone,
+{
+    db: DatabaseSource<'a>,
+    column_family: &'a str,
+    cache: &'a LayeredMap<u64, V>,
+}
+
+impl<V> BlockDatabaseReader<'_, V>
+where
+    V: Encode + Decode + Clone,
+{
+    /// Get the value for a block number
+    //
+    /// It first checks the cache and then the database
+    //
+    /// block_number: u64 - the block number to get the value for
+    /// Returns: Option<V> - the value for the block number
+    pub fn get(&self, key: u64) -> Result<Option<V>, Box<dyn Error>> {
+        if let Some(value) = self.cache.get(&key) {
+            return Ok(Some(value.clone()));
+        }
+
+        let Some(value_bytes) = self.db.get_cf(self.column_family, key.encode_vec())? else {
+            return Ok(None);
+        };
+
+        let value = V::decode_vec(&value_bytes)?;
+        Ok(Some(value))
+    }
+}
+
 // tests
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use alloy::primitives::U256;
     use tempfile::TempDir;
 
     use super::*;
+    use crate::db::database::{open_database, write_batch};
     use crate::db::types::U256ED;
 
+    fn open_block_database(path: &Path) -> BlockDatabase<U256ED> {
+        let db = open_database(path, &["test".to_string()]).unwrap();
+        BlockDatabase::new(db, "test").unwrap()
+    }
+
+    fn commit(db: &BlockDatabase<U256ED>) {
+        let mut batch = WriteBatch::default();
+        db.write_to_batch(&mut batch).unwrap();
+        write_batch(&db.db, batch).unwrap();
+    }
+
+    fn reorg(db: &mut BlockDatabase<U256ED>, latest_valid_block_number: u64) {
+        let mut batch = WriteBatch::default();
+        db.reorg(&mut batch, latest_valid_block_number).unwrap();
+        db.write_to_batch(&mut batch).unwrap();
+        write_batch(&db.db, batch).unwrap();
+    }
+
     #[test]
     fn test_block_database() {
         let tempdir = TempDir::new().unwrap();
-        let mut db = BlockDatabase::<U256ED>::new(tempdir.path(), "test").unwrap();
+        let mut db = open_block_database(tempdir.path());
 
         let block_number = 1;
         let value = U256::from(100).into();
@@ -163,7 +321,7 @@ mod tests {
 
         assert_eq!(db.last_key().unwrap().unwrap(), 3);
 
-        db.commit().unwrap();
+        commit(&db);
         db.clear_cache();
 
         assert_eq!(db.get(1).unwrap().unwrap(), U256::from(100).into());
@@ -171,7 +329,7 @@ mod tests {
         assert_eq!(db.get(3).unwrap().unwrap(), U256::from(300).into());
         assert_eq!(db.last_key().unwrap().unwrap(), 3);
 
-        db.reorg(2).unwrap();
+        reorg(&mut db, 2);
 
         assert_eq!(db.get(1).unwrap().unwrap(), U256::from(100).into());
         assert_eq!(db.get(2).unwrap().unwrap(), U256::from(200).into());
@@ -179,4 +337,50 @@ mod tests {
 
         assert_eq!(db.last_key().unwrap().unwrap(), 2);
     }
+
+    #[test]
+    fn test_block_database_view() {
+        let tempdir = TempDir::new().unwrap();
+        let mut db = open_block_database(tempdir.path());
+
+        db.set(1, U256::from(100).into());
+        commit(&db);
+        db.clear_cache();
+        db.set(2, U256::from(200).into());
+
+        let view = db.view();
+        let snapshot = view.snapshot();
+
+        db.set(3, U256::from(300).into());
+        commit(&db);
+        db.clear_cache();
+        reorg(&mut db, 0);
+
+        assert_eq!(snapshot.get(1).unwrap().unwrap(), U256::from(100).into());
+        assert_eq!(snapshot.get(2).unwrap().unwrap(), U256::from(200).into());
+        assert!(snapshot.get(3).unwrap().is_none());
+        assert!(db.get(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_block_database_abort_block() {
+        let tempdir = TempDir::new().unwrap();
+        let mut db = open_block_database(tempdir.path());
+
+        db.set(1, U256::from(100).into());
+        db.begin_block();
+        db.set(1, U256::from(101).into());
+        db.set(2, U256::from(200).into());
+        db.abort_block();
+
+        assert_eq!(db.get(1).unwrap().unwrap(), U256::from(100).into());
+        assert!(db.get(2).unwrap().is_none());
+        assert_eq!(db.last_key().unwrap().unwrap(), 1);
+
+        db.begin_block();
+        db.set(2, U256::from(200).into());
+        db.end_block();
+        db.abort_block();
+        assert_eq!(db.get(2).unwrap().unwrap(), U256::from(200).into());
+    }
 }
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+use rocksdb::{
+    ColumnFamily, ColumnFamilyDescriptor, Options, WriteBatch, WriteOptions, DB,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+
+/// Open a database with the given column families
+///
+/// The database and the missing column families are created if they don't exist, column families that
+/// exist in the database but are not given are also opened, as RocksDB requires all of them to be opened
+///
+/// path: &Path - the path to the database
+/// column_families: &[String] - the column families used by the stores in the database
+///
+/// Returns: Arc<DB> - the database, shared by all stores using its column families
+pub fn open_database(path: &Path, column_families: &[String]) -> Result<Arc<DB>, Box<dyn Error>> {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_max_open_files(1024);
+
+    // Listing fails if the database doesn't exist yet, the default column family is opened explicitly
+    // so it can be used by name like the others
+    let mut names =
+        DB::list_cf(&opts, path).unwrap_or_else(|_| vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()]);
+    for name in column_families {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    let descriptors = names
+        .into_iter()
+        .map(|name| ColumnFamilyDescriptor::new(name, Options::default()));
+
+    Ok(Arc::new(DB::open_cf_descriptors(&opts, path, descriptors)?))
+}
+
+/// Get a column family of the database by name
+pub fn column_family<'a>(db: &'a DB, name: &str) -> Result<&'a ColumnFamily, Box<dyn Error>> {
+    db.cf_handle(name)
+        .ok_or_else(|| format!("Column family {} not found", name).into())
+}
+
+/// Write a batch to the database atomically
+///
+/// The write is synced to disk before returning, so either all or none of the changes in the batch
+/// are kept if the process or the machine crashes
+pub fn write_batch(db: &DB, batch: WriteBatch) -> Result<(), Box<dyn Error>> {
+    let mut write_options = WriteOptions::default();
+    write_options.set_sync(true);
+    db.write_opt(batch, &write_options)?;
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::error::Error;
+
+use rocksdb::{DBIteratorWithThreadMode, DBRawIteratorWithThreadMode, IteratorMode, Snapshot, DB};
+
+use crate::db::database::column_family;
+
+/// Source to read persisted values from
+///
+/// Live reads see every write as soon as it is made, while snapshot reads see the database
+/// as it was when the snapshot was taken, regardless of writes made after that
+pub enum DatabaseSource<'a> {
+    Live(&'a DB),
+    Snapshot(&'a DB, Snapshot<'a>),
+}
+
+impl<'a> DatabaseSource<'a> {
+    /// Take a snapshot of the database
+    pub fn snapshot(db: &'a DB) -> Self {
+        Self::Snapshot(db, db.snapshot())
+    }
+
+    pub fn get_cf<K: AsRef<[u8]>>(
+        &self,
+        column_family_name: &str,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let cf_handle = column_family(self.db(), column_family_name)?;
+        Ok(match self {
+            Self::Live(db) => db.get_cf(cf_handle, key)?,
+            Self::Snapshot(_, snapshot) => snapshot.get_cf(cf_handle, key)?,
+        })
+    }
+
+    pub fn iterator_cf(
+        &self,
+        column_family_name: &str,
+        mode: IteratorMode,
+    ) -> Result<DBIteratorWithThreadMode<'_, DB>, Box<dyn Error>> {
+        let cf_handle = column_family(self.db(), column_family_name)?;
+        Ok(match self {
+            Self::Live(db) => db.iterator_cf(cf_handle, mode),
+            Self::Snapshot(_, snapshot) => snapshot.iterator_cf(cf_handle, mode),
+        })
+    }
+
+    pub fn raw_iterator_cf(
+        &self,
+        column_family_name: &str,
+    ) -> Result<DBRawIteratorWithThreadMode<'_, DB>, Box<dyn Error>> {
+        let cf_handle = column_family(self.db(), column_family_name)?;
+        Ok(match self {
+            Self::Live(db) => db.raw_iterator_cf(cf_handle),
+            Self::Snapshot(_, snapshot) => snapshot.raw_iterator_cf(cf_handle),
+        })
+    }
+
+    fn db(&self) -> &'a DB {
+        match self {
+            Self::Live(db) => db,
+            Self::Snapshot(db, _) => db,
+        }
+    }
+}
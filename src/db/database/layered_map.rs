@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// Map that can be shared with readers while it's being modified, without copying all of its entries
+///
+/// Entries are kept in layers, the top layer is modified in place unless it's shared with a clone of the
+/// map, in which case it's frozen and a new layer is started. Clones only copy the list of layers, and
+/// modifying a shared map only copies the entries being modified.
+///
+/// Layers are merged when the top layer grows to half the size of the one below it, so there are at most
+/// a logarithmic number of layers and every entry is copied a logarithmic number of times.
+///
+/// K: the type of the key
+/// V: the type of the value, it's cloned when it's modified while shared, so it should be cheap to clone
+pub struct LayeredMap<K, V> {
+    // Oldest layer first, None marks a removed entry, so it's not read from the layers below
+    layers: Vec<Arc<HashMap<K, Option<V>>>>,
+    len: usize,
+}
+
+impl<K, V> Default for LayeredMap<K, V> {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<K, V> Clone for LayeredMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            layers: self.layers.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> LayeredMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// Get the value for a key
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.get(key))
+            .and_then(|value| value.as_ref())
+    }
+
+    /// Whether the map has a value for a key
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Get a mutable reference to the value for a key
+    ///
+    /// A value shared with a clone of the map is copied to the top layer first
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let value = self.get(key)?.clone();
+        self.top_layer()
+            .entry(key.clone())
+            .or_insert(Some(value))
+            .as_mut()
+    }
+
+    /// Set the value for a key
+    pub fn insert(&mut self, key: K, value: V) {
+        if !self.contains_key(&key) {
+            self.len += 1;
+        }
+        self.top_layer().insert(key, Some(value));
+    }
+
+    /// Remove the value for a key
+    pub fn remove(&mut self, key: &K) {
+        if !self.contains_key(key) {
+            return;
+        }
+        self.len -= 1;
+        self.top_layer();
+        let (top, lower_layers) = self.layers.split_last_mut().expect("Top layer is missing");
+        let top = Arc::get_mut(top).expect("Top layer is shared");
+        if lower_layers.iter().any(|layer| layer.contains_key(key)) {
+            top.insert(key.clone(), None);
+        } else {
+            top.remove(key);
+        }
+    }
+
+    /// Get the number of keys with a value
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterate over the keys and values, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut entries: HashMap<&K, &Option<V>> = HashMap::new();
+        for layer in self.layers.iter() {
+            entries.extend(layer.iter());
+        }
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|value| (key, value)))
+    }
+
+    /// Iterate over the keys, in no particular order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    // Returns the top layer, starting a new one if it's shared with a clone of the map
+    fn top_layer(&mut self) -> &mut HashMap<K, Option<V>> {
+        let is_shared = self
+            .layers
+            .last_mut()
+            .is_none_or(|layer| Arc::get_mut(layer).is_none());
+        if is_shared {
+            self.merge_layers();
+            self.layers.push(Arc::new(HashMap::new()));
+        }
+        Arc::get_mut(self.layers.last_mut().expect("Top layer is missing"))
+            .expect("Top layer is shared")
+    }
+
+    // Merges the top layers while the top layer is at least half the size of the one below it
+    fn merge_layers(&mut self) {
+        while self.layers.len() >= 2
+            && self.layers[self.layers.len() - 1].len() * 2
+                >= self.layers[self.layers.len() - 2].len()
+        {
+            let top = self.layers.pop().expect("Top layer is missing");
+            let below = self.layers.pop().expect("Layer is missing");
+            let mut merged = Arc::unwrap_or_clone(below);
+            merged.extend(Arc::unwrap_or_clone(top));
+            if self.layers.is_empty() {
+                // Nothing to hide below the bottom layer
+                merged.retain(|_, value| value.is_some());
+            }
+            self.layers.push(Arc::new(merged));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(map: &LayeredMap<u64, u64>) -> Vec<(u64, u64)> {
+        let mut values: Vec<(u64, u64)> = map.iter().map(|(key, value)| (*key, *value)).collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut map = LayeredMap::default();
+        map.insert(1, 10);
+        map.insert(2, 20);
+        map.insert(1, 11);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&11));
+
+        map.remove(&2);
+        map.remove(&3);
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(&2));
+        assert_eq!(values(&map), vec![(1, 11)]);
+    }
+
+    #[test]
+    fn test_clones_are_not_modified() {
+        let mut map = LayeredMap::default();
+        for key in 0..100 {
+            map.insert(key, key);
+        }
+        let clone = map.clone();
+
+        map.insert(0, 1000);
+        *map.get_mut(&1).unwrap() = 1001;
+        map.remove(&2);
+        map.insert(100, 100);
+
+        assert_eq!(clone.len(), 100);
+        assert_eq!(clone.get(&0), Some(&0));
+        assert_eq!(clone.get(&1), Some(&1));
+        assert_eq!(clone.get(&2), Some(&2));
+        assert_eq!(clone.get(&100), None);
+
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&0), Some(&1000));
+        assert_eq!(map.get(&1), Some(&1001));
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&100), Some(&100));
+        // Only the modified entries are in the new layer
+        assert_eq!(map.layers.len(), 2);
+        assert_eq!(map.layers[1].len(), 4);
+    }
+
+    #[test]
+    fn test_layers_are_merged() {
+        let mut map = LayeredMap::default();
+        let mut clones = Vec::new();
+        for key in 0..1000 {
+            map.insert(key % 300, key);
+            if key % 3 == 0 {
+                map.remove(&((key + 1) % 300));
+            }
+            clones.push(map.clone());
+        }
+        assert!(map.layers.len() <= 12);
+        let expected: Vec<(u64, u64)> = (0..300)
+            .filter_map(|key| {
+                // Last value set for the key, unless it's removed after
+                let last = (0..1000).rev().find(|value| value % 300 == key)?;
+                let removed = (0..1000)
+                    .rev()
+                    .filter(|value| value % 3 == 0)
+                    .find(|value| (value + 1) % 300 == key)
+                    .is_some_and(|removed| removed > last);
+                (!removed).then_some((key, last))
+            })
+            .collect();
+        assert_eq!(values(&map), expected);
+        assert_eq!(map.len(), expected.len());
+        assert_eq!(clones[0].len(), 1);
+        assert_eq!(values(&clones[3]), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+    }
+}
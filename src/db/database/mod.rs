@@ -1,4 +1,19 @@
 #![cfg(feature = "server")]
 
 mod block_database;
-pub use block_database::BlockDatabase;
+pub use block_database::{BlockDatabase, BlockDatabaseReader, BlockDatabaseView};
+
+mod archive_database;
+pub use archive_database::{ArchiveDatabase, ArchiveDatabaseReader};
+
+mod trie_database;
+pub use trie_database::TrieDatabase;
+
+mod layered_map;
+pub use layered_map::LayeredMap;
+
+mod column_family;
+pub use column_family::{column_family, open_database, write_batch};
+
+mod database_source;
+pub use database_source::DatabaseSource;
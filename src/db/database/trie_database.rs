@@ -0,0 +1,668 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::Arc;
+
+use alloy::primitives::{keccak256, Bytes, B256};
+use alloy::trie::nodes::{BranchNodeRef, ExtensionNodeRef, LeafNodeRef, RlpNode, TrieNode};
+use alloy::trie::{Nibbles, TrieMask, EMPTY_ROOT_HASH};
+use alloy_rlp::Decodable;
+use rocksdb::{IteratorMode, WriteBatch, DB};
+
+use crate::db::database::{column_family, write_batch};
+use crate::db::types::{Decode, Encode, B256ED};
+
+/// Database to store Merkle-Patricia tries, as used by Ethereum for state, receipts and transactions
+///
+/// Nodes are stored by the hash of their RLP encoding, so a trie is identified by its root hash and every
+/// version of a trie can be read as long as its root is known. Updating a trie only adds the nodes on the
+/// updated paths, so reorgs don't need to revert anything, and nodes of old versions are only deleted
+/// when the trie is pruned, see prune.
+///
+/// It uses a cache to store new nodes in memory and only writes to the database when its changes are
+/// written with a batch, see write_to_batch
+pub struct TrieDatabase {
+    db: Arc<DB>,
+    column_family: String,
+    cache: HashMap<B256, Vec<u8>>,
+    // Nodes cleared from the cache since the last prune, and nodes kept by the last prune
+    written_nodes: usize,
+    reachable_nodes: usize,
+}
+
+/// A value read from a trie, None if its key is not in the trie, with the proof of its value
+pub type ValueWithProof = (Option<Vec<u8>>, Vec<Bytes>);
+
+/// A trie node while a trie is being updated
+///
+/// Nodes are loaded from the database only when a path through them is updated, others are kept as
+/// references to their stored encoding
+enum Node {
+    Empty,
+    Leaf(Nibbles, Vec<u8>),
+    Extension(Nibbles, Box<Node>),
+    Branch(Box<[Node; 16]>),
+    Stored(RlpNode),
+}
+
+impl TrieDatabase {
+    /// Create a new TrieDatabase
+    ///
+    /// The database should be opened with a column family named after the TrieDatabase
+    ///
+    /// db: Arc<DB> - the database to store the nodes in
+    /// name: &str - the name of the database, also the name of its column family
+    ///
+    /// Returns: TrieDatabase - the created TrieDatabase
+    pub fn new(db: Arc<DB>, name: &str) -> Result<Self, Box<dyn Error>> {
+        column_family(&db, name)?;
+        Ok(Self {
+            db,
+            column_family: name.to_string(),
+            cache: HashMap::new(),
+            written_nodes: 0,
+            reachable_nodes: 0,
+        })
+    }
+
+    /// Get the value for a key in the trie with the given root
+    ///
+    /// root: B256 - the root hash of the trie, EMPTY_ROOT_HASH for an empty trie
+    /// key: B256 - the key, tries with fixed size keys are keyed by the hash of the original key
+    ///
+    /// Returns: Option<Vec<u8>> - the value for the key, None if the key is not in the trie
+    pub fn get(&self, root: B256, key: B256) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        Ok(self.get_proof(root, key)?.0)
+    }
+
+    /// Get the value for a key in the trie with the given root, with a proof of its value
+    ///
+    /// The proof is the list of RLP encoded nodes on the path to the key, starting with the root. Nodes
+    /// shorter than a hash are embedded in their parent, so they're not in the list. If the key is not in
+    /// the trie, the proof shows where its path ends.
+    ///
+    /// root: B256 - the root hash of the trie, EMPTY_ROOT_HASH for an empty trie
+    /// key: B256 - the key, tries with fixed size keys are keyed by the hash of the original key
+    ///
+    /// Returns: ValueWithProof - the value for the key and the proof
+    pub fn get_proof(&self, root: B256, key: B256) -> Result<ValueWithProof, Box<dyn Error>> {
+        let mut node = self.root_node(root);
+        let mut path = Nibbles::unpack(key);
+        let mut proof = Vec::new();
+        loop {
+            match node {
+                Node::Empty => return Ok((None, proof)),
+                Node::Stored(reference) => {
+                    node = match reference.as_hash() {
+                        Some(hash) => {
+                            let rlp = self.node_rlp(hash)?;
+                            let decoded = decode(&rlp)?;
+                            proof.push(rlp.into());
+                            decoded
+                        }
+                        None => decode(&reference)?,
+                    }
+                }
+                Node::Leaf(leaf_key, value) => {
+                    return Ok(((leaf_key == path).then_some(value), proof));
+                }
+                Node::Extension(extension_key, child) => {
+                    if !path.starts_with(&extension_key) {
+                        return Ok((None, proof));
+                    }
+                    path = path.slice(extension_key.len()..);
+                    node = *child;
+                }
+                Node::Branch(mut children) => {
+                    let Some(index) = path.first() else {
+                        return Ok((None, proof));
+                    };
+                    path = path.slice(1..);
+                    node = std::mem::replace(&mut children[index as usize], Node::Empty);
+                }
+            }
+        }
+    }
+
+    /// Apply changes to the trie with the given root
+    ///
+    /// The nodes of the updated trie are added to the cache, the trie with the given root is unchanged
+    ///
+    /// root: B256 - the root hash of the trie to update, EMPTY_ROOT_HASH for an empty trie
+    /// changes: keys and their new values, None to remove a key from the trie
+    ///
+    /// Returns: B256 - the root hash of the updated trie
+    pub fn update(
+        &mut self,
+        root: B256,
+        changes: impl IntoIterator<Item = (B256, Option<Vec<u8>>)>,
+    ) -> Result<B256, Box<dyn Error>> {
+        let mut node = self.root_node(root);
+        let mut changed = false;
+        for (key, value) in changes {
+            let path = Nibbles::unpack(key);
+            node = match value {
+                Some(value) => self.insert(node, path, value)?,
+                None => self.remove(node, path)?,
+            };
+            changed = true;
+        }
+        if !changed {
+            return Ok(root);
+        }
+
+        let mut rlp = Vec::new();
+        match self.encode(node, &mut rlp)? {
+            // The root is always referenced by its hash, even if its encoding is shorter than a hash
+            None => Ok(EMPTY_ROOT_HASH),
+            Some(_) => {
+                let root = keccak256(&rlp);
+                self.cache.insert(root, rlp);
+                Ok(root)
+            }
+        }
+    }
+
+    /// Get the number of nodes in the cache, i.e. nodes added since the last commit
+    pub fn cache_size(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Add the nodes in the cache to a batch
+    ///
+    /// Nodes are written to the database when the batch is written
+    /// It does not clear the cache
+    ///
+    /// batch: &mut WriteBatch - the batch to add the nodes to
+    pub fn write_to_batch(&self, batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        let cf_handle = column_family(&self.db, &self.column_family)?;
+        for (hash, rlp) in self.cache.iter() {
+            batch.put_cf(
+                cf_handle,
+                B256ED::from(*hash).encode_vec(),
+                rlp.encode_vec(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Clear the cache
+    ///
+    /// This does not delete the nodes from the database, make sure to write the cache with a batch before
+    /// clearing it, otherwise tries using the cached nodes can't be read anymore
+    pub fn clear_cache(&mut self) {
+        self.written_nodes += self.cache.len();
+        self.cache = HashMap::new();
+    }
+
+    /// Whether more nodes were written since the last prune than the last prune kept
+    ///
+    /// Pruning reads every reachable node, so pruning only then keeps its cost proportional to the
+    /// number of written nodes, while the database stays within about twice the size of the tries
+    pub fn should_prune(&self) -> bool {
+        self.written_nodes > self.reachable_nodes
+    }
+
+    /// Delete the stored nodes that can't be reached from the given roots
+    ///
+    /// Leaf values can reference the roots of other tries, e.g. accounts reference their storage tries,
+    /// so those tries are kept as well. Nodes in the cache are kept, and missing nodes are skipped, as
+    /// tries of blocks imported from a snapshot aren't stored.
+    ///
+    /// roots: the root hashes of the tries to keep
+    /// leaf_references: returns the root of the trie referenced by a leaf value, if any
+    ///
+    /// Returns: usize - the number of deleted nodes
+    pub fn prune(
+        &mut self,
+        roots: impl IntoIterator<Item = B256>,
+        leaf_references: impl Fn(&[u8]) -> Option<B256>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut reachable = HashSet::new();
+        let mut references: Vec<RlpNode> = roots
+            .into_iter()
+            .map(|root| RlpNode::word_rlp(&root))
+            .collect();
+        while let Some(reference) = references.pop() {
+            let node = match reference.as_hash() {
+                Some(hash) => {
+                    if hash == EMPTY_ROOT_HASH || !reachable.insert(hash) {
+                        continue;
+                    }
+                    match self.stored_node_rlp(hash)? {
+                        Some(rlp) => decode(&rlp)?,
+                        None => continue,
+                    }
+                }
+                None => decode(&reference)?,
+            };
+            match node {
+                Node::Leaf(_, value) => {
+                    references.extend(leaf_references(&value).map(|root| RlpNode::word_rlp(&root)))
+                }
+                Node::Extension(_, child) => {
+                    if let Node::Stored(child) = *child {
+                        references.push(child);
+                    }
+                }
+                Node::Branch(children) => {
+                    references.extend(children.into_iter().filter_map(|child| match child {
+                        Node::Stored(child) => Some(child),
+                        _ => None,
+                    }));
+                }
+                Node::Empty | Node::Stored(_) => {}
+            }
+        }
+
+        let cf_handle = column_family(&self.db, &self.column_family)?;
+        let mut batch = WriteBatch::default();
+        let mut deleted = 0;
+        for entry in self.db.iterator_cf(cf_handle, IteratorMode::Start) {
+            let (key, _) = entry?;
+            let hash = B256ED::decode_vec(&key.to_vec())?.bytes;
+            if !reachable.contains(&hash) && !self.cache.contains_key(&hash) {
+                batch.delete_cf(cf_handle, key);
+                deleted += 1;
+            }
+        }
+        write_batch(&self.db, batch)?;
+
+        self.written_nodes = 0;
+        self.reachable_nodes = reachable.len();
+        Ok(deleted)
+    }
+
+    fn root_node(&self, root: B256) -> Node {
+        if root == EMPTY_ROOT_HASH {
+            Node::Empty
+        } else {
+            Node::Stored(RlpNode::word_rlp(&root))
+        }
+    }
+
+    /// Decode a node from its reference, loading it from the cache or the database if it's a hash
+    fn load(&self, reference: &RlpNode) -> Result<Node, Box<dyn Error>> {
+        match reference.as_hash() {
+            Some(hash) => decode(&self.node_rlp(hash)?),
+            // Nodes shorter than a hash are embedded in their parent
+            None => decode(reference),
+        }
+    }
+
+    fn node_rlp(&self, hash: B256) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.stored_node_rlp(hash)?
+            .ok_or_else(|| format!("Trie node {} not found", hash).into())
+    }
+
+    fn stored_node_rlp(&self, hash: B256) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        if let Some(rlp) = self.cache.get(&hash) {
+            return Ok(Some(rlp.clone()));
+        }
+        self.db
+            .get_cf(
+                column_family(&self.db, &self.column_family)?,
+                B256ED::from(hash).encode_vec(),
+            )?
+            .map(|rlp| Vec::<u8>::decode_vec(&rlp))
+            .transpose()
+    }
+
+    fn insert(&self, node: Node, path: Nibbles, value: Vec<u8>) -> Result<Node, Box<dyn Error>> {
+        Ok(match node {
+            Node::Empty => Node::Leaf(path, value),
+            Node::Stored(reference) => self.insert(self.load(&reference)?, path, value)?,
+            Node::Leaf(leaf_key, leaf_value) => {
+                if leaf_key == path {
+                    return Ok(Node::Leaf(path, value));
+                }
+                // Keys have the same length, so neither is a prefix of the other
+                let common = leaf_key.common_prefix_length(&path);
+                let mut children: [Node; 16] = std::array::from_fn(|_| Node::Empty);
+                children[leaf_key.get_unchecked(common) as usize] =
+                    Node::Leaf(leaf_key.slice(common + 1..), leaf_value);
+                children[path.get_unchecked(common) as usize] =
+                    Node::Leaf(path.slice(common + 1..), value);
+                extend(path.slice(..common), Node::Branch(Box::new(children)))
+            }
+            Node::Extension(extension_key, child) => {
+                let common = extension_key.common_prefix_length(&path);
+                if common == extension_key.len() {
+                    let child = self.insert(*child, path.slice(common..), value)?;
+                    return Ok(Node::Extension(extension_key, Box::new(child)));
+                }
+                let mut children: [Node; 16] = std::array::from_fn(|_| Node::Empty);
+                children[extension_key.get_unchecked(common) as usize] =
+                    extend(extension_key.slice(common + 1..), *child);
+                children[path.get_unchecked(common) as usize] =
+                    Node::Leaf(path.slice(common + 1..), value);
+                extend(path.slice(..common), Node::Branch(Box::new(children)))
+            }
+            Node::Branch(mut children) => {
+                let index = path.get_unchecked(0) as usize;
+                let child = std::mem::replace(&mut children[index], Node::Empty);
+                children[index] = self.insert(child, path.slice(1..), value)?;
+                Node::Branch(children)
+            }
+        })
+    }
+
+    fn remove(&self, node: Node, path: Nibbles) -> Result<Node, Box<dyn Error>> {
+        Ok(match node {
+            Node::Empty => Node::Empty,
+            Node::Stored(reference) => self.remove(self.load(&reference)?, path)?,
+            Node::Leaf(leaf_key, leaf_value) => {
+                if leaf_key == path {
+                    Node::Empty
+                } else {
+                    Node::Leaf(leaf_key, leaf_value)
+                }
+            }
+            Node::Extension(extension_key, child) => {
+                if !path.starts_with(&extension_key) {
+                    return Ok(Node::Extension(extension_key, child));
+                }
+                let child = self.remove(*child, path.slice(extension_key.len()..))?;
+                self.join(extension_key, child)?
+            }
+            Node::Branch(mut children) => {
+                let index = path.get_unchecked(0) as usize;
+                let child = std::mem::replace(&mut children[index], Node::Empty);
+                children[index] = self.remove(child, path.slice(1..))?;
+
+                let mut remaining = children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, child)| !matches!(child, Node::Empty))
+                    .map(|(index, _)| index);
+                match (remaining.next(), remaining.next()) {
+                    (None, _) => Node::Empty,
+                    // A branch with a single child is replaced by the child, with its index added to its path
+                    (Some(index), None) => {
+                        let child = std::mem::replace(&mut children[index], Node::Empty);
+                        self.join(Nibbles::from_nibbles([index as u8]), child)?
+                    }
+                    _ => Node::Branch(children),
+                }
+            }
+        })
+    }
+
+    /// Prefix a node with a path, merging it into the node if it's a leaf or an extension
+    fn join(&self, prefix: Nibbles, node: Node) -> Result<Node, Box<dyn Error>> {
+        Ok(match node {
+            Node::Empty => Node::Empty,
+            Node::Stored(reference) => self.join(prefix, self.load(&reference)?)?,
+            Node::Leaf(key, value) => Node::Leaf(prefix.join(&key), value),
+            Node::Extension(key, child) => Node::Extension(prefix.join(&key), child),
+            Node::Branch(children) => extend(prefix, Node::Branch(children)),
+        })
+    }
+
+    /// RLP encode a node, adding the encodings of its children to the cache if they're referenced by hash
+    ///
+    /// Returns: Option<RlpNode> - the reference to the node in its parent, None if the node is empty
+    fn encode(&mut self, node: Node, rlp: &mut Vec<u8>) -> Result<Option<RlpNode>, Box<dyn Error>> {
+        Ok(Some(match node {
+            Node::Empty => return Ok(None),
+            Node::Stored(reference) => {
+                rlp.extend_from_slice(&match reference.as_hash() {
+                    Some(hash) => self.node_rlp(hash)?,
+                    None => reference.to_vec(),
+                });
+                reference
+            }
+            Node::Leaf(key, value) => LeafNodeRef::new(&key, &value).rlp(rlp),
+            Node::Extension(key, child) => {
+                let child = self
+                    .reference(*child)?
+                    .ok_or("Empty trie extension child")?;
+                ExtensionNodeRef::new(&key, &child).rlp(rlp)
+            }
+            Node::Branch(children) => {
+                let mut stack = Vec::new();
+                let mut state_mask = TrieMask::default();
+                for (index, child) in children.into_iter().enumerate() {
+                    if let Some(child) = self.reference(child)? {
+                        stack.push(child);
+                        state_mask.set_bit(index as u8);
+                    }
+                }
+                BranchNodeRef::new(&stack, state_mask).rlp(rlp)
+            }
+        }))
+    }
+
+    /// Encode a child node and return its reference, nodes referenced by hash are added to the cache
+    fn reference(&mut self, node: Node) -> Result<Option<RlpNode>, Box<dyn Error>> {
+        if let Node::Stored(reference) = node {
+            return Ok(Some(reference));
+        }
+        let mut rlp = Vec::new();
+        let reference = self.encode(node, &mut rlp)?;
+        if let Some(hash) = reference.as_ref().and_then(RlpNode::as_hash) {
+            self.cache.insert(hash, rlp);
+        }
+        Ok(reference)
+    }
+}
+
+/// Decode a node from its RLP encoding, its children are kept as references
+fn decode(mut rlp: &[u8]) -> Result<Node, Box<dyn Error>> {
+    Ok(match TrieNode::decode(&mut rlp)? {
+        TrieNode::EmptyRoot => Node::Empty,
+        TrieNode::Leaf(leaf) => Node::Leaf(leaf.key, leaf.value),
+        TrieNode::Extension(extension) => {
+            Node::Extension(extension.key, Box::new(Node::Stored(extension.child)))
+        }
+        TrieNode::Branch(branch) => {
+            let mut children: [Node; 16] = std::array::from_fn(|_| Node::Empty);
+            let mut stack = branch.stack.into_iter();
+            for index in 0..16u8 {
+                if branch.state_mask.is_bit_set(index) {
+                    children[index as usize] =
+                        Node::Stored(stack.next().ok_or("Invalid trie branch node")?);
+                }
+            }
+            Node::Branch(Box::new(children))
+        }
+    })
+}
+
+/// Prefix a node with an extension, unless the prefix is empty
+fn extend(prefix: Nibbles, node: Node) -> Node {
+    if prefix.is_empty() {
+        node
+    } else {
+        Node::Extension(prefix, Box::new(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use alloy::primitives::U256;
+    use alloy::trie::proof::verify_proof;
+    use alloy::trie::root::storage_root_unhashed;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::db::database::{open_database, write_batch};
+
+    fn trie_database(path: &Path) -> TrieDatabase {
+        let db = open_database(path, &["trie".to_string()]).unwrap();
+        TrieDatabase::new(db, "trie").unwrap()
+    }
+
+    fn storage(slots: &[(u64, u64)]) -> Vec<(B256, Option<Vec<u8>>)> {
+        slots
+            .iter()
+            .map(|(slot, value)| {
+                (
+                    keccak256(B256::from(U256::from(*slot))),
+                    (*value != 0).then(|| alloy_rlp::encode(U256::from(*value))),
+                )
+            })
+            .collect()
+    }
+
+    fn expected_root(slots: &[(u64, u64)]) -> B256 {
+        storage_root_unhashed(
+            slots
+                .iter()
+                .filter(|(_, value)| *value != 0)
+                .map(|(slot, value)| (B256::from(U256::from(*slot)), U256::from(*value))),
+        )
+    }
+
+    #[test]
+    fn test_empty_trie() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        assert_eq!(trie.update(EMPTY_ROOT_HASH, []).unwrap(), EMPTY_ROOT_HASH);
+        assert_eq!(trie.get(EMPTY_ROOT_HASH, B256::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn test_root_matches_ethereum_trie() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        let slots = (0..200u64).map(|slot| (slot, slot + 1)).collect::<Vec<_>>();
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&slots)).unwrap();
+        assert_eq!(root, expected_root(&slots));
+
+        // Single leaf tries are shorter than a hash, but their root is still a hash
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&[(1, 1)])).unwrap();
+        assert_eq!(root, expected_root(&[(1, 1)]));
+    }
+
+    #[test]
+    fn test_update_and_remove() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        let slots = (0..100u64).map(|slot| (slot, slot + 1)).collect::<Vec<_>>();
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&slots)).unwrap();
+
+        // Update half of the slots and remove the others
+        let changes = (0..100u64)
+            .map(|slot| (slot, if slot % 2 == 0 { slot * 3 } else { 0 }))
+            .collect::<Vec<_>>();
+        let updated_root = trie.update(root, storage(&changes)).unwrap();
+        assert_eq!(updated_root, expected_root(&changes));
+
+        // Both versions can still be read
+        let key = keccak256(B256::from(U256::from(1)));
+        assert_eq!(
+            trie.get(root, key).unwrap(),
+            Some(alloy_rlp::encode(U256::from(2)))
+        );
+        assert_eq!(trie.get(updated_root, key).unwrap(), None);
+
+        // Removing everything gives the empty root
+        let removed = (0..100u64).map(|slot| (slot, 0)).collect::<Vec<_>>();
+        assert_eq!(
+            trie.update(updated_root, storage(&removed)).unwrap(),
+            EMPTY_ROOT_HASH
+        );
+    }
+
+    #[test]
+    fn test_write_to_batch() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        let slots = (0..50u64).map(|slot| (slot, slot + 1)).collect::<Vec<_>>();
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&slots)).unwrap();
+
+        let mut batch = WriteBatch::default();
+        trie.write_to_batch(&mut batch).unwrap();
+        write_batch(&trie.db, batch).unwrap();
+        trie.clear_cache();
+        assert_eq!(trie.cache_size(), 0);
+
+        let key = keccak256(B256::from(U256::from(10)));
+        assert_eq!(
+            trie.get(root, key).unwrap(),
+            Some(alloy_rlp::encode(U256::from(11)))
+        );
+        let updated_root = trie.update(root, storage(&[(10, 0)])).unwrap();
+        assert_eq!(trie.get(updated_root, key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_prune() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        let commit = |trie: &mut TrieDatabase| {
+            let mut batch = WriteBatch::default();
+            trie.write_to_batch(&mut batch).unwrap();
+            write_batch(&trie.db, batch).unwrap();
+            trie.clear_cache();
+        };
+        let slots = (0..100u64).map(|slot| (slot, slot + 1)).collect::<Vec<_>>();
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&slots)).unwrap();
+        // A trie with a leaf referencing the first trie, as accounts reference their storage tries
+        let parent_root = trie
+            .update(EMPTY_ROOT_HASH, [(B256::ZERO, Some(root.to_vec()))])
+            .unwrap();
+        commit(&mut trie);
+        let updated_root = trie.update(root, storage(&[(1, 0), (2, 5)])).unwrap();
+        commit(&mut trie);
+        assert!(trie.should_prune());
+
+        let leaf_references = |value: &[u8]| B256::try_from(value).ok();
+        assert!(trie.prune([parent_root], leaf_references).unwrap() > 0);
+        assert!(!trie.should_prune());
+
+        // Only the referenced trie is kept
+        let key = keccak256(B256::from(U256::from(1)));
+        assert_eq!(
+            trie.get(root, key).unwrap(),
+            Some(alloy_rlp::encode(U256::from(2)))
+        );
+        assert!(trie.get(updated_root, key).is_err());
+
+        // Nothing is deleted when every trie is kept
+        let updated_root = trie.update(root, storage(&[(1, 0)])).unwrap();
+        assert_eq!(
+            trie.prune(
+                [parent_root, updated_root, EMPTY_ROOT_HASH],
+                leaf_references
+            )
+            .unwrap(),
+            0
+        );
+        commit(&mut trie);
+        assert_eq!(trie.get(updated_root, key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_proof() {
+        let path = TempDir::new().unwrap();
+        let mut trie = trie_database(path.path());
+        let slots = (0..100u64).map(|slot| (slot, slot + 1)).collect::<Vec<_>>();
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&slots)).unwrap();
+
+        for slot in [0u64, 42, 99, 1000] {
+            let key = keccak256(B256::from(U256::from(slot)));
+            let (value, proof) = trie.get_proof(root, key).unwrap();
+            assert_eq!(value, trie.get(root, key).unwrap());
+            assert_eq!(value.is_some(), slot < 100);
+            assert_eq!(keccak256(&proof[0]), root);
+            verify_proof(root, Nibbles::unpack(key), value.clone(), &proof).unwrap();
+            // A proof doesn't verify a different value
+            assert!(verify_proof(root, Nibbles::unpack(key), Some(vec![0x7f]), &proof).is_err());
+        }
+
+        // Single leaf tries have their root node in the proof
+        let root = trie.update(EMPTY_ROOT_HASH, storage(&[(1, 1)])).unwrap();
+        let key = keccak256(B256::from(U256::from(1)));
+        let (value, proof) = trie.get_proof(root, key).unwrap();
+        assert_eq!(proof.len(), 1);
+        verify_proof(root, Nibbles::unpack(key), value, &proof).unwrap();
+
+        assert_eq!(
+            trie.get_proof(EMPTY_ROOT_HASH, key).unwrap(),
+            (None, vec![])
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use alloy::primitives::B256;
+use rocksdb::{WriteBatch, DB};
+
+use crate::db::database::column_family;
+use crate::db::migration::Migration;
+use crate::db::types::{
+    join_block_traces, BlockResponseED, Decode, Encode, TraceED, TxReceiptED, B256ED,
+};
+use crate::db::Brc20ProgDatabase;
+
+static BLOCKS_NAME: &str = "block_number_to_block";
+static CONSENSUS_HASHES_NAME: &str = "block_number_to_consensus_hash";
+
+/// Computes the consensus hashes of all blocks from their stored traces and state roots
+///
+/// Consensus hashes were only computed for blocks finalised after they were introduced, starting from zero,
+/// so indexers upgraded at different heights didn't agree on them. Blocks are migrated in order, as each
+/// consensus hash includes the one of the previous block. Blocks whose previous block is not stored keep
+/// their consensus hash, e.g. the first block imported from a snapshot.
+///
+/// Blocks indexed before state roots were introduced have a zero state root, so their consensus hashes only
+/// match indexers that migrated from the same version.
+pub struct ConsensusHashesMigration {
+    db: Arc<DB>,
+    // Last migrated block and its consensus hash, as the batch it's in may not be written yet
+    previous: Option<(u64, B256)>,
+}
+
+impl ConsensusHashesMigration {
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db, previous: None }
+    }
+
+    fn get<K: Encode, V: Decode>(
+        &self,
+        column_family_name: &str,
+        key: K,
+    ) -> Result<Option<V>, Box<dyn Error>> {
+        self.db
+            .get_cf(
+                column_family(&self.db, column_family_name)?,
+                key.encode_vec(),
+            )?
+            .map(|value| V::decode_vec(&value))
+            .transpose()
+    }
+
+    /// Trace hash of a block, from the stored traces of its transactions in transaction index order
+    fn trace_hash(&self, block: &BlockResponseED) -> Result<B256, Box<dyn Error>> {
+        let mut transactions = Vec::new();
+        for tx_hash in block.transactions.clone().left().unwrap_or_default() {
+            if let Some(receipt) = self.get::<_, TxReceiptED>("tx_receipt", tx_hash)? {
+                transactions.push((receipt.transaction_index, tx_hash));
+            }
+        }
+        transactions.sort_by_key(|(transaction_index, _)| *transaction_index);
+        let mut traces = Vec::new();
+        for (_, tx_hash) in transactions {
+            if let Some(trace) = self.get::<_, TraceED>("tx_trace", tx_hash)? {
+                traces.push(trace);
+            }
+        }
+        Ok(B256::from_str(&sha256::digest(join_block_traces(&traces)))?)
+    }
+}
+
+impl Migration for ConsensusHashesMigration {
+    fn version(&self) -> u32 {
+        11
+    }
+
+    fn description(&self) -> &'static str {
+        "compute consensus hashes of all blocks"
+    }
+
+    fn column_families(&self) -> Vec<String> {
+        vec![BLOCKS_NAME.to_string()]
+    }
+
+    fn migrate(
+        &mut self,
+        batch: &mut WriteBatch,
+        _column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let block = BlockResponseED::decode_vec(&value.to_vec())?;
+        let block_number: u64 = block.number.into();
+        let previous_consensus_hash = match block_number.checked_sub(1) {
+            None => Some(B256::ZERO),
+            Some(parent_block_number) => match self.previous {
+                Some((previous_block_number, previous_consensus_hash))
+                    if previous_block_number == parent_block_number =>
+                {
+                    Some(previous_consensus_hash)
+                }
+                // The previous block was migrated before the migration was interrupted
+                _ if self
+                    .get::<_, BlockResponseED>(BLOCKS_NAME, parent_block_number)?
+                    .is_some() =>
+                {
+                    Some(
+                        self.get::<_, B256ED>(CONSENSUS_HASHES_NAME, parent_block_number)?
+                            .map_or(B256::ZERO, |hash| hash.bytes),
+                    )
+                }
+                _ => None,
+            },
+        };
+        let stored_consensus_hash = self
+            .get::<_, B256ED>(CONSENSUS_HASHES_NAME, block_number)?
+            .map(|hash| hash.bytes);
+        let consensus_hash = match (previous_consensus_hash, stored_consensus_hash) {
+            (None, Some(stored_consensus_hash)) => stored_consensus_hash,
+            (previous_consensus_hash, _) => Brc20ProgDatabase::compute_consensus_hash(
+                previous_consensus_hash.unwrap_or(B256::ZERO),
+                self.trace_hash(&block)?,
+                block.state_root.bytes,
+            )?,
+        };
+        batch.put_cf(
+            column_family(&self.db, CONSENSUS_HASHES_NAME)?,
+            key,
+            B256ED::from(consensus_hash).encode_vec(),
+        );
+        self.previous = Some((block_number, consensus_hash));
+        Ok(())
+    }
+}
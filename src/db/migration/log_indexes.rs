@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use rocksdb::{WriteBatch, DB};
+
+use crate::db::cached_database::{BlockCachedDatabase, BlockHistoryCacheData};
+use crate::db::database::BlockDatabase;
+use crate::db::migration::Migration;
+use crate::db::types::{AddressED, TxReceiptED, B256ED, U128ED};
+use crate::db::Brc20ProgDatabase;
+
+/// Builds the log address and topic indexes from the receipts of indexed transactions
+///
+/// Receipts are still encoded without their transaction type at this version
+pub struct LogIndexesMigration {
+    db_log_address_index:
+        BlockCachedDatabase<(AddressED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    db_log_topic_index:
+        BlockCachedDatabase<(B256ED, U128ED), B256ED, BlockHistoryCacheData<B256ED>>,
+    latest_block_number: u64,
+}
+
+impl LogIndexesMigration {
+    pub fn new(db: Arc<DB>, archive_mode: bool) -> Result<Self, Box<dyn Error>> {
+        let latest_block_number = BlockDatabase::<B256ED>::new(db.clone(), "block_number_to_hash")?
+            .last_key()?
+            .unwrap_or(0);
+        Ok(Self {
+            db_log_address_index: BlockCachedDatabase::new(
+                db.clone(),
+                "log_address_index",
+                archive_mode,
+            )?,
+            db_log_topic_index: BlockCachedDatabase::new(db, "log_topic_index", archive_mode)?,
+            latest_block_number,
+        })
+    }
+}
+
+impl Migration for LogIndexesMigration {
+    fn version(&self) -> u32 {
+        8
+    }
+
+    fn description(&self) -> &'static str {
+        "index logs by address and topic"
+    }
+
+    fn column_families(&self) -> Vec<String> {
+        vec!["tx_receipt".to_string()]
+    }
+
+    fn migrate(
+        &mut self,
+        _batch: &mut WriteBatch,
+        _column_family: &str,
+        _key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let (receipt, _) = TxReceiptED::decode_untyped(value, 0)?;
+        let block_number: u64 = receipt.block_number.into();
+        for log in receipt.logs.iter() {
+            let log_key: U128ED =
+                Brc20ProgDatabase::get_number_and_index_key(block_number, log.log_index.uint.to())
+                    .into();
+            self.db_log_address_index.set(
+                block_number,
+                &(log.address, log_key),
+                receipt.transaction_hash,
+            )?;
+            if let Some(topic0) = log.topics.first() {
+                self.db_log_topic_index.set(
+                    block_number,
+                    &(*topic0, log_key),
+                    receipt.transaction_hash,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_to_batch(&mut self, batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        self.db_log_address_index
+            .write_to_batch(batch, self.latest_block_number)?;
+        self.db_log_topic_index
+            .write_to_batch(batch, self.latest_block_number)?;
+        // The batch is written right after this, caches are cleared so they don't grow with every receipt
+        self.db_log_address_index.clear_cache();
+        self.db_log_topic_index.clear_cache();
+        Ok(())
+    }
+}
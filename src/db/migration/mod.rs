@@ -0,0 +1,628 @@
+#![cfg(feature = "server")]
+
+mod consensus_hashes;
+mod log_indexes;
+mod state_roots;
+mod transaction_types;
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+use tracing::info;
+
+use crate::db::brc20_prog_database::{CONFIG_NAME, DATABASE_NAME, GLOBAL_VALUES_NAME};
+use crate::db::database::{column_family, write_batch};
+use crate::db::migration::consensus_hashes::ConsensusHashesMigration;
+use crate::db::migration::log_indexes::LogIndexesMigration;
+use crate::db::migration::state_roots::StateRootsMigration;
+use crate::db::migration::transaction_types::TransactionTypesMigration;
+use crate::db::Brc20ProgDatabase;
+use crate::global::database::ConfigDatabase;
+use crate::global::{Brc20ProgConfig, ARCHIVE_MODE_KEY, DB_VERSION, DB_VERSION_KEY};
+
+/// Oldest database version that can be migrated, older databases have to be reindexed from genesis
+static OLDEST_MIGRATED_VERSION: u32 = 7;
+
+/// Number of entries migrated in a single batch, the progress of a step is saved with each batch
+static MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// A step that migrates the database from the previous version to the version of the step
+///
+/// Entries of the column families read by the step are passed to it in order, changes it makes are added
+/// to a batch that is written with the progress of the step, so an interrupted step continues after the
+/// last written entry and no entry is migrated twice
+trait Migration {
+    /// Version of the database after the step
+    fn version(&self) -> u32;
+
+    /// Description of the step, used for logging
+    fn description(&self) -> &'static str;
+
+    /// Column families read by the step, in the order they are migrated
+    fn column_families(&self) -> Vec<String>;
+
+    /// Migrate an entry of a column family, adding the changes to the batch
+    fn migrate(
+        &mut self,
+        batch: &mut WriteBatch,
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Add changes kept in memory by the step to the batch, called before each batch is written
+    fn write_to_batch(&mut self, _batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Migration steps, in order of their versions
+fn migrations(db: &Arc<DB>, archive_mode: bool) -> Result<Vec<Box<dyn Migration>>, Box<dyn Error>> {
+    Ok(vec![
+        Box::new(LogIndexesMigration::new(db.clone(), archive_mode)?),
+        Box::new(TransactionTypesMigration::new(db.clone(), archive_mode)),
+        Box::new(StateRootsMigration::new(db.clone())),
+        Box::new(ConsensusHashesMigration::new(db.clone())),
+    ])
+}
+
+/// Migrate the database to DB_VERSION if it was created by an older version
+///
+/// Steps from the stored version to DB_VERSION are run in order, and the stored version is updated after
+/// each step, so an interrupted migration continues from where it stopped when it's run again
+///
+/// Databases that don't exist yet or are already at DB_VERSION are not changed, and versions newer than
+/// DB_VERSION are left to validate_config_database to reject
+pub fn migrate_database(config: &Brc20ProgConfig) -> Result<(), Box<dyn Error>> {
+    let base_path = Path::new(&config.db_path);
+    if !base_path.join(DATABASE_NAME).exists() && !base_path.join(CONFIG_NAME).exists() {
+        return Ok(());
+    }
+
+    let config_database = Brc20ProgDatabase::open_config_database(base_path)?;
+    let Some(version) = config_database.get(DB_VERSION_KEY.clone())? else {
+        return Ok(());
+    };
+    let mut version: u32 = version.parse()?;
+    if version >= *DB_VERSION {
+        return Ok(());
+    }
+    if version < OLDEST_MIGRATED_VERSION {
+        return Err(format!(
+            "Database version {} can't be migrated to {}, please reindex from genesis",
+            version, *DB_VERSION
+        )
+        .into());
+    }
+    // Databases created before archive mode was introduced are never archives
+    let archive_mode = config_database
+        .get(ARCHIVE_MODE_KEY.clone())?
+        .is_some_and(|value| value == true.to_string());
+    drop(config_database);
+
+    info!(
+        "Migrating database from version {} to {}",
+        version, *DB_VERSION
+    );
+    let db = Brc20ProgDatabase::open_raw_database(base_path, archive_mode)?;
+    let mut config_database = ConfigDatabase::from_column_family(db.clone(), CONFIG_NAME)?;
+    let mut global_values = ConfigDatabase::from_column_family(db.clone(), GLOBAL_VALUES_NAME)?;
+    for mut migration in migrations(&db, archive_mode)? {
+        if migration.version() <= version {
+            continue;
+        }
+        info!(
+            "Migrating database to version {}: {}",
+            migration.version(),
+            migration.description()
+        );
+        run_migration(
+            &db,
+            &mut global_values,
+            &mut config_database,
+            migration.as_mut(),
+        )?;
+
+        version = migration.version();
+        info!("Database migrated to version {}", version);
+    }
+    if version != *DB_VERSION {
+        return Err(format!("No migration found to database version {}", *DB_VERSION).into());
+    }
+    Ok(())
+}
+
+/// Run a migration step, continuing from its saved progress
+///
+/// Progress is kept in the global values as the index of the column family being migrated and the hex
+/// encoded last migrated key in it, an empty key means the column family is not started yet. The database
+/// version is written with the progress of the last column family, so they can't disagree after a crash
+fn run_migration(
+    db: &DB,
+    global_values: &mut ConfigDatabase,
+    config_database: &mut ConfigDatabase,
+    migration: &mut dyn Migration,
+) -> Result<(), Box<dyn Error>> {
+    let progress_key = format!("migration_{}_progress", migration.version());
+    let (mut column_family_index, mut last_key) = match global_values.get(progress_key.clone())? {
+        Some(progress) => parse_progress(&progress)?,
+        None => (0, Vec::new()),
+    };
+
+    let column_families = migration.column_families();
+    while column_family_index < column_families.len() {
+        let name = &column_families[column_family_index];
+        let mode = if last_key.is_empty() {
+            IteratorMode::Start
+        } else {
+            IteratorMode::From(&last_key, Direction::Forward)
+        };
+
+        // Iterators read from an implicit snapshot, so entries rewritten by the step are not seen again
+        let mut batch = WriteBatch::default();
+        let mut batch_size = 0;
+        let mut migrated = 0;
+        for entry in db.iterator_cf(column_family(db, name)?, mode) {
+            let (key, value) = entry?;
+            if *key == *last_key {
+                continue;
+            }
+            migration.migrate(&mut batch, name, &key, &value)?;
+            batch_size += 1;
+            migrated += 1;
+            if batch_size == MIGRATION_BATCH_SIZE {
+                let progress = format!("{}:{}", column_family_index, hex::encode(&key));
+                write_progress(
+                    db,
+                    global_values,
+                    config_database,
+                    migration,
+                    &progress_key,
+                    progress,
+                    batch,
+                )?;
+                info!("Migrated {} entries of {}", migrated, name);
+                batch = WriteBatch::default();
+                batch_size = 0;
+            }
+        }
+
+        column_family_index += 1;
+        last_key = Vec::new();
+        if column_family_index == column_families.len() {
+            config_database.set(DB_VERSION_KEY.clone(), migration.version().to_string())?;
+        }
+        let progress = format!("{}:", column_family_index);
+        write_progress(
+            db,
+            global_values,
+            config_database,
+            migration,
+            &progress_key,
+            progress,
+            batch,
+        )?;
+        info!("Migrated {} entries of {}", migrated, name);
+    }
+
+    // Steps completed by older versions wrote the version separately, after the progress
+    if config_database.get(DB_VERSION_KEY.clone())? != Some(migration.version().to_string()) {
+        config_database.set(DB_VERSION_KEY.clone(), migration.version().to_string())?;
+        config_database.flush()?;
+    }
+    Ok(())
+}
+
+fn write_progress(
+    db: &DB,
+    global_values: &mut ConfigDatabase,
+    config_database: &mut ConfigDatabase,
+    migration: &mut dyn Migration,
+    progress_key: &str,
+    progress: String,
+    mut batch: WriteBatch,
+) -> Result<(), Box<dyn Error>> {
+    migration.write_to_batch(&mut batch)?;
+    global_values.set(progress_key.to_string(), progress)?;
+    global_values.write_to_batch(&mut batch)?;
+    config_database.write_to_batch(&mut batch)?;
+    write_batch(db, batch)?;
+    global_values.clear_cache();
+    config_database.clear_cache();
+    Ok(())
+}
+
+fn parse_progress(progress: &str) -> Result<(usize, Vec<u8>), Box<dyn Error>> {
+    let (column_family_index, last_key) = progress
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid migration progress: {}", progress))?;
+    Ok((column_family_index.parse()?, hex::decode(last_key)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy::primitives::B256;
+    use revm::primitives::{Log, LogData};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::db::cached_database::{
+        BlockCachedDatabase, BlockHistoryCache, BlockHistoryCacheData,
+    };
+    use crate::db::types::{
+        join_block_traces, receipts_root, transactions_root, AddressED, BlockResponseED, Decode,
+        Encode, TraceED, TxED, TxReceiptED, B256ED, U128ED,
+    };
+    use crate::global::CONFIG;
+
+    fn create_database(path: &Path, version: u32) -> Arc<DB> {
+        let db = Brc20ProgDatabase::open_raw_database(path, false).unwrap();
+        let mut config_database =
+            ConfigDatabase::from_column_family(db.clone(), CONFIG_NAME).unwrap();
+        config_database
+            .set(DB_VERSION_KEY.clone(), version.to_string())
+            .unwrap();
+        config_database
+            .set(ARCHIVE_MODE_KEY.clone(), false.to_string())
+            .unwrap();
+        config_database.flush().unwrap();
+        db
+    }
+
+    fn config(path: &Path) -> Brc20ProgConfig {
+        Brc20ProgConfig {
+            db_path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn put(db: &DB, name: &str, key: impl Encode, value: Vec<u8>) {
+        db.put_cf(column_family(db, name).unwrap(), key.encode_vec(), value)
+            .unwrap();
+    }
+
+    fn get<T: Decode>(db: &DB, name: &str, key: impl Encode) -> T {
+        let value = db
+            .get_cf(column_family(db, name).unwrap(), key.encode_vec())
+            .unwrap()
+            .unwrap();
+        T::decode_vec(&value).unwrap()
+    }
+
+    fn stored_version(path: &Path) -> String {
+        Brc20ProgDatabase::open_config_database(path)
+            .unwrap()
+            .get(DB_VERSION_KEY.clone())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn tx(hash: B256ED, tx_type: u8) -> TxED {
+        TxED {
+            hash,
+            nonce: 1u64.into(),
+            block_hash: [2u8; 32].into(),
+            block_number: Some(2u64.into()),
+            transaction_index: Some(0u64.into()),
+            from: [3u8; 20].into(),
+            to: Some([4u8; 20].into()),
+            value: 0u64.into(),
+            gas: 5u64.into(),
+            gas_price: 0u64.into(),
+            input: vec![7, 8, 9].into(),
+            v: 1u8.into(),
+            r: 11u8.into(),
+            s: 12u8.into(),
+            chain_id: CONFIG.read().chain_id.into(),
+            tx_type: tx_type.into(),
+            access_list: None,
+            inscription_id: None,
+        }
+    }
+
+    fn untyped_tx(tx: &TxED) -> Vec<u8> {
+        let mut encoded = tx.encode_vec();
+        // Type (a single 64-bit limb) and the flag of the empty access list
+        encoded.truncate(encoded.len() - 9);
+        encoded
+    }
+
+    fn receipt(hash: B256ED, log_address: AddressED, topic: B256ED) -> TxReceiptED {
+        TxReceiptED::new(
+            [2u8; 32].into(),
+            2u64.into(),
+            None,
+            [3u8; 20].into(),
+            Some([4u8; 20].into()),
+            hash,
+            0u64.into(),
+            true,
+            &vec![Log {
+                address: log_address.address,
+                data: LogData::new(vec![topic.bytes], vec![5u8; 32].into()).unwrap(),
+            }],
+            21000,
+            21000u64.into(),
+            3u64.into(),
+            0u8.into(),
+        )
+        .unwrap()
+    }
+
+    fn untyped_receipt(receipt: &TxReceiptED) -> Vec<u8> {
+        let mut encoded = receipt.encode_vec();
+        // Type (a single 64-bit limb)
+        encoded.truncate(encoded.len() - 8);
+        encoded
+    }
+
+    #[test]
+    fn test_migrate_transaction_types() {
+        let path = TempDir::new().unwrap();
+        let hash: B256ED = [1u8; 32].into();
+        let tx = tx(hash, 0);
+        let receipt = receipt(hash, [6u8; 20].into(), [7u8; 32].into());
+        {
+            let db = create_database(path.path(), 8);
+            put(&db, "tx", hash, untyped_tx(&tx));
+            put(&db, "tx_receipt", hash, untyped_receipt(&receipt));
+            // Caches have the same encoding as values, with their block numbers
+            let mut cache_bytes = Vec::new();
+            1u32.encode(&mut cache_bytes);
+            2u64.encode(&mut cache_bytes);
+            1u8.encode(&mut cache_bytes);
+            cache_bytes.extend(untyped_tx(&tx));
+            put(&db, "tx_cache", hash, cache_bytes);
+        }
+
+        migrate_database(&config(path.path())).unwrap();
+        assert_eq!(stored_version(path.path()), DB_VERSION.to_string());
+
+        let db = Brc20ProgDatabase::open_raw_database(path.path(), false).unwrap();
+        assert_eq!(get::<TxED>(&db, "tx", hash), tx);
+        assert_eq!(get::<TxReceiptED>(&db, "tx_receipt", hash), receipt);
+        assert_eq!(
+            get::<BlockHistoryCacheData<TxED>>(&db, "tx_cache", hash).latest(),
+            Some(tx)
+        );
+    }
+
+    #[test]
+    fn test_migrate_state_roots() {
+        let path = TempDir::new().unwrap();
+        let hash: B256ED = [10u8; 32].into();
+        let tx = tx(hash, 0);
+        let receipt = receipt(hash, [6u8; 20].into(), [7u8; 32].into());
+        let block = |number: u64, tx_hash: B256ED, tx_root: B256ED, receipts_root: B256ED| {
+            BlockResponseED::new(
+                2u64.into(),
+                [4u8; 32].into(),
+                [5u8; 256].into(),
+                0u64.into(),
+                number.into(),
+                8u64.into(),
+                9u64.into(),
+                vec![tx_hash],
+                tx_root,
+                [14u8; 32].into(),
+                receipts_root,
+                [0u8; 32].into(),
+            )
+        };
+        {
+            let db = create_database(path.path(), 9);
+            // Transaction of block 3 is missing
+            for (number, tx_hash) in [(2u64, hash), (3, [11u8; 32].into())] {
+                let mut encoded =
+                    block(number, tx_hash, [12u8; 32].into(), [13u8; 32].into()).encode_vec();
+                // State root
+                encoded.truncate(encoded.len() - 32);
+                put(&db, "block_number_to_block", number, encoded);
+            }
+            put(&db, "tx", hash, tx.encode_vec());
+            put(&db, "tx_receipt", hash, receipt.encode_vec());
+        }
+
+        migrate_database(&config(path.path())).unwrap();
+        assert_eq!(stored_version(path.path()), DB_VERSION.to_string());
+
+        let db = Brc20ProgDatabase::open_raw_database(path.path(), false).unwrap();
+        assert_eq!(
+            get::<BlockResponseED>(&db, "block_number_to_block", 2u64),
+            block(
+                2,
+                hash,
+                transactions_root(&[tx]).into(),
+                receipts_root(&[receipt]).into()
+            )
+        );
+        assert_eq!(
+            get::<BlockResponseED>(&db, "block_number_to_block", 3u64),
+            block(3, [11u8; 32].into(), [0u8; 32].into(), [0u8; 32].into())
+        );
+    }
+
+    #[test]
+    fn test_migrate_consensus_hashes() {
+        let path = TempDir::new().unwrap();
+        let hash: B256ED = [1u8; 32].into();
+        let receipt = receipt(hash, [6u8; 20].into(), [7u8; 32].into());
+        let trace = TraceED {
+            tx_type: "call".to_string(),
+            from: [3u8; 20].into(),
+            to: Some([4u8; 20].into()),
+            calls: vec![],
+            gas: 5u64.into(),
+            gas_used: 5u64.into(),
+            input: vec![7, 8, 9].into(),
+            output: vec![].into(),
+            value: 0u64.into(),
+            error: None,
+            revert_reason: None,
+        };
+        let block = |number: u64, transactions: Vec<B256ED>| {
+            BlockResponseED::new(
+                0u64.into(),
+                [number as u8; 32].into(),
+                [0u8; 256].into(),
+                0u64.into(),
+                number.into(),
+                0u64.into(),
+                0u64.into(),
+                transactions,
+                [0u8; 32].into(),
+                [0u8; 32].into(),
+                [0u8; 32].into(),
+                [number as u8 + 10; 32].into(),
+            )
+        };
+        {
+            let db = create_database(path.path(), 10);
+            let (blocks, consensus_hashes) =
+                ("block_number_to_block", "block_number_to_consensus_hash");
+            put(&db, blocks, 0u64, block(0, vec![]).encode_vec());
+            put(&db, blocks, 1u64, block(1, vec![hash]).encode_vec());
+            put(&db, "tx_receipt", hash, receipt.encode_vec());
+            put(&db, "tx_trace", hash, trace.encode_vec());
+            // Consensus hashes that started from zero at the upgrade height
+            put(&db, consensus_hashes, 1u64, [2u8; 32].encode_vec());
+            // Blocks after a missing block, as imported from a snapshot
+            put(&db, blocks, 5u64, block(5, vec![]).encode_vec());
+            put(&db, consensus_hashes, 5u64, [8u8; 32].encode_vec());
+            put(&db, blocks, 6u64, block(6, vec![]).encode_vec());
+        }
+
+        migrate_database(&config(path.path())).unwrap();
+        assert_eq!(stored_version(path.path()), DB_VERSION.to_string());
+
+        let empty_trace_hash = B256::from_str(&sha256::digest("")).unwrap();
+        let first = Brc20ProgDatabase::compute_consensus_hash(
+            B256::ZERO,
+            empty_trace_hash,
+            [10u8; 32].into(),
+        )
+        .unwrap();
+        let second = Brc20ProgDatabase::compute_consensus_hash(
+            first,
+            B256::from_str(&sha256::digest(join_block_traces(&[trace]))).unwrap(),
+            [11u8; 32].into(),
+        )
+        .unwrap();
+        let db = Brc20ProgDatabase::open_raw_database(path.path(), false).unwrap();
+        let consensus_hash = |block_number: u64| {
+            get::<B256ED>(&db, "block_number_to_consensus_hash", block_number).bytes
+        };
+        assert_eq!(consensus_hash(0), first);
+        assert_eq!(consensus_hash(1), second);
+        assert_eq!(consensus_hash(5), B256::from([8u8; 32]));
+        assert_eq!(
+            consensus_hash(6),
+            Brc20ProgDatabase::compute_consensus_hash(
+                [8u8; 32].into(),
+                empty_trace_hash,
+                [16u8; 32].into()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_migrate_log_indexes() {
+        let path = TempDir::new().unwrap();
+        let hash: B256ED = [1u8; 32].into();
+        let address: AddressED = [6u8; 20].into();
+        let topic: B256ED = [7u8; 32].into();
+        let receipt = receipt(hash, address, topic);
+        {
+            let db = create_database(path.path(), 7);
+            put(&db, "tx_receipt", hash, untyped_receipt(&receipt));
+            put(&db, "block_number_to_hash", 2u64, [2u8; 32].encode_vec());
+        }
+
+        migrate_database(&config(path.path())).unwrap();
+        assert_eq!(stored_version(path.path()), DB_VERSION.to_string());
+
+        let db = Brc20ProgDatabase::open_raw_database(path.path(), false).unwrap();
+        let log_key: U128ED = Brc20ProgDatabase::get_number_and_index_key(2, 3).into();
+        let address_index = BlockCachedDatabase::<
+            (AddressED, U128ED),
+            B256ED,
+            BlockHistoryCacheData<B256ED>,
+        >::new(db.clone(), "log_address_index", false)
+        .unwrap();
+        assert_eq!(
+            address_index.latest(&(address, log_key)).unwrap(),
+            Some(hash)
+        );
+        let topic_index = BlockCachedDatabase::<
+            (B256ED, U128ED),
+            B256ED,
+            BlockHistoryCacheData<B256ED>,
+        >::new(db.clone(), "log_topic_index", false)
+        .unwrap();
+        assert_eq!(topic_index.latest(&(topic, log_key)).unwrap(), Some(hash));
+        assert_eq!(get::<TxReceiptED>(&db, "tx_receipt", hash), receipt);
+    }
+
+    #[test]
+    fn test_migration_resumes_from_progress() {
+        let path = TempDir::new().unwrap();
+        let migrated_hash: B256ED = [1u8; 32].into();
+        let pending_hash: B256ED = [2u8; 32].into();
+        let migrated_tx = tx(migrated_hash, 2);
+        let pending_tx = tx(pending_hash, 0);
+        {
+            let db = create_database(path.path(), 8);
+            // Transactions were migrated before the migration was interrupted
+            put(&db, "tx", migrated_hash, migrated_tx.encode_vec());
+            put(
+                &db,
+                "account_and_nonce_to_tx_hash",
+                (pending_tx.from, pending_tx.nonce),
+                untyped_tx(&pending_tx),
+            );
+            let mut global_values =
+                ConfigDatabase::from_column_family(db.clone(), GLOBAL_VALUES_NAME).unwrap();
+            global_values
+                .set("migration_9_progress".to_string(), "1:".to_string())
+                .unwrap();
+            global_values.flush().unwrap();
+        }
+
+        migrate_database(&config(path.path())).unwrap();
+
+        let db = Brc20ProgDatabase::open_raw_database(path.path(), false).unwrap();
+        assert_eq!(get::<TxED>(&db, "tx", migrated_hash), migrated_tx);
+        assert_eq!(
+            get::<TxED>(
+                &db,
+                "account_and_nonce_to_tx_hash",
+                (pending_tx.from, pending_tx.nonce)
+            ),
+            pending_tx
+        );
+    }
+
+    #[test]
+    fn test_migrate_unsupported_version() {
+        let path = TempDir::new().unwrap();
+        drop(create_database(path.path(), OLDEST_MIGRATED_VERSION - 1));
+
+        assert!(migrate_database(&config(path.path())).is_err());
+        assert_eq!(
+            stored_version(path.path()),
+            (OLDEST_MIGRATED_VERSION - 1).to_string()
+        );
+    }
+
+    #[test]
+    fn test_migrate_fresh_database() {
+        let path = TempDir::new().unwrap();
+        migrate_database(&config(path.path())).unwrap();
+        assert!(!path.path().join(DATABASE_NAME).exists());
+    }
+}
@@ -0,0 +1,91 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use rocksdb::{WriteBatch, DB};
+
+use crate::db::database::column_family;
+use crate::db::migration::Migration;
+use crate::db::types::{
+    receipts_root, transactions_root, BlockResponseED, Decode, Encode, TxED, TxReceiptED, B256ED,
+};
+
+/// Re-encodes blocks with their state root, and recomputes their transactions and receipts roots
+///
+/// Transactions and receipts roots stored before this version aren't Ethereum trie roots, they're
+/// recomputed from the stored transactions and receipts of the block, and set to zero if any of them
+/// is missing, e.g. in databases imported from a snapshot.
+///
+/// State roots can't be recomputed without the state of every block, so blocks stored before this
+/// version have a zero state root, and the state trie is built from all accounts when the next block is
+/// finalised. The first block with a state root is the cut-over height, state proofs and snapshots of
+/// earlier blocks are rejected since there's no state root to check them against.
+pub struct StateRootsMigration {
+    db: Arc<DB>,
+}
+
+impl StateRootsMigration {
+    pub fn new(db: Arc<DB>) -> Self {
+        Self { db }
+    }
+
+    /// Get the values of the given transactions from a column family, None if any of them is missing
+    fn get_all<T: Decode>(
+        &self,
+        column_family_name: &str,
+        tx_hashes: &[B256ED],
+    ) -> Result<Option<Vec<T>>, Box<dyn Error>> {
+        let column_family = column_family(&self.db, column_family_name)?;
+        let mut values = Vec::new();
+        for tx_hash in tx_hashes {
+            let Some(value) = self.db.get_cf(column_family, tx_hash.encode_vec())? else {
+                return Ok(None);
+            };
+            values.push(T::decode_vec(&value)?);
+        }
+        Ok(Some(values))
+    }
+}
+
+impl Migration for StateRootsMigration {
+    fn version(&self) -> u32 {
+        10
+    }
+
+    fn description(&self) -> &'static str {
+        "encode blocks with their state root and recompute their transactions and receipts roots"
+    }
+
+    fn column_families(&self) -> Vec<String> {
+        vec!["block_number_to_block".to_string()]
+    }
+
+    fn migrate(
+        &mut self,
+        batch: &mut WriteBatch,
+        column_family_name: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let (mut block, _) = BlockResponseED::decode_without_state_root(value, 0)?;
+        let tx_hashes = block.transactions.clone().left().unwrap_or_default();
+        match (
+            self.get_all::<TxED>("tx", &tx_hashes)?,
+            self.get_all::<TxReceiptED>("tx_receipt", &tx_hashes)?,
+        ) {
+            (Some(txes), Some(receipts)) => {
+                block.transactions_root = transactions_root(&txes).into();
+                block.receipts_root = receipts_root(&receipts).into();
+            }
+            _ => {
+                block.transactions_root = [0u8; 32].into();
+                block.receipts_root = [0u8; 32].into();
+            }
+        }
+        batch.put_cf(
+            column_family(&self.db, column_family_name)?,
+            key,
+            block.encode_vec(),
+        );
+        Ok(())
+    }
+}
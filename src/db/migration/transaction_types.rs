@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use rocksdb::{WriteBatch, DB};
+
+use crate::db::cached_database::BlockHistoryCacheData;
+use crate::db::database::{column_family, ArchiveDatabase};
+use crate::db::migration::Migration;
+use crate::db::types::{AddressED, Decode, Encode, TxED, TxReceiptED, B256ED, U64ED};
+
+type Reencoder = fn(&[u8]) -> Result<Vec<u8>, Box<dyn Error>>;
+
+/// Re-encodes transactions and receipts with their transaction type
+///
+/// Transactions are also encoded with their access list, values stored before this version are all
+/// legacy transactions without an access list
+pub struct TransactionTypesMigration {
+    db: Arc<DB>,
+    column_families: Vec<(String, Reencoder)>,
+}
+
+impl TransactionTypesMigration {
+    pub fn new(db: Arc<DB>, archive_mode: bool) -> Self {
+        let mut column_families = Vec::new();
+        add_store::<B256ED, Untyped<TxED>>(&mut column_families, "tx", archive_mode);
+        add_store::<(AddressED, U64ED), Untyped<TxED>>(
+            &mut column_families,
+            "account_and_nonce_to_tx_hash",
+            archive_mode,
+        );
+        add_store::<B256ED, Untyped<TxReceiptED>>(&mut column_families, "tx_receipt", archive_mode);
+        Self {
+            db,
+            column_families,
+        }
+    }
+}
+
+impl Migration for TransactionTypesMigration {
+    fn version(&self) -> u32 {
+        9
+    }
+
+    fn description(&self) -> &'static str {
+        "encode transactions and receipts with their type"
+    }
+
+    fn column_families(&self) -> Vec<String> {
+        self.column_families
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn migrate(
+        &mut self,
+        batch: &mut WriteBatch,
+        column_family_name: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let (_, reencode) = self
+            .column_families
+            .iter()
+            .find(|(name, _)| name == column_family_name)
+            .ok_or_else(|| format!("Unexpected column family {}", column_family_name))?;
+        batch.put_cf(
+            column_family(&self.db, column_family_name)?,
+            key,
+            reencode(value)?,
+        );
+        Ok(())
+    }
+}
+
+/// Add the column families of a BlockCachedDatabase storing V to be re-encoded
+///
+/// Latest values, their history caches and archived values are stored in separate column families
+fn add_store<K, V>(column_families: &mut Vec<(String, Reencoder)>, name: &str, archive_mode: bool)
+where
+    K: Encode + Decode,
+    V: Encode + Decode + Clone + Eq,
+{
+    column_families.push((name.to_string(), reencode::<V>));
+    column_families.push((
+        format!("{}_cache", name),
+        reencode::<BlockHistoryCacheData<V>>,
+    ));
+    if archive_mode {
+        let [values_column_family, _] =
+            ArchiveDatabase::<K, V>::column_families(&format!("{}_archive", name));
+        column_families.push((values_column_family, reencode::<Option<V>>));
+    }
+}
+
+fn reencode<T: Encode + Decode>(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(T::decode(bytes, 0)?.0.encode_vec())
+}
+
+/// A value decoded from its encoding without the transaction type, and encoded in the current format
+#[derive(Clone, PartialEq, Eq)]
+struct Untyped<T>(T);
+
+impl<T: Encode> Encode for Untyped<T> {
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.0.encode(buffer);
+    }
+}
+
+impl Decode for Untyped<TxED> {
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+        let (tx, offset) = TxED::decode_untyped(bytes, offset)?;
+        Ok((Untyped(tx), offset))
+    }
+}
+
+impl Decode for Untyped<TxReceiptED> {
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+        let (receipt, offset) = TxReceiptED::decode_untyped(bytes, offset)?;
+        Ok((Untyped(receipt), offset))
+    }
+}
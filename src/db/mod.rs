@@ -1,8 +1,17 @@
 mod cached_database;
-mod database;
+pub(crate) mod database;
 
 mod brc20_prog_database;
+mod brc20_prog_database_view;
+mod migration;
+mod snapshot;
 pub mod types;
 
 #[cfg(feature = "server")]
 pub use brc20_prog_database::Brc20ProgDatabase;
+#[cfg(feature = "server")]
+pub use brc20_prog_database_view::Brc20ProgDatabaseReader;
+#[cfg(feature = "server")]
+pub(crate) use migration::migrate_database;
+#[cfg(feature = "server")]
+pub(crate) use snapshot::{export_snapshot, import_snapshot};
@@ -0,0 +1,640 @@
+#![cfg(feature = "server")]
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use alloy::primitives::B256;
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::db::brc20_prog_database::{
+    BLOCK_DATABASE_NAMES, DATABASE_NAME, GLOBAL_VALUES_NAME, MAX_BLOCK_NUMBER_KEY,
+    SNAPSHOT_BLOCK_NUMBER_KEY,
+};
+use crate::db::database::{column_family, write_batch};
+use crate::db::types::{BlockResponseED, Decode, Encode, B256ED, U64ED};
+use crate::db::Brc20ProgDatabase;
+use crate::global::database::{validate_config_database, ConfigDatabase};
+use crate::global::{Brc20ProgConfig, DB_VERSION, PROTOCOL_VERSION};
+
+/// Version of the snapshot format, snapshots with a different version can't be imported
+static SNAPSHOT_VERSION: u32 = 1;
+
+/// Name of the file describing the snapshot, with the integrity hashes of the store files
+static MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Stores kept entirely in a snapshot, i.e. accounts, code, storage, inscription ID mappings and the txpool
+static SNAPSHOT_STORE_NAMES: [&str; 7] = [
+    "account",
+    "code",
+    "account_memory",
+    "inscription_id_to_tx_hash",
+    "contract_address_to_inscription_id",
+    "account_and_nonce_to_tx_hash",
+    "pending_tx_hash_to_tx_id",
+];
+
+/// Store mapping block hashes to block numbers, only hashes of recent blocks are kept in a snapshot
+static BLOCK_HASH_TO_NUMBER_NAME: &str = "block_hash_to_number";
+
+/// Number of recent blocks kept in a snapshot, BLOCKHASH can read the hashes of the last 256 blocks
+static SNAPSHOT_BLOCK_COUNT: u64 = 256;
+
+/// Number of entries imported in a single batch
+static IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// Describes a snapshot, written to MANIFEST_FILE_NAME in the snapshot directory
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotManifest {
+    version: u32,
+    db_version: u32,
+    protocol_version: u32,
+    bitcoin_network: String,
+    chain_id: u64,
+    block_number: u64,
+    block_hash: B256,
+    state_root: B256,
+    consensus_hash: Option<B256>,
+    stores: Vec<SnapshotStore>,
+}
+
+/// A store in a snapshot, its entries are kept in a file named after it
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotStore {
+    name: String,
+    entry_count: u64,
+    /// SHA256 hash of the file, same as the output of sha256sum
+    sha256: String,
+}
+
+impl SnapshotStore {
+    fn file_name(name: &str) -> String {
+        format!("{}.bin", name)
+    }
+}
+
+/// Export the state of the latest committed block to a snapshot directory
+///
+/// The snapshot keeps the latest values of the stores in SNAPSHOT_STORE_NAMES, and the last SNAPSHOT_BLOCK_COUNT
+/// blocks. Transactions, receipts, logs and traces are not kept, and neither are the history caches, so blocks
+/// before the snapshot can't be reorged or traced once it's imported.
+///
+/// config: &Brc20ProgConfig - the configuration of the database to export
+/// path: &Path - the snapshot directory, it's created if it doesn't exist and should be empty otherwise
+///
+/// Returns: u64 - the block number of the snapshot
+pub fn export_snapshot(config: &Brc20ProgConfig, path: &Path) -> Result<u64, Box<dyn Error>> {
+    let base_path = Path::new(&config.db_path);
+    if !base_path.join(DATABASE_NAME).exists() {
+        return Err(format!("No database found at {}", config.db_path).into());
+    }
+    if path.exists() && path.read_dir()?.next().is_some() {
+        return Err(format!("Snapshot directory {} is not empty", path.display()).into());
+    }
+    std::fs::create_dir_all(path)?;
+
+    // Opening the stores reverts blocks that were only partially committed by older versions
+    drop(Brc20ProgDatabase::with_archive_mode(
+        base_path,
+        config.archive_mode,
+    )?);
+    let db = Brc20ProgDatabase::open_raw_database(base_path, config.archive_mode)?;
+
+    let block_number: u64 = match db
+        .iterator_cf(
+            column_family(&db, "block_number_to_hash")?,
+            IteratorMode::End,
+        )
+        .next()
+    {
+        Some(entry) => U64ED::decode_vec(&entry?.0.to_vec())?.into(),
+        None => return Err("No blocks to export".into()),
+    };
+    let block = BlockResponseED::decode_vec(
+        &db.get_cf(
+            column_family(&db, "block_number_to_block")?,
+            U64ED::from(block_number).encode_vec(),
+        )?
+        .ok_or_else(|| format!("Block {} not found", block_number))?,
+    )?;
+    if block.state_root.bytes.is_zero() {
+        return Err(format!("State root of block {} is not available", block_number).into());
+    }
+    let consensus_hash = db
+        .get_cf(
+            column_family(&db, "block_number_to_consensus_hash")?,
+            U64ED::from(block_number).encode_vec(),
+        )?
+        .map(|value| B256ED::decode_vec(&value))
+        .transpose()?
+        .map(|hash| hash.bytes);
+    info!("Exporting snapshot of block {}", block_number);
+
+    let first_block_number = block_number.saturating_sub(SNAPSHOT_BLOCK_COUNT - 1);
+    let first_block_key = U64ED::from(first_block_number).encode_vec();
+    let mut stores = Vec::new();
+    for name in SNAPSHOT_STORE_NAMES {
+        stores.push(export_store(&db, path, name, IteratorMode::Start, |_| {
+            Ok(true)
+        })?);
+    }
+    for name in BLOCK_DATABASE_NAMES {
+        let mode = IteratorMode::From(&first_block_key, Direction::Forward);
+        stores.push(export_store(&db, path, name, mode, |_| Ok(true))?);
+    }
+    stores.push(export_store(
+        &db,
+        path,
+        BLOCK_HASH_TO_NUMBER_NAME,
+        IteratorMode::Start,
+        |value| Ok(Into::<u64>::into(U64ED::decode_vec(&value.to_vec())?) >= first_block_number),
+    )?);
+
+    let manifest = SnapshotManifest {
+        version: SNAPSHOT_VERSION,
+        db_version: *DB_VERSION,
+        protocol_version: *PROTOCOL_VERSION,
+        bitcoin_network: config.bitcoin_rpc_network.clone(),
+        chain_id: config.chain_id,
+        block_number,
+        block_hash: block.hash.bytes,
+        state_root: block.state_root.bytes,
+        consensus_hash,
+        stores,
+    };
+    serde_json::to_writer_pretty(File::create(path.join(MANIFEST_FILE_NAME))?, &manifest)?;
+    info!("Exported snapshot of block {}", block_number);
+    Ok(block_number)
+}
+
+/// Write the entries of a store accepted by the filter to its file in the snapshot directory
+///
+/// filter: checks if an entry should be exported, by its value
+fn export_store<F>(
+    db: &DB,
+    path: &Path,
+    name: &str,
+    mode: IteratorMode,
+    filter: F,
+) -> Result<SnapshotStore, Box<dyn Error>>
+where
+    F: Fn(&[u8]) -> Result<bool, Box<dyn Error>>,
+{
+    let file_path = path.join(SnapshotStore::file_name(name));
+    let mut writer = BufWriter::new(File::create(&file_path)?);
+    let mut entry_count = 0;
+    for entry in db.iterator_cf(column_family(db, name)?, mode) {
+        let (key, value) = entry?;
+        if !filter(&value)? {
+            continue;
+        }
+        write_entry(&mut writer, &key)?;
+        write_entry(&mut writer, &value)?;
+        entry_count += 1;
+    }
+    writer.flush()?;
+    drop(writer);
+    info!("Exported {} entries of {}", entry_count, name);
+
+    Ok(SnapshotStore {
+        name: name.to_string(),
+        entry_count,
+        sha256: sha256::try_digest(&file_path)?,
+    })
+}
+
+/// Import a snapshot into an empty database
+///
+/// Files of the snapshot are checked against the hashes in its manifest before anything is imported, and
+/// the state trie is built from the imported accounts and checked against the state root of the snapshot
+/// block. The imported database is removed if the import fails, so it can be run again.
+///
+/// config: &Brc20ProgConfig - the configuration of the database to import into, its path should be empty
+/// path: &Path - the snapshot directory
+///
+/// Returns: u64 - the block number of the snapshot
+pub fn import_snapshot(config: &Brc20ProgConfig, path: &Path) -> Result<u64, Box<dyn Error>> {
+    let base_path = Path::new(&config.db_path);
+    if base_path.exists() && base_path.read_dir()?.next().is_some() {
+        return Err(format!(
+            "Snapshots can only be imported into an empty directory, {} is not empty",
+            config.db_path
+        )
+        .into());
+    }
+    if config.archive_mode {
+        return Err("Snapshots can't be imported into archive databases".into());
+    }
+
+    let manifest: SnapshotManifest =
+        serde_json::from_reader(BufReader::new(File::open(path.join(MANIFEST_FILE_NAME))?))?;
+    validate_manifest(config, path, &manifest)?;
+
+    let result = import_stores(config, path, &manifest);
+    if result.is_err() {
+        if base_path.join(DATABASE_NAME).exists() {
+            std::fs::remove_dir_all(base_path.join(DATABASE_NAME))?;
+        }
+    }
+    result.map(|_| manifest.block_number)
+}
+
+/// Check the manifest is compatible with the configuration, and the files match their hashes
+fn validate_manifest(
+    config: &Brc20ProgConfig,
+    path: &Path,
+    manifest: &SnapshotManifest,
+) -> Result<(), Box<dyn Error>> {
+    if manifest.version != SNAPSHOT_VERSION {
+        return Err(format!(
+            "Snapshot version mismatch: expected {}, found {}",
+            SNAPSHOT_VERSION, manifest.version
+        )
+        .into());
+    }
+    if manifest.db_version != *DB_VERSION {
+        return Err(format!(
+            "Snapshot database version mismatch: expected {}, found {}",
+            *DB_VERSION, manifest.db_version
+        )
+        .into());
+    }
+    if manifest.protocol_version != *PROTOCOL_VERSION {
+        return Err(format!(
+            "Snapshot protocol version mismatch: expected {}, found {}",
+            *PROTOCOL_VERSION, manifest.protocol_version
+        )
+        .into());
+    }
+    if manifest.bitcoin_network != config.bitcoin_rpc_network
+        || manifest.chain_id != config.chain_id
+    {
+        return Err(format!(
+            "Snapshot network mismatch: expected {} with chain id {}, found {} with chain id {}",
+            config.bitcoin_rpc_network,
+            config.chain_id,
+            manifest.bitcoin_network,
+            manifest.chain_id
+        )
+        .into());
+    }
+
+    let expected_names = snapshot_store_names();
+    for (index, store) in manifest.stores.iter().enumerate() {
+        if !expected_names.contains(&store.name.as_str()) {
+            return Err(format!("Snapshot has an unknown store {}", store.name).into());
+        }
+        if manifest.stores[..index]
+            .iter()
+            .any(|other| other.name == store.name)
+        {
+            return Err(format!("Snapshot has the {} store more than once", store.name).into());
+        }
+    }
+    for name in expected_names {
+        let store = manifest_store(manifest, name)?;
+        let sha256 = sha256::try_digest(path.join(SnapshotStore::file_name(name)))?;
+        if sha256 != store.sha256 {
+            return Err(format!(
+                "Snapshot file of {} is corrupted: expected hash {}, found {}",
+                name, store.sha256, sha256
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Names of the stores kept in a snapshot, in the order they're exported and imported
+fn snapshot_store_names() -> Vec<&'static str> {
+    let mut names = SNAPSHOT_STORE_NAMES.to_vec();
+    names.extend(BLOCK_DATABASE_NAMES);
+    names.push(BLOCK_HASH_TO_NUMBER_NAME);
+    names
+}
+
+fn manifest_store<'a>(
+    manifest: &'a SnapshotManifest,
+    name: &str,
+) -> Result<&'a SnapshotStore, Box<dyn Error>> {
+    manifest
+        .stores
+        .iter()
+        .find(|store| store.name == name)
+        .ok_or_else(|| format!("Snapshot doesn't have the {} store", name).into())
+}
+
+fn import_stores(
+    config: &Brc20ProgConfig,
+    path: &Path,
+    manifest: &SnapshotManifest,
+) -> Result<(), Box<dyn Error>> {
+    let base_path = Path::new(&config.db_path);
+    info!("Importing snapshot of block {}", manifest.block_number);
+    validate_config_database(config)?;
+    {
+        let db = Brc20ProgDatabase::open_raw_database(base_path, false)?;
+        for name in snapshot_store_names() {
+            import_store(&db, path, manifest_store(manifest, name)?)?;
+        }
+        let mut global_values = ConfigDatabase::from_column_family(db.clone(), GLOBAL_VALUES_NAME)?;
+        for key in [MAX_BLOCK_NUMBER_KEY, SNAPSHOT_BLOCK_NUMBER_KEY] {
+            global_values.set(key.to_string(), manifest.block_number.to_string())?;
+        }
+        global_values.flush()?;
+    }
+
+    let mut database = Brc20ProgDatabase::new(base_path)?;
+    let block = database
+        .get_block(manifest.block_number)?
+        .ok_or_else(|| format!("Block {} not found", manifest.block_number))?;
+    if database.get_latest_block_height()? != manifest.block_number
+        || block.hash.bytes != manifest.block_hash
+        || block.state_root.bytes != manifest.state_root
+        || database.get_consensus_hash(manifest.block_number)? != manifest.consensus_hash
+    {
+        return Err(format!(
+            "Block {} doesn't match the snapshot manifest",
+            manifest.block_number
+        )
+        .into());
+    }
+    info!("Building the state trie of block {}", manifest.block_number);
+    database.build_imported_state_trie()?;
+    info!("Imported snapshot of block {}", manifest.block_number);
+    Ok(())
+}
+
+fn import_store(db: &DB, path: &Path, store: &SnapshotStore) -> Result<(), Box<dyn Error>> {
+    let cf = column_family(db, &store.name)?;
+    let mut reader = BufReader::new(File::open(
+        path.join(SnapshotStore::file_name(&store.name)),
+    )?);
+    let mut batch = WriteBatch::default();
+    let mut entry_count = 0;
+    while let Some(key) = read_entry(&mut reader)? {
+        let value = read_entry(&mut reader)?
+            .ok_or_else(|| format!("Snapshot file of {} is truncated", store.name))?;
+        batch.put_cf(cf, key, value);
+        entry_count += 1;
+        if batch.len() == IMPORT_BATCH_SIZE {
+            write_batch(db, std::mem::take(&mut batch))?;
+            info!("Imported {} entries of {}", entry_count, store.name);
+        }
+    }
+    write_batch(db, batch)?;
+    if entry_count != store.entry_count {
+        return Err(format!(
+            "Snapshot file of {} has {} entries, expected {}",
+            store.name, entry_count, store.entry_count
+        )
+        .into());
+    }
+    info!("Imported {} entries of {}", entry_count, store.name);
+    Ok(())
+}
+
+/// Write a length prefixed key or value of an entry
+fn write_entry(writer: &mut impl Write, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    writer.write_all(&u32::try_from(bytes.len())?.to_be_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// Read a length prefixed key or value of an entry, None at the end of the file
+fn read_entry(reader: &mut impl Read) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, U256};
+    use revm::state::AccountInfo;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    static ACCOUNT: Address = Address::repeat_byte(1);
+
+    // Creates a database with an account and more blocks than kept in a snapshot
+    fn create_database(path: &Path) -> Brc20ProgConfig {
+        let config = Brc20ProgConfig {
+            db_path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        validate_config_database(&config).unwrap();
+        let mut db = Brc20ProgDatabase::new(path).unwrap();
+        db.set_account_info(
+            ACCOUNT,
+            AccountInfo {
+                balance: U256::from(100),
+                nonce: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        db.set_account_memory(ACCOUNT, U256::from(1), U256::from(10))
+            .unwrap();
+        for block_number in 0..300 {
+            let block_hash = B256::from(U256::from(block_number + 1));
+            let state_root = db.compute_state_root(block_number).unwrap();
+            let block = db
+                .generate_block(block_hash, block_number, 0, 0, 0, state_root)
+                .unwrap();
+            db.set_block(block_number, block).unwrap();
+            db.set_block_hash(block_number, block_hash).unwrap();
+            db.set_consensus_hash(block_number, B256::ZERO, state_root)
+                .unwrap();
+        }
+        db.commit_changes().unwrap();
+        config
+    }
+
+    fn config(path: &Path) -> Brc20ProgConfig {
+        Brc20ProgConfig {
+            db_path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn read_manifest(path: &Path) -> SnapshotManifest {
+        serde_json::from_reader(File::open(path.join(MANIFEST_FILE_NAME)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_snapshot() {
+        let source = TempDir::new().unwrap();
+        let snapshot = TempDir::new().unwrap();
+        let imported = TempDir::new().unwrap();
+        let source_config = create_database(source.path());
+
+        assert_eq!(
+            export_snapshot(&source_config, snapshot.path()).unwrap(),
+            299
+        );
+        let manifest = read_manifest(snapshot.path());
+        let block_store = manifest
+            .stores
+            .iter()
+            .find(|store| store.name == "block_number_to_block")
+            .unwrap();
+        assert_eq!(block_store.entry_count, SNAPSHOT_BLOCK_COUNT);
+        assert_eq!(
+            import_snapshot(&config(imported.path()), snapshot.path()).unwrap(),
+            299
+        );
+
+        let source_db = Brc20ProgDatabase::new(source.path()).unwrap();
+        let db = Brc20ProgDatabase::new(imported.path()).unwrap();
+        assert_eq!(db.get_latest_block_height().unwrap(), 299);
+        assert_eq!(
+            db.get_block(299).unwrap(),
+            source_db.get_block(299).unwrap()
+        );
+        assert_eq!(db.get_consensus_hash(299).unwrap(), manifest.consensus_hash);
+        assert!(db.get_block(44).unwrap().is_some());
+        assert!(db.get_block(43).unwrap().is_none());
+        assert_eq!(
+            db.get_block_number(B256::from(U256::from(45))).unwrap(),
+            Some(44u64.into())
+        );
+        assert!(db
+            .get_block_number(B256::from(U256::from(44)))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.get_account_info(ACCOUNT).unwrap(),
+            source_db.get_account_info(ACCOUNT).unwrap()
+        );
+        // The state trie is built, so accounts can be proven against the snapshot block
+        assert_eq!(
+            db.get_account_proof(ACCOUNT, 299).unwrap(),
+            source_db.get_account_proof(ACCOUNT, 299).unwrap()
+        );
+        // State history isn't imported, so the state before the snapshot block can't be read
+        db.require_state_available(299).unwrap();
+        assert!(db.require_state_available(298).is_err());
+        source_db.require_state_available(298).unwrap();
+    }
+
+    #[test]
+    fn test_import_invalid_snapshot() {
+        let source = TempDir::new().unwrap();
+        let snapshot = TempDir::new().unwrap();
+        let imported = TempDir::new().unwrap();
+        export_snapshot(&create_database(source.path()), snapshot.path()).unwrap();
+        let manifest_path = snapshot.path().join(MANIFEST_FILE_NAME);
+        let manifest_bytes = std::fs::read(&manifest_path).unwrap();
+
+        // Snapshots of another network can't be imported
+        let other_network = Brc20ProgConfig {
+            chain_id: config(imported.path()).chain_id + 1,
+            ..config(imported.path())
+        };
+        assert!(import_snapshot(&other_network, snapshot.path())
+            .unwrap_err()
+            .to_string()
+            .contains("network mismatch"));
+
+        // Files that don't match their hashes are rejected before anything is imported
+        let account_path = snapshot.path().join(SnapshotStore::file_name("account"));
+        let account_bytes = std::fs::read(&account_path).unwrap();
+        let mut corrupted = account_bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        std::fs::write(&account_path, corrupted).unwrap();
+        assert!(import_snapshot(&config(imported.path()), snapshot.path())
+            .unwrap_err()
+            .to_string()
+            .contains("corrupted"));
+        std::fs::write(&account_path, account_bytes).unwrap();
+
+        // Only the stores kept in a snapshot can be imported, each of them once
+        let mut manifest = read_manifest(snapshot.path());
+        let account_store = |name: &str| SnapshotStore {
+            name: name.to_string(),
+            entry_count: manifest.stores[0].entry_count,
+            sha256: manifest.stores[0].sha256.clone(),
+        };
+        let unknown_store = account_store(GLOBAL_VALUES_NAME);
+        let duplicate_store = account_store("account");
+        manifest.stores.push(unknown_store);
+        serde_json::to_writer(File::create(&manifest_path).unwrap(), &manifest).unwrap();
+        assert!(import_snapshot(&config(imported.path()), snapshot.path())
+            .unwrap_err()
+            .to_string()
+            .contains("unknown store"));
+        *manifest.stores.last_mut().unwrap() = duplicate_store;
+        serde_json::to_writer(File::create(&manifest_path).unwrap(), &manifest).unwrap();
+        assert!(import_snapshot(&config(imported.path()), snapshot.path())
+            .unwrap_err()
+            .to_string()
+            .contains("more than once"));
+        assert!(imported.path().read_dir().unwrap().next().is_none());
+        std::fs::write(&manifest_path, &manifest_bytes).unwrap();
+
+        // A state root that doesn't match the imported state fails after the stores are imported, and the
+        // imported database is removed
+        let mut manifest = read_manifest(snapshot.path());
+        manifest.state_root = B256::repeat_byte(2);
+        serde_json::to_writer(File::create(&manifest_path).unwrap(), &manifest).unwrap();
+        assert!(import_snapshot(&config(imported.path()), snapshot.path()).is_err());
+        assert!(imported.path().read_dir().unwrap().next().is_none());
+        std::fs::write(&manifest_path, &manifest_bytes).unwrap();
+
+        // Changed values with matching hashes are caught by the state root of the imported state
+        let memory_path = snapshot
+            .path()
+            .join(SnapshotStore::file_name("account_memory"));
+        let memory_bytes = std::fs::read(&memory_path).unwrap();
+        let mut changed = memory_bytes.clone();
+        *changed.last_mut().unwrap() ^= 1;
+        std::fs::write(&memory_path, changed).unwrap();
+        let mut manifest = read_manifest(snapshot.path());
+        for store in manifest.stores.iter_mut() {
+            if store.name == "account_memory" {
+                store.sha256 = sha256::try_digest(&memory_path).unwrap();
+            }
+        }
+        serde_json::to_writer(File::create(&manifest_path).unwrap(), &manifest).unwrap();
+        assert!(import_snapshot(&config(imported.path()), snapshot.path())
+            .unwrap_err()
+            .to_string()
+            .contains("doesn't match the state root"));
+        assert!(imported.path().read_dir().unwrap().next().is_none());
+        std::fs::write(&memory_path, memory_bytes).unwrap();
+
+        std::fs::write(&manifest_path, manifest_bytes).unwrap();
+        assert_eq!(
+            import_snapshot(&config(imported.path()), snapshot.path()).unwrap(),
+            299
+        );
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut bytes = Vec::new();
+        write_entry(&mut bytes, &[1, 2, 3]).unwrap();
+        write_entry(&mut bytes, &[]).unwrap();
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_entry(&mut reader).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(read_entry(&mut reader).unwrap(), Some(vec![]));
+        assert_eq!(read_entry(&mut reader).unwrap(), None);
+
+        // Entries cut in the middle are an error
+        let mut reader = &bytes[..5];
+        assert!(read_entry(&mut reader).is_err());
+    }
+}
@@ -52,7 +52,7 @@ pub struct BlockResponseED {
     pub base_fee_per_gas: U64ED,
 
     #[serde(rename = "transactionsRoot")]
-    /// The root hash of the transactions in the block
+    /// The root hash of the transactions trie of the block
     pub transactions_root: B256ED,
 
     #[serde(rename = "uncles")]
@@ -79,7 +79,7 @@ pub struct BlockResponseED {
     pub parent_hash: B256ED,
 
     #[serde(rename = "receiptsRoot")]
-    /// The root hash of the receipts in the block
+    /// The root hash of the receipts trie of the block
     pub receipts_root: B256ED,
 
     #[serde(rename = "sha3Uncles")]
@@ -91,7 +91,7 @@ pub struct BlockResponseED {
     pub size: U64ED,
 
     #[serde(rename = "stateRoot")]
-    /// The root hash of the state in the block, not recorded for BRC2.0
+    /// The root hash of the state trie after the block, over all accounts and their storage
     pub state_root: B256ED,
 
     #[serde(rename = "miner")]
@@ -158,6 +158,8 @@ impl BlockResponseED {
         transactions: Vec<B256ED>,
         transactions_root: B256ED,
         parent_hash: B256ED,
+        receipts_root: B256ED,
+        state_root: B256ED,
     ) -> Self {
         Self {
             difficulty: 0u64.into(),
@@ -173,7 +175,7 @@ impl BlockResponseED {
             transactions_root,
             parent_hash,
             size: 0u64.into(),
-            receipts_root: [0u8; 32].into(),
+            receipts_root,
             total_difficulty: 0u64.into(),
             base_fee_per_gas: 0u64.into(),
             uncles: Vec::new(),
@@ -181,7 +183,7 @@ impl BlockResponseED {
             withdrawals_root: [0u8; 32].into(),
             parent_beacon_block_root: [0u8; 32].into(),
             sha3_uncles: [0u8; 32].into(),
-            state_root: [0u8; 32].into(),
+            state_root,
             miner: [0u8; 20].into(),
             mix_hash: [0u8; 32].into(),
             excess_blob_gas: 0u64.into(),
@@ -210,13 +212,20 @@ impl Encode for BlockResponseED {
         self.transactions_root.encode(buffer);
         self.total_difficulty.encode(buffer); // Legacy
         self.parent_hash.encode(buffer);
-        self.receipts_root.encode(buffer); // Legacy
+        self.receipts_root.encode(buffer);
         self.size.encode(buffer); // Legacy
+        self.state_root.encode(buffer);
     }
 }
 
-impl Decode for BlockResponseED {
-    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+impl BlockResponseED {
+    /// Decode a block stored without its state root, as encoded before state roots were computed
+    ///
+    /// The state root of the decoded block is zero
+    pub(crate) fn decode_without_state_root(
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(Self, usize), Box<dyn Error>> {
         let (_difficulty, offset) = U64ED::decode(bytes, offset)?; // Legacy
         let (_gas_limit, offset) = U64ED::decode(bytes, offset)?; // Legacy
 
@@ -234,7 +243,7 @@ impl Decode for BlockResponseED {
 
         let (parent_hash, offset) = Decode::decode(bytes, offset)?;
 
-        let (_receipts_root, offset) = B256ED::decode(bytes, offset)?; // Legacy
+        let (receipts_root, offset) = Decode::decode(bytes, offset)?;
         let (_size, offset) = U64ED::decode(bytes, offset)?; // Legacy
 
         Ok((
@@ -249,12 +258,23 @@ impl Decode for BlockResponseED {
                 transactions,
                 transactions_root,
                 parent_hash,
+                receipts_root,
+                [0u8; 32].into(),
             ),
             offset,
         ))
     }
 }
 
+impl Decode for BlockResponseED {
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+        let (mut block, offset) = BlockResponseED::decode_without_state_root(bytes, offset)?;
+        let (state_root, offset) = Decode::decode(bytes, offset)?;
+        block.state_root = state_root;
+        Ok((block, offset))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,6 +293,8 @@ mod tests {
             vec![[10u8; 32].into(), [11u8; 32].into()],
             [12u8; 32].into(),
             [14u8; 32].into(),
+            [13u8; 32].into(),
+            [15u8; 32].into(),
         );
 
         let encoded = block.encode_vec();
@@ -281,6 +303,32 @@ mod tests {
         assert_eq!(block, decoded);
     }
 
+    #[test]
+    fn test_block_response_decode_without_state_root() {
+        let block = BlockResponseED::new(
+            2u64.into(),
+            [4u8; 32].into(),
+            [5u8; 256].into(),
+            6u64.into(),
+            7u64.into(),
+            8u64.into(),
+            9u64.into(),
+            vec![[10u8; 32].into()],
+            [12u8; 32].into(),
+            [14u8; 32].into(),
+            [13u8; 32].into(),
+            [15u8; 32].into(),
+        );
+
+        let mut encoded = block.encode_vec();
+        encoded.truncate(encoded.len() - 32);
+        let (decoded, offset) = BlockResponseED::decode_without_state_root(&encoded, 0).unwrap();
+
+        assert_eq!(offset, encoded.len());
+        assert_eq!(decoded.receipts_root, block.receipts_root);
+        assert_eq!(decoded.state_root, [0u8; 32].into());
+    }
+
     #[test]
     fn test_block_response_serialize() {
         let block = BlockResponseED::new(
@@ -294,10 +342,12 @@ mod tests {
             vec![[10u8; 32].into(), [11u8; 32].into()],
             [12u8; 32].into(),
             [14u8; 32].into(),
+            [13u8; 32].into(),
+            [15u8; 32].into(),
         );
 
         let serialized = serde_json::to_string(&block).unwrap();
-        assert_eq!(serialized, "{\"difficulty\":\"0x0\",\"gasLimit\":\"0xbb8000000\",\"gasUsed\":\"0x2\",\"hash\":\"0x0404040404040404040404040404040404040404040404040404040404040404\",\"logsBloom\":\"0x05050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505\",\"nonce\":\"0x0000000000000006\",\"number\":\"0x7\",\"timestamp\":\"0x8\",\"mineTimestamp\":\"0x9\",\"transactions\":[\"0x0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a\",\"0x0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b\"],\"baseFeePerGas\":\"0x0\",\"transactionsRoot\":\"0x0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c\",\"uncles\":[],\"withdrawals\":[],\"withdrawalsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"totalDifficulty\":\"0x0\",\"parentBeaconBlockRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e\",\"receiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"sha3Uncles\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"size\":\"0x0\",\"stateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"mixHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"excessBlobGas\":\"0x0\",\"extraData\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"blobGasUsed\":\"0x0\"}");
+        assert_eq!(serialized, "{\"difficulty\":\"0x0\",\"gasLimit\":\"0xbb8000000\",\"gasUsed\":\"0x2\",\"hash\":\"0x0404040404040404040404040404040404040404040404040404040404040404\",\"logsBloom\":\"0x05050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505\",\"nonce\":\"0x0000000000000006\",\"number\":\"0x7\",\"timestamp\":\"0x8\",\"mineTimestamp\":\"0x9\",\"transactions\":[\"0x0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a\",\"0x0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b\"],\"baseFeePerGas\":\"0x0\",\"transactionsRoot\":\"0x0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c\",\"uncles\":[],\"withdrawals\":[],\"withdrawalsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"totalDifficulty\":\"0x0\",\"parentBeaconBlockRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e\",\"receiptsRoot\":\"0x0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d\",\"sha3Uncles\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"size\":\"0x0\",\"stateRoot\":\"0x0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"mixHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"excessBlobGas\":\"0x0\",\"extraData\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"blobGasUsed\":\"0x0\"}");
 
         let deserialized: BlockResponseED = serde_json::from_str(&serialized).unwrap();
         assert_eq!(block, deserialized);
@@ -316,6 +366,8 @@ mod tests {
             vec![],
             [12u8; 32].into(),
             [14u8; 32].into(),
+            [13u8; 32].into(),
+            [15u8; 32].into(),
         );
 
         block.transactions = Either::Right(vec![TxED::new(
@@ -330,10 +382,12 @@ mod tests {
             vec![25u8].into(),
             "0".into(),
             Signature::new(0u8.into(), 0u8.into(), 0u8.into()),
+            0u8.into(),
+            None,
         )]);
 
         let serialized = serde_json::to_string(&block).unwrap();
-        assert_eq!(serialized, "{\"difficulty\":\"0x0\",\"gasLimit\":\"0xbb8000000\",\"gasUsed\":\"0x3\",\"hash\":\"0x0404040404040404040404040404040404040404040404040404040404040404\",\"logsBloom\":\"0x05050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505\",\"nonce\":\"0x0000000000000006\",\"number\":\"0x7\",\"timestamp\":\"0x8\",\"mineTimestamp\":\"0x9\",\"transactions\":[{\"hash\":\"0x1111111111111111111111111111111111111111111111111111111111111111\",\"nonce\":\"0x12\",\"blockHash\":\"0x1313131313131313131313131313131313131313131313131313131313131313\",\"blockNumber\":\"0x14\",\"transactionIndex\":\"0x15\",\"from\":\"0x1616161616161616161616161616161616161616\",\"to\":\"0x1717171717171717171717171717171717171717\",\"value\":\"0x0\",\"gas\":\"0x18\",\"gasPrice\":\"0x0\",\"input\":\"0x19\",\"v\":\"0x0\",\"r\":\"0x0\",\"s\":\"0x0\",\"chainId\":\"0x425243323073\",\"type\":\"0x0\"}],\"baseFeePerGas\":\"0x0\",\"transactionsRoot\":\"0x0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c\",\"uncles\":[],\"withdrawals\":[],\"withdrawalsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"totalDifficulty\":\"0x0\",\"parentBeaconBlockRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e\",\"receiptsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"sha3Uncles\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"size\":\"0x0\",\"stateRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"mixHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"excessBlobGas\":\"0x0\",\"extraData\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"blobGasUsed\":\"0x0\"}");
+        assert_eq!(serialized, "{\"difficulty\":\"0x0\",\"gasLimit\":\"0xbb8000000\",\"gasUsed\":\"0x3\",\"hash\":\"0x0404040404040404040404040404040404040404040404040404040404040404\",\"logsBloom\":\"0x05050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505050505\",\"nonce\":\"0x0000000000000006\",\"number\":\"0x7\",\"timestamp\":\"0x8\",\"mineTimestamp\":\"0x9\",\"transactions\":[{\"hash\":\"0x1111111111111111111111111111111111111111111111111111111111111111\",\"nonce\":\"0x12\",\"blockHash\":\"0x1313131313131313131313131313131313131313131313131313131313131313\",\"blockNumber\":\"0x14\",\"transactionIndex\":\"0x15\",\"from\":\"0x1616161616161616161616161616161616161616\",\"to\":\"0x1717171717171717171717171717171717171717\",\"value\":\"0x0\",\"gas\":\"0x18\",\"gasPrice\":\"0x0\",\"input\":\"0x19\",\"v\":\"0x0\",\"r\":\"0x0\",\"s\":\"0x0\",\"chainId\":\"0x425243323073\",\"type\":\"0x0\"}],\"baseFeePerGas\":\"0x0\",\"transactionsRoot\":\"0x0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c\",\"uncles\":[],\"withdrawals\":[],\"withdrawalsRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"totalDifficulty\":\"0x0\",\"parentBeaconBlockRoot\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"parentHash\":\"0x0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e0e\",\"receiptsRoot\":\"0x0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d\",\"sha3Uncles\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"size\":\"0x0\",\"stateRoot\":\"0x0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f\",\"miner\":\"0x0000000000000000000000000000000000000000\",\"mixHash\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"excessBlobGas\":\"0x0\",\"extraData\":\"0x0000000000000000000000000000000000000000000000000000000000000000\",\"blobGasUsed\":\"0x0\"}");
 
         let deserialized: BlockResponseED = serde_json::from_str(&serialized).unwrap();
         let serialized_again = serde_json::to_string(&deserialized).unwrap();
@@ -1,7 +1,8 @@
 use std::error::Error;
 
-use alloy::primitives::Log;
+use alloy::primitives::{Address, Log, B256};
 use serde::{Deserialize, Serialize};
+use serde_either::SingleOrVec;
 
 use crate::db::types::{AddressED, BytesED, Decode, Encode, B256ED, U64ED};
 
@@ -29,6 +30,11 @@ pub struct LogED {
     #[serde(rename = "logIndex")]
     /// The index of the log entry in the block
     pub log_index: U64ED,
+    #[serde(default)]
+    /// Whether the log was removed by a reorg, only set for logs sent to subscribers
+    ///
+    /// This is not stored in the database
+    pub removed: bool,
 }
 
 impl LogED {
@@ -52,11 +58,61 @@ impl LogED {
                 block_hash,
                 block_number,
                 log_index: start_log_index.into(),
+                removed: false,
             });
             start_log_index += 1;
         }
         log_responses
     }
+
+    // This is used by the server, so doesn't need to be public
+    pub(crate) fn matches(
+        &self,
+        contract_address: Option<Address>,
+        topics: &Option<Vec<SingleOrVec<Option<B256>>>>,
+    ) -> bool {
+        if let Some(ref address) = contract_address {
+            if self.address.address != *address {
+                return false;
+            }
+        }
+
+        let Some(topics) = topics else {
+            return true;
+        };
+        for (idx, topic) in topics.iter().enumerate() {
+            match topic {
+                SingleOrVec::Single(topic) => {
+                    if let Some(topic) = topic {
+                        if self.topics.len() <= idx || self.topics[idx].bytes != *topic {
+                            return false;
+                        }
+                    }
+                }
+                SingleOrVec::Vec(topics) => {
+                    // An OR condition without topics matches any topic, like a single None
+                    if topics.iter().all(|x| x.is_none()) {
+                        continue;
+                    }
+                    if self.topics.len() <= idx {
+                        return false;
+                    }
+                    if !topics.iter().any(|x| {
+                        if let Some(topic) = x {
+                            self.topics[idx].bytes == *topic
+                        } else {
+                            // We ignore the None case in OR condition, it's an invalid case
+                            // and we don't want to match all
+                            false
+                        }
+                    }) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
 }
 
 impl Encode for LogED {
@@ -93,6 +149,7 @@ impl Decode for LogED {
                 block_hash,
                 block_number,
                 log_index,
+                removed: false,
             },
             offset,
         ))
@@ -259,4 +316,59 @@ mod tests {
         let deserialized: Vec<LogED> = serde_json::from_str(&serialized).unwrap();
         assert_eq!(log_responses, deserialized);
     }
+
+    #[test]
+    fn test_log_ed_matches() {
+        let log = Log::new(
+            [1u8; 20].into(),
+            vec![[2u8; 32].into(), [3u8; 32].into()],
+            Vec::new().into(),
+        )
+        .unwrap();
+        let log = &LogED::new_vec(
+            &vec![log],
+            0,
+            0u64.into(),
+            [4u8; 32].into(),
+            [5u8; 32].into(),
+            1u64.into(),
+        )[0];
+
+        assert!(log.matches(None, &None));
+        assert!(log.matches(Some([1u8; 20].into()), &None));
+        assert!(!log.matches(Some([2u8; 20].into()), &None));
+
+        assert!(log.matches(
+            None,
+            &Some(vec![SingleOrVec::Single(Some([2u8; 32].into()))])
+        ));
+        assert!(!log.matches(
+            None,
+            &Some(vec![SingleOrVec::Single(Some([3u8; 32].into()))])
+        ));
+        assert!(log.matches(
+            None,
+            &Some(vec![
+                SingleOrVec::Single(None),
+                SingleOrVec::Vec(vec![Some([6u8; 32].into()), Some([3u8; 32].into())]),
+            ])
+        ));
+        // None in OR condition doesn't match all
+        assert!(!log.matches(
+            None,
+            &Some(vec![SingleOrVec::Vec(vec![None, Some([3u8; 32].into())])])
+        ));
+        // OR condition without topics matches all
+        assert!(log.matches(None, &Some(vec![SingleOrVec::Vec(vec![None])])));
+        assert!(log.matches(None, &Some(vec![SingleOrVec::Vec(vec![])])));
+        // Log has fewer topics than the filter
+        assert!(!log.matches(
+            None,
+            &Some(vec![
+                SingleOrVec::Single(None),
+                SingleOrVec::Single(None),
+                SingleOrVec::Single(Some([3u8; 32].into())),
+            ])
+        ));
+    }
 }
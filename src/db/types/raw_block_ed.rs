@@ -2,13 +2,15 @@
 
 use std::error::Error;
 
+use alloy::consensus::proofs::{calculate_receipt_root, calculate_transaction_root};
 use alloy::consensus::{
-    Block, BlockBody, Header, Receipt, ReceiptWithBloom, SignableTransaction, TxEnvelope, TxLegacy,
+    Block, BlockBody, Header, ReceiptWithBloom, SignableTransaction, TxEip1559, TxEip2930,
+    TxEnvelope, TxLegacy,
 };
-use alloy::primitives::{Bloom, Log, B64};
+use alloy::primitives::{Bloom, B64};
 use alloy::signers::Signature;
 use alloy_rlp::{Decodable, Encodable, RlpDecodable, RlpEncodable};
-use revm::primitives::{Address, Bytes, LogData, TxKind, B256, U256};
+use revm::primitives::{Address, Bytes, TxKind, B256, U256};
 
 use crate::db::types::{Decode, Encode};
 use crate::types::{BlockResponseED, TxED, TxReceiptED};
@@ -51,60 +53,14 @@ impl RawBlock {
                     requests_hash: Some(B256::ZERO),
                 },
                 body: BlockBody {
-                    transactions: transactions
-                        .into_iter()
-                        .map(|tx| {
-                            TxEnvelope::Legacy(
-                                TxLegacy {
-                                    nonce: tx.nonce.into(),
-                                    to: match tx.to {
-                                        Some(addr) => {
-                                            if addr.address.is_zero() {
-                                                TxKind::Create
-                                            } else {
-                                                TxKind::Call(addr.address.into())
-                                            }
-                                        }
-                                        None => TxKind::Create,
-                                    },
-                                    value: U256::from(tx.value.uint),
-                                    gas_price: 0,
-                                    input: tx.input.bytes,
-                                    chain_id: Some(tx.chain_id.into()),
-                                    gas_limit: tx.gas.into(),
-                                }
-                                .into_signed(Signature::new(
-                                    tx.r.uint,
-                                    tx.s.uint,
-                                    (!tx.v.is_zero()).into(),
-                                )),
-                            )
-                        })
-                        .collect(),
+                    transactions: transactions.into_iter().map(tx_envelope).collect(),
                     ommers: Vec::new(),
                     withdrawals: None,
                 },
             },
             receipts: receipts
-                .into_iter()
-                .map(|r| ReceiptWithBloom::<Receipt> {
-                    receipt: Receipt {
-                        status: (!r.status.uint.is_zero()).into(),
-                        cumulative_gas_used: r.cumulative_gas_used.into(),
-                        logs: r
-                            .logs
-                            .into_iter()
-                            .map(|log| Log {
-                                address: log.address.address,
-                                data: LogData::new_unchecked(
-                                    log.topics.into_iter().map(|t| t.bytes).collect(),
-                                    log.data.bytes,
-                                ),
-                            })
-                            .collect(),
-                    },
-                    logs_bloom: Bloom(r.logs_bloom.bytes),
-                })
+                .iter()
+                .map(TxReceiptED::receipt_with_bloom)
                 .collect(),
         }
     }
@@ -132,6 +88,87 @@ impl RawBlock {
     }
 }
 
+/// Root of the transactions trie of a block, as in Ethereum block headers
+///
+/// Transactions are keyed by their index in the block and encoded with their type
+pub(crate) fn transactions_root(transactions: &[TxED]) -> B256 {
+    calculate_transaction_root(
+        &transactions
+            .iter()
+            .cloned()
+            .map(tx_envelope)
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Root of the receipts trie of a block, as in Ethereum block headers
+///
+/// Receipts are keyed by the index of their transaction in the block and encoded with its type
+pub(crate) fn receipts_root(receipts: &[TxReceiptED]) -> B256 {
+    calculate_receipt_root(
+        &receipts
+            .iter()
+            .map(TxReceiptED::receipt_envelope)
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn tx_envelope(tx: TxED) -> TxEnvelope {
+    let to = match tx.to {
+        Some(addr) => {
+            if addr.address.is_zero() {
+                TxKind::Create
+            } else {
+                TxKind::Call(addr.address)
+            }
+        }
+        None => TxKind::Create,
+    };
+    let signature = Signature::new(tx.r.uint, tx.s.uint, !tx.v.is_zero());
+    let access_list = tx.access_list.unwrap_or_default();
+    match tx.tx_type.uint.to::<u8>() {
+        1 => TxEnvelope::Eip2930(
+            TxEip2930 {
+                chain_id: tx.chain_id.into(),
+                nonce: tx.nonce.into(),
+                gas_price: 0,
+                gas_limit: tx.gas.into(),
+                to,
+                value: U256::from(tx.value.uint),
+                access_list,
+                input: tx.input.bytes,
+            }
+            .into_signed(signature),
+        ),
+        2 => TxEnvelope::Eip1559(
+            TxEip1559 {
+                chain_id: tx.chain_id.into(),
+                nonce: tx.nonce.into(),
+                gas_limit: tx.gas.into(),
+                max_fee_per_gas: 0,
+                max_priority_fee_per_gas: 0,
+                to,
+                value: U256::from(tx.value.uint),
+                access_list,
+                input: tx.input.bytes,
+            }
+            .into_signed(signature),
+        ),
+        _ => TxEnvelope::Legacy(
+            TxLegacy {
+                nonce: tx.nonce.into(),
+                to,
+                value: U256::from(tx.value.uint),
+                gas_price: 0,
+                input: tx.input.bytes,
+                chain_id: Some(tx.chain_id.into()),
+                gas_limit: tx.gas.into(),
+            }
+            .into_signed(signature),
+        ),
+    }
+}
+
 impl Encode for RawBlock {
     fn encode(&self, mut buffer: &mut Vec<u8>) {
         Encode::encode(&self.raw_block(), &mut buffer);
@@ -99,6 +99,17 @@ impl TraceED {
     }
 }
 
+/// Joins the traces of a block in transaction order, the trace hash of the block is the sha256 hash of the
+/// joined string
+pub(crate) fn join_block_traces(traces: &[TraceED]) -> String {
+    static TRACE_SEPARATOR: &str = "|";
+    traces
+        .iter()
+        .map(TraceED::get_opi_string)
+        .collect::<Vec<String>>()
+        .join(TRACE_SEPARATOR)
+}
+
 #[cfg(feature = "server")]
 impl From<CallFrame> for TraceED {
     fn from(call: CallFrame) -> Self {
@@ -1,5 +1,6 @@
 use std::error::Error;
 
+use alloy::eips::eip2930::{AccessList, AccessListItem};
 use serde::{Deserialize, Serialize};
 
 use crate::db::types::{AddressED, BytesED, Decode, Encode, B256ED, U64ED, U8ED};
@@ -47,8 +48,15 @@ pub struct TxED {
     /// The chain ID for the transaction
     pub chain_id: U64ED,
     #[serde(rename = "type")]
-    /// The type of the transaction, always 0 for BRC2.0
+    /// The type of the transaction, 0 for legacy, 1 for EIP-2930 and 2 for EIP-1559 transactions
     pub tx_type: U8ED,
+    #[serde(
+        rename = "accessList",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    /// The access list of the transaction, empty for legacy transactions
+    pub access_list: Option<AccessList>,
     #[serde(skip_serializing, skip_deserializing)]
     /// The inscription ID that generated this transaction, if applicable
     pub inscription_id: Option<String>,
@@ -85,6 +93,8 @@ impl TxED {
         input: BytesED,
         inscription_id: String,
         signature: Signature,
+        tx_type: U8ED,
+        access_list: Option<AccessList>,
     ) -> Self {
         TxED {
             hash,
@@ -102,7 +112,8 @@ impl TxED {
             r: signature.r,
             s: signature.s,
             chain_id: CONFIG.read().chain_id.into(),
-            tx_type: 0u8.into(),
+            tx_type,
+            access_list,
             inscription_id: Some(inscription_id),
         }
     }
@@ -125,11 +136,36 @@ impl Encode for TxED {
         self.v.encode(buffer);
         self.r.encode(buffer);
         self.s.encode(buffer);
+        self.tx_type.encode(buffer);
+        self.access_list
+            .as_ref()
+            .map(|access_list| {
+                access_list
+                    .iter()
+                    .map(|item| {
+                        (
+                            AddressED::new(item.address),
+                            item.storage_keys
+                                .iter()
+                                .map(|key| B256ED::new(*key))
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .encode(buffer);
     }
 }
 
-impl Decode for TxED {
-    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+impl TxED {
+    /// Decode a transaction encoded without its type and access list
+    ///
+    /// Transactions were encoded this way before typed transactions were supported, so they are all
+    /// legacy transactions. This is used to migrate databases from older versions.
+    pub(crate) fn decode_untyped(
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(Self, usize), Box<dyn Error>> {
         let (hash, offset) = Decode::decode(bytes, offset)?;
         let (nonce, offset) = Decode::decode(bytes, offset)?;
         let (block_hash, offset) = Decode::decode(bytes, offset)?;
@@ -164,6 +200,7 @@ impl Decode for TxED {
                 s,
                 chain_id: CONFIG.read().chain_id.into(),
                 tx_type: 0u8.into(),
+                access_list: None,
                 inscription_id,
             },
             offset,
@@ -171,6 +208,28 @@ impl Decode for TxED {
     }
 }
 
+impl Decode for TxED {
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+        let (mut tx, offset) = TxED::decode_untyped(bytes, offset)?;
+        let (tx_type, offset) = Decode::decode(bytes, offset)?;
+        let (access_list, offset) = <Option<Vec<(AddressED, Vec<B256ED>)>>>::decode(bytes, offset)?;
+
+        tx.tx_type = tx_type;
+        tx.access_list = access_list.map(|access_list| {
+            AccessList(
+                access_list
+                    .into_iter()
+                    .map(|(address, storage_keys)| AccessListItem {
+                        address: address.address,
+                        storage_keys: storage_keys.into_iter().map(|key| key.bytes).collect(),
+                    })
+                    .collect(),
+            )
+        });
+        Ok((tx, offset))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,11 +253,84 @@ mod tests {
             s: 12u8.into(),
             chain_id: CONFIG.read().chain_id.into(),
             tx_type: 0u8.into(),
+            access_list: None,
+            inscription_id: Some("inscription_id".to_string()),
+        };
+        let encoded = tx.encode_vec();
+        let decoded = TxED::decode_vec(&encoded).unwrap();
+        assert_eq!(tx, decoded);
+    }
+
+    #[test]
+    fn decode_untyped() {
+        let tx = TxED {
+            hash: [1u8; 32].into(),
+            nonce: 1u64.into(),
+            block_hash: [2u8; 32].into(),
+            block_number: Some(2u64.into()),
+            transaction_index: Some(3u64.into()),
+            from: [3u8; 20].into(),
+            to: Some([4u8; 20].into()),
+            value: 4u64.into(),
+            gas: 5u64.into(),
+            gas_price: 6u64.into(),
+            input: vec![7, 8, 9].into(),
+            v: 1u8.into(),
+            r: 11u8.into(),
+            s: 12u8.into(),
+            chain_id: CONFIG.read().chain_id.into(),
+            tx_type: 0u8.into(),
+            access_list: None,
+            inscription_id: Some("inscription_id".to_string()),
+        };
+        let mut encoded = tx.encode_vec();
+        // Drop the type (a single 64-bit limb) and the flag of the empty access list
+        encoded.truncate(encoded.len() - 9);
+        encoded.push(42);
+        let (decoded, offset) = TxED::decode_untyped(&encoded, 0).unwrap();
+        assert_eq!(tx, decoded);
+        assert_eq!(encoded[offset], 42);
+    }
+
+    #[test]
+    fn encode_decode_access_list() {
+        let tx = TxED {
+            hash: [1u8; 32].into(),
+            nonce: 1u64.into(),
+            block_hash: [2u8; 32].into(),
+            block_number: Some(2u64.into()),
+            transaction_index: Some(3u64.into()),
+            from: [3u8; 20].into(),
+            to: Some([4u8; 20].into()),
+            value: 4u64.into(),
+            gas: 5u64.into(),
+            gas_price: 6u64.into(),
+            input: vec![7, 8, 9].into(),
+            v: 1u8.into(),
+            r: 11u8.into(),
+            s: 12u8.into(),
+            chain_id: CONFIG.read().chain_id.into(),
+            tx_type: 2u8.into(),
+            access_list: Some(AccessList(vec![AccessListItem {
+                address: [13u8; 20].into(),
+                storage_keys: vec![[14u8; 32].into(), [15u8; 32].into()],
+            }])),
             inscription_id: Some("inscription_id".to_string()),
         };
         let encoded = tx.encode_vec();
         let decoded = TxED::decode_vec(&encoded).unwrap();
         assert_eq!(tx, decoded);
+
+        let serialized = serde_json::to_value(&tx).unwrap();
+        assert_eq!(serialized["type"], "0x2");
+        assert_eq!(
+            serialized["accessList"][0]["address"],
+            "0x0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d0d"
+        );
+        assert_eq!(
+            serialized["accessList"][0]["storageKeys"][1],
+            format!("0x{}", "0f".repeat(32))
+        );
     }
 
     #[test]
@@ -220,6 +352,7 @@ mod tests {
             s: 12u8.into(),
             chain_id: CONFIG.read().chain_id.into(),
             tx_type: 0u8.into(),
+            access_list: None,
             inscription_id: None,
         };
         let serialized = serde_json::to_string(&tx).unwrap();
@@ -251,6 +384,7 @@ mod tests {
             s: 0u8.into(),
             chain_id: CONFIG.read().chain_id.into(),
             tx_type: 0u8.into(),
+            access_list: None,
             inscription_id: Some("inscription_id".to_string()),
         };
         let serialized = serde_json::to_string(&tx).unwrap();
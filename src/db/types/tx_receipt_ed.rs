@@ -1,6 +1,7 @@
 use std::error::Error;
 
-use alloy::primitives::{logs_bloom, Log};
+use alloy::consensus::{Receipt, ReceiptEnvelope, ReceiptWithBloom};
+use alloy::primitives::{logs_bloom, Bloom, Log, LogData};
 use serde::{Deserialize, Serialize};
 
 use crate::db::types::{AddressED, Decode, Encode, LogED, B2048ED, B256ED, U64ED, U8ED};
@@ -47,7 +48,7 @@ pub struct TxReceiptED {
     /// The effective gas price for the transaction, 0 for BRC2.0
     pub effective_gas_price: U64ED,
     #[serde(rename = "type")]
-    /// The type of the transaction, always 0 for BRC2.0
+    /// The type of the transaction, 0 for legacy, 1 for EIP-2930 and 2 for EIP-1559 transactions
     pub transaction_type: U8ED,
 }
 
@@ -66,6 +67,7 @@ impl TxReceiptED {
         gas_used: u64,
         cumulative_gas_used: U64ED,
         start_log_index: U64ED,
+        transaction_type: U8ED,
     ) -> Result<Self, Box<dyn Error>> {
         Ok(TxReceiptED {
             status: (is_success as u8).into(),
@@ -88,11 +90,45 @@ impl TxReceiptED {
             transaction_index,
             cumulative_gas_used,
             effective_gas_price: 0u64.into(),
-            transaction_type: 0u8.into(),
+            transaction_type,
         })
     }
 }
 
+impl TxReceiptED {
+    /// The receipt with its logs bloom, as it's included in Ethereum blocks
+    pub(crate) fn receipt_with_bloom(&self) -> ReceiptWithBloom {
+        ReceiptWithBloom::<Receipt> {
+            receipt: Receipt {
+                status: (!self.status.uint.is_zero()).into(),
+                cumulative_gas_used: self.cumulative_gas_used.into(),
+                logs: self
+                    .logs
+                    .iter()
+                    .map(|log| Log {
+                        address: log.address.address,
+                        data: LogData::new_unchecked(
+                            log.topics.iter().map(|t| t.bytes).collect(),
+                            log.data.bytes.clone(),
+                        ),
+                    })
+                    .collect(),
+            },
+            logs_bloom: Bloom(self.logs_bloom.bytes),
+        }
+    }
+
+    /// The receipt with its transaction type, as it's encoded in the receipts trie of its block
+    pub(crate) fn receipt_envelope(&self) -> ReceiptEnvelope {
+        let receipt = self.receipt_with_bloom();
+        match self.transaction_type.uint.to::<u8>() {
+            1 => ReceiptEnvelope::Eip2930(receipt),
+            2 => ReceiptEnvelope::Eip1559(receipt),
+            _ => ReceiptEnvelope::Legacy(receipt),
+        }
+    }
+}
+
 impl Encode for TxReceiptED {
     fn encode(&self, buffer: &mut Vec<u8>) {
         self.status.encode(buffer);
@@ -112,11 +148,19 @@ impl Encode for TxReceiptED {
         self.cumulative_gas_used.encode(buffer);
         U64ED::zero().encode(buffer); // Legacy for nonce
         Option::<BytesED>::None.encode(buffer); // Legacy for result bytes
+        self.transaction_type.encode(buffer);
     }
 }
 
-impl Decode for TxReceiptED {
-    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+impl TxReceiptED {
+    /// Decode a receipt encoded without its transaction type
+    ///
+    /// Receipts were encoded this way before typed transactions were supported, so they are all receipts of
+    /// legacy transactions. This is used to migrate databases from older versions.
+    pub(crate) fn decode_untyped(
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<(Self, usize), Box<dyn Error>> {
         let (status, offset) = Decode::decode(bytes, offset)?;
         let (_type, offset) = String::decode(bytes, offset)?; // Legacy, ignore
         let (_reason, offset) = String::decode(bytes, offset)?; // Legacy, ignore
@@ -156,6 +200,15 @@ impl Decode for TxReceiptED {
     }
 }
 
+impl Decode for TxReceiptED {
+    fn decode(bytes: &[u8], offset: usize) -> Result<(Self, usize), Box<dyn Error>> {
+        let (mut receipt, offset) = TxReceiptED::decode_untyped(bytes, offset)?;
+        let (transaction_type, offset) = Decode::decode(bytes, offset)?;
+        receipt.transaction_type = transaction_type;
+        Ok((receipt, offset))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use revm::primitives::LogData;
@@ -184,6 +237,7 @@ mod tests {
             5u64,         // gas_used
             14u64.into(), // cumulative_gas_used
             0u64.into(),  // start_log_index (arbitrary for test)
+            2u8.into(),   // transaction_type
         )
         .unwrap();
         let bytes = tx_receipt_ed.encode_vec();
@@ -213,6 +267,7 @@ mod tests {
             5u64,         // gas_used
             14u64.into(), // cumulative_gas_used
             0u64.into(),  // start_log_index (arbitrary for test)
+            0u8.into(),   // transaction_type
         )
         .unwrap();
         let serialized = serde_json::to_string(&tx_receipt_ed).unwrap();
@@ -242,15 +297,45 @@ mod tests {
             5u64,         // gas_used
             14u64.into(), // cumulative_gas_used
             0u64.into(),  // start_log_index (arbitrary for test)
+            0u8.into(),   // transaction_type
         )
         .unwrap();
 
         // This is a hex dump of the encoded struct, including legacy fields from a previous version
         // This is to ensure backward compatibility with previously stored data
+        // Transaction type is appended at the end, after the legacy fields
         // Future major versions can remove legacy fields and this test can be deleted
-        let encoded_hex = "000000000000000100000000000000000000000101010101010101010101010101010101010101010000000202020202020202020202020202020202020202020202020202020202020202020303030303030303030303030303030303030303030303030303030303030303000000200404040404040404040404040404040404040404040404040404040404040404000000000000000d0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a000000000000000b000000000000000000000000000000050606060606060606060606060606060606060606010707070707070707070707070707070707070707010808080808080808080808080808080808080808000000000000000000000000800000000000000000000000000000000000000000000000000000000000000000000000000000000000040000000000000000000000800800000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000004000000000020000000000000000000000000000000000000000000000000000000002000000008000000000000000000000000000000000000000000000000000000000000000000000000000000000a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a000000000000000b00000000000000000c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c000000000000000d000000000000000e000000000000000000";
+        let encoded_hex = "000000000000000100000000000000000000000101010101010101010101010101010101010101010000000202020202020202020202020202020202020202020202020202020202020202020303030303030303030303030303030303030303030303030303030303030303000000200404040404040404040404040404040404040404040404040404040404040404000000000000000d0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a000000000000000b000000000000000000000000000000050606060606060606060606060606060606060606010707070707070707070707070707070707070707010808080808080808080808080808080808080808000000000000000000000000800000000000000000000000000000000000000000000000000000000000000000000000000000000000040000000000000000000000800800000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000004000000000020000000000000000000000000000000000000000000000000000000002000000008000000000000000000000000000000000000000000000000000000000000000000000000000000000a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a000000000000000b00000000000000000c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c0c000000000000000d000000000000000e0000000000000000000000000000000000";
         let decoded = TxReceiptED::decode_vec(&hex::decode(encoded_hex).unwrap()).unwrap();
 
         assert_eq!(tx_receipt_ed, decoded);
     }
+
+    #[test]
+    fn test_decode_untyped() {
+        let tx_receipt_ed = TxReceiptED::new(
+            [10u8; 32].into(),      // block_hash
+            11u64.into(),           // block_number
+            None,                   // contract_address
+            [6u8; 20].into(),       // from
+            Some([7u8; 20].into()), // to
+            [12u8; 32].into(),      // transaction_hash
+            13u64.into(),           // transaction_index
+            true,                   // is_success
+            &vec![],
+            5u64,         // gas_used
+            14u64.into(), // cumulative_gas_used
+            0u64.into(),  // start_log_index
+            0u8.into(),   // transaction_type
+        )
+        .unwrap();
+
+        // Receipts stored before typed transactions don't have the type at the end
+        let mut encoded = tx_receipt_ed.encode_vec();
+        encoded.truncate(encoded.len() - 8);
+        let (decoded, offset) = TxReceiptED::decode_untyped(&encoded, 0).unwrap();
+
+        assert_eq!(tx_receipt_ed, decoded);
+        assert_eq!(offset, encoded.len());
+    }
 }
@@ -95,6 +95,14 @@ impl U512ED {
             uint: Uint::from_be_bytes::<64>(bytes.as_slice().try_into()?),
         })
     }
+    // Reverse of from_addr_u256, used to read the storage of every account
+    pub(crate) fn to_addr_u256(self) -> (Address, U256) {
+        let bytes = self.uint.to_be_bytes::<64>();
+        (
+            Address::from_slice(&bytes[..20]),
+            U256::from_be_slice(&bytes[32..]),
+        )
+    }
 }
 
 pub fn uint_full_hex<const BITS: usize, const LIMBS: usize, S: Serializer>(
@@ -214,6 +222,7 @@ mod tests {
         let bytes = u512_ed.encode_vec();
         let decoded = U512ED::decode_vec(&bytes).unwrap();
         assert_eq!(u512_ed, decoded);
+        assert_eq!(decoded.to_addr_u256(), (address, u256));
     }
 
     #[test]
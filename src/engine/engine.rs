@@ -2,42 +2,65 @@
 
 use std::collections::HashMap;
 use std::error::Error;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 use std::time::{Duration, UNIX_EPOCH};
 
-use alloy::consensus::transaction::RlpEcdsaDecodableTx;
-use alloy::consensus::{SignableTransaction, TxLegacy};
+use alloy::consensus::transaction::{RlpEcdsaDecodableTx, RlpEcdsaEncodableTx};
+use alloy::consensus::{SignableTransaction, Signed, Transaction, TxEnvelope, TxLegacy};
+use alloy::eips::eip2718::Decodable2718;
+use alloy::eips::eip2930::AccessListResult;
 use alloy::primitives::{keccak256, Address, B256, U256};
-use alloy_rpc_types_trace::geth::CallConfig;
+use alloy_rpc_types_eth::{BlockOverrides, EIP1186AccountProofResponse, EIP1186StorageProof};
+use alloy_rpc_types_trace::geth::{CallConfig, GethDebugTracingOptions};
+use alloy_serde::JsonStorageKey;
 use either::Either::{Left, Right};
+use revm::context::result::ExecutionResult;
 use revm::context::ContextTr;
+use revm::database::CacheDB;
 use revm::handler::EvmTr;
 use revm::inspector::InspectorEvmTr;
+use revm::interpreter::gas::CALL_STIPEND;
 use revm::primitives::Bytes;
-use revm::{ExecuteEvm, InspectCommitEvm};
+use revm::{
+    DatabaseCommit, DatabaseRef, ExecuteCommitEvm, ExecuteEvm, InspectCommitEvm, InspectEvm,
+};
+use revm_inspectors::access_list::AccessListInspector;
 use serde_either::SingleOrVec;
-use tokio::sync::Notify;
+use tokio::sync::{broadcast, Notify};
 use tokio::time::timeout;
 
+use crate::api::types::{
+    ReceiptProof, SimulatedBlock, SimulatedCall, SimulatedCallError, TraceResult,
+};
 use crate::brc20_controller::{load_brc20_deploy_tx, verify_brc20_contract_address};
-use crate::db::types::{BlockResponseED, BytecodeED, LogED, Signature, TraceED, TxED, TxReceiptED};
-use crate::db::Brc20ProgDatabase;
-use crate::engine::evm::get_evm;
-use crate::engine::hardforks::use_rlp_hash_for_tx_hash;
+use crate::db::types::{
+    join_block_traces, BlockResponseED, BytecodeED, LogED, Signature, TxED, TxReceiptED,
+};
+use crate::db::{Brc20ProgDatabase, Brc20ProgDatabaseReader};
+use crate::engine::events::{ChainEvent, CHAIN_EVENT_CAPACITY};
+use crate::engine::evm::{
+    get_cached_read_evm, get_call_trace, get_evm, get_read_evm, get_saved_trace, get_trace,
+    get_tracing_inspector_config, BRC20Evm, EvmOverrides,
+};
+use crate::engine::hardforks::{allow_typed_transactions, use_rlp_hash_for_tx_hash};
 use crate::engine::utils::{
-    get_contract_address, get_gas_limit, get_inscription_byte_len, get_tx_hash, LastBlockInfo,
-    TxInfo,
+    first_mismatch, get_contract_address, get_gas_limit, get_inscription_byte_len, get_tx_hash,
+    LastBlockInfo, LastCommitInfo, TxInfo,
 };
 use crate::engine::validate_bitcoin_rpc_status;
 use crate::global::{
-    SharedData, CONFIG, MAX_FUTURE_TRANSACTION_BLOCKS, MAX_FUTURE_TRANSACTION_NONCES,
-    MAX_REORG_HISTORY_SIZE,
+    SharedData, CONFIG, GAS_PER_BYTE, MAX_FUTURE_TRANSACTION_BLOCKS, MAX_FUTURE_TRANSACTION_NONCES,
+    MAX_REORG_HISTORY_SIZE, MAX_SIMULATE_BLOCKS, MAX_SIMULATE_CALLS,
 };
-use crate::types::{AddressED, PrecompileData};
+use crate::types::{AddressED, PrecompileData, B256ED};
 
 pub struct BRC20ProgEngine {
     db: SharedData<Brc20ProgDatabase>,
     last_block_info: SharedData<LastBlockInfo>,
+    last_commit_info: SharedData<LastCommitInfo>,
     block_finalised: Notify,
+    chain_events: broadcast::Sender<ChainEvent>,
 }
 
 #[derive(Debug)]
@@ -48,12 +71,33 @@ pub struct ReadContractResult {
     pub output: Option<Bytes>,
 }
 
+impl From<ExecutionResult> for ReadContractResult {
+    fn from(output: ExecutionResult) -> Self {
+        Self {
+            status: output.is_success(),
+            status_string: format!("{:?}", output),
+            gas_used: output.gas_used(),
+            output: output.output().cloned(),
+        }
+    }
+}
+
+/// Calls simulated in a block, state and block overrides are applied before the calls
+pub struct SimulateBlockCalls {
+    pub tx_infos: Vec<TxInfo>,
+    pub gas_limits: Vec<u64>,
+    pub precompile_data: Option<PrecompileData>,
+    pub overrides: EvmOverrides,
+}
+
 impl BRC20ProgEngine {
     pub fn new(db: Brc20ProgDatabase) -> Self {
         let engine = BRC20ProgEngine {
             db: SharedData::new(db),
             last_block_info: SharedData::new(LastBlockInfo::new()),
+            last_commit_info: SharedData::new(LastCommitInfo::new()),
             block_finalised: Notify::new(),
+            chain_events: broadcast::channel(CHAIN_EVENT_CAPACITY).0,
         };
 
         engine
@@ -266,6 +310,10 @@ impl BRC20ProgEngine {
             return Ok(Vec::new());
         };
 
+        if tx_info.tx_type != 0 && !allow_typed_transactions(block_number) {
+            return Err("Typed transactions are not enabled yet".into());
+        }
+
         let gas_limit = get_gas_limit(inscription_byte_len);
         let account_nonce = self.get_account_nonce(tx_info.from)?;
 
@@ -273,6 +321,7 @@ impl BRC20ProgEngine {
             if nonce != account_nonce {
                 if nonce > account_nonce && nonce < account_nonce + MAX_FUTURE_TRANSACTION_NONCES {
                     self.db.write_fn(|db| {
+                        db.begin_block()?;
                         db.set_pending_tx(
                             tx_info.from,
                             nonce,
@@ -292,6 +341,8 @@ impl BRC20ProgEngine {
                                     tx_info.r.into(),
                                     tx_info.s.into(),
                                 ),
+                                tx_info.tx_type.into(),
+                                (tx_info.tx_type != 0).then(|| tx_info.access_list.clone()),
                             ),
                             op_return_tx_id,
                         )
@@ -339,6 +390,8 @@ impl BRC20ProgEngine {
                             pending_tx.input.bytes,
                             pending_tx.nonce.into(),
                             pending_tx.hash.bytes,
+                            pending_tx.tx_type.uint.to(),
+                            pending_tx.access_list.unwrap_or_default(),
                             if pending_tx.v.is_zero() { 0 } else { 1 },
                             pending_tx.r.uint,
                             pending_tx.s.uint,
@@ -368,6 +421,17 @@ impl BRC20ProgEngine {
         mut raw_tx: Vec<u8>,
         use_rlp_hash: bool,
     ) -> Result<Option<TxInfo>, Box<dyn Error>> {
+        // Typed transactions start with the type byte, legacy transactions start with an RLP list
+        if raw_tx.first().is_some_and(|byte| *byte < 0x80) {
+            return match TxEnvelope::decode_2718(&mut raw_tx.as_slice())
+                .map_err(|_| "Failed to decode typed transaction")?
+            {
+                TxEnvelope::Eip2930(signed_tx) => Self::get_info_from_signed_tx(&signed_tx),
+                TxEnvelope::Eip1559(signed_tx) => Self::get_info_from_signed_tx(&signed_tx),
+                _ => Err("Unsupported transaction type, only legacy, EIP-2930 and EIP-1559 transactions are supported".into()),
+            };
+        }
+
         let (decoded_raw_tx, signature) =
             TxLegacy::rlp_decode_with_signature(&mut raw_tx.as_mut_slice().as_ref())
                 .map_err(|_| "Failed to decode legacy transaction")?;
@@ -389,7 +453,7 @@ impl BRC20ProgEngine {
 
         Ok(Some(TxInfo::from_raw_transaction(
             recovered_address,
-            decoded_raw_tx,
+            &decoded_raw_tx,
             tx_hash,
             signature.v() as u8,
             signature.r(),
@@ -397,6 +461,29 @@ impl BRC20ProgEngine {
         )))
     }
 
+    fn get_info_from_signed_tx<T>(signed_tx: &Signed<T>) -> Result<Option<TxInfo>, Box<dyn Error>>
+    where
+        T: SignableTransaction<alloy::primitives::Signature> + RlpEcdsaEncodableTx + Transaction,
+    {
+        if signed_tx.tx().chain_id() != Some(CONFIG.read().chain_id) {
+            return Ok(None);
+        }
+
+        let signature = signed_tx.signature();
+        let recovered_address =
+            signature.recover_address_from_prehash(&signed_tx.signature_hash())?;
+
+        // Typed transaction hashes are always the hash of the encoded transaction
+        Ok(Some(TxInfo::from_raw_transaction(
+            recovered_address,
+            signed_tx.tx(),
+            *signed_tx.hash(),
+            signature.v() as u8,
+            signature.r(),
+            signature.s(),
+        )))
+    }
+
     pub fn add_tx_to_block(
         &self,
         timestamp: u64,
@@ -437,6 +524,7 @@ impl BRC20ProgEngine {
         let gas_limit = get_gas_limit(inscription_byte_len);
 
         self.db.write_fn(|db| {
+            db.begin_block()?;
             let processing_start_time = self.last_block_info.read().start_time.elapsed();
 
             let db_moved = core::mem::take(&mut *db);
@@ -456,6 +544,8 @@ impl BRC20ProgEngine {
                 tx.data = tx_info.data.clone();
                 tx.nonce = tx_nonce;
                 tx.gas_limit = gas_limit;
+                tx.tx_type = tx_info.tx_type;
+                tx.access_list = tx_info.access_list.clone();
             });
 
             let tx = evm.ctx().tx().clone();
@@ -470,17 +560,10 @@ impl BRC20ProgEngine {
                 .checked_add(output.as_ref().map(|o| o.gas_used()).unwrap_or(0))
                 .unwrap_or(self.last_block_info.read().gas_used);
 
-            let traces: TraceED = evm
-                .inspector()
-                .geth_builder()
-                .geth_call_traces(
-                    CallConfig {
-                        only_top_call: Some(false),
-                        with_log: Some(true),
-                    },
-                    output.as_ref().map(|o| o.gas_used()).unwrap_or(0),
-                )
-                .into();
+            let traces = get_call_trace(
+                evm.inspector(),
+                output.as_ref().map(|o| o.gas_used()).unwrap_or(0),
+            );
 
             // If this is a contract creation, store it
             if let Some(created_contract) = traces.get_created_contract() {
@@ -493,6 +576,8 @@ impl BRC20ProgEngine {
             if CONFIG.read().evm_record_traces {
                 db.set_tx_trace(tx_hash, traces)?;
             }
+            db.set_tx_op_return_tx_id(tx_hash, op_return_tx_id)?;
+            db.set_tx_timestamp(tx_hash, timestamp)?;
 
             db.set_tx_receipt(
                 block_hash,
@@ -515,6 +600,8 @@ impl BRC20ProgEngine {
                 tx_info.v.into(),
                 tx_info.r.into(),
                 tx_info.s.into(),
+                tx_info.tx_type,
+                (tx_info.tx_type != 0).then(|| tx_info.access_list.clone()),
             )?;
 
             self.last_block_info.write_fn_unchecked(|last_block_info| {
@@ -547,8 +634,33 @@ impl BRC20ProgEngine {
             .get_inscription_id_by_contract_address(contract_address)
     }
 
-    pub fn get_trace(&self, tx_hash: B256) -> Result<Option<TraceED>, Box<dyn Error>> {
-        self.db.read().get_tx_trace(tx_hash)
+    /// Returns the trace of the transaction, `callTracer` is used if no options are given
+    ///
+    /// Saved traces are returned if available, otherwise the transaction is executed again
+    pub fn get_trace(
+        &self,
+        tx_hash: B256,
+        options: Option<GethDebugTracingOptions>,
+    ) -> Result<Option<TraceResult>, Box<dyn Error>> {
+        let options = options.unwrap_or_else(default_tracing_options);
+        let (saved_trace, receipt) = self
+            .db
+            .read_fn(|db| Ok((db.get_tx_trace(tx_hash)?, db.get_tx_receipt(tx_hash)?)))?;
+        if let Some(trace) = saved_trace {
+            if let Some(trace) = get_saved_trace(trace, &options)? {
+                return Ok(Some(trace));
+            }
+        }
+        let Some(receipt) = receipt else {
+            return Ok(None);
+        };
+        let tx_index = receipt.transaction_index.uint.to::<usize>();
+        let traces = self.trace_block_transactions(
+            receipt.block_number.into(),
+            tx_index..=tx_index,
+            &options,
+        )?;
+        Ok(traces.into_iter().next())
     }
 
     pub fn get_block_trace_hash(
@@ -566,49 +678,238 @@ impl BRC20ProgEngine {
         &self,
         block_number: u64,
     ) -> Result<Option<String>, Box<dyn Error>> {
-        static TRACE_SEPARATOR: &str = "|";
+        self.db
+            .read_fn(|db| Self::block_trace_string(db, block_number))
+    }
+
+    fn block_trace_string(
+        db: &Brc20ProgDatabase,
+        block_number: u64,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let Some(transactions) = Self::get_sorted_block_transactions(db, block_number)? else {
+            return Ok(None);
+        };
+        let mut traces = Vec::new();
+        for tx_hash in transactions {
+            if let Some(trace) = db.get_tx_trace(tx_hash.bytes)? {
+                traces.push(trace);
+            }
+        }
+        Ok(Some(join_block_traces(&traces)))
+    }
+
+    /// Returns the consensus hash of the given block, see Brc20ProgDatabase::set_consensus_hash
+    pub fn get_consensus_hash(&self, block_number: u64) -> Result<Option<B256>, Box<dyn Error>> {
+        self.db.read_fn(|db| db.get_consensus_hash(block_number))
+    }
+
+    /// Finds the first block whose consensus hash differs from the reference hashes
+    ///
+    /// Consensus hashes include the hash of the previous block, so once a block differs every following
+    /// block differs as well, and the first divergent block is found with a binary search
+    ///
+    /// first_block_number: u64 - the block number of the first reference hash
+    /// reference_hashes: &[B256] - consensus hashes of consecutive blocks, e.g. from another indexer
+    ///
+    /// Returns: Option<u64> - the first block that differs or is not indexed yet, None if all blocks match
+    pub fn find_divergent_block(
+        &self,
+        first_block_number: u64,
+        reference_hashes: &[B256],
+    ) -> Result<Option<u64>, Box<dyn Error>> {
+        let block_number = |index: usize| {
+            first_block_number
+                .checked_add(index as u64)
+                .ok_or("Block number is out of range")
+        };
         self.db.read_fn(|db| {
-            let Some(block) = db.get_block(block_number)? else {
-                return Ok(None);
-            };
-            let Left(transactions) = block.transactions else {
+            // Indexed blocks have consensus hashes from the first stored block on, a missing hash before
+            // matching ones would break the binary search
+            if !reference_hashes.is_empty()
+                && db.get_block_hash(first_block_number)?.is_some()
+                && db.get_consensus_hash(first_block_number)?.is_none()
+            {
+                return Err(format!(
+                    "Consensus hash of block {} is not available",
+                    first_block_number
+                )
+                .into());
+            }
+            let index = first_mismatch(reference_hashes.len(), |index| {
+                Ok(db.get_consensus_hash(block_number(index)?)? == Some(reference_hashes[index]))
+            })?;
+            Ok(index.map(block_number).transpose()?)
+        })
+    }
+
+    /// Returns the traces for every transaction in the block, in transaction index order
+    ///
+    /// Saved traces are returned if available for every transaction, otherwise the block is executed again
+    pub fn get_block_traces(
+        &self,
+        block_number: u64,
+        options: Option<GethDebugTracingOptions>,
+    ) -> Result<Option<Vec<TraceResult>>, Box<dyn Error>> {
+        let options = options.unwrap_or_else(default_tracing_options);
+        let saved_traces = self.db.read_fn(|db| {
+            let Some(transactions) = Self::get_sorted_block_transactions(db, block_number)? else {
                 return Ok(None);
             };
-            // Sort by tx index (as they may be out of order)
-            let mut transactions = transactions;
-            transactions.sort_by_key(|tx_hash| {
-                db.get_tx_receipt(tx_hash.bytes)
-                    .ok()
-                    .flatten()
-                    .expect("Transaction in block not found in database")
-                    .transaction_index
-            });
-            let mut trace_hash_str = String::new();
+            let mut traces = Vec::new();
             for tx_hash in transactions {
-                if let Some(trace) = db.get_tx_trace(tx_hash.bytes)? {
-                    trace_hash_str.push_str(&trace.get_opi_string());
-                    trace_hash_str.push_str(TRACE_SEPARATOR);
+                let Some(trace) = db.get_tx_trace(tx_hash.bytes)? else {
+                    return Ok(Some(None));
+                };
+                let Some(trace) = get_saved_trace(trace, &options)? else {
+                    return Ok(Some(None));
+                };
+                traces.push(trace);
+            }
+            Ok(Some(Some(traces)))
+        })?;
+        match saved_traces {
+            None => Ok(None),
+            Some(Some(traces)) => Ok(Some(traces)),
+            Some(None) => self
+                .trace_block_transactions(block_number, 0..=usize::MAX, &options)
+                .map(Some),
+        }
+    }
+
+    /// Executes the transactions of a finalised block again on top of the state of the previous block
+    ///
+    /// Traces are returned for transactions with an index in traced_indices, transactions after
+    /// the range are not executed
+    fn trace_block_transactions(
+        &self,
+        block_number: u64,
+        traced_indices: RangeInclusive<usize>,
+        options: &GethDebugTracingOptions,
+    ) -> Result<Vec<TraceResult>, Box<dyn Error>> {
+        if block_number == 0 {
+            return Err(
+                "Transactions in block 0 can't be executed again, as it has no previous state"
+                    .into(),
+            );
+        }
+        let inspector_config = get_tracing_inspector_config(options)?;
+        let (block, transactions) = self.db.read_fn(|db| {
+            let block = db.get_block(block_number)?.ok_or("Block not found")?;
+            let tx_hashes =
+                Self::get_sorted_block_transactions(db, block_number)?.ok_or("Block not found")?;
+            let mut transactions = Vec::new();
+            for tx_hash in tx_hashes
+                .into_iter()
+                .take(traced_indices.end().saturating_add(1))
+            {
+                let tx = db
+                    .get_tx_by_hash(tx_hash.bytes)?
+                    .ok_or("Transaction in block not found in database")?;
+                // Transactions indexed before op_return tx ids were saved use a zero tx id
+                let op_return_tx_id = db
+                    .get_tx_op_return_tx_id(tx_hash.bytes)?
+                    .map_or(B256::ZERO, |x| x.bytes);
+                // Transactions indexed before timestamps were saved use the block timestamp
+                let timestamp: u64 = db
+                    .get_tx_timestamp(tx_hash.bytes)?
+                    .unwrap_or(block.timestamp)
+                    .into();
+                transactions.push((tx, op_return_tx_id, timestamp));
+            }
+            Ok((block, transactions))
+        })?;
+
+        self.read_snapshot(Some(block_number - 1), |snapshot| {
+            let mut evm = get_evm(
+                block_number,
+                block.hash.bytes,
+                block.timestamp.into(),
+                CacheDB::new(snapshot),
+                None,
+                B256::ZERO,
+                &None,
+            );
+            *evm.inspector.config_mut() = inspector_config;
+
+            let mut traces = Vec::new();
+            for (tx_idx, (tx, op_return_tx_id, timestamp)) in transactions.into_iter().enumerate() {
+                evm.precompiles.op_return_tx_id = op_return_tx_id;
+                evm.ctx()
+                    .modify_block(|block| block.timestamp = U256::from(timestamp));
+                evm.ctx().modify_tx(|tx_env| {
+                    tx_env.caller = tx.from.address;
+                    tx_env.kind = tx.to.map(|x| x.address).into();
+                    tx_env.data = tx.input.bytes;
+                    tx_env.nonce = tx.nonce.into();
+                    tx_env.gas_limit = tx.gas.into();
+                    tx_env.tx_type = tx.tx_type.uint.to();
+                    tx_env.access_list = tx.access_list.unwrap_or_default();
+                });
+                let tx_env = evm.ctx().tx().clone();
+
+                if !traced_indices.contains(&tx_idx) {
+                    // Transactions that fail validation don't change the state, same as when indexing
+                    let _ = evm.transact_commit(tx_env);
+                    continue;
                 }
+
+                evm.inspector.fuse();
+                let output = evm.inspect_tx(tx_env)?;
+                traces.push(get_trace(
+                    &evm.inspector,
+                    &output,
+                    options,
+                    evm.ctx.db_ref(),
+                )?);
+                evm.ctx().db_mut().commit(output.state);
             }
-            Ok(Some(
-                trace_hash_str.trim_end_matches(TRACE_SEPARATOR).to_string(),
-            ))
+            Ok(traces)
         })
     }
 
+    /// Returns the transaction hashes of a block in order, or an error if their receipts are not available,
+    /// e.g. for blocks imported from a snapshot
+    fn get_sorted_block_transactions(
+        db: &Brc20ProgDatabase,
+        block_number: u64,
+    ) -> Result<Option<Vec<B256ED>>, Box<dyn Error>> {
+        let Some(block) = db.get_block(block_number)? else {
+            return Ok(None);
+        };
+        let Left(transactions) = block.transactions else {
+            return Ok(None);
+        };
+        let mut indexed_transactions = Vec::new();
+        for tx_hash in transactions {
+            let receipt = db.get_tx_receipt(tx_hash.bytes)?.ok_or_else(|| {
+                format!(
+                    "Transaction {} of block {} not found in database",
+                    tx_hash.bytes, block_number
+                )
+            })?;
+            indexed_transactions.push((receipt.transaction_index, tx_hash));
+        }
+        // Sort by tx index (as they may be out of order)
+        indexed_transactions.sort_by_key(|(transaction_index, _)| *transaction_index);
+        Ok(Some(
+            indexed_transactions
+                .into_iter()
+                .map(|(_, tx_hash)| tx_hash)
+                .collect(),
+        ))
+    }
+
     pub fn get_transaction_count(
         &self,
         account: Address,
-        _block_number: u64,
+        block_number: u64,
     ) -> Result<u64, Box<dyn Error>> {
-        let account_nonce: u64 = self
-            .db
-            .read()
-            .get_account_info(account)?
-            .map(|x| x.nonce.into())
-            .unwrap_or(0);
-
-        Ok(account_nonce)
+        self.read_snapshot(Some(block_number), |snapshot| {
+            Ok(snapshot
+                .get_account_info(account)?
+                .map(|x| x.nonce.into())
+                .unwrap_or(0))
+        })
     }
 
     pub fn get_block_transaction_count_by_number(
@@ -675,6 +976,46 @@ impl BRC20ProgEngine {
         self.db.read().get_tx_receipt(tx_hash)
     }
 
+    /// Get the Merkle proof of a transaction receipt against the receipts root of its block
+    ///
+    /// tx_hash: B256 - the hash of the transaction
+    ///
+    /// Returns: Option<ReceiptProof> - the receipt and its proof, None if the transaction is not found
+    pub fn get_receipt_proof(&self, tx_hash: B256) -> Result<Option<ReceiptProof>, Box<dyn Error>> {
+        self.db.read_fn(|db| {
+            let Some(receipt) = db.get_tx_receipt(tx_hash)? else {
+                return Ok(None);
+            };
+            let block_number: u64 = receipt.block_number.into();
+            let block = db
+                .get_block(block_number)?
+                .ok_or_else(|| format!("Block {} is not finalised yet", block_number))?;
+            // Blocks indexed by older versions don't have a receipts root
+            if block.receipts_root.bytes.is_zero() {
+                return Err(
+                    format!("Receipts root of block {} is not available", block_number).into(),
+                );
+            }
+            let mut receipts = Vec::new();
+            for tx_hash in block.transactions.left().unwrap_or_default() {
+                receipts.push(
+                    db.get_tx_receipt(tx_hash.bytes)?
+                        .ok_or_else(|| format!("Receipt of {} not found", tx_hash.bytes))?,
+                );
+            }
+            let index: u64 = receipt.transaction_index.into();
+            let proof = ReceiptProof::new(&receipts, index as usize)?;
+            if proof.receipts_root != block.receipts_root {
+                return Err(format!(
+                    "Receipts of block {} don't match its receipts root",
+                    block_number
+                )
+                .into());
+            }
+            Ok(Some(proof))
+        })
+    }
+
     pub fn get_logs(
         &self,
         block_number_from: Option<u64>,
@@ -682,9 +1023,16 @@ impl BRC20ProgEngine {
         address: Option<Address>,
         topics: Option<Vec<SingleOrVec<Option<B256>>>>,
     ) -> Result<Vec<LogED>, Box<dyn Error>> {
-        self.db
-            .read()
-            .get_logs(block_number_from, block_number_to, address, topics)
+        self.read_snapshot(None, |snapshot| {
+            snapshot.get_logs(
+                block_number_from,
+                block_number_to,
+                address,
+                topics,
+                CONFIG.read().get_logs_result_limit as u64,
+                CONFIG.read().get_logs_scan_block_limit,
+            )
+        })
     }
 
     pub fn finalise_block(
@@ -701,7 +1049,8 @@ impl BRC20ProgEngine {
 
         self.validate_next_tx(block_tx_count, block_hash, block_number, timestamp)?;
 
-        self.db.write_fn(|db| {
+        let block_response = self.db.write_fn(|db| {
+            db.begin_block()?;
             let (total_time_took, gas_used) = self.last_block_info.read_fn(|info| {
                 let total_time_took = info
                     .total_processing_time
@@ -711,21 +1060,44 @@ impl BRC20ProgEngine {
             })?;
 
             // Save the full block info in the database for ease of access
+            let state_root = db.compute_state_root(block_number)?;
             let block_response = db.generate_block(
                 block_hash,
                 block_number,
                 timestamp,
                 gas_used,
                 total_time_took,
+                state_root,
             )?;
             db.set_block(block_number, block_response.clone())?;
-            db.set_raw_block(block_number, db.generate_raw_block(block_response)?)?;
+            db.set_raw_block(block_number, db.generate_raw_block(block_response.clone())?)?;
+            let trace_hash = B256::from_str(&sha256::digest(
+                Self::block_trace_string(db, block_number)?.unwrap_or_default(),
+            ))?;
+            db.set_consensus_hash(block_number, trace_hash, state_root)?;
 
             // Remove old transactions from the txpool
             db.clear_txpool(block_number)?;
 
             // Set block hash last to avoid race conditions
-            db.set_block_hash(block_number, block_hash)
+            db.set_block_hash(block_number, block_hash)?;
+            db.end_block();
+
+            // Commit according to the commit policy, the block is already finalised at this point,
+            // so a failed commit is retried after the next block instead of failing this one
+            self.last_commit_info
+                .write_fn_unchecked(|info| info.finalised_block_count += 1);
+            let should_commit = self
+                .last_commit_info
+                .read()
+                .should_commit(&CONFIG.read(), db.cache_size());
+            if should_commit {
+                match db.commit_changes() {
+                    Ok(()) => self.reset_last_commit_info(),
+                    Err(error) => tracing::error!("Failed to commit changes: {}", error),
+                }
+            }
+            Ok(block_response)
         })?;
 
         self.last_block_info.write_fn_unchecked(|last_block_info| {
@@ -733,16 +1105,61 @@ impl BRC20ProgEngine {
         });
 
         self.block_finalised.notify_waiters();
+
+        // Logs are only read if there are subscribers
+        if self.chain_events.receiver_count() > 0 {
+            match self.read_snapshot(Some(block_number), |snapshot| {
+                snapshot.get_logs(Some(block_number), Some(block_number), None, None, 0, 0)
+            }) {
+                Ok(logs) => {
+                    let _ = self.chain_events.send(ChainEvent::NewBlock {
+                        block: Box::new(block_response),
+                        logs,
+                    });
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to read logs for subscribers: {}", error);
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Returns the sender for chain events, new receivers can be created by calling subscribe on it
+    pub fn chain_events(&self) -> broadcast::Sender<ChainEvent> {
+        self.chain_events.clone()
+    }
+
     pub async fn read_contract(
         &self,
         tx_info: &TxInfo,
         block_height: Option<u64>,
         gas_limit: Option<u64>,
+        overrides: &EvmOverrides,
     ) -> Result<ReadContractResult, Box<dyn Error>> {
-        self.wait_for_no_waiting_txes().await?;
+        let (output, _) = self
+            .execute_read(tx_info, block_height, gas_limit, overrides, None)
+            .await?;
+
+        Ok(ReadContractResult::from(output))
+    }
+
+    /// Estimates the gas for a call, returns the estimated gas and the result of the call with it
+    ///
+    /// Every execution runs on a single snapshot, so blocks finalised during the estimation don't
+    /// change the result. The call is executed with the upper gas limit first, and the result is
+    /// returned with the upper gas limit if it fails. Otherwise, gas used and refunded by the call
+    /// is the lower bound, and the search starts from the gas that leaves 1/64 of the gas for the
+    /// calls made by the transaction. Gas is estimated with a precision of GAS_PER_BYTE, as
+    /// inscriptions pay for gas per byte.
+    pub async fn estimate_gas(
+        &self,
+        tx_info: &TxInfo,
+        block_height: Option<u64>,
+        upper_gas_limit: u64,
+        overrides: &EvmOverrides,
+    ) -> Result<(u64, ReadContractResult), Box<dyn Error>> {
+        self.wait_for_finalised_state(block_height).await?;
 
         let block_number = if let Some(height) = block_height {
             height
@@ -751,51 +1168,147 @@ impl BRC20ProgEngine {
         };
 
         let timestamp = UNIX_EPOCH.elapsed().map(|x| x.as_secs())?;
-        let nonce = self.get_account_nonce(tx_info.from)?;
 
-        // This isn't actually writing to the database, but the EVM context requires a mutable reference
-        let output = self.db.write_fn(|db| {
-            let db_moved = core::mem::take(&mut *db);
-            let mut evm = get_evm(
-                block_number,
-                B256::ZERO,
-                timestamp,
-                db_moved,
-                None,
-                [0u8; 32].into(),
-                &None,
-            );
+        self.read_snapshot(block_height, |snapshot| {
+            let mut evm = get_read_evm(block_number, timestamp, snapshot, &None, overrides)?;
+            let nonce = evm
+                .ctx
+                .db_ref()
+                .basic_ref(tx_info.from)?
+                .map(|x| x.nonce)
+                .unwrap_or(0);
 
             evm.ctx().modify_tx(|tx| {
                 tx.caller = tx_info.from;
                 tx.kind = tx_info.to;
                 tx.data = tx_info.data.clone();
-                tx.nonce = nonce;
-                tx.gas_limit = gas_limit.unwrap_or(CONFIG.read().evm_call_gas_limit);
+                tx.value = tx_info.value;
+                tx.gas_price = tx_info.gas_price;
+                tx.nonce = tx_info.nonce.unwrap_or(nonce);
             });
 
-            let output = evm.replay().map(|x| x.result);
-            core::mem::swap(&mut *db, evm.ctx().db_mut());
+            // Accounts loaded by the previous executions are kept in the cache of the EVM
+            let mut execute = |gas_limit: u64| {
+                evm.ctx().modify_tx(|tx| tx.gas_limit = gas_limit);
+                evm.replay().map(|x| x.result)
+            };
 
-            output.map_err(|e| e.into())
-        })?;
+            let output = execute(upper_gas_limit)?;
+            let ExecutionResult::Success {
+                gas_used,
+                gas_refunded,
+                ..
+            } = output
+            else {
+                return Ok((upper_gas_limit, ReadContractResult::from(output)));
+            };
 
-        Ok(ReadContractResult {
-            status: output.is_success(),
-            status_string: format!("{:?}", output),
-            gas_used: output.gas_used().into(),
-            output: output.output().cloned(),
+            let mut upper_gas_limit = upper_gas_limit;
+            let mut result = ReadContractResult::from(output);
+            let mut lower_gas_limit = (gas_used + gas_refunded).min(upper_gas_limit);
+
+            // Most calls succeed with the gas spent and the gas kept for the calls they make
+            let optimistic_gas_limit = (gas_used + gas_refunded + CALL_STIPEND) * 64 / 63;
+            let mut next_gas_limit = optimistic_gas_limit;
+            while lower_gas_limit + GAS_PER_BYTE < upper_gas_limit {
+                if next_gas_limit <= lower_gas_limit || next_gas_limit >= upper_gas_limit {
+                    next_gas_limit = (lower_gas_limit + upper_gas_limit) / 2;
+                }
+                match execute(next_gas_limit) {
+                    Ok(output) if output.is_success() => {
+                        upper_gas_limit = next_gas_limit;
+                        result = ReadContractResult::from(output);
+                    }
+                    // Calls can fail with too little gas for the intrinsic gas or the calls they make
+                    _ => lower_gas_limit = next_gas_limit + 1,
+                }
+                next_gas_limit = (lower_gas_limit + upper_gas_limit) / 2;
+            }
+
+            Ok((upper_gas_limit, result))
+        })
+    }
+
+    /// Executes a call without committing it, and returns its trace, `callTracer` is used if no options are given
+    pub async fn trace_call(
+        &self,
+        tx_info: &TxInfo,
+        block_height: Option<u64>,
+        gas_limit: Option<u64>,
+        options: Option<GethDebugTracingOptions>,
+    ) -> Result<TraceResult, Box<dyn Error>> {
+        let options = options.unwrap_or_else(default_tracing_options);
+        let (_, trace) = self
+            .execute_read(
+                tx_info,
+                block_height,
+                gas_limit,
+                &EvmOverrides::default(),
+                Some(&options),
+            )
+            .await?;
+        trace.ok_or("Failed to trace call".into())
+    }
+
+    async fn execute_read(
+        &self,
+        tx_info: &TxInfo,
+        block_height: Option<u64>,
+        gas_limit: Option<u64>,
+        overrides: &EvmOverrides,
+        tracing_options: Option<&GethDebugTracingOptions>,
+    ) -> Result<(ExecutionResult, Option<TraceResult>), Box<dyn Error>> {
+        self.wait_for_finalised_state(block_height).await?;
+
+        let block_number = if let Some(height) = block_height {
+            height
+        } else {
+            self.get_next_block_height()?
+        };
+
+        let timestamp = UNIX_EPOCH.elapsed().map(|x| x.as_secs())?;
+
+        self.read_snapshot(block_height, |snapshot| {
+            let mut evm = get_read_evm(block_number, timestamp, snapshot, &None, overrides)?;
+            let nonce = evm
+                .ctx
+                .db_ref()
+                .basic_ref(tx_info.from)?
+                .map(|x| x.nonce)
+                .unwrap_or(0);
+
+            evm.ctx().modify_tx(|tx| {
+                tx.caller = tx_info.from;
+                tx.kind = tx_info.to;
+                tx.data = tx_info.data.clone();
+                tx.value = tx_info.value;
+                tx.gas_price = tx_info.gas_price;
+                tx.nonce = tx_info.nonce.unwrap_or(nonce);
+                tx.gas_limit = gas_limit.unwrap_or(CONFIG.read().evm_call_gas_limit);
+            });
+
+            let Some(options) = tracing_options else {
+                let output = evm.replay().map(|x| x.result)?;
+                return Ok((output, None));
+            };
+
+            *evm.inspector.config_mut() = get_tracing_inspector_config(options)?;
+            let tx = evm.ctx().tx().clone();
+            let output = evm.inspect_tx(tx)?;
+            let trace = get_trace(&evm.inspector, &output, options, evm.ctx.db_ref())?;
+            Ok((output.result, Some(trace)))
         })
     }
 
     pub async fn read_contract_multi(
         &self,
-        tx_infos: &Vec<TxInfo>,
+        tx_infos: &[TxInfo],
         block_height: Option<u64>,
         precompile_data: Option<PrecompileData>,
         gas_limit: Option<&Vec<u64>>,
+        overrides: &EvmOverrides,
     ) -> Result<Vec<ReadContractResult>, Box<dyn Error>> {
-        self.wait_for_no_waiting_txes().await?;
+        self.wait_for_finalised_state(block_height).await?;
 
         let block_number = if let Some(height) = block_height {
             height
@@ -804,89 +1317,292 @@ impl BRC20ProgEngine {
         };
 
         let timestamp = UNIX_EPOCH.elapsed().map(|x| x.as_secs())?;
-        let mut nonces = HashMap::new();
-        for tx_info in tx_infos {
-            let nonce = self.get_account_nonce(tx_info.from)?;
-            nonces.insert(tx_info.from, nonce);
-        }
 
-        // This isn't actually writing to the database, but the EVM context requires a mutable reference
-        let outputs = self.db.write_fn(|db| {
-            let db_moved = core::mem::take(&mut *db);
-            let mut evm = get_evm(
+        let outputs = self.read_snapshot(block_height, |snapshot| {
+            let mut evm = get_read_evm(
                 block_number,
-                B256::ZERO,
                 timestamp,
-                db_moved,
-                None,
-                [0u8; 32].into(),
+                snapshot,
                 &precompile_data,
-            );
+                overrides,
+            )?;
+            // Only the gas limit of each call is applied
+            let mut gas_budget = u64::MAX;
+            execute_read_calls(
+                &mut evm,
+                tx_infos,
+                gas_limit.map(|gl| gl.as_slice()),
+                &precompile_data,
+                None,
+                &mut gas_budget,
+            )
+        })?;
 
-            let mut outputs = Vec::new();
-            for idx in 0..tx_infos.len() {
-                let tx_info = &tx_infos[idx];
-                let nonce = *nonces.get(&tx_info.from).unwrap_or(&0);
-                nonces.insert(tx_info.from, nonce + 1);
+        Ok(outputs
+            .into_iter()
+            .map(|(output, _)| ReadContractResult::from(output))
+            .collect())
+    }
 
-                evm.precompiles.op_return_tx_id = precompile_data
-                    .as_ref()
-                    .and_then(|data| data.op_return_tx_ids.get(idx).cloned())
-                    .unwrap_or([0u8; 32].into())
-                    .into();
+    /// Simulates calls in a sequence of blocks on top of the state at the given block, without committing them
+    ///
+    /// Each block is simulated on top of the changes made by the previous blocks, block numbers and
+    /// timestamps are increased by one for each block if they are not overridden
+    ///
+    /// Calls of all blocks share a gas budget of the call gas limit, calls without a gas limit use the
+    /// remaining budget
+    pub async fn simulate(
+        &self,
+        blocks: &[SimulateBlockCalls],
+        block_height: Option<u64>,
+        tracing_options: Option<&GethDebugTracingOptions>,
+    ) -> Result<Vec<SimulatedBlock>, Box<dyn Error>> {
+        if blocks.len() > MAX_SIMULATE_BLOCKS {
+            return Err(format!(
+                "Too many blocks, at most {} blocks can be simulated",
+                MAX_SIMULATE_BLOCKS
+            )
+            .into());
+        }
+        let call_count: usize = blocks.iter().map(|block| block.tx_infos.len()).sum();
+        if call_count > MAX_SIMULATE_CALLS {
+            return Err(format!(
+                "Too many calls, at most {} calls can be simulated",
+                MAX_SIMULATE_CALLS
+            )
+            .into());
+        }
+        self.wait_for_finalised_state(block_height).await?;
+
+        let mut gas_budget = CONFIG.read().evm_call_gas_limit;
+        let mut next_block_number = match block_height {
+            Some(height) => height,
+            None => self.get_latest_block_height()?,
+        } + 1;
+        let mut next_timestamp = UNIX_EPOCH.elapsed().map(|x| x.as_secs())?;
+        let mut previous_timestamp = None;
+
+        self.read_snapshot(block_height, |snapshot| {
+            let mut db = CacheDB::new(snapshot);
+            let mut simulated_blocks = Vec::new();
+            for block in blocks {
+                let block_overrides = block.overrides.block.clone().unwrap_or_default();
+                let block_number = match block_overrides.number {
+                    Some(number) => number.try_into().map_err(|_| "Block number is too large")?,
+                    None => next_block_number,
+                };
+                if block_number < next_block_number {
+                    return Err("Block numbers should be increasing".into());
+                }
+                let timestamp = block_overrides.time.unwrap_or(next_timestamp);
+                if previous_timestamp.is_some_and(|previous| timestamp <= previous) {
+                    return Err("Block timestamps should be increasing".into());
+                }
+                let block_hash = generate_block_hash(block_number);
+
+                let overrides = EvmOverrides::new(
+                    block.overrides.state.clone(),
+                    Some(BlockOverrides {
+                        number: Some(U256::from(block_number)),
+                        time: Some(timestamp),
+                        ..block_overrides
+                    }),
+                );
+                let mut evm = get_cached_read_evm(
+                    block_number,
+                    timestamp,
+                    db,
+                    &block.precompile_data,
+                    &overrides,
+                )?;
+                if let Some(options) = tracing_options {
+                    *evm.inspector.config_mut() = get_tracing_inspector_config(options)?;
+                }
+
+                let outputs = execute_read_calls(
+                    &mut evm,
+                    &block.tx_infos,
+                    Some(&block.gas_limits),
+                    &block.precompile_data,
+                    tracing_options,
+                    &mut gas_budget,
+                )?;
+
+                let mut calls = Vec::new();
+                let mut gas_used = 0;
+                let mut log_index = 0;
+                for (idx, (output, trace)) in outputs.into_iter().enumerate() {
+                    let logs = LogED::new_vec(
+                        &output.logs().to_vec(),
+                        log_index,
+                        (idx as u64).into(),
+                        B256::ZERO.into(),
+                        block_hash.into(),
+                        block_number.into(),
+                    );
+                    log_index += logs.len() as u64;
+                    gas_used += output.gas_used();
+
+                    // Error codes are the same as Geth
+                    let error = match &output {
+                        ExecutionResult::Success { .. } => None,
+                        ExecutionResult::Revert { .. } => Some(SimulatedCallError {
+                            code: 3,
+                            message: "execution reverted".to_string(),
+                        }),
+                        ExecutionResult::Halt { reason, .. } => Some(SimulatedCallError {
+                            code: -32015,
+                            message: format!("{:?}", reason),
+                        }),
+                    };
+                    calls.push(SimulatedCall {
+                        return_data: output.output().cloned().unwrap_or_default().into(),
+                        logs,
+                        gas_used: output.gas_used().into(),
+                        status: (output.is_success() as u8).into(),
+                        error,
+                        trace,
+                    });
+                }
+
+                // Following blocks can read the hash of this block
+                db = evm.ctx.journaled_state.database;
+                db.cache
+                    .block_hashes
+                    .insert(U256::from(block_number), block_hash);
+
+                simulated_blocks.push(SimulatedBlock {
+                    number: block_number.into(),
+                    hash: block_hash.into(),
+                    timestamp: timestamp.into(),
+                    gas_used: gas_used.into(),
+                    calls,
+                });
+                next_block_number = block_number + 1;
+                next_timestamp = timestamp + 1;
+                previous_timestamp = Some(timestamp);
+            }
+            Ok(simulated_blocks)
+        })
+    }
+
+    /// Creates an access list for the call, by executing it again with the accessed accounts and
+    /// storage slots until they don't change
+    pub async fn create_access_list(
+        &self,
+        tx_info: &TxInfo,
+        block_height: Option<u64>,
+        gas_limit: Option<u64>,
+        overrides: &EvmOverrides,
+    ) -> Result<AccessListResult, Box<dyn Error>> {
+        self.wait_for_finalised_state(block_height).await?;
+
+        let block_number = if let Some(height) = block_height {
+            height
+        } else {
+            self.get_next_block_height()?
+        };
+
+        let timestamp = UNIX_EPOCH.elapsed().map(|x| x.as_secs())?;
+
+        self.read_snapshot(block_height, |snapshot| {
+            let evm = get_read_evm(block_number, timestamp, snapshot, &None, overrides)?;
+            let nonce = evm
+                .ctx
+                .db_ref()
+                .basic_ref(tx_info.from)?
+                .map(|x| x.nonce)
+                .unwrap_or(0);
+            let mut evm = evm.with_inspector(AccessListInspector::default());
+
+            let mut access_list = tx_info.access_list.clone();
+            loop {
+                let inspector = AccessListInspector::new(access_list.clone());
+                let previous_slots = inspector.touched_slots().clone();
+                evm.inspector = inspector;
 
                 evm.ctx().modify_tx(|tx| {
                     tx.caller = tx_info.from;
                     tx.kind = tx_info.to;
                     tx.data = tx_info.data.clone();
-                    tx.nonce = nonce;
-                    tx.gas_limit = gas_limit.map_or(CONFIG.read().evm_call_gas_limit, |gl| {
-                        gl.get(idx)
-                            .cloned()
-                            .unwrap_or(CONFIG.read().evm_call_gas_limit)
-                    });
+                    tx.value = tx_info.value;
+                    tx.gas_price = tx_info.gas_price;
+                    tx.nonce = tx_info.nonce.unwrap_or(nonce);
+                    tx.gas_limit = gas_limit.unwrap_or(CONFIG.read().evm_call_gas_limit);
+                    tx.access_list = access_list.clone();
                 });
                 let tx = evm.ctx().tx().clone();
-                let result = evm.transact_one(tx);
-                match result {
-                    Ok(output) => outputs.push(output),
-                    Err(e) => {
-                        // Clean up and return error
-                        core::mem::swap(&mut *db, evm.ctx().db_mut());
-                        return Err(e.into());
-                    }
+                let output = evm.inspect_tx(tx)?.result;
+
+                // Accessed slots only grow, as the previous access list is added to the inspector
+                if evm.inspector.touched_slots() == &previous_slots {
+                    access_list.0.sort_by_key(|item| item.address);
+                    return Ok(AccessListResult {
+                        access_list,
+                        gas_used: U256::from(output.gas_used()),
+                        error: (!output.is_success()).then(|| format!("{:?}", output)),
+                    });
                 }
+                access_list = evm.inspector.access_list();
             }
-
-            core::mem::swap(&mut *db, evm.ctx().db_mut());
-
-            Ok(outputs)
-        })?;
-
-        let mut results = Vec::new();
-        for output in outputs {
-            results.push(ReadContractResult {
-                status: output.is_success(),
-                status_string: format!("{:?}", output),
-                gas_used: output.gas_used().into(),
-                output: output.output().cloned(),
-            });
-        }
-
-        Ok(results)
+        })
     }
 
     pub fn get_storage_at(
         &self,
         contract: Address,
         location: U256,
+        block_number: Option<u64>,
     ) -> Result<U256, Box<dyn Error>> {
-        Ok(self
-            .db
-            .read()
-            .get_account_memory(contract, location)?
-            .map(|x| x.uint)
-            .unwrap_or(U256::ZERO))
+        self.db.read_fn(|db| {
+            let value = match block_number {
+                Some(block_number) => {
+                    db.require_state_available(block_number)?;
+                    db.get_account_memory_at(contract, location, block_number)?
+                }
+                None => db.get_account_memory(contract, location)?,
+            };
+            Ok(value.map(|x| x.uint).unwrap_or(U256::ZERO))
+        })
+    }
+
+    /// Get the proof of an account and its storage slots against the state root of a block
+    ///
+    /// account: Address - the account to prove
+    /// storage_keys: Vec<JsonStorageKey> - the storage slots of the account to prove
+    /// block_number: Option<u64> - the block to prove the state at, latest block if None
+    ///
+    /// Returns: EIP1186AccountProofResponse - the account, its storage slots and their proofs
+    pub fn get_proof(
+        &self,
+        account: Address,
+        storage_keys: Vec<JsonStorageKey>,
+        block_number: Option<u64>,
+    ) -> Result<EIP1186AccountProofResponse, Box<dyn Error>> {
+        self.db.read_fn(|db| {
+            let block_number = match block_number {
+                Some(block_number) => block_number,
+                None => db.get_latest_block_height()?,
+            };
+            let (trie_account, account_proof) = db.get_account_proof(account, block_number)?;
+            // Missing accounts are returned as empty accounts, with a proof of their absence
+            let trie_account = trie_account.unwrap_or_default();
+            let mut storage_proof = Vec::new();
+            for key in storage_keys {
+                let (value, proof) =
+                    db.get_storage_proof(trie_account.storage_root, key.as_b256().into())?;
+                storage_proof.push(EIP1186StorageProof { key, value, proof });
+            }
+            Ok(EIP1186AccountProofResponse {
+                address: account,
+                balance: trie_account.balance,
+                code_hash: trie_account.code_hash,
+                nonce: trie_account.nonce,
+                storage_hash: trie_account.storage_root,
+                account_proof,
+                storage_proof,
+            })
+        })
     }
 
     pub fn get_block_by_number(
@@ -929,10 +1645,19 @@ impl BRC20ProgEngine {
     pub fn get_contract_bytecode(
         &self,
         addr: Address,
+        block_number: Option<u64>,
     ) -> Result<Option<BytecodeED>, Box<dyn Error>> {
-        self.db.read_fn(|db| {
-            db.get_account_info(addr)?
-                .map_or(Ok(None), |acct| db.get_code(acct.code_hash.bytes))
+        self.db.read_fn(|db| match block_number {
+            Some(block_number) => {
+                db.require_state_available(block_number)?;
+                db.get_account_info_at(addr, block_number)?
+                    .map_or(Ok(None), |acct| {
+                        db.get_code_at(acct.code_hash.bytes, block_number)
+                    })
+            }
+            None => db
+                .get_account_info(addr)?
+                .map_or(Ok(None), |acct| db.get_code(acct.code_hash.bytes)),
         })
     }
 
@@ -943,13 +1668,37 @@ impl BRC20ProgEngine {
         // Clearing caches will clear all waiting txes, so notify waiters to avoid them waiting indefinitely
         self.block_finalised.notify_waiters();
 
-        self.db.write_fn(|db| db.clear_caches())
+        self.db.write_fn(|db| db.clear_caches())?;
+        self.reset_last_commit_info();
+        Ok(())
+    }
+
+    /// Discards the transactions added to the block being indexed, finalised blocks are kept
+    ///
+    /// Uncommitted finalised blocks are not affected, unlike clear_caches
+    pub fn abort_block(&self) -> Result<(), Box<dyn Error>> {
+        self.db.write_fn(|db| db.abort_block())?;
+
+        self.last_block_info.write_fn_unchecked(|last_block_info| {
+            *last_block_info = LastBlockInfo::new();
+        });
+        // Aborted txes are not waiting anymore, so notify waiters to avoid them waiting indefinitely
+        self.block_finalised.notify_waiters();
+        Ok(())
     }
 
     pub fn commit_to_db(&self) -> Result<(), Box<dyn Error>> {
         self.require_no_waiting_txes()?;
 
-        self.db.write_fn(|db| db.commit_changes())
+        self.db.write_fn(|db| db.commit_changes())?;
+        self.reset_last_commit_info();
+        Ok(())
+    }
+
+    fn reset_last_commit_info(&self) {
+        self.last_commit_info.write_fn_unchecked(|info| {
+            *info = LastCommitInfo::new();
+        });
     }
 
     pub fn reorg(&self, latest_valid_block_number: u64) -> Result<(), Box<dyn Error>> {
@@ -959,14 +1708,58 @@ impl BRC20ProgEngine {
         if latest_valid_block_number > current_block_height {
             return Err("Latest valid block number is greater than current block height".into());
         }
-        if current_block_height - latest_valid_block_number > MAX_REORG_HISTORY_SIZE {
+        if current_block_height - latest_valid_block_number > MAX_REORG_HISTORY_SIZE
+            && !self.db.read().is_archive_mode()
+        {
             return Err("Latest valid block number is too far behind current block height".into());
         }
         if latest_valid_block_number == current_block_height {
             return Ok(());
         }
 
-        self.db.write_fn(|db| db.reorg(latest_valid_block_number))
+        // Logs are only read if there are subscribers
+        let removed_logs = if self.chain_events.receiver_count() > 0 {
+            self.read_snapshot(None, |snapshot| {
+                snapshot.get_logs(
+                    Some(latest_valid_block_number + 1),
+                    Some(current_block_height),
+                    None,
+                    None,
+                    0,
+                    0,
+                )
+            })?
+        } else {
+            Vec::new()
+        };
+
+        self.db.write_fn(|db| db.reorg(latest_valid_block_number))?;
+        // Reorg commits the reverted state to the database
+        self.reset_last_commit_info();
+
+        let _ = self.chain_events.send(ChainEvent::Reorg {
+            removed_logs: removed_logs
+                .into_iter()
+                .map(|log| LogED {
+                    removed: true,
+                    ..log
+                })
+                .collect(),
+        });
+        Ok(())
+    }
+
+    /// Starts the next block, returns an error if the block being indexed has changes, it should be
+    /// finalised or aborted first
+    ///
+    /// The block is checked and started under the same lock, so only one caller can start it
+    pub fn begin_new_block(&self) -> Result<(), Box<dyn Error>> {
+        self.db.write_fn(|db| {
+            if db.is_block_started() {
+                return Err("Block is already started, either finalise or abort it".into());
+            }
+            db.begin_block()
+        })
     }
 
     fn require_no_waiting_txes(&self) -> Result<(), Box<dyn Error>> {
@@ -986,6 +1779,51 @@ impl BRC20ProgEngine {
             .map_err(|_| "Timed out waiting for waiting txes to be processed, either finalise the block or clear caches".into())
     }
 
+    /// Waits for the block the state is read at to be finalised, latest block is used if block_height is None
+    ///
+    /// Finalised blocks can be read while the next block is being indexed, so this only waits if the
+    /// requested block is still being indexed
+    async fn wait_for_finalised_state(
+        &self,
+        block_height: Option<u64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let is_finalised = self.db.read_fn(|db| {
+            let block_number = match block_height {
+                Some(height) => height,
+                None => db.get_latest_block_height()?,
+            };
+            Ok(db.get_block_hash(block_number)?.is_some())
+        })?;
+        if is_finalised {
+            return Ok(());
+        }
+        self.wait_for_no_waiting_txes().await
+    }
+
+    /// Runs a read on a snapshot of the state at the given block, latest block is used if block_height is None
+    ///
+    /// The database is only locked while the snapshot is taken, so the read doesn't block indexing,
+    /// and changes made during the read are not visible to it
+    fn read_snapshot<F, R>(&self, block_height: Option<u64>, f: F) -> Result<R, Box<dyn Error>>
+    where
+        F: FnOnce(Brc20ProgDatabaseReader<'_>) -> Result<R, Box<dyn Error>>,
+    {
+        let view;
+        let snapshot = {
+            let db = self.db.read();
+            if let Some(height) = block_height {
+                db.require_state_available(height)?;
+            }
+            view = db.view()?;
+            // State of blocks that are not finalised yet is not visible, latest finalised state is used
+            let latest_block_number = view.latest_block_number();
+            view.snapshot(block_height.map_or(latest_block_number, |height| {
+                height.min(latest_block_number)
+            }))
+        };
+        f(snapshot)
+    }
+
     fn validate_next_tx(
         &self,
         tx_idx: u64,
@@ -1022,6 +1860,79 @@ impl BRC20ProgEngine {
     }
 }
 
+// Result of a read call, with its trace if it was traced
+type ReadCallOutput = (ExecutionResult, Option<TraceResult>);
+
+/// Executes read calls one after the other, each call sees the changes made by the previous ones
+///
+/// Changes are kept in the cache of the EVM, so the cache can be taken back from the EVM to execute
+/// more calls on top of them. Calls can't use more than the remaining gas budget, which is reduced
+/// by the gas used by each call. Traces are only returned if tracing options are given.
+fn execute_read_calls<DB: DatabaseRef>(
+    evm: &mut BRC20Evm<CacheDB<DB>>,
+    tx_infos: &[TxInfo],
+    gas_limits: Option<&[u64]>,
+    precompile_data: &Option<PrecompileData>,
+    tracing_options: Option<&GethDebugTracingOptions>,
+    gas_budget: &mut u64,
+) -> Result<Vec<ReadCallOutput>, Box<dyn Error>>
+where
+    DB::Error: 'static,
+{
+    let mut outputs = Vec::with_capacity(tx_infos.len());
+    for (idx, tx_info) in tx_infos.iter().enumerate() {
+        if *gas_budget == 0 {
+            return Err("Gas budget of the request is used up".into());
+        }
+        let nonce = evm
+            .ctx
+            .db_ref()
+            .basic_ref(tx_info.from)?
+            .map(|x| x.nonce)
+            .unwrap_or(0);
+
+        evm.precompiles.op_return_tx_id = precompile_data
+            .as_ref()
+            .and_then(|data| data.op_return_tx_ids.get(idx).cloned())
+            .unwrap_or([0u8; 32].into())
+            .into();
+
+        let gas_limit = gas_limits
+            .and_then(|gl| gl.get(idx).cloned())
+            .unwrap_or(CONFIG.read().evm_call_gas_limit)
+            .min(*gas_budget);
+        evm.ctx().modify_tx(|tx| {
+            tx.caller = tx_info.from;
+            tx.kind = tx_info.to;
+            tx.data = tx_info.data.clone();
+            tx.value = tx_info.value;
+            tx.gas_price = tx_info.gas_price;
+            tx.nonce = tx_info.nonce.unwrap_or(nonce);
+            tx.gas_limit = gas_limit;
+        });
+        let tx = evm.ctx().tx().clone();
+
+        let (output, trace) = match tracing_options {
+            Some(options) => {
+                evm.inspector.fuse();
+                let output = evm.inspect_tx(tx)?;
+                let trace = get_trace(&evm.inspector, &output, options, evm.ctx.db_ref())?;
+                evm.ctx().db_mut().commit(output.state);
+                (output.result, Some(trace))
+            }
+            None => (evm.transact_commit(tx)?, None),
+        };
+        *gas_budget -= output.gas_used();
+        outputs.push((output, trace));
+    }
+    Ok(outputs)
+}
+
+/// Traces are returned in `callTracer` format if no options are given, same as the saved traces
+fn default_tracing_options() -> GethDebugTracingOptions {
+    GethDebugTracingOptions::call_tracer(CallConfig::default())
+}
+
 fn generate_block_hash(block_number: u64) -> B256 {
     // +1 to avoid zero hash
     let bytes = (block_number + 1).to_be_bytes();
@@ -1036,12 +1947,19 @@ fn generate_block_hash(block_number: u64) -> B256 {
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+
     use alloy::primitives::B256;
+    use alloy_rpc_types_eth::state::StateOverridesBuilder;
+    use revm::database::EmptyDB;
     use revm::primitives::TxKind;
     use tempfile::TempDir;
     use tokio::sync::OnceCell;
 
     use super::*;
+    use crate::brc20_controller::{
+        decode_brc20_balance_result, load_brc20_balance_tx, load_brc20_mint_tx,
+        BRC20_CONTROLLER_ADDRESS,
+    };
     use crate::db::Brc20ProgDatabase;
     use crate::global::INDEXER_ADDRESS;
 
@@ -1177,6 +2095,89 @@ mod tests {
         assert!(engine.get_block_by_number(2, false).unwrap().is_some());
     }
 
+    #[test]
+    fn test_abort_block() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+        let timestamp = 1622547800;
+        let tx_info = TxInfo::from_inscription(*INDEXER_ADDRESS, TxKind::Create, vec![].into());
+
+        engine.mine_blocks(2, timestamp).unwrap();
+        engine
+            .add_tx_to_block(
+                timestamp,
+                &tx_info,
+                0,
+                2,
+                B256::ZERO,
+                "finalised_inscription_id".to_string(),
+                1000,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        engine.finalise_block(timestamp, 2, B256::ZERO, 1).unwrap();
+        assert!(!engine.db.read().is_block_started());
+
+        engine
+            .add_tx_to_block(
+                timestamp,
+                &tx_info,
+                0,
+                3,
+                B256::ZERO,
+                "aborted_inscription_id".to_string(),
+                1000,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        assert!(engine.begin_new_block().is_err());
+
+        engine.abort_block().unwrap();
+
+        // Uncommitted finalised block is kept, only the started block is discarded
+        assert!(engine.require_no_waiting_txes().is_ok());
+        assert!(!engine.db.read().is_block_started());
+        assert_eq!(engine.get_latest_block_height().unwrap(), 2);
+        assert!(engine
+            .get_transaction_receipt_by_inscription_id("finalised_inscription_id".to_string())
+            .unwrap()
+            .is_some());
+        assert!(engine
+            .get_transaction_receipt_by_inscription_id("aborted_inscription_id".to_string())
+            .unwrap()
+            .is_none());
+
+        // Aborted block can be added again, with the same nonce, only one caller can start it
+        engine.begin_new_block().unwrap();
+        assert!(engine.begin_new_block().is_err());
+        let receipt = engine
+            .add_tx_to_block(
+                timestamp,
+                &tx_info,
+                0,
+                3,
+                B256::ZERO,
+                "aborted_inscription_id".to_string(),
+                1000,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        let tx = engine
+            .get_transaction_by_hash(receipt.transaction_hash.bytes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.nonce, 1u64.into());
+        engine.finalise_block(timestamp, 3, B256::ZERO, 1).unwrap();
+        assert_eq!(engine.get_latest_block_height().unwrap(), 3);
+
+        engine.commit_to_db().unwrap();
+        assert!(engine
+            .get_transaction_receipt_by_inscription_id("aborted_inscription_id".to_string())
+            .unwrap()
+            .is_some());
+    }
+
     #[test]
     fn test_mine_blocks() {
         let temp_dir = TempDir::new().unwrap();
@@ -1724,6 +2725,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decode_raw_typed_tx() {
+        use alloy::consensus::TxEip1559;
+        use alloy::eips::eip2718::Encodable2718;
+        use alloy::eips::eip2930::{AccessList, AccessListItem};
+        use alloy::network::TxSignerSync;
+        use alloy::signers::local::PrivateKeySigner;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+        CONFIG.write_fn_unchecked(|config| {
+            config.chain_id = 0x4252433230;
+        });
+
+        let signer = PrivateKeySigner::random();
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::repeat_byte(0x35),
+            storage_keys: vec![B256::repeat_byte(1)],
+        }]);
+        let sign = |chain_id: u64| {
+            let mut tx = TxEip1559 {
+                chain_id,
+                nonce: 9,
+                to: TxKind::Call(Address::repeat_byte(0x35)),
+                access_list: access_list.clone(),
+                input: vec![0xde, 0xad, 0xbe, 0xef].into(),
+                ..Default::default()
+            };
+            let signature = signer.sign_transaction_sync(&mut tx).unwrap();
+            TxEnvelope::from(tx.into_signed(signature)).encoded_2718()
+        };
+
+        let raw_tx = sign(0x4252433230);
+        let result = engine
+            .get_info_from_raw_tx(raw_tx.clone(), true)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result.tx_type, 2);
+        assert_eq!(result.nonce, Some(9));
+        assert_eq!(result.from, signer.address());
+        assert_eq!(result.to, TxKind::Call(Address::repeat_byte(0x35)));
+        assert_eq!(result.data, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(result.access_list, access_list);
+        // Typed transactions are always hashed as encoded
+        assert_eq!(result.pre_hash, Some(keccak256(&raw_tx)));
+        assert_eq!(
+            engine
+                .get_info_from_raw_tx(raw_tx.clone(), false)
+                .unwrap()
+                .unwrap()
+                .pre_hash,
+            Some(keccak256(&raw_tx))
+        );
+
+        // Transactions for other chains are ignored
+        assert!(engine
+            .get_info_from_raw_tx(sign(1), true)
+            .unwrap()
+            .is_none());
+
+        // Blob transactions are not supported
+        assert!(engine.get_info_from_raw_tx(vec![3, 0xc0], true).is_err());
+    }
+
     #[tokio::test]
     async fn test_mid_block_read_contract() {
         let temp_dir = TempDir::new().unwrap();
@@ -1746,7 +2813,9 @@ mod tests {
             )
             .unwrap();
 
-        let read_result = engine.read_contract(&tx_info, Some(0), Some(1000000)).await;
+        let read_result = engine
+            .read_contract(&tx_info, Some(0), Some(1000000), &EvmOverrides::default())
+            .await;
 
         assert!(read_result.is_err());
     }
@@ -1778,7 +2847,7 @@ mod tests {
         let read_result_finalised_clone = read_result_finalised.clone();
         tokio::spawn(async move {
             let read_result = engine_clone
-                .read_contract(&tx_info, Some(0), Some(1000000))
+                .read_contract(&tx_info, Some(0), Some(1000000), &EvmOverrides::default())
                 .await;
             let result = read_result.map_err(|e| e.to_string());
             read_result_finalised_clone.set(result).unwrap();
@@ -1792,4 +2861,395 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(10)).await;
         assert!(read_result_finalised.get().unwrap().is_ok());
     }
+
+    #[tokio::test]
+    async fn test_read_contract_at_block() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+
+        let ticker: Bytes = "test".as_bytes().to_vec().into();
+        let account = Address::from_slice([1; 20].as_ref());
+
+        engine
+            .add_tx_to_block(
+                1622547800,
+                &load_brc20_deploy_tx(),
+                0,
+                0,
+                B256::ZERO,
+                "BRC20_CONTROLLER_INIT".to_string(),
+                u64::MAX,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        engine.finalise_block(1622547800, 0, B256::ZERO, 1).unwrap();
+
+        for block_number in 1..=2 {
+            engine
+                .add_tx_to_block(
+                    1622547800,
+                    &load_brc20_mint_tx(ticker.clone(), account, U256::from(1000)),
+                    0,
+                    block_number,
+                    B256::ZERO,
+                    format!("mint_inscription_{}", block_number),
+                    1000,
+                    [0u8; 32].into(),
+                )
+                .unwrap();
+            engine
+                .finalise_block(1622547800, block_number, B256::ZERO, 1)
+                .unwrap();
+        }
+
+        let balance_tx = load_brc20_balance_tx(ticker, account);
+        for (block_height, expected_balance) in [
+            (Some(0), U256::ZERO),
+            (Some(1), U256::from(1000)),
+            (Some(2), U256::from(2000)),
+            (None, U256::from(2000)),
+        ] {
+            let result = engine
+                .read_contract(&balance_tx, block_height, None, &EvmOverrides::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                decode_brc20_balance_result(result.output.as_ref()),
+                expected_balance
+            );
+        }
+
+        // Controller is deployed in the genesis block
+        assert!(engine
+            .get_contract_bytecode(*BRC20_CONTROLLER_ADDRESS, Some(0))
+            .unwrap()
+            .is_some());
+
+        // State older than the reorg history is not available
+        engine
+            .mine_blocks(MAX_REORG_HISTORY_SIZE, 1622547800)
+            .unwrap();
+        assert!(engine
+            .read_contract(&balance_tx, Some(1), None, &EvmOverrides::default())
+            .await
+            .is_err());
+        assert!(engine
+            .get_storage_at(*BRC20_CONTROLLER_ADDRESS, U256::ZERO, Some(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_trace_block_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+
+        let ticker: Bytes = "test".as_bytes().to_vec().into();
+        let account = Address::from_slice([1; 20].as_ref());
+
+        engine
+            .add_tx_to_block(
+                1622547800,
+                &load_brc20_deploy_tx(),
+                0,
+                0,
+                B256::ZERO,
+                "BRC20_CONTROLLER_INIT".to_string(),
+                u64::MAX,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        engine.finalise_block(1622547800, 0, B256::ZERO, 1).unwrap();
+
+        let mut receipts = Vec::new();
+        for tx_idx in 0..2 {
+            receipts.push(
+                engine
+                    .add_tx_to_block(
+                        1622547800,
+                        &load_brc20_mint_tx(ticker.clone(), account, U256::from(1000)),
+                        tx_idx,
+                        1,
+                        B256::ZERO,
+                        format!("mint_inscription_{}", tx_idx),
+                        1000,
+                        [0u8; 32].into(),
+                    )
+                    .unwrap(),
+            );
+        }
+        engine.finalise_block(1622547800, 1, B256::ZERO, 2).unwrap();
+
+        // Transactions are executed again with the timestamp they were indexed with
+        for receipt in receipts.iter() {
+            assert_eq!(
+                engine
+                    .db
+                    .read()
+                    .get_tx_timestamp(receipt.transaction_hash.bytes)
+                    .unwrap(),
+                Some(1622547800u64.into())
+            );
+        }
+
+        let options = default_tracing_options();
+        let traces = engine
+            .trace_block_transactions(1, 0..=usize::MAX, &options)
+            .unwrap();
+        assert_eq!(traces.len(), 2);
+        for (trace, receipt) in traces.iter().zip(receipts.iter()) {
+            let TraceResult::Call(trace) = trace else {
+                panic!("Expected a callTracer trace");
+            };
+            assert_eq!(trace.gas_used.uint, U256::from(receipt.gas_used.uint));
+        }
+
+        // Second transaction is executed on top of the first one
+        assert_eq!(
+            engine.trace_block_transactions(1, 1..=1, &options).unwrap(),
+            vec![traces[1].clone()]
+        );
+
+        // Block 0 has no previous state
+        assert!(engine.trace_block_transactions(0, 0..=0, &options).is_err());
+    }
+
+    #[test]
+    fn test_execute_read_calls_gas_budget() {
+        let mut evm = get_read_evm(0, 0, EmptyDB::new(), &None, &EvmOverrides::default()).unwrap();
+        let tx_info = TxInfo::from_inscription(
+            Address::repeat_byte(1),
+            TxKind::Call(Address::repeat_byte(2)),
+            Bytes::new(),
+        );
+        let tx_infos = vec![tx_info.clone(), tx_info];
+
+        let mut gas_budget = 50_000;
+        let outputs =
+            execute_read_calls(&mut evm, &tx_infos, None, &None, None, &mut gas_budget).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs
+            .iter()
+            .all(|(output, trace)| output.is_success() && trace.is_none()));
+        assert_eq!(gas_budget, 50_000 - 2 * 21_000);
+
+        // Gas limit of the call is capped to the remaining budget, which is too low for a call
+        assert!(
+            execute_read_calls(&mut evm, &tx_infos[..1], None, &None, None, &mut gas_budget)
+                .is_err()
+        );
+        assert!(execute_read_calls(&mut evm, &tx_infos[..1], None, &None, None, &mut 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_contract_while_indexing() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+
+        let ticker: Bytes = "test".as_bytes().to_vec().into();
+        let account = Address::from_slice([1; 20].as_ref());
+
+        engine
+            .add_tx_to_block(
+                1622547800,
+                &load_brc20_deploy_tx(),
+                0,
+                0,
+                B256::ZERO,
+                "BRC20_CONTROLLER_INIT".to_string(),
+                u64::MAX,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        engine.finalise_block(1622547800, 0, B256::ZERO, 1).unwrap();
+
+        for block_number in 1..=2 {
+            engine
+                .add_tx_to_block(
+                    1622547800,
+                    &load_brc20_mint_tx(ticker.clone(), account, U256::from(1000)),
+                    0,
+                    block_number,
+                    B256::ZERO,
+                    format!("mint_inscription_{}", block_number),
+                    1000,
+                    [0u8; 32].into(),
+                )
+                .unwrap();
+            if block_number == 1 {
+                engine
+                    .finalise_block(1622547800, block_number, B256::ZERO, 1)
+                    .unwrap();
+            }
+        }
+
+        // Block 2 is still being indexed, reads are served from the latest finalised block without waiting
+        let balance_tx = load_brc20_balance_tx(ticker, account);
+        let result = timeout(
+            Duration::from_secs(1),
+            engine.read_contract(&balance_tx, None, None, &EvmOverrides::default()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            decode_brc20_balance_result(result.output.as_ref()),
+            U256::from(1000)
+        );
+        assert!(!engine
+            .get_logs(Some(1), Some(1), None, None)
+            .unwrap()
+            .is_empty());
+
+        engine.finalise_block(1622547800, 2, B256::ZERO, 1).unwrap();
+        let result = engine
+            .read_contract(&balance_tx, None, None, &EvmOverrides::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            decode_brc20_balance_result(result.output.as_ref()),
+            U256::from(2000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_archive_mode_reads_and_deep_reorg() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::with_archive_mode(temp_dir.path(), true).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+
+        let ticker: Bytes = "test".as_bytes().to_vec().into();
+        let account = Address::from_slice([1; 20].as_ref());
+
+        engine
+            .add_tx_to_block(
+                1622547800,
+                &load_brc20_deploy_tx(),
+                0,
+                0,
+                B256::ZERO,
+                "BRC20_CONTROLLER_INIT".to_string(),
+                u64::MAX,
+                [0u8; 32].into(),
+            )
+            .unwrap();
+        engine.finalise_block(1622547800, 0, B256::ZERO, 1).unwrap();
+
+        for block_number in 1..=2 {
+            engine
+                .add_tx_to_block(
+                    1622547800,
+                    &load_brc20_mint_tx(ticker.clone(), account, U256::from(1000)),
+                    0,
+                    block_number,
+                    B256::ZERO,
+                    format!("mint_inscription_{}", block_number),
+                    1000,
+                    [0u8; 32].into(),
+                )
+                .unwrap();
+            engine
+                .finalise_block(1622547800, block_number, B256::ZERO, 1)
+                .unwrap();
+        }
+        engine.commit_to_db().unwrap();
+        engine
+            .mine_blocks(2 * MAX_REORG_HISTORY_SIZE, 1622547800)
+            .unwrap();
+        engine.commit_to_db().unwrap();
+
+        // State older than the reorg history is still available
+        let balance_tx = load_brc20_balance_tx(ticker, account);
+        let result = engine
+            .read_contract(&balance_tx, Some(1), None, &EvmOverrides::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            decode_brc20_balance_result(result.output.as_ref()),
+            U256::from(1000)
+        );
+
+        // Reorgs deeper than the reorg history are possible
+        engine.reorg(1).unwrap();
+        assert_eq!(engine.get_latest_block_height().unwrap(), 1);
+        let result = engine
+            .read_contract(&balance_tx, None, None, &EvmOverrides::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            decode_brc20_balance_result(result.output.as_ref()),
+            U256::from(1000)
+        );
+        assert!(engine
+            .get_transaction_receipt_by_inscription_id("mint_inscription_2".to_string())
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_gas() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
+        engine.mine_blocks(1, 1622547800).unwrap();
+
+        // Calls the storage writer with all the gas, and reverts if the call fails
+        let caller = Address::repeat_byte(0x11);
+        let writer = Address::repeat_byte(0x22);
+        let caller_code = format!(
+            "6000600060006000600073{}5af1602857600080fd5b00",
+            hex::encode(writer)
+        );
+        // Writes to storage slots 0 and 1
+        let writer_code = "6001600055600160015500";
+        let reverter = Address::repeat_byte(0x33);
+        let overrides = EvmOverrides::new(
+            Some(
+                StateOverridesBuilder::default()
+                    .with_code(caller, Bytes::from(hex::decode(caller_code).unwrap()))
+                    .with_code(writer, Bytes::from(hex::decode(writer_code).unwrap()))
+                    .with_code(reverter, Bytes::from(vec![0x60, 0x00, 0x80, 0xfd]))
+                    .build(),
+            ),
+            None,
+        );
+        let account = Address::repeat_byte(1);
+        let tx_info = TxInfo::from_inscription(account, TxKind::Call(caller), Bytes::new());
+
+        let (estimated_gas, result) = engine
+            .estimate_gas(&tx_info, None, 1_000_000, &overrides)
+            .await
+            .unwrap();
+        assert!(result.status);
+        assert!(estimated_gas >= result.gas_used);
+        assert!(estimated_gas < result.gas_used + 2 * GAS_PER_BYTE);
+
+        // Estimated gas is enough for the call, and the call fails without the gas kept for the writer
+        assert!(
+            engine
+                .read_contract(&tx_info, None, Some(estimated_gas), &overrides)
+                .await
+                .unwrap()
+                .status
+        );
+        assert!(
+            !engine
+                .read_contract(&tx_info, None, Some(result.gas_used), &overrides)
+                .await
+                .unwrap()
+                .status
+        );
+
+        // Failed calls are returned with the upper gas limit
+        let revert_tx_info =
+            TxInfo::from_inscription(account, TxKind::Call(reverter), Bytes::new());
+        let (estimated_gas, result) = engine
+            .estimate_gas(&revert_tx_info, None, 1_000_000, &overrides)
+            .await
+            .unwrap();
+        assert!(!result.status);
+        assert_eq!(estimated_gas, 1_000_000);
+    }
 }
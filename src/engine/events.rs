@@ -0,0 +1,18 @@
+use crate::db::types::{BlockResponseED, LogED};
+
+/// Number of events kept for subscribers that are behind, slower subscribers miss events
+pub const CHAIN_EVENT_CAPACITY: usize = 1024;
+
+/// Changes to the chain, sent to subscribers as blocks are finalised and reorged
+#[derive(Clone, Debug)]
+pub enum ChainEvent {
+    /// A block is finalised, along with the logs emitted in it
+    NewBlock {
+        block: Box<BlockResponseED>,
+        logs: Vec<LogED>,
+    },
+    /// Blocks are removed by a reorg, along with the logs emitted in them
+    ///
+    /// Removed logs are marked with removed: true
+    Reorg { removed_logs: Vec<LogED> },
+}
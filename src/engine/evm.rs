@@ -1,41 +1,64 @@
-use alloy::primitives::{B256, U256};
+use std::error::Error;
+
+use alloy::primitives::{hex, B256, U256};
+use alloy_rpc_types_eth::state::StateOverride;
+use alloy_rpc_types_eth::BlockOverrides;
+use alloy_rpc_types_trace::geth::{
+    CallConfig, FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerType,
+    GethDebugTracingOptions,
+};
+use revm::context::result::ResultAndState;
 use revm::context::{BlockEnv, CfgEnv, Evm, TxEnv};
 use revm::context_interface::block::BlobExcessGasAndPrice;
+use revm::database::CacheDB;
 use revm::handler::instructions::EthInstructions;
 use revm::handler::EthFrame;
 use revm::interpreter::interpreter::EthInterpreter;
-use revm::{Context, Journal, JournalEntry};
+use revm::state::Bytecode;
+use revm::{Context, Database, DatabaseRef, Journal, JournalEntry};
 use revm_inspectors::tracing::{TracingInspector, TracingInspectorConfig};
 
-use crate::db::Brc20ProgDatabase;
+use crate::api::types::TraceResult;
+use crate::db::types::TraceED;
 use crate::engine::hardforks::get_evm_spec;
 use crate::engine::precompiles::BRC20Precompiles;
 use crate::global::CONFIG;
 use crate::types::PrecompileData;
 
-pub fn get_evm(
+/// EVM with BRC20 precompiles, using the tracing inspector to record traces
+pub type BRC20Evm<DB> = Evm<
+    Context<BlockEnv, TxEnv, CfgEnv, DB>,
+    TracingInspector,
+    EthInstructions<EthInterpreter, Context<BlockEnv, TxEnv, CfgEnv, DB>>,
+    BRC20Precompiles,
+    EthFrame<EthInterpreter>,
+>;
+
+/// State and block overrides for calls, in the format used by Geth
+#[derive(Debug, Clone, Default)]
+pub struct EvmOverrides {
+    pub state: Option<StateOverride>,
+    pub block: Option<BlockOverrides>,
+}
+
+impl EvmOverrides {
+    pub fn new(state: Option<StateOverride>, block: Option<BlockOverrides>) -> Self {
+        Self { state, block }
+    }
+}
+
+pub fn get_evm<DB: Database>(
     block_number: u64,
     block_hash: B256,
     timestamp: u64,
-    db: Brc20ProgDatabase,
+    db: DB,
     gas_limit: Option<u64>,
     current_op_return_tx_id: B256,
     precompile_data: &Option<PrecompileData>,
-) -> Evm<
-    Context<BlockEnv, TxEnv, CfgEnv, Brc20ProgDatabase>,
-    TracingInspector,
-    EthInstructions<EthInterpreter, Context<BlockEnv, TxEnv, CfgEnv, Brc20ProgDatabase>>,
-    BRC20Precompiles,
-    EthFrame<EthInterpreter>,
-> {
+) -> BRC20Evm<DB> {
     let evm_spec = get_evm_spec(block_number);
-    let mut ctx: Context<
-        BlockEnv,
-        TxEnv,
-        CfgEnv,
-        Brc20ProgDatabase,
-        Journal<Brc20ProgDatabase, JournalEntry>,
-    > = Context::new(db, evm_spec);
+    let mut ctx: Context<BlockEnv, TxEnv, CfgEnv, DB, Journal<DB, JournalEntry>> =
+        Context::new(db, evm_spec);
 
     ctx.cfg.chain_id = CONFIG.read().chain_id.into();
     ctx.cfg.spec = evm_spec;
@@ -61,3 +84,262 @@ pub fn get_evm(
         BRC20Precompiles::new(evm_spec.into(), current_op_return_tx_id, precompile_data),
     )
 }
+
+/// Returns an EVM for calls that are not committed, with the overrides applied over the database
+///
+/// Overridden state is kept in a cache in front of the database, so the database is never modified
+pub fn get_read_evm<DB: DatabaseRef>(
+    block_number: u64,
+    timestamp: u64,
+    db: DB,
+    precompile_data: &Option<PrecompileData>,
+    overrides: &EvmOverrides,
+) -> Result<BRC20Evm<CacheDB<DB>>, Box<dyn Error>>
+where
+    DB::Error: 'static,
+{
+    get_cached_read_evm(
+        block_number,
+        timestamp,
+        CacheDB::new(db),
+        precompile_data,
+        overrides,
+    )
+}
+
+/// Returns an EVM for calls that are not committed, with the overrides applied over the cache
+///
+/// Changes committed by the EVM are kept in the cache, so the cache can be taken back from the
+/// EVM to simulate more calls on top of them
+pub fn get_cached_read_evm<DB: DatabaseRef>(
+    block_number: u64,
+    timestamp: u64,
+    mut db: CacheDB<DB>,
+    precompile_data: &Option<PrecompileData>,
+    overrides: &EvmOverrides,
+) -> Result<BRC20Evm<CacheDB<DB>>, Box<dyn Error>>
+where
+    DB::Error: 'static,
+{
+    if let Some(state) = &overrides.state {
+        apply_state_overrides(&mut db, state)?;
+    }
+
+    let Some(block) = &overrides.block else {
+        let mut evm = get_evm(
+            block_number,
+            B256::ZERO,
+            timestamp,
+            db,
+            None,
+            B256::ZERO,
+            precompile_data,
+        );
+        // Nonces of calls are not checked against the sender, same as Geth
+        evm.ctx.cfg.disable_nonce_check = true;
+        return Ok(evm);
+    };
+
+    if block.base_fee.is_some_and(|base_fee| !base_fee.is_zero()) {
+        return Err("Base fee can't be overridden, transactions don't pay for gas".into());
+    }
+    for (number, hash) in block.block_hash.iter().flatten() {
+        db.cache.block_hashes.insert(U256::from(*number), *hash);
+    }
+
+    // Block number is overridden before creating the EVM, as it selects the hardfork
+    let block_number = match block.number {
+        Some(number) => number.try_into().map_err(|_| "Block number is too large")?,
+        None => block_number,
+    };
+    let mut evm = get_evm(
+        block_number,
+        B256::ZERO,
+        block.time.unwrap_or(timestamp),
+        db,
+        block.gas_limit,
+        B256::ZERO,
+        precompile_data,
+    );
+    if let Some(coinbase) = block.coinbase {
+        evm.ctx.block.beneficiary = coinbase;
+    }
+    if let Some(difficulty) = block.difficulty {
+        evm.ctx.block.difficulty = difficulty;
+    }
+    if let Some(random) = block.random {
+        evm.ctx.block.prevrandao = Some(random);
+    }
+    evm.ctx.cfg.disable_nonce_check = true;
+    Ok(evm)
+}
+
+fn apply_state_overrides<DB: DatabaseRef>(
+    db: &mut CacheDB<DB>,
+    overrides: &StateOverride,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::Error: 'static,
+{
+    for (address, account_override) in overrides {
+        if account_override.move_precompile_to.is_some() {
+            return Err("movePrecompileToAddress override is not supported".into());
+        }
+        if account_override.state.is_some() && account_override.state_diff.is_some() {
+            return Err(
+                format!("Account {} has both state and stateDiff overrides", address).into(),
+            );
+        }
+
+        let mut info = db.load_account(*address)?.info.clone();
+        if let Some(balance) = account_override.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = account_override.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &account_override.code {
+            info.set_code(Bytecode::new_raw(code.clone()));
+        }
+        db.insert_account_info(*address, info);
+
+        if let Some(state) = &account_override.state {
+            db.replace_account_storage(
+                *address,
+                state
+                    .iter()
+                    .map(|(slot, value)| ((*slot).into(), (*value).into()))
+                    .collect(),
+            )?;
+        }
+        for (slot, value) in account_override.state_diff.iter().flatten() {
+            db.insert_account_storage(*address, (*slot).into(), (*value).into())?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the call trace recorded by the inspector, in `callTracer` format
+pub fn get_call_trace(inspector: &TracingInspector, gas_used: u64) -> TraceED {
+    inspector
+        .geth_builder()
+        .geth_call_traces(
+            CallConfig {
+                only_top_call: Some(false),
+                with_log: Some(true),
+            },
+            gas_used,
+        )
+        .into()
+}
+
+/// Returns the tracer selected in the options, struct logger is used if no tracer is given
+fn get_tracer(
+    options: &GethDebugTracingOptions,
+) -> Result<Option<GethDebugBuiltInTracerType>, Box<dyn Error>> {
+    match &options.tracer {
+        None => Ok(None),
+        Some(GethDebugTracerType::BuiltInTracer(
+            tracer @ (GethDebugBuiltInTracerType::CallTracer
+            | GethDebugBuiltInTracerType::PreStateTracer
+            | GethDebugBuiltInTracerType::FourByteTracer),
+        )) => Ok(Some(*tracer)),
+        Some(tracer) => Err(format!(
+            "Unsupported tracer: {}, supported tracers are callTracer, prestateTracer and 4byteTracer",
+            tracer.as_str()
+        )
+        .into()),
+    }
+}
+
+/// Returns the inspector config needed to record the trace for the tracer selected in the options
+pub fn get_tracing_inspector_config(
+    options: &GethDebugTracingOptions,
+) -> Result<TracingInspectorConfig, Box<dyn Error>> {
+    Ok(match get_tracer(options)? {
+        None => TracingInspectorConfig::from_geth_config(&options.config),
+        Some(GethDebugBuiltInTracerType::PreStateTracer) => {
+            TracingInspectorConfig::from_geth_prestate_config(
+                &options.tracer_config.clone().into_pre_state_config()?,
+            )
+        }
+        Some(_) => TracingInspectorConfig::none(),
+    })
+}
+
+/// Returns the trace recorded by the inspector, in the format of the tracer selected in the options
+///
+/// The database should have the state before the transaction, it's used for the prestate tracer
+pub fn get_trace<DB: DatabaseRef>(
+    inspector: &TracingInspector,
+    result_and_state: &ResultAndState,
+    options: &GethDebugTracingOptions,
+    db: DB,
+) -> Result<TraceResult, Box<dyn Error>> {
+    let result = &result_and_state.result;
+    Ok(match get_tracer(options)? {
+        None => TraceResult::StructLog(inspector.geth_builder().geth_traces(
+            result.gas_used(),
+            result.output().cloned().unwrap_or_default(),
+            options.config,
+        )),
+        Some(GethDebugBuiltInTracerType::PreStateTracer) => TraceResult::PreState(
+            inspector
+                .geth_builder()
+                .geth_prestate_traces(
+                    result_and_state,
+                    &options.tracer_config.clone().into_pre_state_config()?,
+                    db,
+                )
+                .map_err(|error| format!("Failed to read prestate: {}", error))?,
+        ),
+        Some(GethDebugBuiltInTracerType::FourByteTracer) => {
+            TraceResult::FourByte(get_four_byte_trace(inspector))
+        }
+        Some(_) => get_call_trace_result(get_call_trace(inspector, result.gas_used()), options)?,
+    })
+}
+
+/// Returns a saved trace in the format selected in the options
+///
+/// Only `callTracer` traces are saved, None is returned for other tracers, as they need the
+/// transaction to be executed again
+pub fn get_saved_trace(
+    trace: TraceED,
+    options: &GethDebugTracingOptions,
+) -> Result<Option<TraceResult>, Box<dyn Error>> {
+    match get_tracer(options)? {
+        Some(GethDebugBuiltInTracerType::CallTracer) => {
+            get_call_trace_result(trace, options).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn get_call_trace_result(
+    mut trace: TraceED,
+    options: &GethDebugTracingOptions,
+) -> Result<TraceResult, Box<dyn Error>> {
+    let config = options.tracer_config.clone().into_call_config()?;
+    if config.only_top_call.unwrap_or_default() {
+        trace.calls.clear();
+    }
+    Ok(TraceResult::Call(Box::new(trace)))
+}
+
+/// Counts the calls for each selector and calldata size, in `4byteTracer` format
+fn get_four_byte_trace(inspector: &TracingInspector) -> FourByteFrame {
+    let mut frame = FourByteFrame::default();
+    for node in inspector.traces().nodes() {
+        if node.trace.kind.is_any_create() || node.trace.data.len() < 4 {
+            continue;
+        }
+        let key = format!(
+            "{}-{}",
+            hex::encode_prefixed(&node.trace.data[..4]),
+            node.trace.data.len() - 4
+        );
+        *frame.0.entry(key).or_default() += 1;
+    }
+    frame
+}
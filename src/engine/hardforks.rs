@@ -39,3 +39,16 @@ pub fn use_rlp_hash_for_tx_hash(block_number: u64) -> bool {
         _ => true,
     }
 }
+
+const TYPED_TX_ACTIVATION_HEIGHT_MAINNET: Option<u64> = None; // Not scheduled yet
+const TYPED_TX_ACTIVATION_HEIGHT_SIGNET: Option<u64> = Some(0); // Always allow typed transactions on Signet, reindexing required
+
+pub fn allow_typed_transactions(block_number: u64) -> bool {
+    let network = get_bitcoin_network();
+    let activation_height = match network {
+        Network::Bitcoin => TYPED_TX_ACTIVATION_HEIGHT_MAINNET,
+        Network::Signet => TYPED_TX_ACTIVATION_HEIGHT_SIGNET,
+        _ => return true,
+    };
+    activation_height.is_some_and(|height| block_number >= height)
+}
@@ -1,11 +1,14 @@
 #![cfg(feature = "server")]
 
 mod engine;
+mod events;
 mod evm;
 mod hardforks;
 mod precompiles;
 mod utils;
 
-pub use engine::BRC20ProgEngine;
+pub use engine::{BRC20ProgEngine, SimulateBlockCalls};
+pub use events::ChainEvent;
+pub use evm::EvmOverrides;
 pub use precompiles::validate_bitcoin_rpc_status;
 pub use utils::{get_evm_address_from_pkscript, TxInfo};
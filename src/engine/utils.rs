@@ -1,12 +1,13 @@
 use std::error::Error;
 use std::time::{Duration, Instant};
 
-use alloy::consensus::TxLegacy;
+use alloy::consensus::Transaction;
+use alloy::eips::eip2930::AccessList;
 use alloy::primitives::{keccak256, Address, Bytes, B256, U256};
 use revm::context::result::{ExecutionResult, Output};
 use revm::primitives::TxKind;
 
-use crate::global::GAS_PER_BYTE;
+use crate::global::{Brc20ProgConfig, GAS_PER_BYTE};
 
 /// This struct is used to store the unfinalised block information
 pub struct LastBlockInfo {
@@ -33,13 +34,45 @@ impl LastBlockInfo {
     }
 }
 
+/// This struct is used to store the information since the last commit, to decide when to commit
+pub struct LastCommitInfo {
+    pub finalised_block_count: u64,
+    pub time: Instant,
+}
+
+impl LastCommitInfo {
+    pub fn new() -> Self {
+        LastCommitInfo {
+            finalised_block_count: 0,
+            time: Instant::now(),
+        }
+    }
+
+    /// Whether changes should be committed according to the commit policy in the config
+    ///
+    /// Changes are committed if any of the enabled conditions is met, cache entries is the number of
+    /// cached entries that are not committed yet
+    pub fn should_commit(&self, config: &Brc20ProgConfig, cache_entries: usize) -> bool {
+        (config.commit_every_blocks != 0
+            && self.finalised_block_count >= config.commit_every_blocks)
+            || (config.commit_interval_seconds != 0
+                && self.time.elapsed() >= Duration::from_secs(config.commit_interval_seconds))
+            || (config.commit_cache_entries_limit != 0
+                && cache_entries as u64 > config.commit_cache_entries_limit)
+    }
+}
+
 #[derive(Clone)]
 pub struct TxInfo {
     pub from: Address,
     pub to: TxKind,
     pub data: Bytes,
+    pub value: U256,
+    pub gas_price: u128,
     pub nonce: Option<u64>,
     pub pre_hash: Option<B256>,
+    pub tx_type: u8,
+    pub access_list: AccessList,
     pub v: u8,
     pub r: U256,
     pub s: U256,
@@ -51,8 +84,12 @@ impl TxInfo {
             from,
             to,
             data,
+            value: U256::ZERO,
+            gas_price: 0,
             nonce: None,
             pre_hash: None,
+            tx_type: 0,
+            access_list: AccessList::default(),
             v: 0,
             r: U256::ZERO,
             s: U256::ZERO,
@@ -61,7 +98,7 @@ impl TxInfo {
 
     pub fn from_raw_transaction(
         from: Address,
-        raw_tx: TxLegacy,
+        raw_tx: &impl Transaction,
         tx_hash: B256,
         v: u8,
         r: U256,
@@ -69,7 +106,7 @@ impl TxInfo {
     ) -> Self {
         TxInfo {
             from,
-            to: match raw_tx.to.into_to() {
+            to: match raw_tx.kind().into_to() {
                 Some(to) => {
                     if to == Address::ZERO {
                         TxKind::Create
@@ -79,9 +116,13 @@ impl TxInfo {
                 }
                 None => TxKind::Create,
             },
-            data: raw_tx.input,
-            nonce: Some(raw_tx.nonce),
+            data: raw_tx.input().clone(),
+            value: U256::ZERO,
+            gas_price: 0,
+            nonce: Some(raw_tx.nonce()),
             pre_hash: Some(tx_hash),
+            tx_type: raw_tx.ty(),
+            access_list: raw_tx.access_list().cloned().unwrap_or_default(),
             v,
             r,
             s,
@@ -94,6 +135,8 @@ impl TxInfo {
         data: Bytes,
         nonce: u64,
         tx_hash: B256,
+        tx_type: u8,
+        access_list: AccessList,
         v: u8,
         r: U256,
         s: U256,
@@ -102,8 +145,12 @@ impl TxInfo {
             from,
             to,
             data,
+            value: U256::ZERO,
+            gas_price: 0,
             nonce: Some(nonce),
             pre_hash: Some(tx_hash),
+            tx_type,
+            access_list,
             v,
             r,
             s,
@@ -173,6 +220,28 @@ pub fn get_contract_address(result: &ExecutionResult) -> Option<Address> {
     }
 }
 
+/// Find the first index that doesn't match, assuming every index after a mismatch doesn't match either
+///
+/// len: usize - the number of indexes to search
+/// matches: checks if the given index matches
+///
+/// Returns: Option<usize> - the first index that doesn't match, None if all indexes match
+pub fn first_mismatch<F>(len: usize, mut matches: F) -> Result<Option<usize>, Box<dyn Error>>
+where
+    F: FnMut(usize) -> Result<bool, Box<dyn Error>>,
+{
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if matches(mid)? {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok((low < len).then_some(low))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +255,62 @@ mod tests {
             "0x7f217045127859b40ef1a27a5bfe73aa16687467"
         );
     }
+
+    #[test]
+    fn test_should_commit() {
+        let disabled = Brc20ProgConfig {
+            commit_every_blocks: 0,
+            commit_interval_seconds: 0,
+            commit_cache_entries_limit: 0,
+            ..Brc20ProgConfig::from_env()
+        };
+        let mut info = LastCommitInfo::new();
+        info.finalised_block_count = 1000;
+        info.time = Instant::now() - Duration::from_secs(1000);
+        assert!(!info.should_commit(&disabled, 1000));
+
+        let every_blocks = Brc20ProgConfig {
+            commit_every_blocks: 10,
+            ..disabled.clone()
+        };
+        let mut info = LastCommitInfo::new();
+        info.finalised_block_count = 9;
+        assert!(!info.should_commit(&every_blocks, 0));
+        info.finalised_block_count = 10;
+        assert!(info.should_commit(&every_blocks, 0));
+
+        let interval = Brc20ProgConfig {
+            commit_interval_seconds: 60,
+            ..disabled.clone()
+        };
+        let mut info = LastCommitInfo::new();
+        assert!(!info.should_commit(&interval, 0));
+        info.time = Instant::now() - Duration::from_secs(60);
+        assert!(info.should_commit(&interval, 0));
+
+        let cache_entries_limit = Brc20ProgConfig {
+            commit_cache_entries_limit: 100,
+            ..disabled
+        };
+        let info = LastCommitInfo::new();
+        assert!(!info.should_commit(&cache_entries_limit, 100));
+        assert!(info.should_commit(&cache_entries_limit, 101));
+    }
+
+    #[test]
+    fn test_first_mismatch() {
+        for len in 0..20 {
+            for first in 0..=len {
+                let mut checked = 0;
+                let result = first_mismatch(len, |index| {
+                    checked += 1;
+                    Ok(index < first)
+                })
+                .unwrap();
+                assert_eq!(result, (first < len).then_some(first));
+                assert!(checked <= 5);
+            }
+        }
+        assert!(first_mismatch(10, |_| Err("lookup failed".into())).is_err());
+    }
 }
@@ -6,7 +6,7 @@ use crate::global::shared_data::SharedData;
 
 lazy_static::lazy_static! {
     pub(crate) static ref DB_VERSION_KEY: String = "DB_VERSION".to_string();
-    pub(crate) static ref DB_VERSION: u32 = 7;
+    pub(crate) static ref DB_VERSION: u32 = 11;
 
     pub(crate) static ref PROTOCOL_VERSION_KEY: String = "PROTOCOL_VERSION".to_string();
     pub(crate) static ref PROTOCOL_VERSION: u32 = 2;
@@ -14,6 +14,9 @@ lazy_static::lazy_static! {
     static ref DB_PATH_KEY: String = "BRC20_PROG_DB_PATH".to_string();
     static ref DB_PATH_DEFAULT: String = "target/db".to_string();
 
+    pub(crate) static ref ARCHIVE_MODE_KEY: String = "ARCHIVE_MODE".to_string();
+    static ref ARCHIVE_MODE_DEFAULT: bool = false;
+
     static ref BRC20_PROG_RPC_SERVER_ENABLE_AUTH_KEY: String = "BRC20_PROG_RPC_SERVER_ENABLE_AUTH".to_string();
     static ref BRC20_PROG_RPC_SERVER_ENABLE_AUTH_DEFAULT: bool = false;
 
@@ -50,6 +53,21 @@ lazy_static::lazy_static! {
     static ref BATCH_REQUEST_LIMIT_KEY: String = "BATCH_REQUEST_LIMIT".to_string();
     static ref BATCH_REQUEST_LIMIT_DEFAULT: u32 = 50; // 50 requests
 
+    static ref GET_LOGS_RESULT_LIMIT_KEY: String = "GET_LOGS_RESULT_LIMIT".to_string();
+    static ref GET_LOGS_RESULT_LIMIT_DEFAULT: u32 = 10_000; // 10K logs
+
+    static ref GET_LOGS_SCAN_BLOCK_LIMIT_KEY: String = "GET_LOGS_SCAN_BLOCK_LIMIT".to_string();
+    static ref GET_LOGS_SCAN_BLOCK_LIMIT_DEFAULT: u64 = 1_000; // 1K blocks
+
+    static ref COMMIT_EVERY_BLOCKS_KEY: String = "COMMIT_EVERY_BLOCKS".to_string();
+    static ref COMMIT_EVERY_BLOCKS_DEFAULT: u64 = 0; // Disabled
+
+    static ref COMMIT_INTERVAL_SECONDS_KEY: String = "COMMIT_INTERVAL_SECONDS".to_string();
+    static ref COMMIT_INTERVAL_SECONDS_DEFAULT: u64 = 0; // Disabled
+
+    static ref COMMIT_CACHE_ENTRIES_LIMIT_KEY: String = "COMMIT_CACHE_ENTRIES_LIMIT".to_string();
+    static ref COMMIT_CACHE_ENTRIES_LIMIT_DEFAULT: u64 = 0; // Disabled
+
     pub static ref CARGO_PKG_VERSION: String = {
         let version = env!("CARGO_PKG_VERSION");
         if version.is_empty() {
@@ -84,6 +102,9 @@ pub const GAS_PER_BYTE: u64 = 12000; // 12K gas per byte
 pub const MAX_FUTURE_TRANSACTION_NONCES: u64 = 10; // Maximum future transaction nonces allowed
 pub const MAX_FUTURE_TRANSACTION_BLOCKS: u64 = 10; // Maximum future transaction block depth allowed
 
+pub const MAX_SIMULATE_BLOCKS: usize = 256; // Maximum number of blocks simulated in eth_simulateV1
+pub const MAX_SIMULATE_CALLS: usize = 1_000; // Maximum number of calls simulated in eth_simulateV1
+
 pub const GAS_PER_OP_RETURN_TX_ID: u64 = 40; // 40 gas for fetching the op return tx id
 pub const GAS_PER_BITCOIN_RPC_CALL: u64 = 400000; // 400K gas per Bitcoin RPC call
 pub const GAS_PER_BIP_322_VERIFY: u64 = 20000; // 20K gas per BIP-322 verify call
@@ -128,6 +149,9 @@ pub struct Brc20ProgConfig {
     pub fail_on_bitcoin_rpc_error: bool,
     /// Database path
     pub db_path: String,
+    /// Whether to keep every state change, enabling historical reads and reorgs deeper than MAX_REORG_HISTORY_SIZE
+    /// This can only be set for a new database, and uses considerably more disk space
+    pub archive_mode: bool,
 
     /// Max request size in bytes
     pub max_request_size: u32,
@@ -135,6 +159,19 @@ pub struct Brc20ProgConfig {
     pub max_response_size: u32,
     /// Batch request limit (0 for unlimited)
     pub batch_request_limit: u32,
+    /// Max number of logs returned by a single eth_getLogs request (0 for unlimited)
+    pub get_logs_result_limit: u32,
+    /// Max block range of an eth_getLogs request without an address or first topic, as every transaction in
+    /// the range is read for these (0 for unlimited)
+    pub get_logs_scan_block_limit: u64,
+
+    /// Commit changes to the database after this many finalised blocks (0 to disable)
+    pub commit_every_blocks: u64,
+    /// Commit changes to the database when a block is finalised this many seconds after the last commit (0 to disable)
+    pub commit_interval_seconds: u64,
+    /// Commit changes to the database when the number of cached entries exceeds this limit (0 to disable)
+    /// Every cached entry is a value changed since the last commit, along with its recent history
+    pub commit_cache_entries_limit: u64,
 }
 
 impl Default for Brc20ProgConfig {
@@ -164,6 +201,9 @@ impl Brc20ProgConfig {
     /// * `max_request_size` - Max request size in bytes
     /// * `max_response_size` - Max response size in bytes
     /// * `batch_request_limit` - Batch request limit (0 for unlimited)
+    ///
+    /// Other options are set to their defaults, and can be changed on the returned config, e.g.
+    /// `archive_mode`, `get_logs_result_limit` and the commit policy.
     pub fn new(
         brc20_prog_rpc_server_url: String,
         brc20_prog_rpc_server_enable_auth: bool,
@@ -196,9 +236,15 @@ impl Brc20ProgConfig {
             chain_id,
             fail_on_bitcoin_rpc_error,
             db_path,
+            archive_mode: *ARCHIVE_MODE_DEFAULT,
             max_request_size,
             max_response_size,
             batch_request_limit,
+            get_logs_result_limit: *GET_LOGS_RESULT_LIMIT_DEFAULT,
+            get_logs_scan_block_limit: *GET_LOGS_SCAN_BLOCK_LIMIT_DEFAULT,
+            commit_every_blocks: *COMMIT_EVERY_BLOCKS_DEFAULT,
+            commit_interval_seconds: *COMMIT_INTERVAL_SECONDS_DEFAULT,
+            commit_cache_entries_limit: *COMMIT_CACHE_ENTRIES_LIMIT_DEFAULT,
         }
     }
 
@@ -208,6 +254,7 @@ impl Brc20ProgConfig {
     ///
     /// List of environment variables read:
     /// * `BRC20_PROG_DB_PATH` - The path to the database folder (Default: "target/db")
+    /// * `ARCHIVE_MODE` - Whether to keep every state change for historical reads and deep reorgs (Default: false)
     /// * `BRC20_PROG_RPC_SERVER_URL` - The URL of the BRC20 Prog RPC server (Default: "127.0.0.1:18545")
     /// * `BRC20_PROG_RPC_SERVER_ENABLE_AUTH` - Whether to enable authentication for the BRC20 Prog RPC server (Default: false)
     /// * `BRC20_PROG_RPC_SERVER_USER` - The username for the BRC20 Prog RPC server, if authentication is enabled
@@ -222,6 +269,11 @@ impl Brc20ProgConfig {
     /// * `MAX_REQUEST_SIZE` - Max request size in bytes (Default: 10MB)
     /// * `MAX_RESPONSE_SIZE` - Max response size in bytes (Default: 100MB)
     /// * `BATCH_REQUEST_LIMIT` - Batch request limit (0 for unlimited) (Default: 50)
+    /// * `GET_LOGS_RESULT_LIMIT` - Max number of logs returned by eth_getLogs (0 for unlimited) (Default: 10000)
+    /// * `GET_LOGS_SCAN_BLOCK_LIMIT` - Max block range of eth_getLogs without an address or first topic (0 for unlimited) (Default: 1000)
+    /// * `COMMIT_EVERY_BLOCKS` - Commit changes after this many finalised blocks (0 to disable) (Default: 0)
+    /// * `COMMIT_INTERVAL_SECONDS` - Commit changes when this many seconds passed since the last commit (0 to disable) (Default: 0)
+    /// * `COMMIT_CACHE_ENTRIES_LIMIT` - Commit changes when the number of cached entries exceeds this limit (0 to disable) (Default: 0)
     /// # Returns
     /// A new instance of `Brc20ProgConfig` with the configuration values read from environment variables.
     pub fn from_env() -> Self {
@@ -260,6 +312,9 @@ impl Brc20ProgConfig {
                 .map(|x| x == "true")
                 .unwrap_or(*FAIL_ON_BITCOIN_RPC_ERROR_DEFAULT),
             db_path: env::var(&*DB_PATH_KEY).unwrap_or(DB_PATH_DEFAULT.clone()),
+            archive_mode: env::var(&*ARCHIVE_MODE_KEY)
+                .map(|x| x == "true")
+                .unwrap_or(*ARCHIVE_MODE_DEFAULT),
 
             max_request_size: env::var(&*MAX_REQUEST_SIZE_KEY)
                 .map(|x| x.parse::<u32>().unwrap_or(*MAX_REQUEST_SIZE_DEFAULT))
@@ -270,6 +325,28 @@ impl Brc20ProgConfig {
             batch_request_limit: env::var(&*BATCH_REQUEST_LIMIT_KEY)
                 .map(|x| x.parse::<u32>().unwrap_or(*BATCH_REQUEST_LIMIT_DEFAULT))
                 .unwrap_or(*BATCH_REQUEST_LIMIT_DEFAULT),
+            get_logs_result_limit: env::var(&*GET_LOGS_RESULT_LIMIT_KEY)
+                .map(|x| x.parse::<u32>().unwrap_or(*GET_LOGS_RESULT_LIMIT_DEFAULT))
+                .unwrap_or(*GET_LOGS_RESULT_LIMIT_DEFAULT),
+            get_logs_scan_block_limit: env::var(&*GET_LOGS_SCAN_BLOCK_LIMIT_KEY)
+                .map(|x| {
+                    x.parse::<u64>()
+                        .unwrap_or(*GET_LOGS_SCAN_BLOCK_LIMIT_DEFAULT)
+                })
+                .unwrap_or(*GET_LOGS_SCAN_BLOCK_LIMIT_DEFAULT),
+
+            commit_every_blocks: env::var(&*COMMIT_EVERY_BLOCKS_KEY)
+                .map(|x| x.parse::<u64>().unwrap_or(*COMMIT_EVERY_BLOCKS_DEFAULT))
+                .unwrap_or(*COMMIT_EVERY_BLOCKS_DEFAULT),
+            commit_interval_seconds: env::var(&*COMMIT_INTERVAL_SECONDS_KEY)
+                .map(|x| x.parse::<u64>().unwrap_or(*COMMIT_INTERVAL_SECONDS_DEFAULT))
+                .unwrap_or(*COMMIT_INTERVAL_SECONDS_DEFAULT),
+            commit_cache_entries_limit: env::var(&*COMMIT_CACHE_ENTRIES_LIMIT_KEY)
+                .map(|x| {
+                    x.parse::<u64>()
+                        .unwrap_or(*COMMIT_CACHE_ENTRIES_LIMIT_DEFAULT)
+                })
+                .unwrap_or(*COMMIT_CACHE_ENTRIES_LIMIT_DEFAULT),
         }
     }
 }
@@ -305,12 +382,12 @@ mod tests {
     #[cfg(feature = "server")]
     fn valid_config() -> Brc20ProgConfig {
         Brc20ProgConfig::new(
-            "127.0.0.1:18545".to_string(), // rpc url
-            false,                          // enable auth
-            None,                           // user
-            None,                           // password
-            false,                          // record traces
-            1_000_000_000,                  // call gas limit
+            "127.0.0.1:18545".to_string(),        // rpc url
+            false,                                // enable auth
+            None,                                 // user
+            None,                                 // password
+            false,                                // record traces
+            1_000_000_000,                        // call gas limit
             "http://localhost:38332".to_string(), // bitcoin rpc url
             "user".to_string(),
             "pass".to_string(),
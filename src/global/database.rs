@@ -3,50 +3,88 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::path::Path;
+use std::sync::Arc;
 
-use rocksdb::{Options, DB};
+use rocksdb::{WriteBatch, DB};
 
+use crate::db::database::{column_family, open_database, write_batch};
 use crate::db::types::{Decode, Encode};
+use crate::db::Brc20ProgDatabase;
 use crate::global::{
-    Brc20ProgConfig, BITCOIN_RPC_NETWORK_KEY, DB_VERSION, DB_VERSION_KEY, EVM_RECORD_TRACES_KEY,
-    PROTOCOL_VERSION, PROTOCOL_VERSION_KEY,
+    Brc20ProgConfig, ARCHIVE_MODE_KEY, BITCOIN_RPC_NETWORK_KEY, DB_VERSION, DB_VERSION_KEY,
+    EVM_RECORD_TRACES_KEY, PROTOCOL_VERSION, PROTOCOL_VERSION_KEY,
 };
 
+// Values set are kept in the cache until they are flushed or written with a batch, None marks a removed value
 pub struct ConfigDatabase {
-    db: DB,
-    cache: HashMap<String, String>,
+    db: Arc<DB>,
+    column_family: String,
+    cache: HashMap<String, Option<String>>,
 }
 
 impl ConfigDatabase {
     pub fn new(path: &Path, name: &str) -> Result<Self, Box<dyn Error>> {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.set_max_open_files(256);
-        let db = DB::open(&opts, &path.join(Path::new(name)))?;
+        let db = open_database(&path.join(Path::new(name)), &[])?;
+        Self::from_column_family(db, rocksdb::DEFAULT_COLUMN_FAMILY_NAME)
+    }
+
+    /// Create a ConfigDatabase stored in a column family of a shared database
+    pub fn from_column_family(db: Arc<DB>, name: &str) -> Result<Self, Box<dyn Error>> {
+        column_family(&db, name)?;
         Ok(Self {
             db,
+            column_family: name.to_string(),
             cache: HashMap::new(),
         })
     }
 
     pub fn get(&self, key: String) -> Result<Option<String>, Box<dyn Error>> {
         if let Some(value) = self.cache.get(&key) {
-            return Ok(Some(value.clone()));
+            return Ok(value.clone());
         }
         Ok(self
             .db
-            .get(&key.encode_vec())?
+            .get_cf(
+                column_family(&self.db, &self.column_family)?,
+                &key.encode_vec(),
+            )?
             .map_or(None, |value| String::decode_vec(&value).ok()))
     }
 
     pub fn set(&mut self, key: String, value: String) -> Result<(), Box<dyn Error>> {
-        self.db.put(&key.encode_vec(), &value.encode_vec())?;
-        self.cache.insert(key, value);
+        self.cache.insert(key, Some(value));
         Ok(())
     }
 
-    pub fn flush(&self) -> Result<(), Box<dyn Error>> {
-        self.db.flush().map_err(|e| e.into())
+    pub fn remove(&mut self, key: String) -> Result<(), Box<dyn Error>> {
+        self.cache.insert(key, None);
+        Ok(())
+    }
+
+    /// Write the values set or removed since the last flush to the database
+    pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut batch = WriteBatch::default();
+        self.write_to_batch(&mut batch)?;
+        write_batch(&self.db, batch)?;
+        self.clear_cache();
+        Ok(())
+    }
+
+    /// Add the values set or removed since the last flush to a batch, the cache should be cleared after the batch is written
+    pub fn write_to_batch(&self, batch: &mut WriteBatch) -> Result<(), Box<dyn Error>> {
+        let cf = column_family(&self.db, &self.column_family)?;
+        for (key, value) in self.cache.iter() {
+            match value {
+                Some(value) => batch.put_cf(cf, key.encode_vec(), value.encode_vec()),
+                None => batch.delete_cf(cf, key.encode_vec()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Discard the values set or removed since the last flush
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
     }
 
     pub fn validate(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
@@ -79,7 +117,7 @@ pub fn validate_config_database(config: &Brc20ProgConfig) -> Result<(), Box<dyn
     }
     let fresh_run = !db_path.read_dir()?.next().is_some();
 
-    let mut config_database = ConfigDatabase::new(&Path::new(&config.db_path), "config")?;
+    let mut config_database = Brc20ProgDatabase::open_config_database(db_path)?;
     if fresh_run {
         config_database.set(DB_VERSION_KEY.clone(), DB_VERSION.to_string())?;
         config_database.set(PROTOCOL_VERSION_KEY.clone(), PROTOCOL_VERSION.to_string())?;
@@ -91,6 +129,7 @@ pub fn validate_config_database(config: &Brc20ProgConfig) -> Result<(), Box<dyn
             EVM_RECORD_TRACES_KEY.clone(),
             config.evm_record_traces.to_string(),
         )?;
+        config_database.set(ARCHIVE_MODE_KEY.clone(), config.archive_mode.to_string())?;
         config_database.flush()?;
     } else {
         config_database.validate(&*DB_VERSION_KEY, &DB_VERSION.to_string())?;
@@ -100,6 +139,12 @@ pub fn validate_config_database(config: &Brc20ProgConfig) -> Result<(), Box<dyn
             &*EVM_RECORD_TRACES_KEY,
             &config.evm_record_traces.to_string(),
         )?;
+        if config_database.get(ARCHIVE_MODE_KEY.clone())?.is_none() && !config.archive_mode {
+            // Databases created before archive mode was introduced are never archives
+            config_database.set(ARCHIVE_MODE_KEY.clone(), false.to_string())?;
+            config_database.flush()?;
+        }
+        config_database.validate(&*ARCHIVE_MODE_KEY, &config.archive_mode.to_string())?;
     }
     Ok(())
 }
@@ -137,6 +182,21 @@ mod tests {
         assert_eq!(value, Some("value2".to_string()));
     }
 
+    #[test]
+    fn test_config_database_remove() {
+        let temp = TempDir::new().unwrap();
+        let mut db = ConfigDatabase::new(&temp.path(), "config").unwrap();
+        db.set("key".to_string(), "value".to_string()).unwrap();
+        db.flush().unwrap();
+        db.remove("key".to_string()).unwrap();
+        assert_eq!(db.get("key".to_string()).unwrap(), None);
+        db.flush().unwrap();
+        drop(db);
+
+        let db = ConfigDatabase::new(&temp.path(), "config").unwrap();
+        assert_eq!(db.get("key".to_string()).unwrap(), None);
+    }
+
     #[test]
     fn test_config_database_validate() {
         let temp = TempDir::new().unwrap();
@@ -172,6 +232,25 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_config_database_archive_mode() {
+        let temp = TempDir::new().unwrap();
+        let config = Brc20ProgConfig {
+            db_path: temp.path().to_string_lossy().to_string(),
+            archive_mode: false,
+            ..Brc20ProgConfig::from_env()
+        };
+        validate_config_database(&config).unwrap();
+        validate_config_database(&config).unwrap();
+
+        // Archive mode can't be enabled for an existing database
+        let archive_config = Brc20ProgConfig {
+            archive_mode: true,
+            ..config
+        };
+        assert!(validate_config_database(&archive_config).is_err());
+    }
+
     #[test]
     fn test_config_database_flush() {
         let temp = TempDir::new().unwrap();
@@ -179,5 +258,10 @@ mod tests {
         db.set("key".to_string(), "value".to_string()).unwrap();
         let result = db.flush();
         assert!(result.is_ok());
+        drop(db);
+
+        let db = ConfigDatabase::new(&temp.path(), "config").unwrap();
+        let value = db.get("key".to_string()).unwrap();
+        assert_eq!(value, Some("value".to_string()));
     }
 }
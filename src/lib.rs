@@ -44,14 +44,21 @@ pub(crate) mod engine;
 pub(crate) mod global;
 pub(crate) mod server;
 
-pub use api::Brc20ProgApiClient;
+pub use api::{
+    Brc20ProgApiClient, Brc20ProgOverridesApiClient, Brc20ProgSubscriptionApiClient,
+    Brc20ProgTracingApiClient,
+};
 pub use global::Brc20ProgConfig;
 
 pub mod types {
     //! This module contains the types used in the BRC20 programmable module.
     //!
     //! The types are used to interact with the BRC20 programmable module and the JSON-RPC server.
-    pub use crate::api::types::{Base64Bytes, EthCall, GetLogsFilter, PrecompileData, RawBytes};
+    pub use crate::api::types::{
+        Base64Bytes, BlockNumberOrHash, BlockOperation, EthCall, FilterChanges, GetLogsFilter,
+        InscriptionEstimate, PrecompileData, RawBytes, ReceiptProof, SimulateBlock,
+        SimulatePayload, SimulatedBlock, SimulatedCall, SimulatedCallError, TraceResult,
+    };
     pub use crate::db::types::{
         AddressED, BlockResponseED, BytecodeED, BytesED, FixedBytesED, LogED, TraceED, TxED,
         TxReceiptED, UintED, B2048ED, B256ED, U128ED, U256ED, U512ED, U64ED, U8ED,
@@ -60,4 +67,4 @@ pub mod types {
 
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
-pub use server::start;
+pub use server::{export_snapshot, import_snapshot, migrate, start};
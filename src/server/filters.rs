@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use alloy::primitives::{Address, B256};
+use jsonrpsee::core::traits::IdProvider;
+use jsonrpsee::server::RandomIntegerIdProvider;
+use jsonrpsee::types::SubscriptionId;
+use serde_either::SingleOrVec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::api::types::{FilterChanges, GetLogsFilter};
+use crate::engine::ChainEvent;
+
+/// Filters that are not polled for this long are removed
+pub const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Expired filters are removed this often, so abandoned filters don't keep collecting chain events
+pub const FILTER_EXPIRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Max number of filters installed at the same time, new filters are rejected above this
+pub const MAX_FILTERS: usize = 1_000;
+
+enum FilterKind {
+    Block,
+    Logs {
+        filter: GetLogsFilter,
+        address: Option<Address>,
+        topics: Option<Vec<SingleOrVec<Option<B256>>>>,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    },
+}
+
+impl FilterKind {
+    fn block_in_range(&self, block_number: u64) -> bool {
+        match self {
+            Self::Block => true,
+            Self::Logs {
+                from_block,
+                to_block,
+                ..
+            } => {
+                from_block.is_none_or(|from| block_number >= from)
+                    && to_block.is_none_or(|to| block_number <= to)
+            }
+        }
+    }
+}
+
+struct Filter {
+    kind: FilterKind,
+    events: broadcast::Receiver<ChainEvent>,
+    last_poll: Instant,
+}
+
+/// Keeps the filters created by eth_newFilter and eth_newBlockFilter in memory
+///
+/// Each filter holds its own receiver for chain events, and collects them on poll.
+pub struct FilterRegistry {
+    chain_events: broadcast::Sender<ChainEvent>,
+    filters: Mutex<HashMap<String, Filter>>,
+    id_provider: RandomIntegerIdProvider,
+}
+
+impl FilterRegistry {
+    pub fn new(chain_events: broadcast::Sender<ChainEvent>) -> Self {
+        Self {
+            chain_events,
+            filters: Mutex::new(HashMap::new()),
+            id_provider: RandomIntegerIdProvider,
+        }
+    }
+
+    /// Creates a log filter, from_block and to_block are only set for numeric block bounds
+    pub fn new_log_filter(
+        &self,
+        filter: GetLogsFilter,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    ) -> Result<String, Box<dyn Error>> {
+        let address = filter.address.as_ref().map(|x| x.address);
+        let topics = filter.topics_as_b256();
+        self.insert(FilterKind::Logs {
+            filter,
+            address,
+            topics,
+            from_block,
+            to_block,
+        })
+    }
+
+    pub fn new_block_filter(&self) -> Result<String, Box<dyn Error>> {
+        self.insert(FilterKind::Block)
+    }
+
+    /// Returns the stored filter for a log filter, to be used in eth_getFilterLogs
+    pub fn get_log_filter(&self, id: &str) -> Result<GetLogsFilter, Box<dyn Error>> {
+        let mut filters = self.lock();
+        let filter = filters.get_mut(id).ok_or("Filter not found")?;
+        filter.last_poll = Instant::now();
+        match &filter.kind {
+            FilterKind::Logs { filter, .. } => Ok(filter.clone()),
+            FilterKind::Block => Err("Filter is not a log filter".into()),
+        }
+    }
+
+    /// Returns block hashes or logs since the last poll
+    pub fn get_changes(&self, id: &str) -> Result<FilterChanges, Box<dyn Error>> {
+        let mut filters = self.lock();
+        let filter = filters.get_mut(id).ok_or("Filter not found")?;
+        filter.last_poll = Instant::now();
+
+        let mut hashes = Vec::new();
+        let mut logs = Vec::new();
+        loop {
+            let event = match filter.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+                Err(TryRecvError::Lagged(skipped)) => {
+                    // Filters can't recover from missing events, so they are removed
+                    filters.remove(id);
+                    return Err(format!(
+                        "Filter missed {} events and was removed, please create a new filter",
+                        skipped
+                    )
+                    .into());
+                }
+            };
+            match (&filter.kind, event) {
+                (FilterKind::Block, ChainEvent::NewBlock { block, .. }) => hashes.push(block.hash),
+                (FilterKind::Block, ChainEvent::Reorg { .. }) => {}
+                (
+                    kind @ FilterKind::Logs {
+                        address, topics, ..
+                    },
+                    ChainEvent::NewBlock {
+                        logs: event_logs, ..
+                    }
+                    | ChainEvent::Reorg {
+                        removed_logs: event_logs,
+                    },
+                ) => logs.extend(event_logs.into_iter().filter(|log| {
+                    kind.block_in_range(log.block_number.into()) && log.matches(*address, topics)
+                })),
+            }
+        }
+
+        Ok(match filter.kind {
+            FilterKind::Block => FilterChanges::Hashes(hashes),
+            FilterKind::Logs { .. } => FilterChanges::Logs(logs),
+        })
+    }
+
+    /// Removes the filter, returns true if the filter existed
+    pub fn uninstall(&self, id: &str) -> bool {
+        self.lock().remove(id).is_some()
+    }
+
+    /// Removes the filters that are not polled for FILTER_TIMEOUT
+    pub fn remove_expired(&self) {
+        drop(self.lock());
+    }
+
+    fn insert(&self, kind: FilterKind) -> Result<String, Box<dyn Error>> {
+        let id = match self.id_provider.next_id() {
+            SubscriptionId::Num(id) => format!("0x{:x}", id),
+            SubscriptionId::Str(id) => id.into_owned(),
+        };
+        let mut filters = self.lock();
+        if filters.len() >= MAX_FILTERS {
+            return Err(format!(
+                "Too many filters, at most {} filters can be installed, please uninstall unused filters",
+                MAX_FILTERS
+            )
+            .into());
+        }
+        filters.insert(
+            id.clone(),
+            Filter {
+                kind,
+                events: self.chain_events.subscribe(),
+                last_poll: Instant::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Locks the filters, removing the ones that have expired
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Filter>> {
+        let mut filters = self.filters.lock().expect("Failed to acquire filter lock");
+        filters.retain(|_, filter| filter.last_poll.elapsed() < FILTER_TIMEOUT);
+        filters
+    }
+}
+
+/// Removes expired filters every FILTER_EXPIRY_INTERVAL, until the registry is dropped
+pub async fn remove_expired_filters(registry: Weak<FilterRegistry>) {
+    let mut interval = tokio::time::interval(FILTER_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(registry) = registry.upgrade() else {
+            return;
+        };
+        registry.remove_expired();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Bytes, U256};
+
+    use super::*;
+    use crate::db::types::{BlockResponseED, LogED};
+    use crate::types::AddressED;
+
+    fn log(address: Address, block_number: u64) -> LogED {
+        LogED {
+            address: address.into(),
+            topics: vec![B256::ZERO.into()],
+            data: Bytes::new().into(),
+            transaction_index: 0u64.into(),
+            log_index: 0u64.into(),
+            transaction_hash: B256::ZERO.into(),
+            block_hash: B256::ZERO.into(),
+            block_number: block_number.into(),
+            removed: false,
+        }
+    }
+
+    fn new_block(number: u64, logs: Vec<LogED>) -> ChainEvent {
+        let block = BlockResponseED::new(
+            0u64.into(),
+            B256::from(U256::from(number)).into(),
+            [0u8; 256].into(),
+            0u64.into(),
+            number.into(),
+            0u64.into(),
+            0u128.into(),
+            Vec::new(),
+            B256::ZERO.into(),
+            B256::ZERO.into(),
+            B256::ZERO.into(),
+            B256::ZERO.into(),
+        );
+        ChainEvent::NewBlock {
+            block: Box::new(block),
+            logs,
+        }
+    }
+
+    #[test]
+    fn test_block_filter() {
+        let (sender, _) = broadcast::channel(16);
+        let registry = FilterRegistry::new(sender.clone());
+        let id = registry.new_block_filter().unwrap();
+        assert!(id.starts_with("0x"));
+
+        sender.send(new_block(1, Vec::new())).unwrap();
+        sender.send(new_block(2, Vec::new())).unwrap();
+
+        let FilterChanges::Hashes(hashes) = registry.get_changes(&id).unwrap() else {
+            panic!("Expected hashes");
+        };
+        assert_eq!(
+            hashes,
+            vec![
+                B256::from(U256::from(1)).into(),
+                B256::from(U256::from(2)).into()
+            ]
+        );
+
+        // Changes are only returned once
+        let FilterChanges::Hashes(hashes) = registry.get_changes(&id).unwrap() else {
+            panic!("Expected hashes");
+        };
+        assert!(hashes.is_empty());
+
+        assert!(registry.get_log_filter(&id).is_err());
+        assert!(registry.uninstall(&id));
+        assert!(!registry.uninstall(&id));
+        assert!(registry.get_changes(&id).is_err());
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let (sender, _) = broadcast::channel(16);
+        let registry = FilterRegistry::new(sender.clone());
+        let address = Address::repeat_byte(1);
+        let filter = GetLogsFilter {
+            from_block: Some("0x2".to_string()),
+            to_block: None,
+            address: Some(AddressED::from(address)),
+            topics: None,
+        };
+        let id = registry.new_log_filter(filter, Some(2), None).unwrap();
+
+        sender
+            .send(new_block(
+                1,
+                vec![log(address, 1), log(Address::repeat_byte(2), 1)],
+            ))
+            .unwrap();
+        sender
+            .send(new_block(
+                2,
+                vec![log(address, 2), log(Address::repeat_byte(2), 2)],
+            ))
+            .unwrap();
+        let mut removed_log = log(address, 2);
+        removed_log.removed = true;
+        sender
+            .send(ChainEvent::Reorg {
+                removed_logs: vec![removed_log.clone()],
+            })
+            .unwrap();
+
+        let FilterChanges::Logs(logs) = registry.get_changes(&id).unwrap() else {
+            panic!("Expected logs");
+        };
+        assert_eq!(logs, vec![log(address, 2), removed_log]);
+        assert_eq!(
+            registry.get_log_filter(&id).unwrap().from_block,
+            Some("0x2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lagged_filter_is_removed() {
+        let (sender, _) = broadcast::channel(2);
+        let registry = FilterRegistry::new(sender.clone());
+        let id = registry.new_block_filter().unwrap();
+        for number in 0..4 {
+            sender.send(new_block(number, Vec::new())).unwrap();
+        }
+        assert!(registry.get_changes(&id).is_err());
+        assert!(!registry.uninstall(&id));
+    }
+
+    #[test]
+    fn test_expired_filters_are_removed() {
+        let (sender, _) = broadcast::channel(16);
+        let registry = FilterRegistry::new(sender.clone());
+        let expired_id = registry.new_block_filter().unwrap();
+        let id = registry.new_block_filter().unwrap();
+        registry
+            .filters
+            .lock()
+            .unwrap()
+            .get_mut(&expired_id)
+            .unwrap()
+            .last_poll -= FILTER_TIMEOUT;
+
+        registry.remove_expired();
+        let filters = registry.filters.lock().unwrap();
+        assert!(!filters.contains_key(&expired_id));
+        assert!(filters.contains_key(&id));
+        // Receivers of removed filters are dropped, so they don't hold chain events
+        assert_eq!(sender.receiver_count(), 1);
+    }
+
+    #[test]
+    fn test_max_filters() {
+        let (sender, _) = broadcast::channel(16);
+        let registry = FilterRegistry::new(sender);
+        let ids: Vec<String> = (0..MAX_FILTERS)
+            .map(|_| registry.new_block_filter().unwrap())
+            .collect();
+        assert!(registry.new_block_filter().is_err());
+
+        assert!(registry.uninstall(&ids[0]));
+        assert!(registry.new_block_filter().is_ok());
+    }
+}
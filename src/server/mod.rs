@@ -2,7 +2,9 @@
 
 mod auth;
 mod error;
+mod filters;
 mod rpc_server;
 mod start;
+mod subscription_server;
 
-pub use start::start;
+pub use start::{export_snapshot, import_snapshot, migrate, start};
@@ -1,12 +1,20 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
+use alloy::eips::eip2930::AccessListResult;
 use alloy::primitives::Bytes;
+use alloy_rpc_types_eth::state::StateOverride;
+use alloy_rpc_types_eth::{BlockOverrides, EIP1186AccountProofResponse};
+use alloy_rpc_types_trace::geth::GethDebugTracingOptions;
+use alloy_serde::JsonStorageKey;
 use hyper::Method;
 use jsonrpsee::core::middleware::RpcServiceBuilder;
 use jsonrpsee::core::{async_trait, RpcResult};
-use jsonrpsee::server::{BatchRequestConfig, Server, ServerConfigBuilder, ServerHandle};
+use jsonrpsee::server::{
+    BatchRequestConfig, RandomStringIdProvider, Server, ServerConfigBuilder, ServerHandle,
+};
 use revm::primitives::TxKind;
 use revm::state::Bytecode;
 use tower::ServiceBuilder;
@@ -14,25 +22,38 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::validate_request::ValidateRequestHeaderLayer;
 use tracing::{debug, info, instrument, warn};
 
-use crate::api::types::{select_bytes, EthCall, GetLogsFilter};
-use crate::api::{Brc20ProgApiServer, INDEXER_METHODS};
+use crate::api::types::{
+    select_bytes, BlockNumberOrHash, BlockOperation, EthCall, FilterChanges, GetLogsFilter,
+    InscriptionEstimate, ReceiptProof, SimulatePayload, SimulatedBlock, TraceResult,
+};
+use crate::api::{
+    Brc20ProgApiServer, Brc20ProgOverridesApiServer, Brc20ProgSubscriptionApiServer,
+    Brc20ProgTracingApiServer, INDEXER_METHODS,
+};
 use crate::brc20_controller::{
     decode_brc20_balance_result, load_brc20_balance_tx, load_brc20_burn_tx, load_brc20_mint_tx,
 };
 use crate::db::types::{
     AddressED, BlockResponseED, BytecodeED, LogED, TraceED, TxED, TxReceiptED, B256ED, U256ED,
+    U64ED,
+};
+use crate::engine::{
+    get_evm_address_from_pkscript, BRC20ProgEngine, EvmOverrides, SimulateBlockCalls, TxInfo,
 };
-use crate::engine::{get_evm_address_from_pkscript, BRC20ProgEngine, TxInfo};
 use crate::global::{CONFIG, GAS_PER_BYTE, INVALID_ADDRESS};
 use crate::server::auth::{HttpNonBlockingAuth, RpcAuthMiddleware};
 use crate::server::error::{
     wrap_rpc_error, wrap_rpc_error_string, wrap_rpc_error_string_with_data,
 };
+use crate::server::filters::{remove_expired_filters, FilterRegistry};
+use crate::server::subscription_server::SubscriptionServer;
 use crate::types::{Base64Bytes, PrecompileData, RawBytes};
 use crate::Brc20ProgConfig;
 
+#[derive(Clone)]
 struct RpcServer {
-    engine: BRC20ProgEngine,
+    engine: Arc<BRC20ProgEngine>,
+    filters: Arc<FilterRegistry>,
 }
 
 impl RpcServer {
@@ -50,6 +71,14 @@ impl RpcServer {
         }
     }
 
+    /// Parses numeric block bounds for filters, block tags are not bounds as they move with the chain
+    fn parse_filter_bound(&self, number: Option<&String>) -> Result<Option<u64>, Box<dyn Error>> {
+        match number.map(|x| x.as_str()) {
+            None | Some("latest" | "safe" | "finalized" | "pending") => Ok(None),
+            Some(number) => self.parse_block_number(number).map(Some),
+        }
+    }
+
     async fn resolve_block_hash_or_number(
         &self,
         hash_or_number: &str,
@@ -57,7 +86,8 @@ impl RpcServer {
         match self.parse_block_number(hash_or_number) {
             Ok(block_number) => Ok(block_number),
             Err(_) => {
-                let hash = serde_json::from_str::<B256ED>(hash_or_number)
+                let hash = B256ED::try_from(hash_or_number)
+                    .or_else(|_| serde_json::from_str::<B256ED>(hash_or_number))
                     .map_err(|_| "Invalid block hash or number")?;
                 if let Ok(Some(block)) = self.engine.get_block_by_hash(hash.bytes, false) {
                     Ok(block.number.into())
@@ -67,12 +97,235 @@ impl RpcServer {
             }
         }
     }
+
+    /// Resolves a block parameter, given as a string or an EIP-1898 object, to a block number
+    ///
+    /// Reorged blocks are removed from the database, so a block found by hash is always
+    /// canonical, and requireCanonical doesn't need to be checked.
+    async fn resolve_block(&self, block: &BlockNumberOrHash) -> Result<u64, Box<dyn Error>> {
+        match block {
+            BlockNumberOrHash::String(hash_or_number) => {
+                self.resolve_block_hash_or_number(hash_or_number).await
+            }
+            BlockNumberOrHash::Number { block_number } => self.parse_block_number(block_number),
+            BlockNumberOrHash::Hash { block_hash, .. } => {
+                if let Ok(Some(block)) = self.engine.get_block_by_hash(block_hash.bytes, false) {
+                    Ok(block.number.into())
+                } else {
+                    Err("Block not found".into())
+                }
+            }
+        }
+    }
+
+    /// Resolves an optional block parameter, None is kept to use the latest state
+    async fn resolve_optional_block(
+        &self,
+        block: Option<&BlockNumberOrHash>,
+    ) -> Result<Option<u64>, Box<dyn Error>> {
+        match block {
+            Some(block) => self.resolve_block(block).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Estimates the gas for a transaction, execution errors are returned with the revert data
+    async fn estimate_gas(
+        &self,
+        tx_info: &TxInfo,
+        block_height: Option<&BlockNumberOrHash>,
+        upper_gas_limit: u64,
+        overrides: &EvmOverrides,
+    ) -> RpcResult<u64> {
+        let block_height = self
+            .resolve_optional_block(block_height)
+            .await
+            .map_err(wrap_rpc_error)?;
+
+        let Ok((estimated_gas, result)) = self
+            .engine
+            .estimate_gas(tx_info, block_height, upper_gas_limit, overrides)
+            .await
+        else {
+            return Err(wrap_rpc_error_string_with_data(
+                3,
+                "Call failed",
+                "0x".into(),
+            ));
+        };
+
+        if !result.status {
+            let data_string = result.output.unwrap_or(Bytes::new()).to_string();
+            return Err(wrap_rpc_error_string_with_data(
+                3,
+                format!("Execution reverted: {}", result.status_string).as_str(),
+                data_string,
+            ));
+        }
+        debug!(
+            "estimate_gas: estimated gas: {}, used: {}",
+            estimated_gas, result.gas_used
+        );
+        Ok(estimated_gas)
+    }
+
+    /// Executes the operations of a block in order and finalises it, returns all receipts
+    ///
+    /// Each operation gets the next transaction index, transactions can execute pending
+    /// transactions as well, so an operation can add any number of receipts
+    async fn add_block_operations(
+        &self,
+        timestamp: u64,
+        hash: B256ED,
+        operations: Vec<BlockOperation>,
+    ) -> RpcResult<Vec<TxReceiptED>> {
+        let mut receipts = Vec::new();
+        for operation in operations {
+            let tx_idx = receipts.len() as u64;
+            match operation {
+                BlockOperation::Deploy {
+                    from_pkscript,
+                    data,
+                    base64_data,
+                    inscription_id,
+                    inscription_byte_len,
+                    op_return_tx_id,
+                } => receipts.push(
+                    self.brc20_deploy(
+                        from_pkscript,
+                        data,
+                        base64_data,
+                        timestamp,
+                        hash,
+                        tx_idx,
+                        inscription_id,
+                        inscription_byte_len,
+                        op_return_tx_id,
+                    )
+                    .await?,
+                ),
+                BlockOperation::Call {
+                    from_pkscript,
+                    contract_address,
+                    contract_inscription_id,
+                    data,
+                    base64_data,
+                    inscription_id,
+                    inscription_byte_len,
+                    op_return_tx_id,
+                } => receipts.extend(
+                    self.brc20_call(
+                        from_pkscript,
+                        contract_address,
+                        contract_inscription_id,
+                        data,
+                        base64_data,
+                        timestamp,
+                        hash,
+                        tx_idx,
+                        inscription_id,
+                        inscription_byte_len,
+                        op_return_tx_id,
+                    )
+                    .await?,
+                ),
+                BlockOperation::Transact {
+                    raw_tx_data,
+                    base64_raw_tx_data,
+                    inscription_id,
+                    inscription_byte_len,
+                    op_return_tx_id,
+                } => receipts.extend(
+                    self.brc20_transact(
+                        raw_tx_data,
+                        base64_raw_tx_data,
+                        timestamp,
+                        hash,
+                        tx_idx,
+                        inscription_id,
+                        inscription_byte_len,
+                        op_return_tx_id,
+                    )
+                    .await?,
+                ),
+                BlockOperation::Deposit {
+                    to_pkscript,
+                    ticker,
+                    amount,
+                    inscription_id,
+                } => receipts.push(
+                    self.brc20_deposit(
+                        to_pkscript,
+                        ticker,
+                        amount,
+                        timestamp,
+                        hash,
+                        tx_idx,
+                        inscription_id,
+                    )
+                    .await?,
+                ),
+                BlockOperation::Withdraw {
+                    from_pkscript,
+                    ticker,
+                    amount,
+                    inscription_id,
+                } => receipts.push(
+                    self.brc20_withdraw(
+                        from_pkscript,
+                        ticker,
+                        amount,
+                        timestamp,
+                        hash,
+                        tx_idx,
+                        inscription_id,
+                    )
+                    .await?,
+                ),
+            }
+        }
+
+        self.brc20_finalise_block(timestamp, hash, receipts.len() as u64)
+            .await?;
+        Ok(receipts)
+    }
 }
 
 fn log_call() {
     info!("rpc.request");
 }
 
+/// Creates the transaction info for a call, missing sender is replaced with the invalid address
+fn call_tx_info(call: &EthCall) -> Result<TxInfo, Box<dyn Error>> {
+    let Some(data) = &call.data else {
+        return Err("No data or input provided".into());
+    };
+    Ok(TxInfo {
+        value: call.value.map(|x| x.uint).unwrap_or_default(),
+        gas_price: call
+            .gas_price
+            .map(|x| x.uint.to::<u128>())
+            .unwrap_or_default(),
+        nonce: call.nonce.map(|x| x.into()),
+        ..TxInfo::from_inscription(
+            call.from
+                .as_ref()
+                .map(|x| x.address)
+                .unwrap_or(*INVALID_ADDRESS),
+            call.to.as_ref().map(|x| x.address).into(),
+            data.value().unwrap_or_default().clone(),
+        )
+    })
+}
+
+/// Returns the gas limit for a call, capped at the configured call gas limit
+fn call_gas_limit(call: &EthCall) -> u64 {
+    let max_gas_limit = CONFIG.read().evm_call_gas_limit;
+    call.gas
+        .map(|gas| Into::<u64>::into(gas).min(max_gas_limit))
+        .unwrap_or(max_gas_limit)
+}
+
 #[async_trait]
 impl Brc20ProgApiServer for RpcServer {
     #[instrument(skip(self), level = "error")]
@@ -161,6 +414,7 @@ impl Brc20ProgApiServer for RpcServer {
                 ),
                 None,
                 None,
+                &EvmOverrides::default(),
             )
             .await
             .map(|receipt| {
@@ -172,6 +426,55 @@ impl Brc20ProgApiServer for RpcServer {
             .map_err(wrap_rpc_error)
     }
 
+    #[instrument(skip(self, data, base64_data), level = "error")]
+    async fn brc20_estimate_inscription(
+        &self,
+        from_pkscript: String,
+        contract_address: Option<AddressED>,
+        contract_inscription_id: Option<String>,
+        data: Option<RawBytes>,
+        base64_data: Option<Base64Bytes>,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<InscriptionEstimate> {
+        log_call();
+
+        let Some(data) = select_bytes(&data, &base64_data).map_err(wrap_rpc_error)? else {
+            return Err(wrap_rpc_error_string("Invalid data"));
+        };
+
+        let to = if let Some(contract_inscription_id) = contract_inscription_id {
+            TxKind::Call(
+                self.engine
+                    .get_contract_address_by_inscription_id(contract_inscription_id)
+                    .map_err(wrap_rpc_error)?
+                    .ok_or_else(|| wrap_rpc_error_string("Contract not found"))?,
+            )
+        } else if let Some(contract_address) = contract_address {
+            TxKind::Call(contract_address.address)
+        } else {
+            TxKind::Create
+        };
+
+        let from_address = get_evm_address_from_pkscript(&from_pkscript).map_err(wrap_rpc_error)?;
+        let base64_data = Base64Bytes::from_bytes(data.clone()).map_err(wrap_rpc_error)?;
+        let max_gas_limit = CONFIG.read().evm_call_gas_limit;
+        let gas = self
+            .estimate_gas(
+                &TxInfo::from_inscription(from_address, to, data),
+                block.as_ref(),
+                max_gas_limit,
+                &EvmOverrides::default(),
+            )
+            .await?;
+
+        Ok(InscriptionEstimate {
+            gas: gas.into(),
+            inscription_byte_len: gas.div_ceil(GAS_PER_BYTE).into(),
+            compression: base64_data.compression().unwrap_or_default().into(),
+            base64_data,
+        })
+    }
+
     #[instrument(skip(self), level = "error")]
     async fn brc20_initialise(
         &self,
@@ -215,6 +518,17 @@ impl Brc20ProgApiServer for RpcServer {
             .map_err(wrap_rpc_error)
     }
 
+    #[instrument(skip(self), level = "error")]
+    async fn brc20_get_receipt_proof(
+        &self,
+        transaction: B256ED,
+    ) -> RpcResult<Option<ReceiptProof>> {
+        log_call();
+        self.engine
+            .get_receipt_proof(transaction.bytes)
+            .map_err(wrap_rpc_error)
+    }
+
     #[instrument(skip(self), level = "error")]
     async fn brc20_get_inscription_id_by_tx_hash(
         &self,
@@ -384,6 +698,34 @@ impl Brc20ProgApiServer for RpcServer {
             .map_err(wrap_rpc_error)
     }
 
+    #[instrument(skip(self, operations), level = "error")]
+    async fn brc20_add_block(
+        &self,
+        timestamp: u64,
+        hash: B256ED,
+        operations: Vec<BlockOperation>,
+    ) -> RpcResult<Vec<TxReceiptED>> {
+        log_call();
+
+        // Operations are executed one by one, so the block is aborted if any of them fails
+        // Starting the block fails if another call already started it, that block is left as is
+        self.engine.begin_new_block().map_err(wrap_rpc_error)?;
+        match self.add_block_operations(timestamp, hash, operations).await {
+            Ok(receipts) => Ok(receipts),
+            Err(error) => {
+                warn!("Aborting block: {}", error.message());
+                self.engine.abort_block().map_err(wrap_rpc_error)?;
+                Err(error)
+            }
+        }
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn brc20_abort_block(&self) -> RpcResult<()> {
+        warn!("Aborting block!");
+        self.engine.abort_block().map_err(wrap_rpc_error)
+    }
+
     #[instrument(skip(self), level = "error")]
     async fn brc20_reorg(&self, latest_valid_block_number: u64) -> RpcResult<()> {
         warn!("Reorg!");
@@ -419,12 +761,12 @@ impl Brc20ProgApiServer for RpcServer {
     #[instrument(skip(self), level = "error")]
     async fn eth_get_block_by_number(
         &self,
-        block: String,
+        block: BlockNumberOrHash,
         is_full: Option<bool>,
     ) -> RpcResult<BlockResponseED> {
         // Skip logs since this is a common call
         // log_call();
-        let block_number = self.parse_block_number(&block).map_err(wrap_rpc_error)?;
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         if let Some(block) = self
             .engine
             .get_block_by_number(block_number, is_full.unwrap_or(false))
@@ -458,10 +800,10 @@ impl Brc20ProgApiServer for RpcServer {
     async fn eth_get_transaction_count(
         &self,
         account: AddressED,
-        block: String,
+        block: BlockNumberOrHash,
     ) -> RpcResult<String> {
         log_call();
-        let block_number = self.parse_block_number(&block).map_err(wrap_rpc_error)?;
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         self.engine
             .get_transaction_count(account.address, block_number)
             .map(|count| format!("0x{:x}", count))
@@ -473,9 +815,12 @@ impl Brc20ProgApiServer for RpcServer {
         skip(self),
         level = "error"
     )]
-    async fn eth_get_block_transaction_count_by_number(&self, block: String) -> RpcResult<String> {
+    async fn eth_get_block_transaction_count_by_number(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<String> {
         log_call();
-        let block_number = self.parse_block_number(&block).map_err(wrap_rpc_error)?;
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         self.engine
             .get_block_transaction_count_by_number(block_number)
             .map(|count| format!("0x{:x}", count))
@@ -518,90 +863,107 @@ impl Brc20ProgApiServer for RpcServer {
     }
 
     #[instrument(skip(self), level = "error")]
-    async fn eth_call(&self, call: EthCall, block_height: Option<String>) -> RpcResult<String> {
+    async fn eth_new_filter(&self, filter: GetLogsFilter) -> RpcResult<String> {
         log_call();
-        let Some(data) = call.data else {
-            return Err(wrap_rpc_error_string("No data or input provided"));
-        };
-        let block_height = if let Some(block_height) = block_height {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
-        let receipt = self
-            .engine
-            .read_contract(
-                &TxInfo::from_inscription(
-                    call.from
-                        .as_ref()
-                        .map(|x| x.address)
-                        .unwrap_or(*INVALID_ADDRESS),
-                    call.to.as_ref().map(|x| x.address).into(),
-                    data.value().unwrap_or_default().clone(),
-                ),
-                block_height,
-                None,
-            )
-            .await;
-        let Ok(result) = receipt else {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                "Call failed",
-                "0x".into(),
-            ));
-        };
-        let data_string = result.output.unwrap_or(Bytes::new()).to_string();
-        if !result.status {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                format!("Execution reverted: {}", result.status_string).as_str(),
-                data_string,
-            ));
-        }
-        Ok(data_string)
+        let from_block = self
+            .parse_filter_bound(filter.from_block.as_ref())
+            .map_err(wrap_rpc_error)?;
+        let to_block = self
+            .parse_filter_bound(filter.to_block.as_ref())
+            .map_err(wrap_rpc_error)?;
+        self.filters
+            .new_log_filter(filter, from_block, to_block)
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_new_block_filter(&self) -> RpcResult<String> {
+        log_call();
+        self.filters.new_block_filter().map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_get_filter_changes(&self, filter_id: String) -> RpcResult<FilterChanges> {
+        log_call();
+        self.filters.get_changes(&filter_id).map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_get_filter_logs(&self, filter_id: String) -> RpcResult<Vec<LogED>> {
+        log_call();
+        let filter = self
+            .filters
+            .get_log_filter(&filter_id)
+            .map_err(wrap_rpc_error)?;
+        self.eth_get_logs(filter).await
     }
 
     #[instrument(skip(self), level = "error")]
+    async fn eth_uninstall_filter(&self, filter_id: String) -> RpcResult<bool> {
+        log_call();
+        Ok(self.filters.uninstall(&filter_id))
+    }
+
+    async fn eth_call(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String> {
+        self.eth_call_with_overrides(call, block_height, None, None)
+            .await
+    }
+
     async fn eth_call_many(
         &self,
         calls: Vec<EthCall>,
-        block_height: Option<String>,
+        block_height: Option<BlockNumberOrHash>,
+        precompile_data: Option<PrecompileData>,
+    ) -> RpcResult<Vec<String>> {
+        self.eth_call_many_with_overrides(calls, block_height, precompile_data, None, None)
+            .await
+    }
+
+    async fn eth_estimate_gas(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String> {
+        self.eth_estimate_gas_with_overrides(call, block_height, None, None)
+            .await
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_estimate_gas_many(
+        &self,
+        calls: Vec<EthCall>,
+        block_height: Option<BlockNumberOrHash>,
         precompile_data: Option<PrecompileData>,
     ) -> RpcResult<Vec<String>> {
         log_call();
-        let block_height = if let Some(block_height) = block_height {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
+        let start_block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
 
         let mut txinfos = Vec::with_capacity(calls.len());
         for call in &calls {
-            let Some(data) = &call.data else {
-                return Err(wrap_rpc_error_string("No data or input provided"));
-            };
-            txinfos.push(TxInfo::from_inscription(
-                call.from
-                    .as_ref()
-                    .map(|x| x.address)
-                    .unwrap_or(*INVALID_ADDRESS),
-                call.to.as_ref().map(|x| x.address).into(),
-                data.value().unwrap_or_default().clone(),
-            ));
+            txinfos.push(call_tx_info(call).map_err(wrap_rpc_error)?);
         }
-        debug!("eth_call_many: prepared {} calls", txinfos.len());
+        let max_gas_limits: Vec<u64> = calls.iter().map(call_gas_limit).collect();
 
-        let receipts = self
+        // First run to check if all calls can succeed with the max gas limit, to avoid
+        // unnecessary bisection for all calls.
+        let Ok(results) = self
             .engine
-            .read_contract_multi(&txinfos, block_height, precompile_data, None)
-            .await;
-        let Ok(results) = receipts else {
+            .read_contract_multi(
+                &txinfos,
+                start_block_height,
+                precompile_data.clone(),
+                Some(max_gas_limits.as_ref()),
+                &EvmOverrides::default(),
+            )
+            .await
+        else {
             return Err(wrap_rpc_error_string_with_data(
                 3,
                 "Call failed",
@@ -609,11 +971,7 @@ impl Brc20ProgApiServer for RpcServer {
             ));
         };
 
-        let mut outputs = Vec::with_capacity(results.len());
-        let mut result_idx = 0;
-        for result in results {
-            let data_string = result.output.unwrap_or(Bytes::new()).to_string();
-
+        for (result_idx, result) in results.iter().enumerate() {
             if !result.status {
                 return Err(wrap_rpc_error_string_with_data(
                     3,
@@ -622,205 +980,18 @@ impl Brc20ProgApiServer for RpcServer {
                         result_idx, result.status_string
                     )
                     .as_str(),
-                    data_string,
+                    result.output.as_ref().unwrap_or(&Bytes::new()).to_string(),
                 ));
             }
-            outputs.push(data_string);
-            result_idx += 1;
         }
 
-        Ok(outputs)
-    }
-
-    #[instrument(skip(self), level = "error")]
-    async fn eth_estimate_gas(
-        &self,
-        call: EthCall,
-        block_height: Option<String>,
-    ) -> RpcResult<String> {
-        log_call();
-        let Some(data) = call.data else {
-            return Err(wrap_rpc_error_string("No data or input provided"));
-        };
-        let start_block_height = if let Some(block_height) = block_height.clone() {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
+        let mut estimated_gases = max_gas_limits.clone();
+        for i in 0..txinfos.len() {
+            let mut upper_gas_limit = max_gas_limits[i];
+            let mut lower_gas_limit = 21_000u64;
 
-        let tx_info = TxInfo::from_inscription(
-            call.from
-                .as_ref()
-                .map(|x| x.address)
-                .unwrap_or(*INVALID_ADDRESS),
-            call.to.as_ref().map(|x| x.address).into(),
-            data.value().unwrap_or_default().clone(),
-        );
-
-        let Ok(result) = self
-            .engine
-            .read_contract(&tx_info, start_block_height, None)
-            .await
-        else {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                "Call failed",
-                "0x".into(),
-            ));
-        };
-
-        if !result.status {
-            let data_string = result.output.unwrap_or(Bytes::new()).to_string();
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                format!("Execution reverted: {}", result.status_string).as_str(),
-                data_string,
-            ));
-        }
-
-        let mut upper_gas_limit = CONFIG.read().evm_call_gas_limit;
-        let mut lower_gas_limit = 21_000u64;
-        let mut estimated_gas;
-
-        while lower_gas_limit + GAS_PER_BYTE < upper_gas_limit {
-            estimated_gas = (lower_gas_limit + upper_gas_limit) / 2;
-            let receipt = self
-                .engine
-                .read_contract(&tx_info, start_block_height, Some(estimated_gas))
-                .await;
-            let Ok(result) = receipt else {
-                lower_gas_limit = estimated_gas + 1;
-                debug!("eth_estimate_gas: estimated gas too low: {}", estimated_gas);
-                continue;
-            };
-            if result.status {
-                upper_gas_limit = estimated_gas;
-                debug!(
-                    "eth_estimate_gas: estimated gas sufficient: {}, used: {}",
-                    estimated_gas, result.gas_used
-                );
-            } else {
-                lower_gas_limit = estimated_gas + 1;
-                debug!("eth_estimate_gas: estimated gas too low: {}", estimated_gas);
-            }
-        }
-        estimated_gas = upper_gas_limit;
-
-        let receipt = self
-            .engine
-            .read_contract(&tx_info, start_block_height, Some(estimated_gas))
-            .await;
-
-        let Ok(result) = receipt else {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                "Call failed",
-                "0x".into(),
-            ));
-        };
-
-        let end_block_height = if let Some(block_height) = block_height {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
-
-        if start_block_height != end_block_height {
-            debug!(
-                "eth_estimate_gas: block height changed from {:?} to {:?} during estimation",
-                start_block_height, end_block_height
-            );
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                "Block height changed during estimation, please try again",
-                "0x".into(),
-            ));
-        }
-
-        let data_string = result.output.unwrap_or(Bytes::new()).to_string();
-        if !result.status {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                format!("Execution reverted: {}", result.status_string).as_str(),
-                data_string,
-            ));
-        }
-        Ok(format!("0x{:x}", estimated_gas))
-    }
-
-    #[instrument(skip(self), level = "error")]
-    async fn eth_estimate_gas_many(
-        &self,
-        calls: Vec<EthCall>,
-        block_height: Option<String>,
-        precompile_data: Option<PrecompileData>,
-    ) -> RpcResult<Vec<String>> {
-        log_call();
-        let start_block_height = if let Some(block_height) = block_height.clone() {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
-
-        let mut txinfos = Vec::with_capacity(calls.len());
-        for call in &calls {
-            let Some(data) = &call.data else {
-                return Err(wrap_rpc_error_string("No data or input provided"));
-            };
-            txinfos.push(TxInfo::from_inscription(
-                call.from
-                    .as_ref()
-                    .map(|x| x.address)
-                    .unwrap_or(*INVALID_ADDRESS),
-                call.to.as_ref().map(|x| x.address).into(),
-                data.value().unwrap_or_default().clone(),
-            ));
-        }
-
-        // First run to check if all calls can succeed with the max gas limit, to avoid
-        // unnecessary bisection for all calls.
-        let Ok(results) = self
-            .engine
-            .read_contract_multi(&txinfos, start_block_height, precompile_data.clone(), None)
-            .await
-        else {
-            return Err(wrap_rpc_error_string_with_data(
-                3,
-                "Call failed",
-                "0x".into(),
-            ));
-        };
-
-        for (result_idx, result) in results.iter().enumerate() {
-            if !result.status {
-                return Err(wrap_rpc_error_string_with_data(
-                    3,
-                    format!(
-                        "Execution with index {} reverted: {}",
-                        result_idx, result.status_string
-                    )
-                    .as_str(),
-                    result.output.as_ref().unwrap_or(&Bytes::new()).to_string(),
-                ));
-            }
-        }
-
-        let mut estimated_gases: Vec<u64> = vec![CONFIG.read().evm_call_gas_limit; txinfos.len()];
-        for i in 0..txinfos.len() {
-            let mut upper_gas_limit = CONFIG.read().evm_call_gas_limit;
-            let mut lower_gas_limit = 21_000u64;
-
-            while lower_gas_limit + GAS_PER_BYTE < upper_gas_limit {
-                estimated_gases[i] = (lower_gas_limit + upper_gas_limit) / 2;
+            while lower_gas_limit + GAS_PER_BYTE < upper_gas_limit {
+                estimated_gases[i] = (lower_gas_limit + upper_gas_limit) / 2;
 
                 let receipts = self
                     .engine
@@ -829,6 +1000,7 @@ impl Brc20ProgApiServer for RpcServer {
                         start_block_height,
                         precompile_data.clone(),
                         Some(estimated_gases.as_ref()),
+                        &EvmOverrides::default(),
                     )
                     .await;
                 let Ok(result) = receipts else {
@@ -856,16 +1028,17 @@ impl Brc20ProgApiServer for RpcServer {
             estimated_gases[i] = upper_gas_limit;
         }
 
-        let receipts = self
+        let Ok(results) = self
             .engine
             .read_contract_multi(
                 &txinfos,
                 start_block_height,
                 precompile_data,
                 Some(estimated_gases.as_ref()),
+                &EvmOverrides::default(),
             )
-            .await;
-        let Ok(results) = receipts else {
+            .await
+        else {
             return Err(wrap_rpc_error_string_with_data(
                 3,
                 "Call failed",
@@ -873,14 +1046,10 @@ impl Brc20ProgApiServer for RpcServer {
             ));
         };
 
-        let end_block_height = if let Some(block_height) = block_height {
-            Some(
-                self.parse_block_number(&block_height)
-                    .map_err(wrap_rpc_error)?,
-            )
-        } else {
-            None
-        };
+        let end_block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
 
         if start_block_height != end_block_height {
             debug!(
@@ -922,23 +1091,117 @@ impl Brc20ProgApiServer for RpcServer {
     }
 
     #[instrument(skip(self), level = "error")]
-    async fn eth_get_storage_at(&self, contract: AddressED, location: U256ED) -> RpcResult<String> {
+    async fn eth_simulate_v1(
+        &self,
+        payload: SimulatePayload,
+        block_height: Option<BlockNumberOrHash>,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Vec<SimulatedBlock>> {
+        log_call();
+        let block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+
+        let mut blocks = Vec::with_capacity(payload.block_state_calls.len());
+        for block in payload.block_state_calls {
+            let mut tx_infos = Vec::with_capacity(block.calls.len());
+            for call in &block.calls {
+                tx_infos.push(call_tx_info(call).map_err(wrap_rpc_error)?);
+            }
+            blocks.push(SimulateBlockCalls {
+                tx_infos,
+                gas_limits: block.calls.iter().map(call_gas_limit).collect(),
+                precompile_data: block.precompile_data,
+                overrides: EvmOverrides::new(block.state_overrides, block.block_overrides),
+            });
+        }
+        debug!("eth_simulate_v1: prepared {} blocks", blocks.len());
+
+        self.engine
+            .simulate(&blocks, block_height, options.as_ref())
+            .await
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_create_access_list(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+    ) -> RpcResult<AccessListResult> {
         log_call();
+        let tx_info = call_tx_info(&call).map_err(wrap_rpc_error)?;
+        let block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+        self.engine
+            .create_access_list(
+                &tx_info,
+                block_height,
+                Some(call_gas_limit(&call)),
+                &EvmOverrides::new(state_overrides, None),
+            )
+            .await
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_get_storage_at(
+        &self,
+        contract: AddressED,
+        location: U256ED,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<String> {
+        log_call();
+        let block_number = self
+            .resolve_optional_block(block.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
         Ok(format!(
             "0x{}",
-            hex::encode(self.engine
-                .get_storage_at(contract.address, location.uint)
-                .map_err(wrap_rpc_error)?
-                .to_be_bytes_vec())
+            hex::encode(
+                self.engine
+                    .get_storage_at(contract.address, location.uint, block_number)
+                    .map_err(wrap_rpc_error)?
+                    .to_be_bytes_vec()
+            )
         ))
     }
 
     #[instrument(skip(self), level = "error")]
-    async fn eth_get_code(&self, contract: AddressED) -> RpcResult<BytecodeED> {
+    async fn eth_get_proof(
+        &self,
+        account: AddressED,
+        storage_keys: Vec<JsonStorageKey>,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
+        log_call();
+        let block_number = self
+            .resolve_optional_block(block.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+        self.engine
+            .get_proof(account.address, storage_keys, block_number)
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_get_code(
+        &self,
+        contract: AddressED,
+        block: Option<BlockNumberOrHash>,
+    ) -> RpcResult<BytecodeED> {
         log_call();
+        let block_number = self
+            .resolve_optional_block(block.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
         if let Some(bytecode) = self
             .engine
-            .get_contract_bytecode(contract.address)
+            .get_contract_bytecode(contract.address, block_number)
             .map_err(wrap_rpc_error)?
         {
             Ok(bytecode)
@@ -961,29 +1224,126 @@ impl Brc20ProgApiServer for RpcServer {
     #[instrument(skip(self), level = "error")]
     async fn debug_trace_transaction(&self, transaction: B256ED) -> RpcResult<Option<TraceED>> {
         log_call();
+        match self
+            .engine
+            .get_trace(transaction.bytes, None)
+            .map_err(wrap_rpc_error)?
+        {
+            Some(TraceResult::Call(trace)) => Ok(Some(*trace)),
+            Some(_) => Err(wrap_rpc_error_string("Expected a callTracer trace")),
+            None => Ok(None),
+        }
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn debug_trace_call(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<TraceResult> {
+        log_call();
+        let tx_info = call_tx_info(&call).map_err(wrap_rpc_error)?;
+        let block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+        self.engine
+            .trace_call(&tx_info, block_height, Some(call_gas_limit(&call)), options)
+            .await
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn debug_trace_block_by_number(
+        &self,
+        block: BlockNumberOrHash,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<Vec<TraceResult>>> {
+        log_call();
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         self.engine
-            .get_trace(transaction.bytes)
+            .get_block_traces(block_number, options)
             .map_err(wrap_rpc_error)
     }
 
     #[instrument(skip(self), level = "error")]
-    async fn debug_get_block_trace_string(&self, block: String) -> RpcResult<Option<String>> {
+    async fn debug_trace_block_by_hash(
+        &self,
+        block: B256ED,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<Vec<TraceResult>>> {
         log_call();
-        let block_number = self.parse_block_number(&block).map_err(wrap_rpc_error)?;
+        let Some(block) = self
+            .engine
+            .get_block_by_hash(block.bytes, false)
+            .map_err(wrap_rpc_error)?
+        else {
+            return Ok(None);
+        };
+        self.engine
+            .get_block_traces(block.number.into(), options)
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn debug_get_block_trace_string(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>> {
+        log_call();
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         self.engine
             .get_block_trace_string(block_number)
             .map_err(wrap_rpc_error)
     }
 
     #[instrument(skip(self), level = "error")]
-    async fn debug_get_block_trace_hash(&self, block: String) -> RpcResult<Option<String>> {
+    async fn debug_get_block_trace_hash(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>> {
         log_call();
-        let block_number = self.parse_block_number(&block).map_err(wrap_rpc_error)?;
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
         self.engine
             .get_block_trace_hash(block_number)
             .map_err(wrap_rpc_error)
     }
 
+    #[instrument(skip(self), level = "error")]
+    async fn brc20_get_consensus_hash(
+        &self,
+        block: BlockNumberOrHash,
+    ) -> RpcResult<Option<B256ED>> {
+        log_call();
+        let block_number = self.resolve_block(&block).await.map_err(wrap_rpc_error)?;
+        self.engine
+            .get_consensus_hash(block_number)
+            .map(|hash| hash.map(B256ED::from))
+            .map_err(wrap_rpc_error)
+    }
+
+    #[instrument(skip(self, reference_hashes), level = "error")]
+    async fn brc20_find_divergent_block(
+        &self,
+        first_block: BlockNumberOrHash,
+        reference_hashes: Vec<B256ED>,
+    ) -> RpcResult<Option<U64ED>> {
+        log_call();
+        let first_block_number = self
+            .resolve_block(&first_block)
+            .await
+            .map_err(wrap_rpc_error)?;
+        let reference_hashes = reference_hashes
+            .into_iter()
+            .map(|hash| hash.bytes)
+            .collect::<Vec<_>>();
+        self.engine
+            .find_divergent_block(first_block_number, &reference_hashes)
+            .map(|block_number| block_number.map(U64ED::from))
+            .map_err(wrap_rpc_error)
+    }
+
     #[instrument(skip(self), level = "error")]
     async fn eth_get_transaction_by_hash(&self, transaction: B256ED) -> RpcResult<Option<TxED>> {
         log_call();
@@ -1068,12 +1428,12 @@ impl Brc20ProgApiServer for RpcServer {
     #[instrument(name = "debug_getRawHeader", skip(self), level = "error")]
     async fn debug_get_raw_header(
         &self,
-        block_hash_or_number: String,
+        block_hash_or_number: BlockNumberOrHash,
     ) -> RpcResult<Option<String>> {
         log_call();
         self.engine
             .get_raw_header(
-                self.resolve_block_hash_or_number(&block_hash_or_number)
+                self.resolve_block(&block_hash_or_number)
                     .await
                     .map_err(wrap_rpc_error)?,
             )
@@ -1081,11 +1441,14 @@ impl Brc20ProgApiServer for RpcServer {
     }
 
     #[instrument(name = "debug_getRawBlock", skip(self), level = "error")]
-    async fn debug_get_raw_block(&self, block_hash_or_number: String) -> RpcResult<Option<String>> {
+    async fn debug_get_raw_block(
+        &self,
+        block_hash_or_number: BlockNumberOrHash,
+    ) -> RpcResult<Option<String>> {
         log_call();
         self.engine
             .get_raw_block(
-                self.resolve_block_hash_or_number(&block_hash_or_number)
+                self.resolve_block(&block_hash_or_number)
                     .await
                     .map_err(wrap_rpc_error)?,
             )
@@ -1095,12 +1458,12 @@ impl Brc20ProgApiServer for RpcServer {
     #[instrument(name = "debug_getRawReceipts", skip(self), level = "error")]
     async fn debug_get_raw_receipts(
         &self,
-        block_hash_or_number: String,
+        block_hash_or_number: BlockNumberOrHash,
     ) -> RpcResult<Option<Vec<String>>> {
         log_call();
         self.engine
             .get_raw_receipts(
-                self.resolve_block_hash_or_number(&block_hash_or_number)
+                self.resolve_block(&block_hash_or_number)
                     .await
                     .map_err(wrap_rpc_error)?,
             )
@@ -1108,6 +1471,150 @@ impl Brc20ProgApiServer for RpcServer {
     }
 }
 
+#[async_trait]
+impl Brc20ProgTracingApiServer for RpcServer {
+    #[instrument(skip(self), level = "error")]
+    async fn debug_trace_transaction_with_options(
+        &self,
+        transaction: B256ED,
+        options: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<Option<TraceResult>> {
+        log_call();
+        self.engine
+            .get_trace(transaction.bytes, options)
+            .map_err(wrap_rpc_error)
+    }
+}
+
+#[async_trait]
+impl Brc20ProgOverridesApiServer for RpcServer {
+    #[instrument(skip(self), level = "error")]
+    async fn eth_call_with_overrides(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<String> {
+        log_call();
+        let tx_info = call_tx_info(&call).map_err(wrap_rpc_error)?;
+        let block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+        let receipt = self
+            .engine
+            .read_contract(
+                &tx_info,
+                block_height,
+                Some(call_gas_limit(&call)),
+                &EvmOverrides::new(state_overrides, block_overrides),
+            )
+            .await;
+        let Ok(result) = receipt else {
+            return Err(wrap_rpc_error_string_with_data(
+                3,
+                "Call failed",
+                "0x".into(),
+            ));
+        };
+        let data_string = result.output.unwrap_or(Bytes::new()).to_string();
+        if !result.status {
+            return Err(wrap_rpc_error_string_with_data(
+                3,
+                format!("Execution reverted: {}", result.status_string).as_str(),
+                data_string,
+            ));
+        }
+        Ok(data_string)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_call_many_with_overrides(
+        &self,
+        calls: Vec<EthCall>,
+        block_height: Option<BlockNumberOrHash>,
+        precompile_data: Option<PrecompileData>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<String>> {
+        log_call();
+        let block_height = self
+            .resolve_optional_block(block_height.as_ref())
+            .await
+            .map_err(wrap_rpc_error)?;
+
+        let mut txinfos = Vec::with_capacity(calls.len());
+        for call in &calls {
+            txinfos.push(call_tx_info(call).map_err(wrap_rpc_error)?);
+        }
+        let gas_limits: Vec<u64> = calls.iter().map(call_gas_limit).collect();
+        debug!("eth_call_many: prepared {} calls", txinfos.len());
+
+        let receipts = self
+            .engine
+            .read_contract_multi(
+                &txinfos,
+                block_height,
+                precompile_data,
+                Some(gas_limits.as_ref()),
+                &EvmOverrides::new(state_overrides, block_overrides),
+            )
+            .await;
+        let Ok(results) = receipts else {
+            return Err(wrap_rpc_error_string_with_data(
+                3,
+                "Call failed",
+                "0x".into(),
+            ));
+        };
+
+        let mut outputs = Vec::with_capacity(results.len());
+        let mut result_idx = 0;
+        for result in results {
+            let data_string = result.output.unwrap_or(Bytes::new()).to_string();
+
+            if !result.status {
+                return Err(wrap_rpc_error_string_with_data(
+                    3,
+                    format!(
+                        "Execution with index {} reverted: {}",
+                        result_idx, result.status_string
+                    )
+                    .as_str(),
+                    data_string,
+                ));
+            }
+            outputs.push(data_string);
+            result_idx += 1;
+        }
+
+        Ok(outputs)
+    }
+
+    #[instrument(skip(self), level = "error")]
+    async fn eth_estimate_gas_with_overrides(
+        &self,
+        call: EthCall,
+        block_height: Option<BlockNumberOrHash>,
+        state_overrides: Option<StateOverride>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<String> {
+        log_call();
+        let tx_info = call_tx_info(&call).map_err(wrap_rpc_error)?;
+        let overrides = EvmOverrides::new(state_overrides, block_overrides);
+        let estimated_gas = self
+            .estimate_gas(
+                &tx_info,
+                block_height.as_ref(),
+                call_gas_limit(&call),
+                &overrides,
+            )
+            .await?;
+        Ok(format!("0x{:x}", estimated_gas))
+    }
+}
+
 pub async fn start_rpc_server(
     engine: BRC20ProgEngine,
     config: Brc20ProgConfig,
@@ -1144,7 +1651,28 @@ pub async fn start_rpc_server(
     let rpc_middleware = RpcServiceBuilder::new()
         .rpc_logger(1024)
         .layer_fn(|service| RpcAuthMiddleware::new(service, &*INDEXER_METHODS));
-    let module = RpcServer { engine }.into_rpc();
+    let subscription_server = SubscriptionServer {
+        chain_events: engine.chain_events(),
+    };
+    let filters = Arc::new(FilterRegistry::new(engine.chain_events()));
+    tokio::spawn(remove_expired_filters(Arc::downgrade(&filters)));
+    let rpc_server = RpcServer {
+        engine: Arc::new(engine),
+        filters,
+    };
+    let mut module = Brc20ProgApiServer::into_rpc(rpc_server.clone());
+    // Served by Brc20ProgOverridesApi and Brc20ProgTracingApi, which also accept overrides and tracer options
+    for method in [
+        "eth_call",
+        "eth_callMany",
+        "eth_estimateGas",
+        "debug_traceTransaction",
+    ] {
+        module.remove_method(method);
+    }
+    module.merge(Brc20ProgOverridesApiServer::into_rpc(rpc_server.clone()))?;
+    module.merge(Brc20ProgTracingApiServer::into_rpc(rpc_server))?;
+    module.merge(subscription_server.into_rpc())?;
 
     let handle = Server::builder()
         .set_config(
@@ -1156,6 +1684,8 @@ pub async fn start_rpc_server(
                 } else {
                     BatchRequestConfig::Limit(config.batch_request_limit)
                 })
+                // Subscription IDs are strings, as expected by Ethereum clients
+                .set_id_provider(RandomStringIdProvider::new(16))
                 .build(),
         )
         .set_http_middleware(http_middleware)
@@ -1184,8 +1714,10 @@ mod tests {
     fn create_test_server() -> RpcServer {
         let temp_dir = TempDir::new().unwrap();
         let db = Brc20ProgDatabase::new(temp_dir.path()).unwrap();
+        let engine = BRC20ProgEngine::new(db);
         RpcServer {
-            engine: BRC20ProgEngine::new(db),
+            filters: Arc::new(FilterRegistry::new(engine.chain_events())),
+            engine: Arc::new(engine),
         }
     }
 
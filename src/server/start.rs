@@ -4,7 +4,10 @@ use std::path::Path;
 use jsonrpsee::server::ServerHandle;
 use tracing::info;
 
-use crate::db::Brc20ProgDatabase;
+use crate::db::{
+    export_snapshot as export_database_snapshot, import_snapshot as import_database_snapshot,
+    migrate_database, Brc20ProgDatabase,
+};
 use crate::engine::BRC20ProgEngine;
 use crate::global::database::validate_config_database;
 use crate::global::{validate_config, Brc20ProgConfig, CONFIG};
@@ -12,7 +15,8 @@ use crate::server::rpc_server::start_rpc_server;
 
 /// Starts the BRC20 programmable module server.
 ///
-/// This function initializes the logging, validates the configuration, initializes the database, and starts the JSON-RPC server.
+/// This function initializes the logging, migrates a database created by an older version, validates the configuration,
+/// initializes the database, and starts the JSON-RPC server.
 ///
 /// Server can be configured by passing a `Brc20ProgConfig` instance.
 ///
@@ -33,6 +37,7 @@ use crate::server::rpc_server::start_rpc_server;
 ///
 /// This function will return an error if:
 /// * The logging initialization fails.
+/// * The database migration fails.
 /// * The configuration validation fails.
 /// * The database initialization fails.
 /// * The JSON-RPC server fails to start.
@@ -76,10 +81,117 @@ pub async fn start(config: Brc20ProgConfig) -> Result<ServerHandle, Box<dyn Erro
         *value = config.clone();
     });
 
+    migrate_database(&config)?;
     validate_config_database(&config)?;
     validate_config(&config)?;
 
-    let engine = BRC20ProgEngine::new(Brc20ProgDatabase::new(&Path::new(&config.db_path))?);
+    let engine = BRC20ProgEngine::new(Brc20ProgDatabase::with_archive_mode(
+        &Path::new(&config.db_path),
+        config.archive_mode,
+    )?);
     info!("Latest block number: {}", engine.get_latest_block_height()?);
     start_rpc_server(engine, config).await
 }
+
+/// Migrates the database of the BRC20 programmable module to the current version.
+///
+/// Databases created by older versions are migrated step by step, each step is resumable, so an interrupted
+/// migration continues from where it stopped when this is called again. Progress is logged as the migration runs.
+///
+/// `start` also runs the migration, this can be used to migrate the database without starting the server.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The database version is too old to be migrated, the database has to be reindexed from genesis.
+/// * A migration step fails.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use brc20_prog::{Brc20ProgConfig, migrate};
+///
+/// pub fn migrate_database() -> Result<(), Box<dyn Error>> {
+///     migrate(Brc20ProgConfig::from_env())
+/// }
+/// ```
+pub fn migrate(config: Brc20ProgConfig) -> Result<(), Box<dyn Error>> {
+    // Transactions are decoded with the configured chain id
+    CONFIG.write_fn_unchecked(|value| {
+        *value = config.clone();
+    });
+
+    migrate_database(&config)
+}
+
+/// Exports a snapshot of the state of the latest committed block, to bootstrap other databases with `import_snapshot`.
+///
+/// The snapshot is written to a directory, with a file per store and a `manifest.json` file describing the snapshot,
+/// including its block hash, state root, consensus hash and the SHA256 hashes of the store files. It has accounts,
+/// code, storage, inscription ID mappings, the pending transaction pool and the last 256 blocks. Transactions,
+/// receipts, logs and traces are not exported.
+///
+/// The database is migrated to the current version first, and the server shouldn't be running on it.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The database doesn't exist, or has no blocks.
+/// * The snapshot directory is not empty.
+/// * The database migration fails.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use std::path::Path;
+/// use brc20_prog::{Brc20ProgConfig, export_snapshot};
+///
+/// pub fn export() -> Result<u64, Box<dyn Error>> {
+///     export_snapshot(Brc20ProgConfig::from_env(), Path::new("snapshot"))
+/// }
+/// ```
+pub fn export_snapshot(config: Brc20ProgConfig, path: &Path) -> Result<u64, Box<dyn Error>> {
+    CONFIG.write_fn_unchecked(|value| {
+        *value = config.clone();
+    });
+
+    migrate_database(&config)?;
+    validate_config_database(&config)?;
+    export_database_snapshot(&config, path)
+}
+
+/// Imports a snapshot exported by `export_snapshot` into an empty database, returning the block number of the snapshot.
+///
+/// Store files are checked against the hashes in the manifest before they are imported, and the state trie is
+/// built from the imported accounts and checked against the state root of the snapshot block. Snapshots should come
+/// from a trusted source, their block hash and consensus hash can be compared with another indexer.
+///
+/// Indexing continues from the block after the snapshot, blocks before it can't be reorged or traced.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// * The database path is not empty, or archive mode is enabled.
+/// * The snapshot was exported by another version, or for another network.
+/// * A store file doesn't match its hash, or the state root doesn't match the snapshot block.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use std::path::Path;
+/// use brc20_prog::{Brc20ProgConfig, import_snapshot};
+///
+/// pub fn import() -> Result<u64, Box<dyn Error>> {
+///     import_snapshot(Brc20ProgConfig::from_env(), Path::new("snapshot"))
+/// }
+/// ```
+pub fn import_snapshot(config: Brc20ProgConfig, path: &Path) -> Result<u64, Box<dyn Error>> {
+    CONFIG.write_fn_unchecked(|value| {
+        *value = config.clone();
+    });
+
+    import_database_snapshot(&config, path)
+}
@@ -0,0 +1,124 @@
+use alloy::primitives::{Address, B256};
+use jsonrpsee::core::{async_trait, SubscriptionResult};
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use serde::Serialize;
+use serde_either::SingleOrVec;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{instrument, warn};
+
+use crate::api::types::GetLogsFilter;
+use crate::api::Brc20ProgSubscriptionApiServer;
+use crate::engine::ChainEvent;
+use crate::server::error::wrap_rpc_error_string;
+
+pub struct SubscriptionServer {
+    pub chain_events: broadcast::Sender<ChainEvent>,
+}
+
+enum SubscriptionKind {
+    NewHeads,
+    Logs {
+        address: Option<Address>,
+        topics: Option<Vec<SingleOrVec<Option<B256>>>>,
+    },
+}
+
+impl SubscriptionKind {
+    fn parse(kind: &str, filter: Option<GetLogsFilter>) -> Option<Self> {
+        match kind {
+            "newHeads" => Some(Self::NewHeads),
+            "logs" => Some(Self::Logs {
+                address: filter
+                    .as_ref()
+                    .and_then(|x| x.address.as_ref())
+                    .map(|x| x.address),
+                topics: filter.and_then(|x| x.topics_as_b256()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the items to be sent to the subscriber for the event, in order
+    fn items(&self, event: &ChainEvent) -> Result<Vec<SubscriptionMessage>, serde_json::Error> {
+        match (self, event) {
+            (Self::NewHeads, ChainEvent::NewBlock { block, .. }) => Ok(vec![to_message(block)?]),
+            (Self::NewHeads, ChainEvent::Reorg { .. }) => Ok(Vec::new()),
+            (Self::Logs { address, topics }, ChainEvent::NewBlock { logs, .. })
+            | (
+                Self::Logs { address, topics },
+                ChainEvent::Reorg {
+                    removed_logs: logs, ..
+                },
+            ) => logs
+                .iter()
+                .filter(|log| log.matches(*address, topics))
+                .map(to_message)
+                .collect(),
+        }
+    }
+}
+
+fn to_message(item: &impl Serialize) -> Result<SubscriptionMessage, serde_json::Error> {
+    Ok(serde_json::value::to_raw_value(item)?.into())
+}
+
+async fn send_events(
+    sink: SubscriptionSink,
+    kind: SubscriptionKind,
+    mut events: broadcast::Receiver<ChainEvent>,
+) {
+    loop {
+        let event = tokio::select! {
+            _ = sink.closed() => return,
+            event = events.recv() => event,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                // Subscribers can't recover from missing events, so they are dropped
+                warn!("Subscriber missed {} events, closing subscription", skipped);
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let items = match kind.items(&event) {
+            Ok(items) => items,
+            Err(error) => {
+                warn!("Failed to serialize subscription item: {}", error);
+                return;
+            }
+        };
+        for item in items {
+            if sink.send(item).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Brc20ProgSubscriptionApiServer for SubscriptionServer {
+    #[instrument(skip(self, pending), level = "error")]
+    async fn eth_subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kind: String,
+        filter: Option<GetLogsFilter>,
+    ) -> SubscriptionResult {
+        let Some(kind) = SubscriptionKind::parse(&kind, filter) else {
+            pending
+                .reject(wrap_rpc_error_string(
+                    "Unsupported subscription, only newHeads and logs are supported",
+                ))
+                .await;
+            return Ok(());
+        };
+
+        // Receiver is created before accepting, so no events are missed after the response
+        let events = self.chain_events.subscribe();
+        let sink = pending.accept().await?;
+        tokio::spawn(send_events(sink, kind, events));
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
 use std::error::Error;
 use std::net::TcpListener;
+use std::path::Path;
 
 use brc20_prog::types::{EthCall, RawBytes};
 use brc20_prog::{start, Brc20ProgApiClient, Brc20ProgConfig};
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::server::ServerHandle;
+use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
 use rust_embed::Embed;
 use tempfile::TempDir;
 use tokio::runtime::Runtime;
@@ -19,11 +21,10 @@ fn get_free_port() -> u16 {
     port
 }
 
-pub async fn spawn_test_server(config: Brc20ProgConfig) -> (ServerHandle, HttpClient) {
-    let db_path = TempDir::new().unwrap();
+async fn spawn_server(db_path: &Path, config: Brc20ProgConfig) -> (ServerHandle, String) {
     let server_address = format!("127.0.0.1:{}", get_free_port());
     let server = start(Brc20ProgConfig {
-        db_path: db_path.path().to_str().unwrap().to_string(),
+        db_path: db_path.to_str().unwrap().to_string(),
         brc20_prog_rpc_server_url: server_address.clone(),
         fail_on_bitcoin_rpc_error: false,
         evm_record_traces: true,
@@ -32,6 +33,33 @@ pub async fn spawn_test_server(config: Brc20ProgConfig) -> (ServerHandle, HttpCl
     .await
     .expect("Failed to start server");
 
+    (server, server_address)
+}
+
+/// Removes the database directory once the server is stopped or the test runtime shuts down
+fn remove_when_stopped(server: &ServerHandle, db_path: TempDir) {
+    let server = server.clone();
+    tokio::spawn(async move {
+        server.stopped().await;
+        drop(db_path);
+    });
+}
+
+pub async fn spawn_test_server(config: Brc20ProgConfig) -> (ServerHandle, HttpClient) {
+    let db_path = TempDir::new().unwrap();
+    let (server, client) = spawn_test_server_with_db_path(db_path.path(), config).await;
+    remove_when_stopped(&server, db_path);
+
+    (server, client)
+}
+
+/// Spawns a test server using the database at the given path, e.g. to restart a server on the same database
+pub async fn spawn_test_server_with_db_path(
+    db_path: &Path,
+    config: Brc20ProgConfig,
+) -> (ServerHandle, HttpClient) {
+    let (server, server_address) = spawn_server(db_path, config).await;
+
     (
         server,
         HttpClientBuilder::default()
@@ -40,6 +68,41 @@ pub async fn spawn_test_server(config: Brc20ProgConfig) -> (ServerHandle, HttpCl
     )
 }
 
+pub async fn spawn_test_server_with_ws_client(
+    config: Brc20ProgConfig,
+) -> (ServerHandle, HttpClient, WsClient) {
+    let db_path = TempDir::new().unwrap();
+    let (server, server_address) = spawn_server(db_path.path(), config).await;
+    remove_when_stopped(&server, db_path);
+
+    (
+        server,
+        HttpClientBuilder::default()
+            .build(format!("http://{}", server_address))
+            .expect("Failed to create client"),
+        WsClientBuilder::default()
+            .build(format!("ws://{}", server_address))
+            .await
+            .expect("Failed to create WebSocket client"),
+    )
+}
+
+/// The hex encoded "test pkscript", used as the sender and receiver of test inscriptions
+pub const TEST_PKSCRIPT: &str = "7465737420706b736372697074";
+
+/// Deploys the BRC20 controller, Bitcoin RPC is unreachable in tests, so only that check can fail
+pub async fn initialise_controller(client: &HttpClient, timestamp: u64) {
+    if let Err(e) = client
+        .brc20_initialise([0u8; 32].into(), timestamp, 0)
+        .await
+    {
+        assert!(
+            e.to_string().contains("Bitcoin RPC"),
+            "brc20_initialise failed for an unexpected reason: {e}"
+        );
+    }
+}
+
 pub fn is_in_ci() -> bool {
     // Check if the environment variable "CI" is set to "true"
     std::env::var("CI").map_or(false, |val| val == "true")
@@ -0,0 +1,276 @@
+use std::error::Error;
+
+use alloy::primitives::{B256, U64};
+use alloy::trie::EMPTY_ROOT_HASH;
+
+use brc20_prog::types::{BlockOperation, RawBytes};
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::{initialise_controller, load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
+
+/// A block with a deploy, a call to the deployed contract by its inscription id and a deposit is
+/// added in one request, and a block with a failing operation is discarded as a whole.
+#[tokio::test]
+async fn test_add_block() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let from_pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let receipts = client
+        .brc20_add_block(
+            timestamp,
+            [1u8; 32].into(),
+            vec![
+                BlockOperation::Deploy {
+                    from_pkscript: from_pkscript.clone(),
+                    data: RawBytes::new(deploy_data.clone()).into(),
+                    base64_data: None,
+                    inscription_id: "deploy_inscription".to_string(),
+                    inscription_byte_len: deploy_data.len() as u64,
+                    op_return_tx_id: [1; 32].into(),
+                },
+                BlockOperation::Call {
+                    from_pkscript: from_pkscript.clone(),
+                    contract_address: None,
+                    contract_inscription_id: Some("deploy_inscription".to_string()),
+                    data: RawBytes::new(call_data.clone()).into(),
+                    base64_data: None,
+                    inscription_id: "call_inscription".to_string(),
+                    inscription_byte_len: call_data.len() as u64,
+                    op_return_tx_id: [2; 32].into(),
+                },
+                BlockOperation::Deposit {
+                    to_pkscript: from_pkscript.clone(),
+                    ticker: "test".to_string(),
+                    amount: 100u64.into(),
+                    inscription_id: "deposit_inscription".to_string(),
+                },
+            ],
+        )
+        .await?;
+
+    assert_eq!(receipts.len(), 3);
+    assert!(receipts.iter().all(|receipt| !receipt.status.is_zero()));
+    assert_eq!(receipts[1].to, receipts[0].contract_address);
+    for (tx_idx, receipt) in receipts.iter().enumerate() {
+        assert_eq!(receipt.transaction_index, (tx_idx as u64).into());
+    }
+
+    let block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    assert_eq!(block.hash, [1u8; 32].into());
+    assert_eq!(block.transactions.left().unwrap().len(), 3);
+    // The header commits to the state after the block and to its transactions and receipts
+    assert_ne!(block.state_root, B256::ZERO.into());
+    assert_ne!(block.transactions_root, EMPTY_ROOT_HASH.into());
+    assert_ne!(block.receipts_root, EMPTY_ROOT_HASH.into());
+    assert_eq!(
+        client
+            .brc20_balance(from_pkscript.clone(), "test".to_string())
+            .await?,
+        "0x64"
+    );
+
+    // The withdrawal has an invalid pkscript, so the deposit before it is discarded as well
+    assert!(client
+        .brc20_add_block(
+            timestamp,
+            [2u8; 32].into(),
+            vec![
+                BlockOperation::Deposit {
+                    to_pkscript: from_pkscript.clone(),
+                    ticker: "test".to_string(),
+                    amount: 100u64.into(),
+                    inscription_id: "discarded_deposit_inscription".to_string(),
+                },
+                BlockOperation::Withdraw {
+                    from_pkscript: "invalid pkscript".to_string(),
+                    ticker: "test".to_string(),
+                    amount: 100u64.into(),
+                    inscription_id: "discarded_withdraw_inscription".to_string(),
+                },
+            ],
+        )
+        .await
+        .is_err());
+
+    let latest_block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    assert_eq!(latest_block.hash, block.hash);
+    assert!(client
+        .brc20_get_tx_receipt_by_inscription_id("discarded_deposit_inscription".to_string())
+        .await?
+        .is_none());
+    assert_eq!(
+        client
+            .brc20_balance(from_pkscript.clone(), "test".to_string())
+            .await?,
+        "0x64"
+    );
+
+    // Next block can be added after a discarded one
+    let receipts = client
+        .brc20_add_block(
+            timestamp,
+            [3u8; 32].into(),
+            vec![BlockOperation::Withdraw {
+                from_pkscript: from_pkscript.clone(),
+                ticker: "test".to_string(),
+                amount: 40u64.into(),
+                inscription_id: "withdraw_inscription".to_string(),
+            }],
+        )
+        .await?;
+    assert_eq!(receipts.len(), 1);
+    assert!(!receipts[0].status.is_zero());
+    let latest_block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    assert_eq!(latest_block.hash, [3u8; 32].into());
+    assert_eq!(latest_block.number.uint, block.number.uint + U64::from(1));
+    assert_ne!(latest_block.state_root, block.state_root);
+    assert_eq!(
+        client
+            .brc20_balance(from_pkscript, "test".to_string())
+            .await?,
+        "0x3c"
+    );
+
+    server.stop()?;
+    Ok(())
+}
+
+/// A partially indexed block is aborted without losing the uncommitted finalised blocks, and
+/// it can be indexed again from the first transaction.
+#[tokio::test]
+async fn test_abort_block() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let pkscript = TEST_PKSCRIPT.to_string();
+    let ticker = "test".to_string();
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+
+    client
+        .brc20_deposit(
+            pkscript.clone(),
+            ticker.clone(),
+            100u64.into(),
+            timestamp,
+            [1u8; 32].into(),
+            0,
+            "finalised_deposit".to_string(),
+        )
+        .await?;
+    client
+        .brc20_finalise_block(timestamp, [1u8; 32].into(), 1)
+        .await?;
+
+    client
+        .brc20_deposit(
+            pkscript.clone(),
+            ticker.clone(),
+            100u64.into(),
+            timestamp,
+            [2u8; 32].into(),
+            0,
+            "aborted_deposit".to_string(),
+        )
+        .await?;
+
+    // Blocks can't be added while another one is being indexed
+    assert!(client
+        .brc20_add_block(timestamp, [2u8; 32].into(), vec![])
+        .await
+        .is_err());
+
+    client.brc20_abort_block().await?;
+
+    assert!(client
+        .brc20_get_tx_receipt_by_inscription_id("aborted_deposit".to_string())
+        .await?
+        .is_none());
+    assert!(client
+        .brc20_get_tx_receipt_by_inscription_id("finalised_deposit".to_string())
+        .await?
+        .is_some());
+
+    // The block is indexed again from the first transaction
+    client
+        .brc20_deposit(
+            pkscript.clone(),
+            ticker.clone(),
+            50u64.into(),
+            timestamp,
+            [2u8; 32].into(),
+            0,
+            "deposit".to_string(),
+        )
+        .await?;
+    client
+        .brc20_finalise_block(timestamp, [2u8; 32].into(), 1)
+        .await?;
+    client.brc20_commit_to_database().await?;
+
+    assert_eq!(client.brc20_balance(pkscript, ticker).await?, "0x96");
+
+    server.stop()?;
+    Ok(())
+}
+
+/// Concurrent blocks don't interleave, each call either indexes its whole block or fails without
+/// changing the block started by the other call.
+#[tokio::test]
+async fn test_concurrent_add_block() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let pkscript = TEST_PKSCRIPT.to_string();
+    let ticker = "test".to_string();
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+    let start_block = client.eth_block_number().await?;
+
+    let add_block = |hash: u8| {
+        let operations = (0..20)
+            .map(|i| BlockOperation::Deposit {
+                to_pkscript: pkscript.clone(),
+                ticker: ticker.clone(),
+                amount: 1u64.into(),
+                inscription_id: format!("deposit_{}_{}", hash, i),
+            })
+            .collect();
+        client.brc20_add_block(timestamp, [hash; 32].into(), operations)
+    };
+    let (first, second) = tokio::join!(add_block(1), add_block(2));
+    let added: Vec<_> = [(1u8, first), (2u8, second)]
+        .into_iter()
+        .filter_map(|(hash, result)| result.ok().map(|receipts| (hash, receipts)))
+        .collect();
+    assert!(!added.is_empty());
+
+    for (hash, receipts) in &added {
+        assert_eq!(receipts.len(), 20);
+        for (index, receipt) in receipts.iter().enumerate() {
+            assert_eq!(receipt.block_hash.bytes, B256::from([*hash; 32]));
+            assert_eq!(receipt.transaction_index.uint, U64::from(index));
+        }
+    }
+
+    let start_block = u64::from_str_radix(start_block.trim_start_matches("0x"), 16)?;
+    assert_eq!(
+        client.eth_block_number().await?,
+        format!("0x{:x}", start_block + added.len() as u64)
+    );
+    assert_eq!(
+        client.brc20_balance(pkscript, ticker).await?,
+        format!("0x{:x}", 20 * added.len())
+    );
+
+    server.stop()?;
+    Ok(())
+}
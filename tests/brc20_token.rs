@@ -2,7 +2,7 @@ use std::error::Error;
 
 use brc20_prog::Brc20ProgApiClient;
 use revm::primitives::U256;
-use test_utils::spawn_test_server;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
 
 fn balance_to_u256(balance: &str) -> U256 {
     U256::from_str_radix(balance.trim_start_matches("0x"), 16)
@@ -14,21 +14,11 @@ fn balance_to_u256(balance: &str) -> U256 {
 #[tokio::test]
 async fn test_deposit_balance_withdraw_roundtrip() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let pkscript = TEST_PKSCRIPT.to_string();
     let ticker = "TEST".to_string();
     let timestamp = 42;
 
-    // Deploy the BRC20_Controller. brc20_initialise also pings the Bitcoin RPC,
-    // which is unreachable in tests; the controller is deployed and genesis is
-    // finalised before that check runs. Tolerate only that specific failure so
-    // an unrelated init regression still fails the test (and it may succeed
-    // outright where a Bitcoin RPC is configured).
-    if let Err(e) = client.brc20_initialise([1u8; 32].into(), timestamp, 0).await {
-        assert!(
-            e.to_string().contains("Bitcoin RPC"),
-            "brc20_initialise failed for an unexpected reason: {e}"
-        );
-    }
+    initialise_controller(&client, timestamp).await;
 
     // No deposits yet.
     let balance = client.brc20_balance(pkscript.clone(), ticker.clone()).await?;
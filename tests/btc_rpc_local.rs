@@ -48,7 +48,7 @@ async fn test_btc_get_tx_details_signet_live() -> Result<(), Box<dyn Error>> {
                     Some(get_tx_details_precompile.into()),
                     load_file_as_eth_bytes("btc_get_tx_details_signet_call_tx_data")?,
                 ),
-                Some("latest".to_string()),
+                Some("latest".into()),
             )
             .await?;
 
@@ -0,0 +1,55 @@
+use std::error::Error;
+
+use brc20_prog::{Brc20ProgApiClient, Brc20ProgConfig};
+use test_utils::spawn_test_server;
+
+/// Changes are committed automatically every 2 finalised blocks, clearing caches only discards
+/// the blocks finalised after the last commit.
+#[tokio::test]
+async fn test_commit_every_blocks() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Brc20ProgConfig {
+        commit_every_blocks: 2,
+        ..Default::default()
+    })
+    .await;
+
+    // Blocks 0 and 1 are committed, block 2 is only in the caches
+    client.brc20_mine(3, 42).await?;
+    assert_eq!(client.eth_block_number().await?, "0x2");
+
+    client.brc20_clear_caches().await?;
+    assert_eq!(client.eth_block_number().await?, "0x1");
+
+    // Blocks 2 and 3 are committed, counting from the last commit
+    client.brc20_mine(2, 42).await?;
+    client.brc20_clear_caches().await?;
+    assert_eq!(client.eth_block_number().await?, "0x3");
+
+    // Manual commits are still available, and they reset the count
+    client.brc20_mine(1, 42).await?;
+    client.brc20_commit_to_database().await?;
+    client.brc20_mine(1, 42).await?;
+    client.brc20_clear_caches().await?;
+    assert_eq!(client.eth_block_number().await?, "0x4");
+
+    server.stop()?;
+    Ok(())
+}
+
+/// Changes are committed automatically when the number of cached entries exceeds the limit.
+#[tokio::test]
+async fn test_commit_cache_entries_limit() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Brc20ProgConfig {
+        commit_cache_entries_limit: 1,
+        ..Default::default()
+    })
+    .await;
+
+    // Every finalised block adds more than one cached entry, so it's committed right away
+    client.brc20_mine(2, 42).await?;
+    client.brc20_clear_caches().await?;
+    assert_eq!(client.eth_block_number().await?, "0x1");
+
+    server.stop()?;
+    Ok(())
+}
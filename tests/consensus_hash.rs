@@ -0,0 +1,88 @@
+use std::error::Error;
+
+use alloy::primitives::B256;
+use brc20_prog::types::BlockOperation;
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
+
+/// Consensus hashes change with every block, and the first divergent block is found against a list
+/// of reference hashes.
+#[tokio::test]
+async fn test_consensus_hash() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+    for i in 1..=3u8 {
+        client
+            .brc20_add_block(
+                timestamp,
+                [i; 32].into(),
+                vec![BlockOperation::Deposit {
+                    to_pkscript: pkscript.clone(),
+                    ticker: "test".to_string(),
+                    amount: 100u64.into(),
+                    inscription_id: format!("deposit_inscription_{}", i),
+                }],
+            )
+            .await?;
+    }
+
+    let mut hashes = Vec::new();
+    for block_number in 0..=3 {
+        let hash = client
+            .brc20_get_consensus_hash(block_number.to_string().into())
+            .await?
+            .unwrap();
+        assert!(!hashes.contains(&hash));
+        hashes.push(hash);
+    }
+    assert_eq!(
+        client.brc20_get_consensus_hash("latest".into()).await?,
+        hashes.last().cloned()
+    );
+    assert!(client
+        .brc20_get_consensus_hash("0x100".into())
+        .await?
+        .is_none());
+
+    // Matching hashes have no divergent block
+    assert!(client
+        .brc20_find_divergent_block("0x0".into(), hashes.clone())
+        .await?
+        .is_none());
+    assert!(client
+        .brc20_find_divergent_block("2".into(), hashes[2..].to_vec())
+        .await?
+        .is_none());
+
+    // A different hash diverges at its block
+    let mut reference_hashes = hashes.clone();
+    reference_hashes[2] = B256::ZERO.into();
+    assert_eq!(
+        client
+            .brc20_find_divergent_block("0".into(), reference_hashes)
+            .await?,
+        Some(2u64.into())
+    );
+
+    // Blocks that are not indexed yet diverge as well
+    let mut reference_hashes = hashes.clone();
+    reference_hashes.push(B256::ZERO.into());
+    assert_eq!(
+        client
+            .brc20_find_divergent_block("0".into(), reference_hashes)
+            .await?,
+        Some(4u64.into())
+    );
+
+    // Block numbers past the last block number are rejected
+    assert!(client
+        .brc20_find_divergent_block("0xffffffffffffffff".into(), hashes.clone())
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
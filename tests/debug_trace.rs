@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use alloy::primitives::Bytes;
+use alloy_rpc_types_trace::geth::{
+    GethDebugBuiltInTracerType, GethDebugTracingOptions, PreStateConfig, PreStateFrame,
+};
+use brc20_prog::types::{EthCall, RawBytes, TraceResult};
+use brc20_prog::{Brc20ProgApiClient, Brc20ProgTracingApiClient};
+use revm::primitives::U256;
+use test_utils::{load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
+
+/// debug_traceCall traces a read-only call, debug_traceBlockBy* return the traces of every transaction in a block.
+#[tokio::test]
+async fn test_debug_trace_call_and_block() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let from_pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+    let block_hash = [3u8; 32].into();
+
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let deploy_data_length = deploy_data.len() as u64;
+    let deploy_response = client
+        .brc20_deploy(
+            from_pkscript.clone(),
+            RawBytes::new(deploy_data).into(),
+            None,
+            timestamp,
+            block_hash,
+            0,
+            "deploy_inscription".to_string(),
+            deploy_data_length,
+            [1; 32].into(),
+        )
+        .await?;
+    let contract_address = deploy_response.contract_address.unwrap();
+
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let call_data_length = call_data.len() as u64;
+    let call_response = client
+        .brc20_call(
+            from_pkscript,
+            contract_address.clone().into(),
+            None,
+            RawBytes::new(call_data.clone()).into(),
+            None,
+            timestamp,
+            block_hash,
+            1,
+            "call_inscription".to_string(),
+            call_data_length,
+            [2; 32].into(),
+        )
+        .await?
+        .unwrap();
+    client
+        .brc20_finalise_block(timestamp, block_hash, 2)
+        .await?;
+
+    let expected_output =
+        Bytes::from_str(&load_file_as_string("brc20_prog_helper_call_response")?).unwrap();
+
+    let TraceResult::Call(call_trace) = client
+        .debug_trace_call(
+            EthCall::new(None, Some(contract_address), RawBytes::new(call_data)),
+            Some("latest".into()),
+            None,
+        )
+        .await?
+    else {
+        panic!("Expected a callTracer trace");
+    };
+    assert_eq!(call_trace.output.bytes, expected_output);
+
+    let transaction_trace = client
+        .debug_trace_transaction(call_response.transaction_hash)
+        .await?
+        .unwrap();
+    // Tracing API returns the same trace if no options are given
+    assert_eq!(
+        client
+            .debug_trace_transaction_with_options(call_response.transaction_hash, None)
+            .await?,
+        Some(TraceResult::Call(Box::new(transaction_trace.clone())))
+    );
+
+    let traces = client
+        .debug_trace_block_by_number("0x0".into(), None)
+        .await?
+        .unwrap();
+    assert_eq!(traces.len(), 2);
+    assert_eq!(traces[1], TraceResult::Call(Box::new(transaction_trace)));
+    let TraceResult::Call(trace) = &traces[1] else {
+        panic!("Expected a callTracer trace");
+    };
+    assert_eq!(trace.output.bytes, expected_output);
+
+    let traces_by_hash = client
+        .debug_trace_block_by_hash(block_hash, None)
+        .await?
+        .unwrap();
+    assert_eq!(traces_by_hash, traces);
+
+    assert!(client
+        .debug_trace_block_by_number("0x1".into(), None)
+        .await?
+        .is_none());
+    assert!(client
+        .debug_trace_block_by_hash([4u8; 32].into(), None)
+        .await?
+        .is_none());
+
+    server.stop()?;
+    Ok(())
+}
+
+/// debug_traceCall supports prestateTracer, 4byteTracer and the struct logger.
+#[tokio::test]
+async fn test_debug_trace_call_tracers() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let from_pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+    let block_hash = [3u8; 32].into();
+
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let deploy_data_length = deploy_data.len() as u64;
+    let contract_address = client
+        .brc20_deploy(
+            from_pkscript,
+            RawBytes::new(deploy_data).into(),
+            None,
+            timestamp,
+            block_hash,
+            0,
+            "deploy_inscription".to_string(),
+            deploy_data_length,
+            [1; 32].into(),
+        )
+        .await?
+        .contract_address
+        .unwrap();
+    client
+        .brc20_finalise_block(timestamp, block_hash, 1)
+        .await?;
+
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let eth_call = EthCall::new(
+        None,
+        Some(contract_address.clone()),
+        RawBytes::new(call_data.clone()),
+    );
+
+    let TraceResult::PreState(PreStateFrame::Default(prestate)) = client
+        .debug_trace_call(
+            eth_call.clone(),
+            None,
+            Some(GethDebugTracingOptions::prestate_tracer(Default::default())),
+        )
+        .await?
+    else {
+        panic!("Expected a prestateTracer trace");
+    };
+    assert!(prestate.0.contains_key(&contract_address.address));
+
+    let diff_mode = PreStateConfig {
+        diff_mode: Some(true),
+        ..Default::default()
+    };
+    let TraceResult::PreState(PreStateFrame::Diff(_)) = client
+        .debug_trace_call(
+            eth_call.clone(),
+            None,
+            Some(GethDebugTracingOptions::prestate_tracer(diff_mode)),
+        )
+        .await?
+    else {
+        panic!("Expected a prestateTracer diff");
+    };
+
+    let TraceResult::FourByte(four_byte) = client
+        .debug_trace_call(
+            eth_call.clone(),
+            None,
+            Some(GethDebugTracingOptions::new_tracer(
+                GethDebugBuiltInTracerType::FourByteTracer,
+            )),
+        )
+        .await?
+    else {
+        panic!("Expected a 4byteTracer trace");
+    };
+    assert_eq!(four_byte.0.get(&format!("{}-0", call_data)), Some(&1));
+
+    let TraceResult::StructLog(struct_log) = client
+        .debug_trace_call(eth_call.clone(), None, Some(Default::default()))
+        .await?
+    else {
+        panic!("Expected a struct logger trace");
+    };
+    assert!(!struct_log.failed);
+    assert!(!struct_log.struct_logs.is_empty());
+    assert_eq!(
+        struct_log.return_value,
+        Bytes::from_str(&load_file_as_string("brc20_prog_helper_call_response")?).unwrap()
+    );
+
+    assert!(client
+        .debug_trace_call(
+            eth_call,
+            None,
+            Some(GethDebugTracingOptions::new_tracer(
+                GethDebugBuiltInTracerType::FlatCallTracer,
+            )),
+        )
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
+
+/// Past transactions are executed again for tracers other than callTracer.
+#[tokio::test]
+async fn test_debug_trace_past_transactions() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let from_pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let deploy_data_length = deploy_data.len() as u64;
+    let contract_address = client
+        .brc20_deploy(
+            from_pkscript.clone(),
+            RawBytes::new(deploy_data).into(),
+            None,
+            timestamp,
+            [3u8; 32].into(),
+            0,
+            "deploy_inscription".to_string(),
+            deploy_data_length,
+            [1; 32].into(),
+        )
+        .await?
+        .contract_address
+        .unwrap();
+    client
+        .brc20_finalise_block(timestamp, [3u8; 32].into(), 1)
+        .await?;
+
+    // Two calls in block 1, so the second one is executed on top of the first one
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let call_data_length = call_data.len() as u64;
+    let mut call_hashes = Vec::new();
+    for tx_idx in 0..2 {
+        let call_response = client
+            .brc20_call(
+                from_pkscript.clone(),
+                contract_address.clone().into(),
+                None,
+                RawBytes::new(call_data.clone()).into(),
+                None,
+                timestamp,
+                [4u8; 32].into(),
+                tx_idx,
+                format!("call_inscription_{}", tx_idx),
+                call_data_length,
+                [2; 32].into(),
+            )
+            .await?
+            .unwrap();
+        call_hashes.push(call_response.transaction_hash);
+    }
+    client
+        .brc20_finalise_block(timestamp, [4u8; 32].into(), 2)
+        .await?;
+
+    let expected_output =
+        Bytes::from_str(&load_file_as_string("brc20_prog_helper_call_response")?).unwrap();
+
+    let trace = client
+        .debug_trace_transaction(call_hashes[1].clone())
+        .await?
+        .unwrap();
+    assert_eq!(trace.gas_used.uint, U256::from(21440));
+
+    let diff_mode = PreStateConfig {
+        diff_mode: Some(true),
+        ..Default::default()
+    };
+    let Some(TraceResult::PreState(PreStateFrame::Diff(diff))) = client
+        .debug_trace_transaction_with_options(
+            call_hashes[1].clone(),
+            Some(GethDebugTracingOptions::prestate_tracer(diff_mode)),
+        )
+        .await?
+    else {
+        panic!("Expected a prestateTracer diff");
+    };
+    // Sender nonce is increased by the transaction, on top of the first call
+    let sender = trace.from.address;
+    assert_eq!(diff.pre[&sender].nonce, Some(2));
+    assert_eq!(diff.post[&sender].nonce, Some(3));
+
+    let struct_logs = client
+        .debug_trace_block_by_number("0x1".into(), Some(Default::default()))
+        .await?
+        .unwrap();
+    assert_eq!(struct_logs.len(), 2);
+    for struct_log in struct_logs {
+        let TraceResult::StructLog(struct_log) = struct_log else {
+            panic!("Expected a struct logger trace");
+        };
+        assert!(!struct_log.failed);
+        assert_eq!(struct_log.gas, 21440);
+        assert_eq!(struct_log.return_value, expected_output);
+    }
+
+    // There's no state before block 0
+    let deploy_hash = client
+        .eth_get_transaction_by_block_number_and_index(0, Some(0))
+        .await?
+        .unwrap()
+        .hash;
+    assert!(client
+        .debug_trace_transaction_with_options(
+            deploy_hash,
+            Some(GethDebugTracingOptions::prestate_tracer(Default::default())),
+        )
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
@@ -5,12 +5,12 @@ use alloy::primitives::{Bytes, U64};
 use brc20_prog::types::{Base64Bytes, RawBytes};
 use brc20_prog::Brc20ProgApiClient;
 use revm::primitives::U256;
-use test_utils::{load_file_as_string, spawn_test_server};
+use test_utils::{load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
 
 #[tokio::test]
 async fn test_deploy_call() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let from_pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let from_pkscript = TEST_PKSCRIPT.to_string();
     let timestamp = 42;
     let block_hash = [0u8; 32].into();
 
@@ -77,7 +77,7 @@ async fn test_deploy_call() -> Result<(), Box<dyn Error>> {
 #[tokio::test]
 async fn test_deploy_call_encoded() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let from_pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let from_pkscript = TEST_PKSCRIPT.to_string();
     let timestamp = 42;
     let block_hash = [0u8; 32].into();
 
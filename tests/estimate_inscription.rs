@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use alloy::primitives::Bytes;
+use brc20_prog::types::{Base64Bytes, RawBytes};
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::{load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
+
+// Gas is paid for every byte of the inscription
+const GAS_PER_BYTE: u64 = 12000;
+
+/// Deploy and call inscriptions are estimated, and the estimated inscription byte length
+/// provides enough gas for the transaction.
+#[tokio::test]
+async fn test_estimate_inscription() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let from_pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+    let block_hash = [0u8; 32].into();
+
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let deploy_estimate = client
+        .brc20_estimate_inscription(
+            from_pkscript.clone(),
+            None,
+            None,
+            RawBytes::new(deploy_data.clone()).into(),
+            None,
+            None,
+        )
+        .await?;
+
+    let gas: u64 = deploy_estimate.gas.into();
+    let inscription_byte_len: u64 = deploy_estimate.inscription_byte_len.into();
+    assert!(gas >= 1793621);
+    assert!(inscription_byte_len * GAS_PER_BYTE >= gas);
+    assert!((inscription_byte_len - 1) * GAS_PER_BYTE < gas);
+
+    // Returned data is the smallest encoding of the deploy data
+    let deploy_bytes = Bytes::from_str(&deploy_data)?;
+    let encoded = Base64Bytes::from_bytes(deploy_bytes.clone())?;
+    assert_eq!(deploy_estimate.base64_data, encoded);
+    assert_eq!(
+        deploy_estimate.compression,
+        encoded.compression().unwrap().into()
+    );
+
+    // Encoded data gives the same estimate
+    let encoded_estimate = client
+        .brc20_estimate_inscription(
+            from_pkscript.clone(),
+            None,
+            None,
+            None,
+            encoded.clone().into(),
+            None,
+        )
+        .await?;
+    assert_eq!(encoded_estimate.gas, deploy_estimate.gas);
+
+    // Estimated inscription byte length is enough for the deploy
+    let deploy_response = client
+        .brc20_deploy(
+            from_pkscript.clone(),
+            None,
+            deploy_estimate.base64_data.into(),
+            timestamp,
+            block_hash,
+            0,
+            "deploy_inscription".to_string(),
+            inscription_byte_len,
+            [1; 32].into(),
+        )
+        .await?;
+    assert!(!deploy_response.status.is_zero());
+    client
+        .brc20_finalise_block(timestamp, block_hash, 1)
+        .await?;
+
+    // Calls can be estimated with the contract address or the inscription id
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let call_estimate = client
+        .brc20_estimate_inscription(
+            from_pkscript.clone(),
+            deploy_response.contract_address.clone(),
+            None,
+            RawBytes::new(call_data.clone()).into(),
+            None,
+            Some("latest".into()),
+        )
+        .await?;
+    let call_gas: u64 = call_estimate.gas.into();
+    assert!(call_gas > 21000);
+    assert_eq!(
+        call_estimate.inscription_byte_len,
+        call_gas.div_ceil(GAS_PER_BYTE).into()
+    );
+
+    let call_estimate_by_id = client
+        .brc20_estimate_inscription(
+            from_pkscript.clone(),
+            None,
+            Some("deploy_inscription".to_string()),
+            RawBytes::new(call_data.clone()).into(),
+            None,
+            None,
+        )
+        .await?;
+    assert_eq!(call_estimate_by_id.gas, call_estimate.gas);
+
+    // Unknown contracts and missing data are rejected
+    assert!(client
+        .brc20_estimate_inscription(
+            from_pkscript.clone(),
+            None,
+            Some("unknown_inscription".to_string()),
+            RawBytes::new(call_data).into(),
+            None,
+            None,
+        )
+        .await
+        .is_err());
+    assert!(client
+        .brc20_estimate_inscription(from_pkscript, None, None, None, None, None)
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
@@ -0,0 +1,365 @@
+use std::error::Error;
+
+use alloy::eips::eip2930::AccessListItem;
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy_rpc_types_eth::state::{AccountOverride, StateOverride, StateOverridesBuilder};
+use alloy_rpc_types_eth::BlockOverrides;
+use brc20_prog::types::{BlockNumberOrHash, EthCall, RawBytes};
+use brc20_prog::{Brc20ProgApiClient, Brc20ProgOverridesApiClient};
+use test_utils::spawn_test_server;
+
+// PUSH1 0 SLOAD PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN, returns storage slot 0
+const STORAGE_READER: &str = "60005460005260206000f3";
+// NUMBER PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN, returns the block number
+const NUMBER_READER: &str = "4360005260206000f3";
+// CALLER BALANCE PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN, returns the balance of the caller
+const BALANCE_READER: &str = "333160005260206000f3";
+
+fn code(code: &str) -> Bytes {
+    Bytes::from(hex::decode(code).unwrap())
+}
+
+fn word(value: u64) -> String {
+    B256::from(U256::from(value)).to_string()
+}
+
+/// State and block overrides are applied to calls without changing the state, and access lists
+/// are created for calls.
+#[tokio::test]
+async fn test_eth_call_overrides() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    client.brc20_mine(1, 42).await?;
+
+    let caller = Address::repeat_byte(1);
+    let contract = Address::repeat_byte(0x12);
+    let eth_call = EthCall::new(
+        Some(caller.into()),
+        Some(contract.into()),
+        RawBytes::new("0x".to_string()),
+    );
+    let latest = Some(BlockNumberOrHash::from("latest"));
+
+    // There is no code at the address without overrides
+    assert_eq!(
+        client.eth_call(eth_call.clone(), latest.clone()).await?,
+        "0x"
+    );
+
+    // Code and individual storage slots
+    let storage_override: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(STORAGE_READER))
+        .with_state_diff(contract, [(B256::ZERO, B256::from(U256::from(42)))])
+        .build();
+    assert_eq!(
+        client
+            .eth_call_with_overrides(
+                eth_call.clone(),
+                latest.clone(),
+                Some(storage_override.clone()),
+                None
+            )
+            .await?,
+        word(42)
+    );
+
+    // State replaces the whole storage, so slot 0 is empty
+    let state_override: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(STORAGE_READER))
+        .with_state(
+            contract,
+            [(B256::with_last_byte(1), B256::with_last_byte(7))],
+        )
+        .build();
+    assert_eq!(
+        client
+            .eth_call_with_overrides(eth_call.clone(), latest.clone(), Some(state_override), None)
+            .await?,
+        word(0)
+    );
+
+    // State and stateDiff can't be used together
+    let mut invalid_override = storage_override.clone();
+    invalid_override
+        .get_mut(&contract)
+        .unwrap()
+        .set_state([(B256::ZERO, B256::ZERO)]);
+    assert!(client
+        .eth_call_with_overrides(
+            eth_call.clone(),
+            latest.clone(),
+            Some(invalid_override),
+            None
+        )
+        .await
+        .is_err());
+
+    // Balance of the caller
+    let balance_override: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(BALANCE_READER))
+        .with_balance(caller, U256::from(1000))
+        .build();
+    assert_eq!(
+        client
+            .eth_call_with_overrides(
+                eth_call.clone(),
+                latest.clone(),
+                Some(balance_override),
+                None
+            )
+            .await?,
+        word(1000)
+    );
+
+    // Block number
+    let number_override: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(NUMBER_READER))
+        .build();
+    assert_eq!(
+        client
+            .eth_call_with_overrides(
+                eth_call.clone(),
+                latest.clone(),
+                Some(number_override.clone()),
+                Some(BlockOverrides::default().with_number(U256::from(1000))),
+            )
+            .await?,
+        word(1000)
+    );
+
+    // Transactions don't pay for gas, so base fee can't be overridden
+    assert!(client
+        .eth_call_with_overrides(
+            eth_call.clone(),
+            latest.clone(),
+            Some(number_override),
+            Some(BlockOverrides::default().with_base_fee(U256::from(1))),
+        )
+        .await
+        .is_err());
+
+    // Precompiles can't be moved
+    let mut precompile_override = StateOverride::default();
+    precompile_override.insert(
+        Address::with_last_byte(1),
+        AccountOverride::default().with_move_precompile_to_opt(Some(contract)),
+    );
+    assert!(client
+        .eth_call_with_overrides(
+            eth_call.clone(),
+            latest.clone(),
+            Some(precompile_override),
+            None
+        )
+        .await
+        .is_err());
+
+    // Overrides are applied to every call in eth_callMany
+    assert_eq!(
+        client
+            .eth_call_many_with_overrides(
+                vec![eth_call.clone(), eth_call.clone()],
+                latest.clone(),
+                None,
+                Some(storage_override.clone()),
+                None,
+            )
+            .await?,
+        vec![word(42), word(42)]
+    );
+
+    // Reading a cold storage slot costs more than a plain call
+    let estimated_gas = client
+        .eth_estimate_gas_with_overrides(
+            eth_call.clone(),
+            latest.clone(),
+            Some(storage_override.clone()),
+            None,
+        )
+        .await?;
+    assert!(u64::from_str_radix(estimated_gas.trim_start_matches("0x"), 16)? > 21000 + 2100);
+
+    // Storage slots of the called contract are in the access list, caller is excluded
+    let access_list = client
+        .eth_create_access_list(eth_call.clone(), latest.clone(), Some(storage_override))
+        .await?;
+    assert_eq!(
+        access_list.access_list.0,
+        vec![AccessListItem {
+            address: contract,
+            storage_keys: vec![B256::ZERO],
+        }]
+    );
+    assert!(access_list.error.is_none());
+    assert!(access_list.gas_used > U256::from(21000));
+
+    // Overrides are not saved
+    assert_eq!(client.eth_call(eth_call, latest).await?, "0x");
+
+    server.stop()?;
+    Ok(())
+}
+
+/// Gas, gas price, value and nonce are used in calls, and blocks can be selected with EIP-1898
+/// block objects.
+#[tokio::test]
+async fn test_eth_call_fields() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    client.brc20_mine(2, 42).await?;
+
+    let caller = Address::repeat_byte(1);
+    let contract = Address::repeat_byte(0x12);
+    let eth_call = EthCall::new(
+        Some(caller.into()),
+        Some(contract.into()),
+        RawBytes::new("0x".to_string()),
+    );
+    let latest = Some(BlockNumberOrHash::from("latest"));
+    let overrides: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(BALANCE_READER))
+        .with_balance(caller, U256::from(100_000))
+        .build();
+
+    // Value is transferred before the balance is read
+    let value_call = EthCall {
+        value: Some(U256::from(1000).into()),
+        ..eth_call.clone()
+    };
+    assert_eq!(
+        client
+            .eth_call_with_overrides(
+                value_call.clone(),
+                latest.clone(),
+                Some(overrides.clone()),
+                None
+            )
+            .await?,
+        word(99_000)
+    );
+
+    // Value can't be sent without balance
+    assert!(client.eth_call(value_call, latest.clone()).await.is_err());
+
+    // Gas is paid with the given gas price
+    let gas_price_call = EthCall {
+        gas: Some(60_000u64.into()),
+        gas_price: Some(1u64.into()),
+        ..eth_call.clone()
+    };
+    assert_eq!(
+        client
+            .eth_call_with_overrides(
+                gas_price_call.clone(),
+                latest.clone(),
+                Some(overrides.clone()),
+                None
+            )
+            .await?,
+        word(40_000)
+    );
+    let expensive_call = EthCall {
+        gas_price: Some(2u64.into()),
+        ..gas_price_call
+    };
+    assert!(client
+        .eth_call_with_overrides(
+            expensive_call,
+            latest.clone(),
+            Some(overrides.clone()),
+            None
+        )
+        .await
+        .is_err());
+
+    // Calls fail if the gas limit is too low
+    let low_gas_call = EthCall {
+        gas: Some(21_010u64.into()),
+        ..eth_call.clone()
+    };
+    assert!(client
+        .eth_call_with_overrides(
+            low_gas_call.clone(),
+            latest.clone(),
+            Some(overrides.clone()),
+            None
+        )
+        .await
+        .is_err());
+    assert!(client
+        .eth_estimate_gas_with_overrides(
+            low_gas_call,
+            latest.clone(),
+            Some(overrides.clone()),
+            None
+        )
+        .await
+        .is_err());
+
+    // Nonce isn't checked against the sender
+    let nonce_call = EthCall {
+        nonce: Some(5u64.into()),
+        ..eth_call.clone()
+    };
+    assert_eq!(
+        client
+            .eth_call_with_overrides(nonce_call, latest.clone(), Some(overrides.clone()), None)
+            .await?,
+        word(100_000)
+    );
+
+    // Blocks can be selected by hash and number objects
+    let block = client.eth_get_block_by_number("0x0".into(), None).await?;
+    let by_hash = BlockNumberOrHash::Hash {
+        block_hash: block.hash,
+        require_canonical: true,
+    };
+    let by_number = BlockNumberOrHash::Number {
+        block_number: "0x0".to_string(),
+    };
+    let number_override: StateOverride = StateOverridesBuilder::default()
+        .with_code(contract, code(NUMBER_READER))
+        .build();
+    for block in [by_hash.clone(), by_number.clone()] {
+        assert_eq!(
+            client
+                .eth_call_with_overrides(
+                    eth_call.clone(),
+                    Some(block),
+                    Some(number_override.clone()),
+                    None
+                )
+                .await?,
+            word(0)
+        );
+    }
+    assert_eq!(
+        client
+            .eth_get_transaction_count(caller.into(), by_hash)
+            .await?,
+        "0x0"
+    );
+    assert_eq!(
+        client
+            .eth_get_code(contract.into(), Some(by_number))
+            .await?
+            .bytecode
+            .original_bytes(),
+        Bytes::new()
+    );
+
+    // Unknown block hashes are rejected
+    assert!(client
+        .eth_call_with_overrides(
+            eth_call,
+            Some(BlockNumberOrHash::Hash {
+                block_hash: B256::repeat_byte(0xff).into(),
+                require_canonical: false,
+            }),
+            None,
+            None
+        )
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
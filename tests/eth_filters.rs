@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use brc20_prog::types::{AddressED, FilterChanges, GetLogsFilter, LogED};
+use brc20_prog::Brc20ProgApiClient;
+use revm::primitives::U256;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
+
+// Canonical BRC20_Controller address; mint emits Transfer logs from here.
+const CONTROLLER_ADDRESS: &str = "0xc54dd4581af2dbf18e4d90840226756e9d2b3cdb";
+
+// Empty changes are deserialized as hashes, as the response doesn't tell them apart
+fn into_logs(changes: FilterChanges) -> Vec<LogED> {
+    match changes {
+        FilterChanges::Logs(logs) => logs,
+        FilterChanges::Hashes(hashes) if hashes.is_empty() => Vec::new(),
+        FilterChanges::Hashes(_) => panic!("Expected logs"),
+    }
+}
+
+/// Polling filters return new block hashes and logs since the last poll, and removed logs after a reorg.
+#[tokio::test]
+async fn test_eth_filters() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let timestamp = 42;
+    let controller_address = AddressED::from(Address::from_str(CONTROLLER_ADDRESS)?);
+
+    initialise_controller(&client, timestamp).await;
+
+    let block_filter = client.eth_new_block_filter().await?;
+    let log_filter = client
+        .eth_new_filter(GetLogsFilter {
+            from_block: None,
+            to_block: None,
+            address: Some(controller_address.clone()),
+            topics: None,
+        })
+        .await?;
+    let unrelated_filter = client
+        .eth_new_filter(GetLogsFilter {
+            from_block: None,
+            to_block: None,
+            address: Some(AddressED::from(Address::from_str(
+                "0x000000000000000000000000000000000000dead",
+            )?)),
+            topics: None,
+        })
+        .await?;
+    assert_ne!(block_filter, log_filter);
+
+    // Deposit -> mint -> Transfer log, in block 1.
+    client
+        .brc20_deposit(
+            TEST_PKSCRIPT.to_string(),
+            "TEST".to_string(),
+            U256::from(1000).into(),
+            timestamp,
+            [2u8; 32].into(),
+            0,
+            "deposit_inscription".to_string(),
+        )
+        .await?;
+    client
+        .brc20_finalise_block(timestamp, [2u8; 32].into(), 1)
+        .await?;
+
+    let FilterChanges::Hashes(hashes) = client.eth_get_filter_changes(block_filter.clone()).await?
+    else {
+        panic!("Expected block hashes");
+    };
+    assert_eq!(hashes, vec![[2u8; 32].into()]);
+
+    let logs = into_logs(client.eth_get_filter_changes(log_filter.clone()).await?);
+    assert!(!logs.is_empty());
+    assert!(logs
+        .iter()
+        .all(|log| log.address == controller_address && !log.removed));
+
+    let unrelated_logs = into_logs(client.eth_get_filter_changes(unrelated_filter).await?);
+    assert!(unrelated_logs.is_empty());
+
+    // Changes are only returned once, but filter logs return every matching log
+    let no_logs = into_logs(client.eth_get_filter_changes(log_filter.clone()).await?);
+    assert!(no_logs.is_empty());
+    assert_eq!(client.eth_get_filter_logs(log_filter.clone()).await?, logs);
+    assert!(client
+        .eth_get_filter_logs(block_filter.clone())
+        .await
+        .is_err());
+
+    // Reorg removes block 1, so its logs are returned again as removed
+    client.brc20_reorg(0).await?;
+
+    let removed_logs = into_logs(client.eth_get_filter_changes(log_filter.clone()).await?);
+    assert_eq!(removed_logs.len(), logs.len());
+    for (removed_log, log) in removed_logs.iter().zip(logs.iter()) {
+        assert!(removed_log.removed);
+        assert_eq!(removed_log.transaction_hash, log.transaction_hash);
+        assert_eq!(removed_log.log_index, log.log_index);
+    }
+
+    // Uninstalled filters are no longer available
+    assert!(client.eth_uninstall_filter(log_filter.clone()).await?);
+    assert!(!client.eth_uninstall_filter(log_filter.clone()).await?);
+    assert!(client.eth_get_filter_changes(log_filter).await.is_err());
+
+    server.stop()?;
+    Ok(())
+}
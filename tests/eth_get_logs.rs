@@ -6,7 +6,7 @@ use brc20_prog::types::{AddressED, B256ED, GetLogsFilter};
 use brc20_prog::Brc20ProgApiClient;
 use revm::primitives::U256;
 use serde_either::SingleOrVec;
-use test_utils::spawn_test_server;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
 
 // Canonical BRC20_Controller address; mint emits Transfer logs from here.
 const CONTROLLER_ADDRESS: &str = "0xc54dd4581af2dbf18e4d90840226756e9d2b3cdb";
@@ -25,19 +25,11 @@ fn filter(address: Option<&str>, from: Option<&str>, to: Option<&str>) -> GetLog
 #[tokio::test]
 async fn test_eth_get_logs_filtering() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let pkscript = TEST_PKSCRIPT.to_string();
     let ticker = "TEST".to_string();
     let timestamp = 42;
 
-    // Deploy controller. initialise also pings the unreachable Bitcoin RPC; the
-    // controller is deployed before that check. Tolerate only that specific
-    // failure so an unrelated init regression still fails the test.
-    if let Err(e) = client.brc20_initialise([1u8; 32].into(), timestamp, 0).await {
-        assert!(
-            e.to_string().contains("Bitcoin RPC"),
-            "brc20_initialise failed for an unexpected reason: {e}"
-        );
-    }
+    initialise_controller(&client, timestamp).await;
 
     // Deposit -> mint -> Transfer log, in block 1.
     client
@@ -71,8 +63,7 @@ async fn test_eth_get_logs_filtering() -> Result<(), Box<dyn Error>> {
         .await?;
     assert!(none.is_empty(), "unrelated address must match no logs");
 
-    // A block range covering block 1 returns the logs (ranges are capped at 5
-    // blocks, so keep them small).
+    // A block range covering block 1 returns the logs.
     let in_range = client
         .eth_get_logs(filter(Some(CONTROLLER_ADDRESS), Some("0x0"), Some("0x1")))
         .await?;
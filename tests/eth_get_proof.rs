@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use alloy::primitives::{keccak256, Address, B256, U256};
+use alloy::trie::proof::verify_proof;
+use alloy::trie::{Nibbles, TrieAccount, EMPTY_ROOT_HASH};
+use alloy_rpc_types_eth::EIP1186AccountProofResponse;
+use alloy_serde::JsonStorageKey;
+use brc20_prog::types::{AddressED, BlockOperation};
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
+
+// Canonical BRC20_Controller address
+const CONTROLLER_ADDRESS: &str = "0xc54dd4581af2dbf18e4d90840226756e9d2b3cdb";
+
+// Verifies the account and storage proofs of a response against a state root
+fn verify_account_proof(response: &EIP1186AccountProofResponse, state_root: B256) {
+    let account = TrieAccount {
+        nonce: response.nonce,
+        balance: response.balance,
+        storage_root: response.storage_hash,
+        code_hash: response.code_hash,
+    };
+    // Accounts that don't exist are proven to be absent from the state trie
+    let expected = (account != TrieAccount::default()).then(|| alloy_rlp::encode(account));
+    verify_proof(
+        state_root,
+        Nibbles::unpack(keccak256(response.address)),
+        expected,
+        &response.account_proof,
+    )
+    .unwrap();
+    for storage_proof in response.storage_proof.iter() {
+        verify_proof(
+            response.storage_hash,
+            Nibbles::unpack(keccak256(storage_proof.key.as_b256())),
+            (!storage_proof.value.is_zero()).then(|| alloy_rlp::encode(storage_proof.value)),
+            &storage_proof.proof,
+        )
+        .unwrap();
+    }
+}
+
+/// Account and storage proofs of the controller and a token contract verify against the state root of
+/// the requested block, and their values match the ones returned by eth_getStorageAt.
+#[tokio::test]
+async fn test_eth_get_proof() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+    let controller_address = AddressED::from(Address::from_str(CONTROLLER_ADDRESS)?);
+    let storage_keys = (0..8u64)
+        .map(|slot| JsonStorageKey::from(U256::from(slot)))
+        .collect::<Vec<_>>();
+
+    initialise_controller(&client, timestamp).await;
+    let deposit = |inscription_id: &str| {
+        vec![BlockOperation::Deposit {
+            to_pkscript: pkscript.clone(),
+            ticker: "test".to_string(),
+            amount: 100u64.into(),
+            inscription_id: inscription_id.to_string(),
+        }]
+    };
+
+    let receipts = client
+        .brc20_add_block(timestamp, [1u8; 32].into(), deposit("first_deposit"))
+        .await?;
+    // Balances are kept by the token contract, which emits the Transfer log of the deposit
+    let token_address = receipts[0].logs.last().unwrap().address.clone();
+    let block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    let proof = client
+        .eth_get_proof(controller_address.clone(), storage_keys.clone(), None)
+        .await?;
+    assert_ne!(proof.storage_hash, EMPTY_ROOT_HASH);
+    assert_eq!(proof.storage_proof.len(), storage_keys.len());
+    verify_account_proof(&proof, block.state_root.bytes);
+    for storage_proof in proof.storage_proof.iter() {
+        let value = client
+            .eth_get_storage_at(
+                controller_address.clone(),
+                U256::from_be_bytes(storage_proof.key.as_b256().0).into(),
+                None,
+            )
+            .await?;
+        assert_eq!(U256::from_str(&value)?, storage_proof.value);
+    }
+
+    // Proofs of earlier blocks are still against their own state root
+    let token_proof = client
+        .eth_get_proof(token_address.clone(), storage_keys.clone(), None)
+        .await?;
+    verify_account_proof(&token_proof, block.state_root.bytes);
+    client
+        .brc20_add_block(timestamp, [2u8; 32].into(), deposit("second_deposit"))
+        .await?;
+    let latest_block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    let latest_token_proof = client
+        .eth_get_proof(token_address.clone(), storage_keys.clone(), None)
+        .await?;
+    verify_account_proof(&latest_token_proof, latest_block.state_root.bytes);
+    assert_ne!(latest_token_proof.storage_hash, token_proof.storage_hash);
+    let token_proof_at_block = client
+        .eth_get_proof(
+            token_address,
+            storage_keys.clone(),
+            Some(block.hash.bytes.to_string().into()),
+        )
+        .await?;
+    assert_eq!(token_proof_at_block, token_proof);
+
+    // Accounts that don't exist have an empty account and storage, with proofs of their absence
+    let missing = client
+        .eth_get_proof([9u8; 20].into(), storage_keys, None)
+        .await?;
+    assert_eq!(missing.storage_hash, EMPTY_ROOT_HASH);
+    assert!(missing
+        .storage_proof
+        .iter()
+        .all(|storage_proof| { storage_proof.value.is_zero() && storage_proof.proof.is_empty() }));
+    verify_account_proof(&missing, latest_block.state_root.bytes);
+
+    // Blocks that are not indexed yet can't be proven
+    assert!(client
+        .eth_get_proof([9u8; 20].into(), vec![], Some("0x100".into()))
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
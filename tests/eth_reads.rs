@@ -1,17 +1,17 @@
 use std::error::Error;
 
 use alloy::primitives::U64;
-use brc20_prog::types::RawBytes;
+use brc20_prog::types::{BlockNumberOrHash, RawBytes};
 use brc20_prog::Brc20ProgApiClient;
 use revm::primitives::U256;
-use test_utils::{load_file_as_string, spawn_test_server};
+use test_utils::{load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
 
 /// Deploys a contract, finalises its block, then cross-checks the eth_* read
 /// methods (block/tx getters, counts, code, storage) against known values.
 #[tokio::test]
 async fn test_eth_read_methods() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let from_pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let from_pkscript = TEST_PKSCRIPT.to_string();
     let timestamp = 42;
     let block_hash = [0u8; 32].into();
 
@@ -19,8 +19,8 @@ async fn test_eth_read_methods() -> Result<(), Box<dyn Error>> {
     let deploy_data_length = deploy_data.len() as u64;
     let deploy = client
         .brc20_deploy(
-            from_pkscript,
-            RawBytes::new(deploy_data).into(),
+            from_pkscript.clone(),
+            RawBytes::new(deploy_data.clone()).into(),
             None,
             timestamp,
             block_hash,
@@ -41,7 +41,7 @@ async fn test_eth_read_methods() -> Result<(), Box<dyn Error>> {
 
     // The block by number, and the same block fetched by its hash.
     let block = client
-        .eth_get_block_by_number("0x0".to_string(), Some(false))
+        .eth_get_block_by_number("0x0".into(), Some(false))
         .await?;
     assert_eq!(block.number.uint, U64::from(0u64));
     let block_by_hash = client
@@ -52,7 +52,7 @@ async fn test_eth_read_methods() -> Result<(), Box<dyn Error>> {
     // One transaction in the block, by both lookups.
     assert_eq!(
         client
-            .eth_get_block_transaction_count_by_number("0x0".to_string())
+            .eth_get_block_transaction_count_by_number("0x0".into())
             .await?,
         "0x1"
     );
@@ -83,19 +83,91 @@ async fn test_eth_read_methods() -> Result<(), Box<dyn Error>> {
     // Deployer nonce advanced to 1 after the deploy.
     assert_eq!(
         client
-            .eth_get_transaction_count(deploy.from.clone(), "0x0".to_string())
+            .eth_get_transaction_count(deploy.from.clone(), "0x0".into())
             .await?,
         "0x1"
     );
 
     // The deployed contract has code and queryable storage.
-    let code = client.eth_get_code(contract_address.clone()).await?;
+    let code = client.eth_get_code(contract_address.clone(), None).await?;
     assert!(!code.bytecode.original_bytes().is_empty());
     let storage = client
-        .eth_get_storage_at(contract_address, U256::ZERO.into())
+        .eth_get_storage_at(contract_address.clone(), U256::ZERO.into(), None)
         .await?;
     assert!(storage.starts_with("0x"));
 
+    // Same state is returned when queried at the block, by number and by hash.
+    let code_at_block = client
+        .eth_get_code(contract_address.clone(), Some("0x0".into()))
+        .await?;
+    assert_eq!(code_at_block, code);
+    let storage_at_block = client
+        .eth_get_storage_at(
+            contract_address,
+            U256::ZERO.into(),
+            Some(block.hash.bytes.to_string().into()),
+        )
+        .await?;
+    assert_eq!(storage_at_block, storage);
+
+    // Deploy again in block 1, so the nonce at block 0 is a past nonce
+    client
+        .brc20_deploy(
+            from_pkscript,
+            RawBytes::new(deploy_data).into(),
+            None,
+            timestamp,
+            [1; 32].into(),
+            0,
+            "second_deploy_inscription".to_string(),
+            deploy_data_length,
+            [2; 32].into(),
+        )
+        .await?;
+    client
+        .brc20_finalise_block(timestamp, [1; 32].into(), 1)
+        .await?;
+    assert_eq!(
+        client
+            .eth_get_transaction_count(deploy.from.clone(), "0x0".into())
+            .await?,
+        "0x1"
+    );
+    assert_eq!(
+        client
+            .eth_get_transaction_count(deploy.from.clone(), "latest".into())
+            .await?,
+        "0x2"
+    );
+
+    // Block parameters accept EIP-1898 block objects
+    let by_hash = BlockNumberOrHash::Hash {
+        block_hash: block.hash.clone(),
+        require_canonical: false,
+    };
+    assert_eq!(
+        client
+            .eth_get_block_by_number(by_hash.clone(), Some(false))
+            .await?
+            .number
+            .uint,
+        U64::from(0u64)
+    );
+    assert_eq!(
+        client
+            .eth_get_transaction_count(deploy.from.clone(), by_hash)
+            .await?,
+        "0x1"
+    );
+    assert_eq!(
+        client
+            .eth_get_block_transaction_count_by_number(BlockNumberOrHash::Number {
+                block_number: "0x1".to_string(),
+            })
+            .await?,
+        "0x1"
+    );
+
     server.stop()?;
     Ok(())
 }
@@ -0,0 +1,173 @@
+use std::error::Error;
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy_rpc_types_eth::state::{StateOverride, StateOverridesBuilder};
+use alloy_rpc_types_eth::BlockOverrides;
+use alloy_rpc_types_trace::geth::{GethDebugBuiltInTracerType, GethDebugTracingOptions};
+use brc20_prog::types::{
+    EthCall, RawBytes, SimulateBlock, SimulatePayload, SimulatedCallError, TraceResult,
+};
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::spawn_test_server;
+
+// Increases storage slot 0, logs the new value with the block number as topic, and returns it
+const COUNTER: &str = "600054600101806000556000524360206000a160206000f3";
+// Reverts before block 1000, returns 1 after
+const LOCK: &str = "6103e8431015600d57600080fd5b600160005260206000f3";
+
+fn code(code: &str) -> Bytes {
+    Bytes::from(hex::decode(code).unwrap())
+}
+
+fn call(to: Address) -> EthCall {
+    EthCall::new(
+        Some(Address::repeat_byte(1).into()),
+        Some(to.into()),
+        RawBytes::new("0x".to_string()),
+    )
+}
+
+fn word(value: u64) -> Bytes {
+    B256::from(U256::from(value)).into()
+}
+
+/// Calls are simulated in a sequence of blocks, each block on top of the previous one.
+#[tokio::test]
+async fn test_eth_simulate() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    client.brc20_mine(2, 42).await?;
+
+    let counter = Address::repeat_byte(0x12);
+    let lock = Address::repeat_byte(0x34);
+    let state_overrides: StateOverride = StateOverridesBuilder::default()
+        .with_code(counter, code(COUNTER))
+        .with_code(lock, code(LOCK))
+        .build();
+    let payload = SimulatePayload {
+        block_state_calls: vec![
+            SimulateBlock {
+                state_overrides: Some(state_overrides),
+                calls: vec![call(counter), call(counter), call(lock)],
+                ..Default::default()
+            },
+            SimulateBlock {
+                block_overrides: Some(BlockOverrides::default().with_number(U256::from(1000))),
+                calls: vec![call(counter), call(lock)],
+                ..Default::default()
+            },
+        ],
+    };
+
+    let blocks = client
+        .eth_simulate_v1(payload.clone(), Some("latest".into()), None)
+        .await?;
+    assert_eq!(blocks.len(), 2);
+
+    // First block follows the latest block, state overrides and changes are kept in the next block
+    let first_block = &blocks[0];
+    assert_eq!(first_block.number, 2u64.into());
+    assert_eq!(first_block.calls[0].return_data.bytes, word(1));
+    assert_eq!(first_block.calls[1].return_data.bytes, word(2));
+    assert_eq!(first_block.calls[1].logs.len(), 1);
+    assert_eq!(
+        first_block.calls[1].logs[0].topics,
+        vec![B256::from(U256::from(2)).into()]
+    );
+    assert_eq!(first_block.calls[1].logs[0].block_hash, first_block.hash);
+    assert_eq!(first_block.calls[1].logs[0].transaction_index, 1u64.into());
+    assert_eq!(first_block.calls[1].logs[0].log_index, 1u64.into());
+
+    // Lock reverts before block 1000
+    assert_eq!(first_block.calls[2].status, 0u8.into());
+    assert_eq!(
+        first_block.calls[2].error,
+        Some(SimulatedCallError {
+            code: 3,
+            message: "execution reverted".to_string(),
+        })
+    );
+    let call_gas_used: u64 = first_block
+        .calls
+        .iter()
+        .map(|call| Into::<u64>::into(call.gas_used))
+        .sum();
+    assert_eq!(first_block.gas_used, call_gas_used.into());
+
+    let second_block = &blocks[1];
+    assert_eq!(second_block.number, 1000u64.into());
+    assert!(second_block.timestamp > first_block.timestamp);
+    assert_eq!(second_block.calls[0].return_data.bytes, word(3));
+    assert_eq!(
+        second_block.calls[0].logs[0].topics,
+        vec![B256::from(U256::from(1000)).into()]
+    );
+    assert_eq!(second_block.calls[0].logs[0].log_index, 0u64.into());
+    assert_eq!(second_block.calls[1].status, 1u8.into());
+    assert_eq!(second_block.calls[1].return_data.bytes, word(1));
+    assert!(second_block.calls[1].error.is_none());
+    assert!(second_block.calls[1].trace.is_none());
+
+    // Calls are traced if tracer options are given
+    let traced_blocks = client
+        .eth_simulate_v1(
+            payload.clone(),
+            None,
+            Some(
+                GethDebugTracingOptions::default()
+                    .with_tracer(GethDebugBuiltInTracerType::CallTracer.into()),
+            ),
+        )
+        .await?;
+    let Some(TraceResult::Call(trace)) = &traced_blocks[1].calls[0].trace else {
+        panic!("Expected call trace");
+    };
+    assert_eq!(trace.to, Some(counter.into()));
+
+    // Number of blocks and calls of a request are limited
+    let too_many_blocks = SimulatePayload {
+        block_state_calls: vec![SimulateBlock::default(); 257],
+    };
+    assert!(client
+        .eth_simulate_v1(too_many_blocks, None, None)
+        .await
+        .is_err());
+    let too_many_calls = SimulatePayload {
+        block_state_calls: vec![
+            SimulateBlock {
+                calls: vec![call(counter); 500],
+                ..Default::default()
+            },
+            SimulateBlock {
+                calls: vec![call(counter); 501],
+                ..Default::default()
+            },
+        ],
+    };
+    assert!(client
+        .eth_simulate_v1(too_many_calls, None, None)
+        .await
+        .is_err());
+
+    // Block numbers and timestamps should be increasing
+    let mut decreasing_number = payload.clone();
+    decreasing_number.block_state_calls[1].block_overrides =
+        Some(BlockOverrides::default().with_number(U256::from(1)));
+    assert!(client
+        .eth_simulate_v1(decreasing_number, None, None)
+        .await
+        .is_err());
+    let mut decreasing_timestamp = payload;
+    decreasing_timestamp.block_state_calls[1].block_overrides =
+        Some(BlockOverrides::default().with_time(1));
+    assert!(client
+        .eth_simulate_v1(decreasing_timestamp, None, None)
+        .await
+        .is_err());
+
+    // Simulated changes are not saved
+    let code = client.eth_get_code(counter.into(), None).await?;
+    assert!(code.bytecode.original_bytes().is_empty());
+
+    server.stop()?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::str::FromStr;
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use brc20_prog::types::{AddressED, GetLogsFilter};
+use brc20_prog::{Brc20ProgApiClient, Brc20ProgSubscriptionApiClient};
+use jsonrpsee::core::client::Subscription;
+use revm::primitives::U256;
+use serde_json::Value;
+use test_utils::{initialise_controller, spawn_test_server_with_ws_client, TEST_PKSCRIPT};
+use tokio::time::timeout;
+
+// Canonical BRC20_Controller address; mint emits Transfer logs from here.
+const CONTROLLER_ADDRESS: &str = "0xc54dd4581af2dbf18e4d90840226756e9d2b3cdb";
+
+async fn next_item(subscription: &mut Subscription<Value>) -> Value {
+    timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .expect("Timed out waiting for subscription item")
+        .expect("Subscription closed")
+        .expect("Invalid subscription item")
+}
+
+/// Subscribers get new heads and logs as blocks are finalised, and removed logs after a reorg.
+#[tokio::test]
+async fn test_eth_subscribe() -> Result<(), Box<dyn Error>> {
+    let (server, client, ws_client) = spawn_test_server_with_ws_client(Default::default()).await;
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+
+    let mut new_heads = ws_client
+        .eth_subscribe("newHeads".to_string(), None)
+        .await?;
+    let mut logs = ws_client
+        .eth_subscribe(
+            "logs".to_string(),
+            Some(GetLogsFilter {
+                from_block: None,
+                to_block: None,
+                address: Some(AddressED::from(Address::from_str(CONTROLLER_ADDRESS)?)),
+                topics: None,
+            }),
+        )
+        .await?;
+    let mut unrelated_logs = ws_client
+        .eth_subscribe(
+            "logs".to_string(),
+            Some(GetLogsFilter {
+                from_block: None,
+                to_block: None,
+                address: Some(AddressED::from(Address::from_str(
+                    "0x000000000000000000000000000000000000dead",
+                )?)),
+                topics: None,
+            }),
+        )
+        .await?;
+
+    // Deposit -> mint -> Transfer log, in block 1.
+    client
+        .brc20_deposit(
+            TEST_PKSCRIPT.to_string(),
+            "TEST".to_string(),
+            U256::from(1000).into(),
+            timestamp,
+            [2u8; 32].into(),
+            0,
+            "deposit_inscription".to_string(),
+        )
+        .await?;
+    client
+        .brc20_finalise_block(timestamp, [2u8; 32].into(), 1)
+        .await?;
+
+    let head = next_item(&mut new_heads).await;
+    assert_eq!(head["number"], "0x1");
+    assert_eq!(
+        head["hash"],
+        "0x0202020202020202020202020202020202020202020202020202020202020202"
+    );
+
+    let log = next_item(&mut logs).await;
+    assert_eq!(log["address"], CONTROLLER_ADDRESS);
+    assert_eq!(log["blockNumber"], "0x1");
+    assert_eq!(log["removed"], false);
+
+    // Reorg removes block 1, so its logs are sent again as removed
+    client.brc20_reorg(0).await?;
+
+    let mut removed_log = next_item(&mut logs).await;
+    while removed_log["removed"] == false {
+        removed_log = next_item(&mut logs).await;
+    }
+    assert_eq!(removed_log["transactionHash"], log["transactionHash"]);
+    assert_eq!(removed_log["logIndex"], log["logIndex"]);
+
+    // Logs from other addresses are not sent
+    assert!(
+        timeout(Duration::from_millis(100), unrelated_logs.next())
+            .await
+            .is_err(),
+        "unrelated address must not be notified"
+    );
+
+    // Unsupported subscriptions are rejected
+    assert!(ws_client
+        .eth_subscribe("newPendingTransactions".to_string(), None)
+        .await
+        .is_err());
+
+    server.stop()?;
+    Ok(())
+}
@@ -107,7 +107,7 @@ async fn test_bip322_verify() -> Result<(), Box<dyn Error>> {
                 Some(bip322_precompile.into()),
                 load_file_as_eth_bytes("bip322_verify_call_tx_data")?,
             ),
-            Some("latest".to_string()),
+            Some("latest".into()),
         )
         .await?;
 
@@ -122,10 +122,10 @@ async fn test_bip322_verify() -> Result<(), Box<dyn Error>> {
             Some(bip322_precompile.into()),
             load_file_as_eth_bytes("bip322_verify_call_tx_data")?,
         ),
-        Some("latest".to_string()),
+        Some("latest".into()),
     ).await?;
 
-    assert_eq!(gas_response.as_str().to_lowercase(), "0xc93c");
+    assert_eq!(gas_response.as_str().to_lowercase(), "0xb914");
 
     server.stop()?;
 
@@ -146,7 +146,7 @@ async fn test_btc_locked_pkscript() -> Result<(), Box<dyn Error>> {
                 Some(btc_locked_pkscript_precompile.into()),
                 load_file_as_eth_bytes("btc_get_locked_pkscript_call_tx_data")?,
             ),
-            Some("latest".to_string()),
+            Some("latest".into()),
         )
         .await?;
 
@@ -161,10 +161,10 @@ async fn test_btc_locked_pkscript() -> Result<(), Box<dyn Error>> {
             Some(btc_locked_pkscript_precompile.into()),
             load_file_as_eth_bytes("btc_get_locked_pkscript_call_tx_data")?,
         ),
-        Some("latest".to_string()),
+        Some("latest".into()),
     ).await?;
 
-    assert_eq!(gas_response.as_str().to_lowercase(), "0xab6f");
+    assert_eq!(gas_response.as_str().to_lowercase(), "0xb061");
 
     server.stop()?;
 
@@ -193,7 +193,7 @@ async fn verify_btc_last_sat_loc_signet() -> Result<(), Box<dyn Error>> {
                 Some(btc_last_sat_loc_precompile.into()),
                 load_file_as_eth_bytes("btc_last_sat_loc_signet_call_tx_data")?,
             ),
-            Some("latest".to_string()),
+            Some("latest".into()),
         )
         .await?;
 
@@ -208,7 +208,7 @@ async fn verify_btc_last_sat_loc_signet() -> Result<(), Box<dyn Error>> {
             Some(btc_last_sat_loc_precompile.into()),
             load_file_as_eth_bytes("btc_last_sat_loc_signet_call_tx_data")?,
         ),
-        Some("latest".to_string()),
+        Some("latest".into()),
     ).await?;
 
     assert_eq!(gas_response.as_str().to_lowercase(), "0xc8a6c");
@@ -244,7 +244,7 @@ async fn verify_btc_get_tx_details(
                 Some(btc_get_tx_details_precompile.into()),
                 load_file_as_eth_bytes(call_file)?,
             ),
-            Some("latest".to_string()),
+            Some("latest".into()),
         )
         .await?;
 
@@ -256,7 +256,7 @@ async fn verify_btc_get_tx_details(
             Some(btc_get_tx_details_precompile.into()),
             load_file_as_eth_bytes(call_file)?,
         ),
-        Some("latest".to_string()),
+        Some("latest".into()),
     ).await?;
 
     assert_eq!(gas_response.as_str().to_lowercase(), gas);
@@ -0,0 +1,76 @@
+use std::error::Error;
+
+use alloy::primitives::B256;
+use brc20_prog::types::BlockOperation;
+use brc20_prog::Brc20ProgApiClient;
+use test_utils::{initialise_controller, spawn_test_server, TEST_PKSCRIPT};
+
+/// Receipt proofs of every transaction in a block verify against the receipts root of the block
+/// header, and can't be used with another block's receipts root.
+#[tokio::test]
+async fn test_receipt_proof() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let pkscript = TEST_PKSCRIPT.to_string();
+    let timestamp = 42;
+
+    initialise_controller(&client, timestamp).await;
+
+    let receipts = client
+        .brc20_add_block(
+            timestamp,
+            [1u8; 32].into(),
+            vec![
+                BlockOperation::Deposit {
+                    to_pkscript: pkscript.clone(),
+                    ticker: "test".to_string(),
+                    amount: 100u64.into(),
+                    inscription_id: "deposit_inscription".to_string(),
+                },
+                BlockOperation::Withdraw {
+                    from_pkscript: pkscript.clone(),
+                    ticker: "test".to_string(),
+                    amount: 40u64.into(),
+                    inscription_id: "withdraw_inscription".to_string(),
+                },
+                BlockOperation::Deposit {
+                    to_pkscript: pkscript,
+                    ticker: "other".to_string(),
+                    amount: 10u64.into(),
+                    inscription_id: "other_deposit_inscription".to_string(),
+                },
+            ],
+        )
+        .await?;
+    let block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+
+    for receipt in receipts.iter() {
+        let proof = client
+            .brc20_get_receipt_proof(receipt.transaction_hash.clone())
+            .await?
+            .unwrap();
+        assert_eq!(&proof.receipt, receipt);
+        assert_eq!(proof.receipts_root, block.receipts_root);
+        proof.verify(block.receipts_root.bytes)?;
+    }
+
+    // The withdrawal log is proven along with its receipt
+    let withdraw_proof = client
+        .brc20_get_receipt_proof(receipts[1].transaction_hash.clone())
+        .await?
+        .unwrap();
+    assert!(!withdraw_proof.receipt.logs.is_empty());
+    let genesis = client
+        .eth_get_block_by_number("0x0".into(), Some(false))
+        .await?;
+    assert!(withdraw_proof.verify(genesis.receipts_root.bytes).is_err());
+
+    assert!(client
+        .brc20_get_receipt_proof(B256::ZERO.into())
+        .await?
+        .is_none());
+
+    server.stop()?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::error::Error;
+use std::path::Path;
+
+use brc20_prog::types::{BlockNumberOrHash, BlockOperation, BlockResponseED, RawBytes, B256ED};
+use brc20_prog::{export_snapshot, import_snapshot, Brc20ProgApiClient, Brc20ProgConfig};
+use jsonrpsee::http_client::HttpClient;
+use tempfile::TempDir;
+use test_utils::{
+    initialise_controller, load_file_as_string, spawn_test_server_with_db_path, TEST_PKSCRIPT,
+};
+
+fn config(db_path: &Path) -> Brc20ProgConfig {
+    Brc20ProgConfig {
+        db_path: db_path.to_str().unwrap().to_string(),
+        // Same as the test server
+        evm_record_traces: true,
+        ..Default::default()
+    }
+}
+
+fn deposit(inscription_id: &str) -> Vec<BlockOperation> {
+    vec![BlockOperation::Deposit {
+        to_pkscript: TEST_PKSCRIPT.to_string(),
+        ticker: "test".to_string(),
+        amount: 100u64.into(),
+        inscription_id: inscription_id.to_string(),
+    }]
+}
+
+// Adds the same block to a server, and returns it with its consensus hash
+async fn add_next_block(
+    client: &HttpClient,
+) -> Result<(BlockResponseED, Option<B256ED>), Box<dyn Error>> {
+    client
+        .brc20_add_block(42, [9u8; 32].into(), deposit("deposit_after_snapshot"))
+        .await?;
+    Ok((
+        client
+            .eth_get_block_by_number("latest".into(), Some(false))
+            .await?,
+        client.brc20_get_consensus_hash("latest".into()).await?,
+    ))
+}
+
+/// A database imported from a snapshot continues indexing with the same state and consensus hashes as the
+/// database the snapshot was exported from.
+#[tokio::test]
+async fn test_snapshot() -> Result<(), Box<dyn Error>> {
+    let source = TempDir::new()?;
+    let snapshot = TempDir::new()?;
+    let imported = TempDir::new()?;
+    let source_path = source.path();
+    let snapshot_path = snapshot.path().join("snapshot");
+    let imported_path = imported.path();
+
+    let (server, client) = spawn_test_server_with_db_path(source_path, Default::default()).await;
+    initialise_controller(&client, 42).await;
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let deploy_data_length = deploy_data.len() as u64;
+    let contract_address = client
+        .brc20_deploy(
+            TEST_PKSCRIPT.to_string(),
+            RawBytes::new(deploy_data).into(),
+            None,
+            42,
+            [1u8; 32].into(),
+            0,
+            "deploy_inscription".to_string(),
+            deploy_data_length,
+            [1u8; 32].into(),
+        )
+        .await?
+        .contract_address
+        .unwrap();
+    client.brc20_finalise_block(42, [1u8; 32].into(), 1).await?;
+    for i in 2..=3u8 {
+        client
+            .brc20_add_block(42, [i; 32].into(), deposit(&format!("deposit_{}", i)))
+            .await?;
+    }
+    let snapshot_block = client
+        .eth_get_block_by_number("latest".into(), Some(false))
+        .await?;
+    client.brc20_commit_to_database().await?;
+    server.stop()?;
+    server.stopped().await;
+
+    let block_number = export_snapshot(config(source_path), &snapshot_path)?;
+    assert_eq!(snapshot_block.number, block_number.into());
+    // Snapshots can't overwrite each other
+    assert!(export_snapshot(config(source_path), &snapshot_path).is_err());
+    assert_eq!(
+        import_snapshot(config(imported_path), &snapshot_path)?,
+        block_number
+    );
+    assert!(import_snapshot(config(imported_path), &snapshot_path).is_err());
+
+    let (server, client) = spawn_test_server_with_db_path(source_path, Default::default()).await;
+    let expected = add_next_block(&client).await?;
+    server.stop()?;
+    server.stopped().await;
+
+    let (server, client) = spawn_test_server_with_db_path(imported_path, Default::default()).await;
+    assert_eq!(
+        client
+            .eth_get_block_by_number("latest".into(), Some(false))
+            .await?,
+        snapshot_block
+    );
+    assert_eq!(
+        client
+            .brc20_get_inscription_id_by_contract_address(contract_address.into())
+            .await?,
+        Some("deploy_inscription".to_string())
+    );
+    let imported = add_next_block(&client).await?;
+    assert_ne!(expected.0.state_root, snapshot_block.state_root);
+    assert!(expected.1.is_some());
+    assert_eq!(imported.0.state_root, expected.0.state_root);
+    assert_eq!(imported.0.hash, expected.0.hash);
+    assert_eq!(imported.1, expected.1);
+
+    // State history isn't imported, so the state before the snapshot block can't be read
+    let snapshot_block_number = BlockNumberOrHash::from(format!("0x{:x}", block_number));
+    let previous_block_number = BlockNumberOrHash::from(format!("0x{:x}", block_number - 1));
+    assert!(client
+        .eth_get_code(contract_address.into(), Some(snapshot_block_number.clone()))
+        .await
+        .is_ok());
+    assert!(client
+        .eth_get_code(contract_address.into(), Some(previous_block_number))
+        .await
+        .is_err());
+
+    // Transactions of imported blocks aren't kept, so they can't be traced
+    assert!(client
+        .debug_get_block_trace_string(snapshot_block_number.clone())
+        .await
+        .is_err());
+    assert!(client
+        .debug_get_block_trace_hash(snapshot_block_number.clone())
+        .await
+        .is_err());
+    assert!(client
+        .debug_trace_block_by_number(snapshot_block_number, None)
+        .await
+        .is_err());
+    // Blocks indexed after the import are traced
+    assert!(client
+        .debug_get_block_trace_string("latest".into())
+        .await?
+        .is_some());
+
+    // Blocks before the snapshot can't be reorged, their history isn't imported
+    assert!(client.brc20_reorg(block_number - 1).await.is_err());
+    client.brc20_reorg(block_number).await?;
+
+    server.stop()?;
+    Ok(())
+}
@@ -1,20 +1,22 @@
 use std::error::Error;
 use std::str::FromStr;
 
-use alloy::primitives::{Bytes, U256};
-use alloy_consensus::TxLegacy;
+use alloy::eips::eip2930::{AccessList, AccessListItem};
+use alloy::eips::Encodable2718;
+use alloy::primitives::{keccak256, Bytes, U256};
+use alloy_consensus::{TxEip1559, TxEip2930, TxLegacy};
 use alloy_network::{EthereumWallet, TransactionBuilder};
 use alloy_rpc_types_eth::TransactionRequest;
 use alloy_signer_local::PrivateKeySigner;
 use brc20_prog::types::{Base64Bytes, RawBytes};
 use brc20_prog::Brc20ProgApiClient;
 use revm::primitives::Address;
-use test_utils::{load_file_as_string, spawn_test_server};
+use test_utils::{load_file_as_string, spawn_test_server, TEST_PKSCRIPT};
 
 #[tokio::test]
 async fn test_transact() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let from_pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let from_pkscript = TEST_PKSCRIPT.to_string();
     let timestamp = 42;
     let block_hash = [0u8; 32].into();
 
@@ -147,7 +149,7 @@ async fn test_transact() -> Result<(), Box<dyn Error>> {
 #[tokio::test]
 async fn test_transact_encoded() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;
-    let from_pkscript = "7465737420706b736372697074".to_string(); // "test pkscript"
+    let from_pkscript = TEST_PKSCRIPT.to_string();
     let timestamp = 42;
     let block_hash = [0u8; 32].into();
 
@@ -270,6 +272,118 @@ async fn test_transact_encoded() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_transact_typed() -> Result<(), Box<dyn Error>> {
+    let (server, client) = spawn_test_server(Default::default()).await;
+    let timestamp = 42;
+    let block_hash = [0u8; 32].into();
+
+    let chain_id = client.eth_chain_id().await.unwrap();
+    let chain_id_number = u64::from_str_radix(chain_id.trim_start_matches("0x"), 16)?;
+
+    let wallet = EthereumWallet::new(PrivateKeySigner::random());
+
+    // Deploy with an EIP-1559 transaction
+    let deploy_data = load_file_as_string("brc20_prog_helper_deploy_tx_data")?;
+    let tx_builder: TransactionRequest = TxEip1559::default().into();
+    let tx_builder = tx_builder
+        .with_chain_id(chain_id_number)
+        .with_nonce(0)
+        .with_max_fee_per_gas(0)
+        .with_max_priority_fee_per_gas(0)
+        .with_gas_limit(0)
+        .with_to(Address::ZERO)
+        .with_value(U256::ZERO)
+        .with_input(Bytes::from_str(&deploy_data).unwrap());
+
+    let encoded_tx = tx_builder.build(&wallet).await.unwrap().encoded_2718();
+    assert_eq!(encoded_tx[0], 2);
+
+    let deploy_response = client
+        .brc20_transact(
+            RawBytes::new(hex::encode(&encoded_tx)).into(),
+            None,
+            timestamp,
+            block_hash,
+            0,
+            "deploy_inscription".to_string(),
+            encoded_tx.len() as u64,
+            [1; 32].into(),
+        )
+        .await?;
+
+    let receipt = deploy_response.first().unwrap();
+    assert!(!receipt.status.is_zero());
+    assert_eq!(receipt.transaction_type, 2u8.into());
+    assert_eq!(receipt.transaction_hash.bytes, keccak256(&encoded_tx));
+
+    let tx = client
+        .eth_get_transaction_by_hash(receipt.transaction_hash)
+        .await?
+        .unwrap();
+    assert_eq!(tx.tx_type, 2u8.into());
+    assert_eq!(tx.access_list, Some(AccessList::default()));
+
+    let contract_address = receipt.contract_address.unwrap();
+
+    // Call with an EIP-2930 transaction, using an access list
+    let access_list = AccessList(vec![AccessListItem {
+        address: contract_address.address,
+        storage_keys: vec![[0u8; 32].into()],
+    }]);
+    let call_data = load_file_as_string("brc20_prog_helper_call_tx_data")?;
+    let call_tx_builder: TransactionRequest = TxEip2930::default().into();
+    let call_tx_builder = call_tx_builder
+        .with_chain_id(chain_id_number)
+        .with_nonce(1)
+        .with_gas_price(0)
+        .with_gas_limit(0)
+        .with_to(contract_address.address)
+        .with_value(U256::ZERO)
+        .with_access_list(access_list.clone())
+        .with_input(Bytes::from_str(&call_data).unwrap());
+
+    let encoded_tx = call_tx_builder.build(&wallet).await.unwrap().encoded_2718();
+    assert_eq!(encoded_tx[0], 1);
+
+    let call_response = client
+        .brc20_transact(
+            RawBytes::new(hex::encode(&encoded_tx)).into(),
+            None,
+            timestamp,
+            block_hash,
+            1,
+            "call_inscription".to_string(),
+            encoded_tx.len() as u64,
+            [2; 32].into(),
+        )
+        .await?;
+
+    let receipt = call_response.first().unwrap();
+    assert!(!receipt.status.is_zero());
+    assert_eq!(receipt.transaction_type, 1u8.into());
+
+    let tx = client
+        .eth_get_transaction_by_hash(receipt.transaction_hash)
+        .await?
+        .unwrap();
+    assert_eq!(tx.tx_type, 1u8.into());
+    assert_eq!(tx.access_list, Some(access_list));
+
+    let trace = client
+        .debug_trace_transaction(receipt.transaction_hash)
+        .await?;
+
+    assert_eq!(
+        trace.unwrap().output.bytes,
+        Bytes::from_str(&load_file_as_string("brc20_prog_helper_call_response")?).unwrap()
+    );
+
+    server.stop()?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_transact_out_of_order() -> Result<(), Box<dyn Error>> {
     let (server, client) = spawn_test_server(Default::default()).await;